@@ -46,14 +46,15 @@ use std::error::Error;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+use subspace_core_primitives::objects::GlobalObject;
 use subspace_core_primitives::{
     Piece, PieceIndex, SegmentCommitment, SegmentHeader, SegmentIndex, Solution,
 };
 use subspace_farmer_components::FarmerProtocolInfo;
 use subspace_networking::libp2p::Multiaddr;
 use subspace_rpc_primitives::{
-    FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo, SolutionResponse,
-    MAX_SEGMENT_INDEXES_PER_REQUEST,
+    ArchivedObjectMappings, FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo,
+    SolutionResponse, MAX_SEGMENT_INDEXES_PER_REQUEST,
 };
 use tracing::{debug, error, warn};
 
@@ -97,6 +98,14 @@ pub trait SubspaceRpcApi {
     )]
     fn subscribe_archived_segment_header(&self);
 
+    /// Archived object mappings subscription
+    #[subscription(
+        name = "subspace_subscribeArchivedObjectMappings" => "subspace_archived_object_mappings",
+        unsubscribe = "subspace_unsubscribeArchivedObjectMappings",
+        item = ArchivedObjectMappings,
+    )]
+    fn subscribe_archived_object_mappings(&self);
+
     #[method(name = "subspace_segmentCommitments")]
     async fn segment_commitments(
         &self,
@@ -543,6 +552,54 @@ where
         Ok(())
     }
 
+    fn subscribe_archived_object_mappings(&self, mut sink: SubscriptionSink) -> SubscriptionResult {
+        let stream = self.archived_segment_notification_stream.subscribe().map(
+            |archived_segment_notification| {
+                // Acknowledgement sender is dropped right away, object mappings subscribers don't
+                // hold block import
+                let archived_segment = archived_segment_notification.archived_segment;
+                let segment_index = archived_segment.segment_header.segment_index();
+
+                // Object mappings are only present for source pieces, in the same order
+                let objects = segment_index
+                    .segment_piece_indexes_source_first()
+                    .zip(&archived_segment.object_mapping)
+                    .flat_map(|(piece_index, piece_object_mapping)| {
+                        piece_object_mapping
+                            .objects
+                            .iter()
+                            .map(move |piece_object| {
+                                (
+                                    piece_object.hash(),
+                                    GlobalObject::V0 {
+                                        piece_index,
+                                        offset: piece_object.offset(),
+                                    },
+                                )
+                            })
+                    })
+                    .collect();
+
+                ArchivedObjectMappings {
+                    segment_index,
+                    objects,
+                }
+            },
+        );
+
+        let fut = async move {
+            sink.pipe_from_stream(stream).await;
+        };
+
+        self.executor.spawn(
+            "subspace-archived-object-mappings-subscription",
+            Some("rpc"),
+            fut.boxed(),
+        );
+
+        Ok(())
+    }
+
     async fn acknowledge_archived_segment_header(
         &self,
         segment_index: SegmentIndex,
@@ -320,6 +320,13 @@ impl AsRef<[u8]> for PublicKey {
     }
 }
 
+impl From<&PublicKey> for PublicKey {
+    #[inline]
+    fn from(public_key: &PublicKey) -> Self {
+        *public_key
+    }
+}
+
 impl PublicKey {
     /// Public key hash.
     pub fn hash(&self) -> Blake2b256Hash {
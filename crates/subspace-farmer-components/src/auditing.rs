@@ -4,7 +4,7 @@ use std::collections::VecDeque;
 use std::mem;
 use subspace_core_primitives::crypto::Scalar;
 use subspace_core_primitives::{Blake2b256Hash, PublicKey, SectorId, SolutionRange};
-use subspace_verification::is_within_solution_range;
+use subspace_verification::calculate_solution_distance;
 
 #[derive(Debug, Clone)]
 pub(crate) struct ChunkCandidate {
@@ -12,6 +12,8 @@ pub(crate) struct ChunkCandidate {
     pub(crate) chunk_offset: u32,
     /// Audit chunk offsets in above chunk
     pub(crate) audit_chunk_offsets: VecDeque<u8>,
+    /// Solution distances of above audit chunks, in the same order
+    pub(crate) solution_distances: VecDeque<SolutionRange>,
 }
 
 /// Audit a single sector and generate a stream of solutions, where `sector` must be positioned
@@ -47,25 +49,35 @@ pub fn audit_sector<'a>(
     let s_bucket =
         &sector[sector_contents_map_size + s_bucket_audit_offset..][..s_bucket_audit_size];
 
+    let mut best_solution_distance = SolutionRange::MAX;
+
     // Map all winning chunks
     let winning_chunks = s_bucket
         .array_chunks::<{ Scalar::FULL_BYTES }>()
         .enumerate()
         .filter_map(|(chunk_offset, chunk)| {
             // Check all audit chunks within chunk, there might be more than one winning
-            let winning_audit_chunk_offsets = chunk
+            let (winning_audit_chunk_offsets, solution_distances) = chunk
                 .array_chunks::<{ mem::size_of::<SolutionRange>() }>()
                 .enumerate()
                 .filter_map(|(audit_chunk_offset, &audit_chunk)| {
-                    is_within_solution_range(
+                    let solution_distance = calculate_solution_distance(
                         global_challenge,
                         SolutionRange::from_le_bytes(audit_chunk),
                         &sector_slot_challenge,
-                        solution_range,
-                    )
-                    .then_some(audit_chunk_offset as u8)
+                    );
+
+                    // Same check as in `is_within_solution_range()`, but distance is also needed
+                    // for ranking of candidates
+                    if solution_distance > solution_range / 2 {
+                        return None;
+                    }
+
+                    best_solution_distance = best_solution_distance.min(solution_distance);
+
+                    Some((audit_chunk_offset as u8, solution_distance))
                 })
-                .collect::<VecDeque<_>>();
+                .unzip::<_, _, VecDeque<_>, VecDeque<_>>();
 
             // In case none of the audit chunks are winning, we don't care about this sector
             if winning_audit_chunk_offsets.is_empty() {
@@ -75,6 +87,7 @@ pub fn audit_sector<'a>(
             Some(ChunkCandidate {
                 chunk_offset: chunk_offset as u32,
                 audit_chunk_offsets: winning_audit_chunk_offsets,
+                solution_distances,
             })
         })
         .collect::<VecDeque<_>>();
@@ -92,5 +105,6 @@ pub fn audit_sector<'a>(
         sector,
         sector_metadata,
         winning_chunks,
+        best_solution_distance,
     ))
 }
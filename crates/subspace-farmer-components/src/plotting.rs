@@ -66,6 +66,10 @@ pub trait PieceGetter {
         piece_index: PieceIndex,
         retry_policy: PieceGetterRetryPolicy,
     ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>>;
+
+    /// Called after piece that couldn't be retrieved was reconstructed from other pieces of its
+    /// segment, `success` indicates whether reconstruction succeeded
+    fn on_piece_recovery(&self, _piece_index: PieceIndex, _success: bool) {}
 }
 
 #[async_trait]
@@ -80,6 +84,10 @@ where
     ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
         self.as_ref().get_piece(piece_index, retry_policy).await
     }
+
+    fn on_piece_recovery(&self, piece_index: PieceIndex, success: bool) {
+        self.as_ref().on_piece_recovery(piece_index, success)
+    }
 }
 
 #[async_trait]
@@ -446,6 +454,7 @@ async fn download_sector<PG: PieceGetter>(
                 };
                 let recovered_piece =
                     recover_missing_piece(piece_getter, kzg.clone(), piece_index).await;
+                piece_getter.on_piece_recovery(piece_index, recovered_piece.is_ok());
 
                 return (piece_index, recovered_piece.map(Some).map_err(Into::into));
             }
@@ -7,6 +7,7 @@ use subspace_core_primitives::crypto::kzg::{Commitment, Kzg, Witness};
 use subspace_core_primitives::crypto::Scalar;
 use subspace_core_primitives::{
     PieceOffset, PosProof, PublicKey, Record, SBucket, SectorId, SectorIndex, Solution,
+    SolutionRange,
 };
 use subspace_erasure_coding::ErasureCoding;
 use subspace_proof_of_space::{Quality, Table};
@@ -75,6 +76,7 @@ pub struct SolutionCandidates<'a> {
     sector: &'a [u8],
     sector_metadata: &'a SectorMetadata,
     chunk_candidates: VecDeque<ChunkCandidate>,
+    best_solution_distance: SolutionRange,
 }
 
 impl<'a> SolutionCandidates<'a> {
@@ -86,6 +88,7 @@ impl<'a> SolutionCandidates<'a> {
         sector: &'a [u8],
         sector_metadata: &'a SectorMetadata,
         chunk_candidates: VecDeque<ChunkCandidate>,
+        best_solution_distance: SolutionRange,
     ) -> Self {
         Self {
             public_key,
@@ -95,9 +98,60 @@ impl<'a> SolutionCandidates<'a> {
             sector,
             sector_metadata,
             chunk_candidates,
+            best_solution_distance,
         }
     }
 
+    /// Smallest distance to the challenge among all candidates, smaller is better
+    pub fn best_solution_distance(&self) -> SolutionRange {
+        self.best_solution_distance
+    }
+
+    /// Split into candidates with a single audit chunk each, such that candidates of different
+    /// sectors can be ranked by their individual distance to the challenge.
+    ///
+    /// Note that some of the resulting candidates might not produce a solution when proven.
+    pub fn split(self) -> Vec<Self> {
+        let Self {
+            public_key,
+            sector_index,
+            sector_id,
+            s_bucket,
+            sector,
+            sector_metadata,
+            chunk_candidates,
+            best_solution_distance: _,
+        } = self;
+
+        chunk_candidates
+            .into_iter()
+            .flat_map(|chunk_candidate| {
+                let chunk_offset = chunk_candidate.chunk_offset;
+
+                chunk_candidate
+                    .audit_chunk_offsets
+                    .into_iter()
+                    .zip(chunk_candidate.solution_distances)
+                    .map(move |(audit_chunk_offset, solution_distance)| {
+                        Self::new(
+                            public_key,
+                            sector_index,
+                            sector_id,
+                            s_bucket,
+                            sector,
+                            sector_metadata,
+                            VecDeque::from([ChunkCandidate {
+                                chunk_offset,
+                                audit_chunk_offsets: VecDeque::from([audit_chunk_offset]),
+                                solution_distances: VecDeque::from([solution_distance]),
+                            }]),
+                            solution_distance,
+                        )
+                    })
+            })
+            .collect()
+    }
+
     /// Total number of candidates
     pub fn len(&self) -> usize {
         self.chunk_candidates
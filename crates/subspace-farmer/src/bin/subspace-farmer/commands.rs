@@ -1,6 +1,20 @@
+mod benchmark;
 mod farm;
+mod gateway;
+mod identity;
 mod info;
+mod migrate;
+mod plotting_server;
+mod scrub;
 mod shared;
+mod signer;
 
+pub(crate) use benchmark::benchmark;
 pub(crate) use farm::farm_multi_disk;
+pub(crate) use gateway::gateway;
+pub(crate) use identity::identity;
 pub(crate) use info::info;
+pub(crate) use migrate::{migrate, MigrateAction};
+pub(crate) use plotting_server::plotting_server;
+pub(crate) use scrub::scrub;
+pub(crate) use signer::signer;
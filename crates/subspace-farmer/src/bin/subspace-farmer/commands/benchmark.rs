@@ -0,0 +1,83 @@
+use crate::DiskFarm;
+use anyhow::anyhow;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::Record;
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer::single_disk_plot::SingleDiskPlot;
+use subspace_proof_of_space::Table;
+
+pub(crate) async fn benchmark<PosTable>(
+    disk_farms: Vec<DiskFarm>,
+    proving_sectors: usize,
+    plotting: bool,
+) -> anyhow::Result<()>
+where
+    PosTable: Table,
+{
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let erasure_coding = ErasureCoding::new(
+        NonZeroUsize::new(Record::NUM_S_BUCKETS.next_power_of_two().ilog2() as usize).unwrap(),
+    )
+    .map_err(|error| anyhow!(error))?;
+
+    for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
+        if disk_farm_index > 0 {
+            println!();
+        }
+
+        let DiskFarm { directory, .. } = disk_farm;
+
+        println!("Single disk farm {disk_farm_index}:");
+        println!("  Directory: {}", directory.display());
+
+        let results = match SingleDiskPlot::benchmark::<PosTable>(
+            &directory,
+            &kzg,
+            &erasure_coding,
+            proving_sectors,
+            plotting,
+        )
+        .await
+        {
+            Ok(results) => results,
+            Err(error) => {
+                println!("  Failed to benchmark farm: {error}");
+                continue;
+            }
+        };
+
+        println!("  Sectors: {}", results.sector_count);
+        if results.sector_count > 0 {
+            println!(
+                "  Auditing: {:?} total, {:?} per sector, {:?} total in parallel",
+                results.audit_duration,
+                results.audit_duration / results.sector_count as u32,
+                results.parallel_audit_duration
+            );
+        }
+
+        if results.proving_durations.is_empty() {
+            println!("  Proving: no sectors proven");
+        } else {
+            let total_proving_duration = results.proving_durations.iter().sum::<Duration>();
+            println!(
+                "  Proving: {:?} per sector on average, {:?} max ({} sectors)",
+                total_proving_duration / results.proving_durations.len() as u32,
+                results
+                    .proving_durations
+                    .iter()
+                    .max()
+                    .expect("Not empty; qed"),
+                results.proving_durations.len()
+            );
+        }
+
+        if let Some(plotting_duration) = results.plotting_duration {
+            println!("  Plotting: {plotting_duration:?} per sector");
+        }
+    }
+
+    Ok(())
+}
@@ -1,58 +1,71 @@
 mod dsn;
+mod metrics;
 
-use crate::commands::farm::dsn::configure_dsn;
-use crate::commands::shared::print_disk_farm_info;
+use crate::commands::farm::dsn::{configure_dsn, LocalPieceCache};
+use crate::commands::farm::metrics::{FarmerMetrics, MetricsPieceGetter};
+use crate::commands::shared::objects::{fill_object_mappings, start_object_rpc_server};
+use crate::commands::shared::{
+    derive_libp2p_keypair, print_disk_farm_info, read_identity_passphrase,
+};
 use crate::utils::{get_required_plot_space_with_overhead, shutdown_signal};
 use crate::{DiskFarm, FarmingArgs};
 use anyhow::{anyhow, Context, Result};
-use futures::future::{select, Either};
+use futures::future::{join_all, select, Either};
 use futures::stream::FuturesUnordered;
 use futures::{FutureExt, StreamExt};
 use lru::LruCache;
 use parking_lot::Mutex;
+use prometheus_client::registry::Registry;
 use std::collections::HashMap;
 use std::num::NonZeroUsize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
 use subspace_core_primitives::{
-    ArchivedHistorySegment, Piece, PieceIndex, PieceIndexHash, PieceOffset, Record, SegmentIndex,
+    ArchivedHistorySegment, Piece, PieceIndex, PieceIndexHash, PieceOffset, PublicKey, Record,
+    SegmentIndex,
 };
 use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer::reward_signer::remote::RemoteRewardSigner;
+use subspace_farmer::reward_signer::RewardSigner;
+use subspace_farmer::reward_signing::reward_signing;
+use subspace_farmer::single_disk_plot::remote_plotting::RemotePlotter;
 use subspace_farmer::single_disk_plot::{
     SingleDiskPlot, SingleDiskPlotError, SingleDiskPlotOptions,
 };
 use subspace_farmer::utils::archival_storage_pieces::ArchivalStoragePieces;
-use subspace_farmer::utils::farmer_piece_cache::FarmerPieceCache;
 use subspace_farmer::utils::farmer_piece_getter::FarmerPieceGetter;
 use subspace_farmer::utils::node_piece_getter::NodePieceGetter;
 use subspace_farmer::utils::piece_cache::PieceCache;
 use subspace_farmer::utils::piece_validator::SegmentCommitmentPieceValidator;
 use subspace_farmer::utils::readers_and_pieces::{PieceDetails, ReadersAndPieces};
 use subspace_farmer::utils::run_future_in_dedicated_thread;
-use subspace_farmer::{Identity, NodeClient, NodeRpcClient};
+use subspace_farmer::utils::sector_index_allocator::SectorIndexAllocator;
+use subspace_farmer::utils::shared_secret::SharedSecret;
+use subspace_farmer::{Identity, MultiNodeClient, NodeClient, ObjectMappings};
 use subspace_farmer_components::piece_caching::PieceMemoryCache;
 use subspace_farmer_components::plotting::{PieceGetter, PieceGetterRetryPolicy};
-use subspace_networking::libp2p::identity::{ed25519, Keypair};
+use subspace_networking::start_prometheus_metrics_server;
 use subspace_networking::utils::multihash::ToMultihash;
 use subspace_networking::utils::piece_announcement::announce_single_piece_index_hash_with_backoff;
-use subspace_networking::utils::piece_provider::PieceProvider;
+use subspace_networking::utils::piece_provider::{PieceProvider, PieceProviderConfig};
 use subspace_proof_of_space::Table;
 use tokio::sync::broadcast;
 use tokio::time::sleep;
 use tracing::{debug, error, info, info_span, trace, warn, Instrument};
-use zeroize::Zeroizing;
 
 const RECORDS_ROOTS_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(1_000_000).expect("Not zero; qed");
 const GET_PIECE_MAX_RETRIES_COUNT: u16 = 3;
 const GET_PIECE_DELAY_IN_SECS: u64 = 3;
+const ARCHIVED_SEGMENTS_CHANNEL_CAPACITY: usize = 16;
+const PIECE_PROVIDER_METRICS_UPDATE_INTERVAL: Duration = Duration::from_secs(15);
 
 /// Start farming by using multiple replica plot in specified path and connecting to WebSocket
 /// server at specified address.
 pub(crate) async fn farm_multi_disk<PosTable>(
     base_path: PathBuf,
-    disk_farms: Vec<DiskFarm>,
+    mut disk_farms: Vec<DiskFarm>,
     farming_args: FarmingArgs,
 ) -> Result<(), anyhow::Error>
 where
@@ -69,19 +82,53 @@ where
     let FarmingArgs {
         node_rpc_url,
         reward_address,
+        identity,
+        reward_signer,
+        reward_signer_secret_file,
         plot_size: _,
         max_pieces_in_sector,
         disk_concurrency,
         disable_farming,
         mut dsn,
         max_concurrent_plots,
+        sector_plotting_concurrency,
+        farming_thread_pool_size,
+        solutions_limit,
+        solution_selection_policy,
         no_info: _,
+        metrics_endpoint,
+        object_rpc_listen_on,
+        object_mappings_size,
+        plotting_server,
+        plotting_server_secret_file,
+        replot_invalid_sectors,
+        reassign_overlapping_sector_ranges,
+        piece_request_concurrency,
+        piece_request_hedge_threshold_ms,
     } = farming_args;
 
+    // Disk piece cache file is stored in farm directory, so it is taken out of allocated space
+    let disk_piece_cache_size = dsn.disk_piece_cache_size.as_u64();
+    for disk_farm in &mut disk_farms {
+        disk_farm.allocated_plotting_space = disk_farm
+            .allocated_plotting_space
+            .checked_sub(disk_piece_cache_size)
+            .filter(|allocated_plotting_space| *allocated_plotting_space > 0)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Farm at {} is too small to fit disk piece cache of {}",
+                    disk_farm.directory.display(),
+                    dsn.disk_piece_cache_size
+                )
+            })?;
+    }
+
     let readers_and_pieces = Arc::new(Mutex::new(None));
 
-    info!(url = %node_rpc_url, "Connecting to node RPC");
-    let node_client = NodeRpcClient::new(&node_rpc_url).await?;
+    info!(urls = ?node_rpc_url, "Connecting to node RPC");
+    let node_client = MultiNodeClient::new(node_rpc_url.clone())
+        .await
+        .map_err(|error| anyhow!(error))?;
 
     let concurrent_plotting_semaphore = Arc::new(tokio::sync::Semaphore::new(
         farming_args.max_concurrent_plots.get(),
@@ -102,31 +149,108 @@ where
         + 1usize;
     let archival_storage_pieces = ArchivalStoragePieces::new(cuckoo_filter_capacity);
 
-    let (node, mut node_runner, piece_cache) = {
-        // TODO: Temporary networking identity derivation from the first disk farm identity.
-        let directory = disk_farms
+    let object_mappings_path = base_path.join("object-mappings");
+
+    let (reward_signers, networking_identity) = match reward_signer {
+        Some(reward_signer_address) => {
+            let secret_file = reward_signer_secret_file
+                .expect("Secret file is required together with reward signer by CLI; qed");
+            let secret = SharedSecret::read_from(&secret_file).with_context(|| {
+                format!(
+                    "Failed to read signer secret from {}",
+                    secret_file.display()
+                )
+            })?;
+
+            info!(address = %reward_signer_address, "Connecting to remote reward signer");
+            let reward_signer: Arc<dyn RewardSigner> =
+                Arc::new(RemoteRewardSigner::connect(reward_signer_address, secret).await?);
+
+            // Secret key of networking identity is not used for anything valuable, so it is fine
+            // to store it locally when rewards are signed remotely
+            let networking_identity =
+                Identity::open_or_create_file(base_path.join("networking-identity.bin"), None)?;
+
+            (vec![reward_signer; disk_farms.len()], networking_identity)
+        }
+        None => {
+            let identity_files = match &identity.identity_file {
+                Some(identity_file) => vec![identity_file.clone()],
+                None => disk_farms
+                    .iter()
+                    .map(|disk_farm| Identity::file_path(&disk_farm.directory))
+                    .collect(),
+            };
+            // Passphrase is confirmed whenever new or plaintext identity is about to be encrypted
+            let mut all_encrypted = true;
+            for identity_file in &identity_files {
+                all_encrypted &= Identity::is_file_encrypted(identity_file)?;
+            }
+            let passphrase = read_identity_passphrase(&identity, !all_encrypted)?;
+
+            let identities = identity_files
+                .iter()
+                .map(|identity_file| {
+                    Identity::open_or_create_file(
+                        identity_file,
+                        passphrase.as_deref().map(String::as_str),
+                    )
+                    .with_context(|| {
+                        format!("Failed to open identity at {}", identity_file.display())
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            // TODO: Temporary networking identity derivation from the first disk farm identity.
+            let networking_identity = identities
+                .first()
+                .expect("Disk farm collection should not be empty at this point.")
+                .clone();
+
+            let reward_signers = if identities.len() == disk_farms.len() {
+                identities
+                    .into_iter()
+                    .map(|identity| Arc::new(identity) as Arc<dyn RewardSigner>)
+                    .collect()
+            } else {
+                // Shared identity is used by all disk farms
+                vec![
+                    Arc::new(networking_identity.clone()) as Arc<dyn RewardSigner>;
+                    disk_farms.len()
+                ]
+            };
+
+            (reward_signers, networking_identity)
+        }
+    };
+
+    let (node, mut node_runner, piece_cache, public_key) = {
+        let keypair = derive_libp2p_keypair(networking_identity.secret_key());
+        let public_key = reward_signers
             .first()
             .expect("Disk farm collection should not be empty at this point.")
-            .directory
-            .clone();
-        // TODO: Update `Identity` to use more specific error type and remove this `.unwrap()`
-        let identity = Identity::open_or_create(&directory).unwrap();
-        let keypair = derive_libp2p_keypair(identity.secret_key());
+            .public_key();
 
         if dsn.bootstrap_nodes.is_empty() {
             dsn.bootstrap_nodes = farmer_app_info.dsn_bootstrap_nodes.clone();
         }
 
-        configure_dsn(
+        let (node, node_runner, piece_cache) = configure_dsn(
             hex::encode(farmer_app_info.genesis_hash),
-            base_path,
+            base_path.clone(),
             keypair,
             dsn,
             &readers_and_pieces,
             node_client.clone(),
             piece_memory_cache.clone(),
             archival_storage_pieces.clone(),
-        )?
+            &disk_farms
+                .iter()
+                .map(|disk_farm| disk_farm.directory.as_path())
+                .collect::<Vec<_>>(),
+        )?;
+
+        (node, node_runner, piece_cache, public_key)
     };
 
     let piece_cache = Arc::new(tokio::sync::Mutex::new(piece_cache));
@@ -139,7 +263,7 @@ where
     // TODO: Consider introducing and using global in-memory segment header cache (this comment is
     //  in multiple files)
     let segment_commitments_cache = Mutex::new(LruCache::new(RECORDS_ROOTS_CACHE_SIZE));
-    let piece_provider = PieceProvider::new(
+    let piece_provider = PieceProvider::with_config(
         node.clone(),
         Some(SegmentCommitmentPieceValidator::new(
             node.clone(),
@@ -147,12 +271,38 @@ where
             kzg.clone(),
             segment_commitments_cache,
         )),
+        PieceProviderConfig {
+            max_concurrent_requests: piece_request_concurrency,
+            hedge_threshold: Duration::from_millis(piece_request_hedge_threshold_ms),
+        },
     );
+    let node_piece_getter = NodePieceGetter::new(piece_provider);
+    let piece_provider = Arc::clone(node_piece_getter.piece_provider());
     let piece_getter = Arc::new(FarmerPieceGetter::new(
-        NodePieceGetter::new(piece_provider),
+        node_piece_getter,
         piece_cache.clone(),
     ));
 
+    let mut metrics_registry = Registry::default();
+    let farmer_metrics = FarmerMetrics::new(&mut metrics_registry);
+    // Piece provider stats are only sampled when there is someone to export them to
+    if metrics_endpoint.is_some() {
+        tokio::spawn({
+            let farmer_metrics = farmer_metrics.clone();
+
+            async move {
+                loop {
+                    farmer_metrics.observe_piece_provider(&piece_provider);
+                    sleep(PIECE_PROVIDER_METRICS_UPDATE_INTERVAL).await;
+                }
+            }
+        });
+    }
+    let plotting_piece_getter = Arc::new(MetricsPieceGetter::new(
+        piece_getter.clone(),
+        farmer_metrics.clone(),
+    ));
+
     let last_segment_index = farmer_app_info.protocol_info.history_size.segment_index();
 
     let _piece_cache_population = run_future_in_dedicated_thread(
@@ -165,12 +315,20 @@ where
         "pieces-cache-population".to_string(),
     )?;
 
+    // Plots are notified about archived segments only after pieces are cached, segment is
+    // acknowledged to the node afterwards, plots never acknowledge segments themselves
+    let (archived_segments_sender, _) = broadcast::channel(ARCHIVED_SEGMENTS_CHANNEL_CAPACITY);
     let _piece_cache_maintainer = run_future_in_dedicated_thread(
         Box::pin({
             let piece_cache = piece_cache.clone();
             let node_client = node_client.clone();
+            let archived_segments_sender = archived_segments_sender.clone();
 
-            fill_piece_cache_from_archived_segments(node_client, piece_cache)
+            fill_piece_cache_from_archived_segments(
+                node_client,
+                piece_cache,
+                archived_segments_sender,
+            )
         }),
         "pieces-cache-maintainer".to_string(),
     )?;
@@ -194,11 +352,45 @@ where
         None => farmer_app_info.protocol_info.max_pieces_in_sector,
     };
 
+    // Registry of sector index ranges is stored next to shared identity if there is one
+    let sector_index_registry_directory = identity
+        .identity_file
+        .as_deref()
+        .and_then(Path::parent)
+        .filter(|directory| !directory.as_os_str().is_empty())
+        .unwrap_or(base_path.as_path());
+    let sector_index_allocator = SectorIndexAllocator::new(
+        sector_index_registry_directory,
+        disk_farms
+            .iter()
+            .map(|disk_farm| disk_farm.directory.as_path()),
+        reassign_overlapping_sector_ranges,
+    )?;
+
+    // Shared by all farms, such that jobs of all farms are distributed across plotting servers
+    let remote_plotter = match plotting_server_secret_file {
+        Some(secret_file) => {
+            let secret = SharedSecret::read_from(&secret_file).with_context(|| {
+                format!(
+                    "Failed to read plotting server secret from {}",
+                    secret_file.display()
+                )
+            })?;
+
+            RemotePlotter::new(plotting_server, secret)
+        }
+        None => None,
+    };
+
     // TODO: Check plot and metadata sizes to ensure there is enough space for farmer to not
     //  fail later
-    for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
-        debug!(url = %node_rpc_url, %disk_farm_index, "Connecting to node RPC");
-        let node_client = NodeRpcClient::new(&node_rpc_url).await?;
+    for (disk_farm_index, (disk_farm, reward_signer)) in
+        disk_farms.into_iter().zip(&reward_signers).enumerate()
+    {
+        debug!(urls = ?node_rpc_url, %disk_farm_index, "Connecting to node RPC");
+        let node_client = MultiNodeClient::new(node_rpc_url.clone())
+            .await
+            .map_err(|error| anyhow!(error))?;
 
         let single_disk_plot_fut = SingleDiskPlot::new::<_, _, PosTable>(
             SingleDiskPlotOptions {
@@ -208,11 +400,20 @@ where
                 max_pieces_in_sector,
                 node_client,
                 reward_address,
+                public_key: reward_signer.public_key(),
                 kzg: kzg.clone(),
                 erasure_coding: erasure_coding.clone(),
-                piece_getter: piece_getter.clone(),
+                piece_getter: plotting_piece_getter.clone(),
                 concurrent_plotting_semaphore: Arc::clone(&concurrent_plotting_semaphore),
+                sector_plotting_concurrency,
+                farming_thread_pool_size,
+                solutions_limit,
+                solution_selection_policy: solution_selection_policy.into(),
+                sector_index_allocator: sector_index_allocator.clone(),
                 piece_memory_cache: piece_memory_cache.clone(),
+                remote_plotter: remote_plotter.clone(),
+                replot_invalid_sectors,
+                archived_segments: archived_segments_sender.subscribe(),
             },
             disk_farm_index,
         );
@@ -242,9 +443,36 @@ where
             print_disk_farm_info(disk_farm.directory, disk_farm_index);
         }
 
+        farmer_metrics.observe(&single_disk_plot);
+
         single_disk_plots.push(single_disk_plot);
     }
 
+    // The same signer might be used by multiple farms, but every reward hash must only be signed
+    // once, so there is one reward signing task per distinct signer
+    let mut distinct_reward_signers = HashMap::<PublicKey, Arc<dyn RewardSigner>>::new();
+    for reward_signer in reward_signers {
+        distinct_reward_signers
+            .entry(reward_signer.public_key())
+            .or_insert(reward_signer);
+    }
+    let mut reward_signing_futures = Vec::with_capacity(distinct_reward_signers.len());
+    for (public_key, reward_signer) in distinct_reward_signers {
+        let node_client = MultiNodeClient::new(node_rpc_url.clone())
+            .await
+            .map_err(|error| anyhow!(error))?;
+        let farmer_metrics = farmer_metrics.clone();
+
+        reward_signing_futures.push(
+            reward_signing(node_client, reward_signer, move |_hash| {
+                farmer_metrics.observe_reward_signature(&public_key);
+            })
+            .await
+            .map_err(|error| anyhow!(error))?,
+        );
+    }
+    let mut reward_signing_fut = Box::pin(join_all(reward_signing_futures)).fuse();
+
     // Store piece readers so we can reference them later
     let piece_readers = single_disk_plots
         .iter()
@@ -299,6 +527,41 @@ where
         .lock()
         .replace(ReadersAndPieces::new(piece_readers, plotted_pieces));
 
+    if let Some(metrics_endpoint) = metrics_endpoint {
+        start_prometheus_metrics_server(metrics_endpoint, metrics_registry).await?;
+    }
+
+    let _object_rpc_server = match object_rpc_listen_on {
+        Some(object_rpc_listen_on) => {
+            let object_mappings = ObjectMappings::open_or_create(
+                &object_mappings_path,
+                public_key,
+                object_mappings_size.as_u64(),
+            )?;
+
+            let object_mappings_filler = run_future_in_dedicated_thread(
+                Box::pin({
+                    let node_client = node_client.clone();
+                    let object_mappings = object_mappings.clone();
+
+                    fill_object_mappings(node_client, object_mappings)
+                }),
+                "object-mappings-filler".to_string(),
+            )?;
+
+            let object_rpc_server_handle = start_object_rpc_server(
+                object_rpc_listen_on,
+                Arc::clone(&readers_and_pieces),
+                piece_getter.clone(),
+                object_mappings,
+            )
+            .await?;
+
+            Some((object_rpc_server_handle, object_mappings_filler))
+        }
+        None => None,
+    };
+
     let mut single_disk_plots_stream = single_disk_plots
         .into_iter()
         .enumerate()
@@ -313,10 +576,14 @@ where
             let (dropped_sender, _dropped_receiver) = broadcast::channel::<()>(1);
 
             // Collect newly plotted pieces
-            // TODO: Once we have replotting, this will have to be updated
             single_disk_plot
                 .on_sector_plotted(Arc::new(
-                    move |(sector_offset, plotted_sector, plotting_permit)| {
+                    move |(
+                        sector_offset,
+                        plotted_sector,
+                        maybe_old_plotted_sector,
+                        plotting_permit,
+                    )| {
                         let _span_guard = span.enter();
                         let plotting_permit = Arc::clone(plotting_permit);
                         let node = node.clone();
@@ -331,6 +598,21 @@ where
                                 .as_mut()
                                 .expect("Initial value was populated above; qed");
 
+                            if let Some(old_plotted_sector) = maybe_old_plotted_sector {
+                                // Sector was replotted, pieces of the old sector are no longer
+                                // available
+                                readers_and_pieces.delete_pieces(
+                                    disk_farm_index,
+                                    old_plotted_sector.sector_index,
+                                    old_plotted_sector
+                                        .piece_indexes
+                                        .iter()
+                                        .map(|piece_index| piece_index.hash()),
+                                );
+                                archival_storage_pieces
+                                    .delete_pieces(&old_plotted_sector.piece_indexes);
+                            }
+
                             let new_pieces = plotted_sector
                                 .piece_indexes
                                 .iter()
@@ -454,20 +736,14 @@ where
         _ = networking_fut => {
             info!("Node runner exited.")
         },
-    );
 
-    anyhow::Ok(())
-}
-
-fn derive_libp2p_keypair(schnorrkel_sk: &schnorrkel::SecretKey) -> Keypair {
-    let mut secret_bytes = Zeroizing::new(schnorrkel_sk.to_ed25519_bytes());
-
-    let keypair = ed25519::Keypair::from(
-        ed25519::SecretKey::try_from_bytes(&mut secret_bytes.as_mut()[..32])
-            .expect("Secret key is exactly 32 bytes in size; qed"),
+        // Reward signing future
+        _ = reward_signing_fut => {
+            info!("Reward signing exited.")
+        },
     );
 
-    Keypair::from(keypair)
+    anyhow::Ok(())
 }
 
 /// Populates piece cache on startup. It waits for the new segment index and check all pieces from
@@ -476,7 +752,7 @@ fn derive_libp2p_keypair(schnorrkel_sk: &schnorrkel::SecretKey) -> Keypair {
 async fn populate_pieces_cache<PG, PC>(
     segment_index: SegmentIndex,
     piece_getter: Arc<FarmerPieceGetter<PG, PC>>,
-    piece_cache: Arc<tokio::sync::Mutex<FarmerPieceCache>>,
+    piece_cache: Arc<tokio::sync::Mutex<LocalPieceCache>>,
 ) where
     PG: PieceGetter + Send + Sync,
     PC: PieceCache + Send + 'static,
@@ -529,8 +805,9 @@ async fn populate_pieces_cache<PG, PC>(
 
 /// Subscribes to a new segment index and adds pieces from the segment to the cache if required.
 async fn fill_piece_cache_from_archived_segments(
-    node_client: NodeRpcClient,
-    piece_cache: Arc<tokio::sync::Mutex<FarmerPieceCache>>,
+    node_client: MultiNodeClient,
+    piece_cache: Arc<tokio::sync::Mutex<LocalPieceCache>>,
+    archived_segments_sender: broadcast::Sender<SegmentIndex>,
 ) {
     let segment_headers_notifications = node_client
         .subscribe_archived_segment_headers()
@@ -597,6 +874,9 @@ async fn fill_piece_cache_from_archived_segments(
                     }
                 }
 
+                // Nobody might be listening if all plots exited
+                let _ = archived_segments_sender.send(segment_index);
+
                 match node_client
                     .acknowledge_archived_segment_header(segment_index)
                     .await
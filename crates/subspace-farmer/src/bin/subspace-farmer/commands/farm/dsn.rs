@@ -2,20 +2,24 @@ use crate::DsnArgs;
 use anyhow::Context;
 use futures::StreamExt;
 use parking_lot::Mutex;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
-use subspace_core_primitives::SegmentIndex;
+use subspace_core_primitives::{Piece, SegmentIndex};
 use subspace_farmer::utils::archival_storage_pieces::ArchivalStoragePieces;
+use subspace_farmer::utils::disk_piece_cache::DiskPieceCache;
 use subspace_farmer::utils::farmer_piece_cache::FarmerPieceCache;
 use subspace_farmer::utils::farmer_provider_storage::FarmerProviderStorage;
 use subspace_farmer::utils::parity_db_store::ParityDbStore;
+use subspace_farmer::utils::piece_cache::PieceCache;
 use subspace_farmer::utils::readers_and_pieces::ReadersAndPieces;
-use subspace_farmer::{NodeClient, NodeRpcClient};
+use subspace_farmer::{MultiNodeClient, NodeClient};
 use subspace_farmer_components::piece_caching::PieceMemoryCache;
 use subspace_networking::libp2p::identity::Keypair;
+use subspace_networking::libp2p::kad::record::Key;
 use subspace_networking::libp2p::kad::ProviderRecord;
+use subspace_networking::libp2p::mdns::Config as MdnsConfig;
 use subspace_networking::libp2p::multiaddr::Protocol;
 use subspace_networking::utils::multihash::ToMultihash;
 use subspace_networking::{
@@ -29,6 +33,57 @@ use tracing::{debug, error, info, trace, Instrument};
 
 const ROOT_BLOCK_NUMBER_LIMIT: u64 = 1000;
 
+/// Piece cache of the farmer, either stored in parity-db in base path or in dedicated files of
+/// farms
+#[derive(Clone)]
+pub(super) enum LocalPieceCache {
+    /// Parity-db cache with size limited in pieces
+    ParityDb(FarmerPieceCache),
+    /// Cache files in farm directories with size limited in bytes
+    Disk(DiskPieceCache),
+}
+
+impl LocalPieceCache {
+    fn size(&self) -> usize {
+        match self {
+            Self::ParityDb(piece_cache) => piece_cache.size(),
+            Self::Disk(piece_cache) => piece_cache.size(),
+        }
+    }
+}
+
+impl PieceCache for LocalPieceCache {
+    type KeysIterator = Vec<Key>;
+
+    fn should_cache(&self, key: &Key) -> bool {
+        match self {
+            Self::ParityDb(piece_cache) => piece_cache.should_cache(key),
+            Self::Disk(piece_cache) => piece_cache.should_cache(key),
+        }
+    }
+
+    fn add_piece(&mut self, key: Key, piece: Piece) {
+        match self {
+            Self::ParityDb(piece_cache) => piece_cache.add_piece(key, piece),
+            Self::Disk(piece_cache) => piece_cache.add_piece(key, piece),
+        }
+    }
+
+    fn get_piece(&self, key: &Key) -> Option<Piece> {
+        match self {
+            Self::ParityDb(piece_cache) => piece_cache.get_piece(key),
+            Self::Disk(piece_cache) => piece_cache.get_piece(key),
+        }
+    }
+
+    fn keys(&self) -> Self::KeysIterator {
+        match self {
+            Self::ParityDb(piece_cache) => piece_cache.keys().into_iter().collect(),
+            Self::Disk(piece_cache) => piece_cache.keys(),
+        }
+    }
+}
+
 #[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub(super) fn configure_dsn(
     protocol_prefix: String,
@@ -38,8 +93,10 @@ pub(super) fn configure_dsn(
         listen_on,
         bootstrap_nodes,
         piece_cache_size,
+        disk_piece_cache_size,
         provided_keys_limit,
         disable_private_ips,
+        enable_mdns,
         reserved_peers,
         in_connections,
         out_connections,
@@ -48,14 +105,15 @@ pub(super) fn configure_dsn(
         target_connections,
     }: DsnArgs,
     readers_and_pieces: &Arc<Mutex<Option<ReadersAndPieces>>>,
-    node_client: NodeRpcClient,
+    node_client: MultiNodeClient,
     piece_memory_cache: PieceMemoryCache,
     archival_storage_pieces: ArchivalStoragePieces,
+    farm_directories: &[&Path],
 ) -> Result<
     (
         Node,
-        NodeRunner<FarmerProviderStorage<ParityDbProviderStorage, FarmerPieceCache>>,
-        FarmerPieceCache,
+        NodeRunner<FarmerProviderStorage<ParityDbProviderStorage, LocalPieceCache>>,
+        LocalPieceCache,
     ),
     anyhow::Error,
 > {
@@ -86,14 +144,33 @@ pub(super) fn configure_dsn(
         "Provider storage initialized successfully"
     );
 
-    info!(
-        db_path = ?piece_cache_db_path,
-        size = ?piece_cache_size,
-        "Initializing piece cache..."
-    );
-    let piece_store =
-        ParityDbStore::new(&piece_cache_db_path).map_err(|err| anyhow::anyhow!(err.to_string()))?;
-    let piece_cache = FarmerPieceCache::new(piece_store.clone(), piece_cache_size, peer_id);
+    let piece_cache = if disk_piece_cache_size.as_u64() == 0 {
+        info!(
+            db_path = ?piece_cache_db_path,
+            size = ?piece_cache_size,
+            "Initializing piece cache..."
+        );
+        let piece_store = ParityDbStore::new(&piece_cache_db_path)
+            .map_err(|err| anyhow::anyhow!(err.to_string()))?;
+
+        LocalPieceCache::ParityDb(FarmerPieceCache::new(
+            piece_store,
+            piece_cache_size,
+            peer_id,
+        ))
+    } else {
+        info!(
+            size_per_farm = %disk_piece_cache_size,
+            "Initializing disk piece cache..."
+        );
+
+        LocalPieceCache::Disk(DiskPieceCache::open(
+            farm_directories
+                .iter()
+                .map(|directory| (*directory, disk_piece_cache_size.as_u64())),
+            peer_id,
+        )?)
+    };
     info!(
         current_size = ?piece_cache.size(),
         "Piece cache initialized successfully"
@@ -153,6 +230,7 @@ pub(super) fn configure_dsn(
         reserved_peers,
         listen_on,
         allow_non_global_addresses_in_dht: !disable_private_ips,
+        mdns: enable_mdns.then(MdnsConfig::default),
         networking_parameters_registry,
         request_response_protocols: vec![
             PieceAnnouncementRequestHandler::create({
@@ -185,7 +263,7 @@ pub(super) fn configure_dsn(
                     let multihash = piece_index_hash.to_multihash();
 
                     let weak_readers_and_pieces = weak_readers_and_pieces.clone();
-                    let piece_store = piece_store.clone();
+                    let piece_cache = piece_cache.clone();
                     let piece_memory_cache = piece_memory_cache.clone();
 
                     async move {
@@ -193,7 +271,7 @@ pub(super) fn configure_dsn(
                             return Some(PieceByHashResponse { piece: Some(piece) });
                         }
 
-                        let piece_from_store = piece_store.get(&multihash.into());
+                        let piece_from_store = piece_cache.get_piece(&multihash.into());
 
                         if let Some(piece) = piece_from_store {
                             Some(PieceByHashResponse { piece: Some(piece) })
@@ -0,0 +1,306 @@
+#[cfg(test)]
+mod tests;
+
+use async_trait::async_trait;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::registry::Registry;
+use std::error::Error;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use subspace_core_primitives::{Piece, PieceIndex, PublicKey};
+use subspace_farmer::single_disk_plot::{SingleDiskPlot, SingleDiskPlotId};
+use subspace_farmer_components::plotting::{PieceGetter, PieceGetterRetryPolicy};
+use subspace_networking::utils::piece_provider::{PieceProvider, PieceValidator};
+
+type Labels = Vec<(String, String)>;
+
+fn new_histogram(start: f64) -> Histogram {
+    Histogram::new(exponential_buckets(start, 2.0, 16))
+}
+
+fn new_plotting_time_histogram() -> Histogram {
+    new_histogram(1.0)
+}
+
+fn new_auditing_time_histogram() -> Histogram {
+    new_histogram(0.001)
+}
+
+fn farm_labels(single_disk_plot_id: &SingleDiskPlotId) -> Labels {
+    vec![("farm_id".to_string(), single_disk_plot_id.to_string())]
+}
+
+/// Metrics of plotting and farming across all farms of this farmer
+#[derive(Clone)]
+pub(super) struct FarmerMetrics {
+    sectors_plotted: Family<Labels, Gauge>,
+    sectors_remaining: Family<Labels, Gauge>,
+    sector_plotting_time: Family<Labels, Histogram, fn() -> Histogram>,
+    piece_download_failures: Counter,
+    piece_reconstructions: Family<Labels, Counter>,
+    auditing_time: Family<Labels, Histogram, fn() -> Histogram>,
+    solutions_found: Family<Labels, Counter>,
+    solutions_submitted: Family<Labels, Counter>,
+    skipped_slots: Family<Labels, Counter>,
+    reward_signatures: Family<Labels, Counter>,
+    piece_providers: Gauge,
+    piece_provider_requests: Family<Labels, Gauge>,
+    piece_provider_average_latency: Gauge<f64, AtomicU64>,
+}
+
+impl FarmerMetrics {
+    pub(super) fn new(registry: &mut Registry) -> Self {
+        let registry = registry.sub_registry_with_prefix("subspace_farmer");
+
+        let metrics = Self {
+            sectors_plotted: Family::default(),
+            sectors_remaining: Family::default(),
+            sector_plotting_time: Family::new_with_constructor(new_plotting_time_histogram),
+            piece_download_failures: Counter::default(),
+            piece_reconstructions: Family::default(),
+            auditing_time: Family::new_with_constructor(new_auditing_time_histogram),
+            solutions_found: Family::default(),
+            solutions_submitted: Family::default(),
+            skipped_slots: Family::default(),
+            reward_signatures: Family::default(),
+            piece_providers: Gauge::default(),
+            piece_provider_requests: Family::default(),
+            piece_provider_average_latency: Gauge::default(),
+        };
+
+        registry.register(
+            "sectors_plotted",
+            "Number of sectors plotted in farm",
+            metrics.sectors_plotted.clone(),
+        );
+        registry.register(
+            "sectors_remaining",
+            "Number of sectors remaining to be plotted in farm",
+            metrics.sectors_remaining.clone(),
+        );
+        registry.register(
+            "sector_plotting_time_seconds",
+            "Time it took to plot (or replot) a sector",
+            metrics.sector_plotting_time.clone(),
+        );
+        registry.register(
+            "piece_download_failures",
+            "Number of pieces that could not be downloaded for plotting",
+            metrics.piece_download_failures.clone(),
+        );
+        registry.register(
+            "piece_reconstructions",
+            "Number of attempts to reconstruct pieces that could not be downloaded",
+            metrics.piece_reconstructions.clone(),
+        );
+        registry.register(
+            "auditing_time_seconds",
+            "Time it took to audit all sectors of the farm for a slot",
+            metrics.auditing_time.clone(),
+        );
+        registry.register(
+            "solutions_found",
+            "Number of solutions found",
+            metrics.solutions_found.clone(),
+        );
+        registry.register(
+            "solutions_submitted",
+            "Number of solutions successfully submitted to the node",
+            metrics.solutions_submitted.clone(),
+        );
+        registry.register(
+            "skipped_slots",
+            "Number of slots skipped because farming of previous slot took too long",
+            metrics.skipped_slots.clone(),
+        );
+        registry.register(
+            "reward_signatures",
+            "Number of reward hashes signed",
+            metrics.reward_signatures.clone(),
+        );
+        registry.register(
+            "piece_providers",
+            "Number of DSN piece providers requested recently",
+            metrics.piece_providers.clone(),
+        );
+        registry.register(
+            "piece_provider_requests",
+            "Number of piece requests to recently requested DSN piece providers",
+            metrics.piece_provider_requests.clone(),
+        );
+        registry.register(
+            "piece_provider_average_latency_seconds",
+            "Average latency of successful piece requests to recently requested DSN piece providers",
+            metrics.piece_provider_average_latency.clone(),
+        );
+
+        metrics
+    }
+
+    /// Subscribe to events of single disk plot and record corresponding metrics
+    pub(super) fn observe(&self, single_disk_plot: &SingleDiskPlot) {
+        let labels = farm_labels(single_disk_plot.id());
+
+        let plotted_sectors_count = single_disk_plot.plotted_sectors_count();
+        self.sectors_plotted
+            .get_or_create(&labels)
+            .set(plotted_sectors_count as i64);
+        self.sectors_remaining.get_or_create(&labels).set(
+            single_disk_plot
+                .total_sectors_count()
+                .saturating_sub(plotted_sectors_count) as i64,
+        );
+
+        single_disk_plot
+            .on_sector_plotting_finished(Arc::new({
+                let metrics = self.clone();
+                let labels = labels.clone();
+
+                move |sector_plotting_details| {
+                    metrics
+                        .sector_plotting_time
+                        .get_or_create(&labels)
+                        .observe(sector_plotting_details.plotting_duration.as_secs_f64());
+
+                    if !sector_plotting_details.replotting {
+                        metrics.sectors_plotted.get_or_create(&labels).inc();
+                        metrics.sectors_remaining.get_or_create(&labels).dec();
+                    }
+                }
+            }))
+            .detach();
+
+        single_disk_plot
+            .on_solution(Arc::new({
+                let metrics = self.clone();
+                let labels = labels.clone();
+
+                move |solution_response| {
+                    metrics
+                        .solutions_found
+                        .get_or_create(&labels)
+                        .inc_by(solution_response.solutions.len() as u64);
+                }
+            }))
+            .detach();
+
+        single_disk_plot
+            .on_slot_farmed(Arc::new({
+                let metrics = self.clone();
+                let labels = labels.clone();
+
+                move |slot_farming_details| {
+                    metrics
+                        .auditing_time
+                        .get_or_create(&labels)
+                        .observe(slot_farming_details.audit_duration.as_secs_f64());
+                    metrics
+                        .solutions_submitted
+                        .get_or_create(&labels)
+                        .inc_by(slot_farming_details.solution_count as u64);
+                }
+            }))
+            .detach();
+
+        single_disk_plot
+            .on_slot_skipped(Arc::new({
+                let metrics = self.clone();
+
+                move |_slot| {
+                    metrics.skipped_slots.get_or_create(&labels).inc();
+                }
+            }))
+            .detach();
+    }
+
+    /// Record reward hash signed by reward signer with provided public key
+    pub(super) fn observe_reward_signature(&self, public_key: &PublicKey) {
+        self.reward_signatures
+            .get_or_create(&vec![("public_key".to_string(), hex::encode(public_key))])
+            .inc();
+    }
+
+    /// Record statistics of piece requests to DSN piece providers
+    pub(super) fn observe_piece_provider<PV>(&self, piece_provider: &PieceProvider<PV>)
+    where
+        PV: PieceValidator,
+    {
+        let provider_stats = piece_provider.provider_stats();
+
+        let mut successful_requests = 0;
+        let mut failed_requests = 0;
+        let mut cancelled_requests = 0;
+        let mut latencies = Vec::new();
+        for (_provider_id, provider_stats) in &provider_stats {
+            successful_requests += provider_stats.successful_requests;
+            failed_requests += provider_stats.failed_requests;
+            cancelled_requests += provider_stats.cancelled_requests;
+            latencies.extend(provider_stats.average_latency);
+        }
+
+        self.piece_providers.set(provider_stats.len() as i64);
+        for (result, requests) in [
+            ("success", successful_requests),
+            ("failure", failed_requests),
+            ("cancelled", cancelled_requests),
+        ] {
+            self.piece_provider_requests
+                .get_or_create(&vec![("result".to_string(), result.to_string())])
+                .set(requests as i64);
+        }
+        if !latencies.is_empty() {
+            self.piece_provider_average_latency.set(
+                latencies
+                    .iter()
+                    .map(|latency| latency.as_secs_f64())
+                    .sum::<f64>()
+                    / latencies.len() as f64,
+            );
+        }
+    }
+}
+
+/// Piece getter wrapper that records piece download and reconstruction metrics
+pub(super) struct MetricsPieceGetter<PG> {
+    inner: PG,
+    metrics: FarmerMetrics,
+}
+
+impl<PG> MetricsPieceGetter<PG> {
+    pub(super) fn new(inner: PG, metrics: FarmerMetrics) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+#[async_trait]
+impl<PG> PieceGetter for MetricsPieceGetter<PG>
+where
+    PG: PieceGetter + Send + Sync,
+{
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+        retry_policy: PieceGetterRetryPolicy,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
+        let result = self.inner.get_piece(piece_index, retry_policy).await;
+
+        if !matches!(result, Ok(Some(_))) {
+            self.metrics.piece_download_failures.inc();
+        }
+
+        result
+    }
+
+    fn on_piece_recovery(&self, piece_index: PieceIndex, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        self.metrics
+            .piece_reconstructions
+            .get_or_create(&vec![("result".to_string(), result.to_string())])
+            .inc();
+
+        self.inner.on_piece_recovery(piece_index, success);
+    }
+}
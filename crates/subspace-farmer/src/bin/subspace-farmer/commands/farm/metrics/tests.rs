@@ -0,0 +1,56 @@
+use crate::commands::farm::metrics::{FarmerMetrics, MetricsPieceGetter};
+use async_trait::async_trait;
+use prometheus_client::encoding::text::encode;
+use prometheus_client::registry::Registry;
+use std::error::Error;
+use subspace_core_primitives::{Piece, PieceIndex};
+use subspace_farmer_components::plotting::{PieceGetter, PieceGetterRetryPolicy};
+
+/// Piece getter that only has the very first piece
+struct TestPieceGetter;
+
+#[async_trait]
+impl PieceGetter for TestPieceGetter {
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+        _retry_policy: PieceGetterRetryPolicy,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
+        Ok((piece_index == PieceIndex::ZERO).then(Piece::default))
+    }
+}
+
+fn encode_metrics(registry: &Registry) -> String {
+    let mut encoded = String::new();
+    encode(&mut encoded, registry).unwrap();
+    encoded
+}
+
+#[tokio::test]
+async fn piece_getter_records_failures_and_reconstructions() {
+    let mut registry = Registry::default();
+    let farmer_metrics = FarmerMetrics::new(&mut registry);
+    let piece_getter = MetricsPieceGetter::new(TestPieceGetter, farmer_metrics);
+
+    assert!(piece_getter
+        .get_piece(PieceIndex::ZERO, PieceGetterRetryPolicy::default())
+        .await
+        .unwrap()
+        .is_some());
+    assert!(encode_metrics(&registry).contains("subspace_farmer_piece_download_failures_total 0"));
+
+    assert!(piece_getter
+        .get_piece(PieceIndex::ONE, PieceGetterRetryPolicy::default())
+        .await
+        .unwrap()
+        .is_none());
+    assert!(encode_metrics(&registry).contains("subspace_farmer_piece_download_failures_total 1"));
+
+    piece_getter.on_piece_recovery(PieceIndex::ONE, true);
+    piece_getter.on_piece_recovery(PieceIndex::ONE, true);
+    piece_getter.on_piece_recovery(PieceIndex::ONE, false);
+
+    let encoded = encode_metrics(&registry);
+    assert!(encoded.contains("subspace_farmer_piece_reconstructions_total{result=\"success\"} 2"));
+    assert!(encoded.contains("subspace_farmer_piece_reconstructions_total{result=\"failure\"} 1"));
+}
@@ -0,0 +1,437 @@
+#[cfg(test)]
+mod tests;
+
+use crate::commands::shared::derive_libp2p_keypair;
+use crate::commands::shared::objects::{create_object_server, fill_object_mappings};
+use crate::utils::shutdown_signal;
+use crate::GatewayArgs;
+use actix_web::http::header::{
+    self, ByteRangeSpec, ContentRange, ContentRangeSpec, EntityTag, Header, IfNoneMatch, Range,
+};
+use actix_web::http::StatusCode;
+use actix_web::web::{self, Bytes, Data, Path};
+use actix_web::{get, App, HttpRequest, HttpResponse, HttpServer};
+use anyhow::anyhow;
+use futures::{stream, FutureExt, Stream, StreamExt};
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::error::Error;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::{Blake2b256Hash, SegmentIndex};
+use subspace_farmer::jsonrpsee::core::error::Error as ObjectError;
+use subspace_farmer::utils::node_piece_getter::NodePieceGetter;
+use subspace_farmer::utils::piece_validator::SegmentCommitmentPieceValidator;
+use subspace_farmer::utils::run_future_in_dedicated_thread;
+use subspace_farmer::ws_rpc_server::RpcServerImpl;
+use subspace_farmer::{Identity, NodeClient, NodeRpcClient, ObjectMappings};
+use subspace_networking::libp2p::multiaddr::Protocol;
+use subspace_networking::utils::piece_provider::PieceProvider;
+use subspace_networking::{
+    create, peer_id, Config, MemoryProviderStorage, NetworkingParametersManager, PeerInfoProvider,
+};
+use tracing::{error, info};
+
+const RECORDS_ROOTS_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(1_000_000).expect("Not zero; qed");
+/// File in gateway directory with index of the first segment object mappings were collected from
+const OBJECT_MAPPINGS_START_FILE: &str = "object-mappings-start";
+
+/// Index of the first segment object mappings were collected from.
+///
+/// Node only sends object mappings of newly archived segments and there is no way to retrieve
+/// mappings of segments archived earlier, so only objects archived since gateway was started for
+/// the first time (and while it was running) can be found.
+#[derive(Debug, Copy, Clone)]
+struct ObjectMappingsStart(SegmentIndex);
+
+impl ObjectMappingsStart {
+    /// Read from gateway directory, `next_segment_index` is stored and returned if gateway is
+    /// started for the first time
+    fn read_or_store(
+        directory: &FsPath,
+        next_segment_index: SegmentIndex,
+    ) -> Result<Self, anyhow::Error> {
+        let file = directory.join(OBJECT_MAPPINGS_START_FILE);
+
+        if file.exists() {
+            let segment_index = fs::read_to_string(&file)?
+                .trim()
+                .parse::<u64>()
+                .map_err(|error| anyhow!("Invalid {}: {error}", file.display()))?;
+
+            return Ok(Self(SegmentIndex::from(segment_index)));
+        }
+
+        fs::write(&file, next_segment_index.to_string())?;
+
+        Ok(Self(next_segment_index))
+    }
+}
+
+/// Start HTTP gateway that serves archived objects by their hash, retrieving pieces from DSN
+pub(crate) async fn gateway(
+    base_path: PathBuf,
+    gateway_args: GatewayArgs,
+) -> Result<(), anyhow::Error> {
+    let signal = shutdown_signal();
+
+    let GatewayArgs {
+        node_rpc_url,
+        listen_on,
+        mut bootstrap_nodes,
+        dsn_listen_on,
+        object_mappings_size,
+    } = gateway_args;
+
+    info!(url = %node_rpc_url, "Connecting to node RPC");
+    let node_client = NodeRpcClient::new(&node_rpc_url).await?;
+
+    let farmer_app_info = node_client
+        .farmer_app_info()
+        .await
+        .map_err(|error| anyhow!(error))?;
+
+    if bootstrap_nodes.is_empty() {
+        bootstrap_nodes = farmer_app_info.dsn_bootstrap_nodes.clone();
+    }
+
+    // Gateway has its own directory, such that it can run alongside farmer with the same base path
+    let directory = base_path.join("gateway");
+    fs::create_dir_all(&directory)?;
+
+    // Gateway identity is only used for networking, hence it is not encrypted
+    let identity = Identity::open_or_create(&directory, None)?;
+    let keypair = derive_libp2p_keypair(identity.secret_key());
+
+    let (node, mut node_runner) = {
+        let networking_parameters_registry = NetworkingParametersManager::new(
+            &directory.join("known_addresses_db"),
+            bootstrap_nodes,
+        )
+        .map(|manager| manager.boxed())?;
+
+        let default_config = Config::new(
+            hex::encode(farmer_app_info.genesis_hash),
+            keypair.clone(),
+            MemoryProviderStorage::new(peer_id(&keypair)),
+            PeerInfoProvider::new_client(),
+        );
+        let config = Config {
+            listen_on: dsn_listen_on,
+            networking_parameters_registry,
+            ..default_config
+        };
+
+        create(config)?
+    };
+
+    node.on_new_listener(Arc::new({
+        let node = node.clone();
+
+        move |address| {
+            info!(
+                "DSN listening on {}",
+                address.clone().with(Protocol::P2p(node.id().into()))
+            );
+        }
+    }))
+    .detach();
+
+    let kzg = Kzg::new(embedded_kzg_settings());
+    // TODO: Consider introducing and using global in-memory segment header cache (this comment is
+    //  in multiple files)
+    let segment_commitments_cache = Mutex::new(LruCache::new(RECORDS_ROOTS_CACHE_SIZE));
+    let piece_provider = PieceProvider::new(
+        node.clone(),
+        Some(SegmentCommitmentPieceValidator::new(
+            node.clone(),
+            node_client.clone(),
+            kzg,
+            segment_commitments_cache,
+        )),
+    );
+
+    // Mappings of the segment that is being archived now are the first ones to be received
+    let object_mappings_start = ObjectMappingsStart::read_or_store(
+        &directory,
+        farmer_app_info.protocol_info.history_size.segment_index() + SegmentIndex::ONE,
+    )?;
+    info!(
+        first_segment_index = %object_mappings_start.0,
+        "Only objects archived since this segment can be found"
+    );
+
+    let object_mappings = ObjectMappings::open_or_create(
+        &directory.join("object-mappings"),
+        identity.public_key().to_bytes().into(),
+        object_mappings_size.as_u64(),
+    )?;
+
+    let object_mappings_filler = run_future_in_dedicated_thread(
+        Box::pin({
+            let node_client = node_client.clone();
+            let object_mappings = object_mappings.clone();
+
+            fill_object_mappings(node_client, object_mappings)
+        }),
+        "object-mappings-filler".to_string(),
+    )?;
+    let mut object_mappings_filler = Box::pin(object_mappings_filler).fuse();
+
+    let object_server = Data::new(create_object_server(
+        // Gateway doesn't have any plots, all pieces are retrieved from DSN
+        Arc::new(Mutex::new(None)),
+        NodePieceGetter::new(piece_provider),
+        object_mappings,
+    ));
+
+    let http_server = HttpServer::new(move || {
+        App::new()
+            .app_data(object_server.clone())
+            .app_data(Data::new(object_mappings_start))
+            .service(get_object)
+    })
+    .bind(listen_on)?
+    .run();
+    let mut http_server = Box::pin(http_server).fuse();
+
+    info!(address = %listen_on, "Started HTTP gateway");
+
+    let networking_fut = run_future_in_dedicated_thread(
+        Box::pin(async move { node_runner.run().await }),
+        "gateway-networking".to_string(),
+    )?;
+    let mut networking_fut = Box::pin(networking_fut).fuse();
+
+    futures::select!(
+        // Signal future
+        _ = signal.fuse() => {},
+
+        // HTTP server future
+        result = http_server => {
+            result?;
+        },
+
+        // Object mappings filler future
+        _ = object_mappings_filler => {
+            info!("Object mappings filler exited.")
+        },
+
+        // Node runner future
+        _ = networking_fut => {
+            info!("Node runner exited.")
+        },
+    );
+
+    anyhow::Ok(())
+}
+
+/// Retrieve object by its hash (hex-encoded), supports range requests.
+///
+/// Object data is streamed as pieces are retrieved, so response starts as soon as object length is
+/// known.
+#[get("/{object_hash}")]
+async fn get_object(
+    request: HttpRequest,
+    object_hash: Path<String>,
+    object_server: Data<RpcServerImpl>,
+    object_mappings_start: Data<ObjectMappingsStart>,
+) -> HttpResponse {
+    let object_id = match hex::decode(object_hash.as_str())
+        .ok()
+        .and_then(|bytes| Blake2b256Hash::try_from(bytes.as_slice()).ok())
+    {
+        Some(object_id) => object_id,
+        None => {
+            return HttpResponse::BadRequest().body("Object hash must be 32 bytes encoded as hex");
+        }
+    };
+
+    // Object hash is derived from its contents, so it is a perfect entity tag
+    let entity_tag = EntityTag::new_strong(hex::encode(object_id));
+
+    if is_not_modified(&request, &entity_tag) {
+        return HttpResponse::NotModified()
+            .insert_header(header::ETag(entity_tag))
+            .finish();
+    }
+
+    let read_object_result = web::block({
+        let object_server = object_server.clone();
+
+        move || object_server.read_object(object_id)
+    })
+    .await;
+
+    let object_data = match read_object_result {
+        Ok(Ok(Some(object_data))) => object_data,
+        Ok(Ok(None)) => {
+            return HttpResponse::NotFound().body(format!(
+                "Object not found, gateway only knows objects archived since segment {} while it \
+                was running",
+                object_mappings_start.0
+            ));
+        }
+        Ok(Err(error)) => {
+            error!(%error, object_id = %hex::encode(object_id), "Failed to retrieve object");
+            return HttpResponse::InternalServerError().body("Failed to retrieve object");
+        }
+        Err(error) => {
+            error!(%error, object_id = %hex::encode(object_id), "Object retrieval task failed");
+            return HttpResponse::InternalServerError().body("Failed to retrieve object");
+        }
+    };
+
+    let data_length = u64::from(object_data.length());
+    // Pieces are retrieved one chunk at a time as response is sent, retrieval stops if client
+    // disconnects
+    let data = stream::unfold(
+        Some(object_data.into_chunks()),
+        move |maybe_chunks| async move {
+            let mut chunks = maybe_chunks?;
+
+            let (maybe_chunk, chunks) = match web::block(move || (chunks.next(), chunks)).await {
+                Ok(result) => result,
+                Err(error) => {
+                    error!(
+                        %error,
+                        object_id = %hex::encode(object_id),
+                        "Object retrieval task failed"
+                    );
+                    return Some((
+                        Err(ObjectError::Custom(
+                            "Object retrieval task failed".to_string(),
+                        )),
+                        None,
+                    ));
+                }
+            };
+
+            match maybe_chunk? {
+                Ok(chunk) => Some((Ok(Bytes::from(chunk)), Some(chunks))),
+                Err(error) => {
+                    // Headers are sent already, so client will see truncated response
+                    error!(
+                        %error,
+                        object_id = %hex::encode(object_id),
+                        "Failed to retrieve object"
+                    );
+                    Some((Err(error), None))
+                }
+            }
+        },
+    );
+
+    object_response(&request, entity_tag, data_length, data)
+}
+
+/// Whether client already has the object with specified entity tag according to `If-None-Match`
+/// header of the request
+fn is_not_modified(request: &HttpRequest, entity_tag: &EntityTag) -> bool {
+    match IfNoneMatch::parse(request) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(entity_tags)) => entity_tags
+            .iter()
+            .any(|other_entity_tag| other_entity_tag.weak_eq(entity_tag)),
+        Err(_error) => false,
+    }
+}
+
+/// Response with object data of `data_length` bytes, or its part if request contains a satisfiable
+/// `Range` header.
+///
+/// Data is streamed in chunks of arbitrary size, chunks after the end of requested range are not
+/// polled.
+fn object_response<S, E>(
+    request: &HttpRequest,
+    entity_tag: EntityTag,
+    data_length: u64,
+    data: S,
+) -> HttpResponse
+where
+    S: Stream<Item = Result<Bytes, E>> + 'static,
+    E: Into<Box<dyn Error>> + 'static,
+{
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header(header::ETag(entity_tag))
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .content_type("application/octet-stream");
+
+    // Only single range is supported, requests with multiple ranges get the whole object
+    let (start, end) = match Range::parse(request) {
+        Ok(Range::Bytes(byte_range_specs)) if byte_range_specs.len() == 1 => {
+            let byte_range_spec: &ByteRangeSpec = &byte_range_specs[0];
+
+            match byte_range_spec.to_satisfiable_range(data_length) {
+                Some((start, end)) => {
+                    response
+                        .status(StatusCode::PARTIAL_CONTENT)
+                        .insert_header(ContentRange(ContentRangeSpec::Bytes {
+                            range: Some((start, end)),
+                            instance_length: Some(data_length),
+                        }));
+
+                    (start, end)
+                }
+                None => {
+                    return HttpResponse::RangeNotSatisfiable()
+                        .insert_header(ContentRange(ContentRangeSpec::Bytes {
+                            range: None,
+                            instance_length: Some(data_length),
+                        }))
+                        .finish();
+                }
+            }
+        }
+        _ => {
+            if data_length == 0 {
+                return response.finish();
+            }
+
+            (0, data_length - 1)
+        }
+    };
+
+    response
+        .no_chunking(end - start + 1)
+        .streaming(data_range(data, start, end))
+}
+
+/// Part of the data stream within `start..=end` byte range, chunks are trimmed accordingly
+fn data_range<S, E>(data: S, start: u64, end: u64) -> impl Stream<Item = Result<Bytes, E>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+{
+    stream::unfold(
+        (Box::pin(data), 0u64),
+        move |(mut data, chunk_start)| async move {
+            // Range is sent already
+            if chunk_start > end {
+                return None;
+            }
+
+            let chunk = match data.next().await? {
+                Ok(chunk) => chunk,
+                Err(error) => {
+                    return Some((Err(error), (data, u64::MAX)));
+                }
+            };
+            let chunk_length = chunk.len() as u64;
+
+            let chunk = chunk.slice(
+                start.saturating_sub(chunk_start).min(chunk_length) as usize
+                    ..(end + 1).saturating_sub(chunk_start).min(chunk_length) as usize,
+            );
+
+            Some((Ok(chunk), (data, chunk_start + chunk_length)))
+        },
+    )
+    .filter(|chunk_result| {
+        let empty_chunk = matches!(chunk_result, Ok(chunk) if chunk.is_empty());
+        async move { !empty_chunk }
+    })
+}
@@ -0,0 +1,204 @@
+use crate::commands::gateway::{is_not_modified, object_response};
+use actix_web::body::to_bytes;
+use actix_web::http::header::{self, EntityTag};
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use actix_web::web::Bytes;
+use actix_web::HttpResponse;
+use futures::{stream, Stream, StreamExt};
+use std::convert::Infallible;
+use std::io;
+
+/// Size of chunks object data is streamed in, similar to a piece
+const CHUNK_SIZE: usize = 1024;
+
+fn entity_tag() -> EntityTag {
+    EntityTag::new_strong("abcd".to_string())
+}
+
+/// Object data that spans multiple chunks
+fn object_data() -> Bytes {
+    (0..=u8::MAX)
+        .cycle()
+        .take(CHUNK_SIZE * 2 + 1)
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Object data streamed in chunks of `CHUNK_SIZE` bytes
+fn data_stream(data: &Bytes) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    let data = data.clone();
+
+    stream::iter(
+        (0..data.len())
+            .step_by(CHUNK_SIZE)
+            .map(move |offset| Ok(data.slice(offset..data.len().min(offset + CHUNK_SIZE))))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn header_value(response: &HttpResponse, name: header::HeaderName) -> Option<&str> {
+    response
+        .headers()
+        .get(name)
+        .map(|value| value.to_str().unwrap())
+}
+
+#[test]
+fn not_modified_only_with_matching_entity_tag() {
+    let entity_tag = entity_tag();
+
+    let request = TestRequest::default().to_http_request();
+    assert!(!is_not_modified(&request, &entity_tag));
+
+    for if_none_match in ["\"abcd\"", "W/\"abcd\"", "\"other\", \"abcd\"", "*"] {
+        let request = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, if_none_match))
+            .to_http_request();
+        assert!(is_not_modified(&request, &entity_tag), "{if_none_match}");
+    }
+
+    let request = TestRequest::default()
+        .insert_header((header::IF_NONE_MATCH, "\"other\""))
+        .to_http_request();
+    assert!(!is_not_modified(&request, &entity_tag));
+}
+
+#[tokio::test]
+async fn whole_object_without_range() {
+    let data = object_data();
+
+    let request = TestRequest::default().to_http_request();
+    let response = object_response(
+        &request,
+        entity_tag(),
+        data.len() as u64,
+        data_stream(&data),
+    );
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_value(&response, header::ETAG), Some("\"abcd\""));
+    assert_eq!(
+        header_value(&response, header::ACCEPT_RANGES),
+        Some("bytes")
+    );
+    assert_eq!(header_value(&response, header::CONTENT_RANGE), None);
+    assert_eq!(to_bytes(response.into_body()).await.unwrap(), data);
+}
+
+#[tokio::test]
+async fn single_range_of_object() {
+    let data = object_data();
+    let data_length = data.len();
+
+    for (range, start, end) in [
+        ("bytes=10-19", 10, 19),
+        ("bytes=-5", data_length - 5, data_length - 1),
+        // Range within a chunk other than the first one
+        ("bytes=1030-1039", 1030, 1039),
+        // Range across chunk boundary
+        ("bytes=1000-2000", 1000, 2000),
+        // Range that spans multiple chunks and goes beyond the end of the object
+        ("bytes=100-", 100, data_length - 1),
+        ("bytes=100-1000000000", 100, data_length - 1),
+    ] {
+        let request = TestRequest::default()
+            .insert_header((header::RANGE, range))
+            .to_http_request();
+        let response = object_response(
+            &request,
+            entity_tag(),
+            data.len() as u64,
+            data_stream(&data),
+        );
+
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT, "{range}");
+        assert_eq!(
+            header_value(&response, header::CONTENT_RANGE),
+            Some(format!("bytes {start}-{end}/{data_length}").as_str()),
+            "{range}"
+        );
+        assert_eq!(
+            to_bytes(response.into_body()).await.unwrap(),
+            data.slice(start..=end),
+            "{range}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn unsatisfiable_range_of_object() {
+    let data = object_data();
+    let data_length = data.len();
+
+    let request = TestRequest::default()
+        .insert_header((header::RANGE, format!("bytes={data_length}-")))
+        .to_http_request();
+    let response = object_response(
+        &request,
+        entity_tag(),
+        data_length as u64,
+        data_stream(&data),
+    );
+
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(
+        header_value(&response, header::CONTENT_RANGE),
+        Some(format!("bytes */{data_length}").as_str())
+    );
+    assert!(to_bytes(response.into_body()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn whole_object_with_multiple_ranges() {
+    let data = object_data();
+
+    let request = TestRequest::default()
+        .insert_header((header::RANGE, "bytes=0-1,5-6"))
+        .to_http_request();
+    let response = object_response(
+        &request,
+        entity_tag(),
+        data.len() as u64,
+        data_stream(&data),
+    );
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(to_bytes(response.into_body()).await.unwrap(), data);
+}
+
+#[tokio::test]
+async fn chunks_after_range_are_not_polled() {
+    let data = object_data();
+    let data_length = data.len();
+
+    let request = TestRequest::default()
+        .insert_header((header::RANGE, "bytes=10-1030"))
+        .to_http_request();
+    // Only the first two chunks are needed for the range
+    let data_stream = data_stream(&data.slice(..CHUNK_SIZE * 2)).chain(stream::poll_fn(|_| {
+        panic!("Chunk after requested range must not be polled");
+    }));
+    let response = object_response(&request, entity_tag(), data_length as u64, data_stream);
+
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        to_bytes(response.into_body()).await.unwrap(),
+        data.slice(10..=1030)
+    );
+}
+
+#[tokio::test]
+async fn object_retrieval_error_fails_response() {
+    let data = object_data();
+
+    let request = TestRequest::default().to_http_request();
+    let data_stream = stream::iter([
+        Ok(data.slice(..CHUNK_SIZE)),
+        Err(io::Error::new(io::ErrorKind::Other, "Piece not found")),
+    ]);
+    let response = object_response(&request, entity_tag(), data.len() as u64, data_stream);
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(to_bytes(response.into_body()).await.is_err());
+}
@@ -0,0 +1,102 @@
+use crate::commands::shared::read_identity_passphrase;
+use crate::{DiskFarm, IdentityArgs, IdentityCommand};
+use anyhow::{anyhow, Context};
+use bip39::{Language, Mnemonic};
+use std::path::PathBuf;
+use subspace_farmer::Identity;
+use zeroize::Zeroizing;
+
+pub(crate) fn identity(
+    disk_farms: Vec<DiskFarm>,
+    identity_command: IdentityCommand,
+) -> anyhow::Result<()> {
+    match identity_command {
+        IdentityCommand::Export { identity } => export(disk_farms, identity),
+        IdentityCommand::Import { identity, force } => import(disk_farms, identity, force),
+    }
+}
+
+/// Identity files of disk farms, a single file if shared identity file is used
+fn identity_files(disk_farms: Vec<DiskFarm>, identity_args: &IdentityArgs) -> Vec<PathBuf> {
+    match &identity_args.identity_file {
+        Some(identity_file) => vec![identity_file.clone()],
+        None => disk_farms
+            .into_iter()
+            .map(|disk_farm| Identity::file_path(disk_farm.directory))
+            .collect(),
+    }
+}
+
+fn export(disk_farms: Vec<DiskFarm>, identity_args: IdentityArgs) -> anyhow::Result<()> {
+    let identity_files = identity_files(disk_farms, &identity_args);
+    // Plaintext identity is encrypted when opened with passphrase, so passphrase is confirmed then
+    let mut all_encrypted = true;
+    for identity_file in &identity_files {
+        all_encrypted &= Identity::is_file_encrypted(identity_file)?;
+    }
+    let passphrase = read_identity_passphrase(&identity_args, !all_encrypted)?;
+
+    for (index, identity_file) in identity_files.into_iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+
+        let identity =
+            Identity::open_file(&identity_file, passphrase.as_deref().map(String::as_str))
+                .with_context(|| format!("Failed to open identity at {}", identity_file.display()))?
+                .ok_or_else(|| anyhow!("Identity not found at {}", identity_file.display()))?;
+        let mnemonic = Mnemonic::from_entropy(identity.entropy(), Language::English)
+            .map_err(|error| anyhow!("Failed to create mnemonic from identity: {error}"))?;
+
+        println!("Identity {}:", identity_file.display());
+        println!(
+            "  Public key: 0x{}",
+            hex::encode(identity.public_key().to_bytes())
+        );
+        println!("  Mnemonic: {}", mnemonic.phrase());
+    }
+
+    Ok(())
+}
+
+fn import(
+    disk_farms: Vec<DiskFarm>,
+    identity_args: IdentityArgs,
+    force: bool,
+) -> anyhow::Result<()> {
+    let identity_files = identity_files(disk_farms, &identity_args);
+
+    if !force {
+        if let Some(identity_file) = identity_files
+            .iter()
+            .find(|identity_file| identity_file.exists())
+        {
+            return Err(anyhow!(
+                "Identity already exists at {}, use `--force` to replace it",
+                identity_file.display()
+            ));
+        }
+    }
+
+    let phrase = Zeroizing::new(rpassword::prompt_password("Mnemonic: ")?);
+    let mnemonic = Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .map_err(|error| anyhow!("Invalid mnemonic: {error}"))?;
+    let passphrase = read_identity_passphrase(&identity_args, true)?;
+
+    for identity_file in identity_files {
+        let identity = Identity::from_entropy_file(
+            &identity_file,
+            mnemonic.entropy().to_vec(),
+            passphrase.as_deref().map(String::as_str),
+        )
+        .with_context(|| format!("Failed to write identity to {}", identity_file.display()))?;
+
+        println!(
+            "Imported identity with public key 0x{} to {}",
+            hex::encode(identity.public_key().to_bytes()),
+            identity_file.display()
+        );
+    }
+
+    Ok(())
+}
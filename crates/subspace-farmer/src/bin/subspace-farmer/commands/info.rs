@@ -1,7 +1,122 @@
 use crate::commands::shared::print_disk_farm_info;
 use crate::DiskFarm;
+use serde::Serialize;
+use std::ops::Range;
+use std::path::PathBuf;
+use subspace_core_primitives::SectorIndex;
+use subspace_farmer::single_disk_plot::{SingleDiskPlot, SingleDiskPlotId, SingleDiskPlotSummary};
+use subspace_farmer_components::sector::sector_size;
+
+/// Machine-readable information about single disk farm
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+enum DiskFarmInfo {
+    #[serde(rename_all = "camelCase")]
+    Found {
+        directory: PathBuf,
+        id: SingleDiskPlotId,
+        genesis_hash: String,
+        public_key: String,
+        /// Sector indexes plot will have once fully plotted
+        sector_range: Range<SectorIndex>,
+        pieces_in_sector: u16,
+        allocated_space: u64,
+        plotted_sector_count: usize,
+        plotted_sectors: Vec<PlottedSectorInfo>,
+        /// Error that prevented reading of plotted sectors, in which case they are empty
+        plotted_sectors_error: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    NotFound { directory: PathBuf },
+    #[serde(rename_all = "camelCase")]
+    InUse {
+        directory: PathBuf,
+        pid: Option<u32>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Error { directory: PathBuf, error: String },
+}
+
+/// Machine-readable information about plotted sector
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum PlottedSectorInfo {
+    #[serde(rename_all = "camelCase")]
+    Plotted {
+        sector_index: SectorIndex,
+        history_size: u64,
+        expires_at: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Error { error: String },
+}
+
+impl DiskFarmInfo {
+    fn collect(directory: PathBuf) -> Self {
+        match SingleDiskPlot::collect_summary(directory) {
+            SingleDiskPlotSummary::Found { info, directory } => {
+                // Plot info is still useful even if sectors metadata can't be read
+                let (sectors_metadata, plotted_sectors_error) =
+                    match SingleDiskPlot::read_sectors_metadata(&directory) {
+                        Ok(sectors_metadata) => (sectors_metadata, None),
+                        Err(error) => (Vec::new(), Some(error.to_string())),
+                    };
+
+                let total_sector_count =
+                    info.allocated_space() / sector_size(info.pieces_in_sector()) as u64;
+
+                Self::Found {
+                    directory,
+                    id: *info.id(),
+                    genesis_hash: format!("0x{}", hex::encode(info.genesis_hash())),
+                    public_key: format!("0x{}", hex::encode(info.public_key())),
+                    sector_range: info.first_sector_index()
+                        ..info.first_sector_index() + total_sector_count,
+                    pieces_in_sector: info.pieces_in_sector(),
+                    allocated_space: info.allocated_space(),
+                    plotted_sector_count: sectors_metadata.len(),
+                    plotted_sectors: sectors_metadata
+                        .into_iter()
+                        .map(|maybe_sector_metadata| match maybe_sector_metadata {
+                            Ok(sector_metadata) => PlottedSectorInfo::Plotted {
+                                sector_index: sector_metadata.sector_index,
+                                history_size: sector_metadata.history_size.get(),
+                                expires_at: u64::from(sector_metadata.expires_at),
+                            },
+                            Err(error) => PlottedSectorInfo::Error {
+                                error: format!("Failed to decode sector metadata: {error}"),
+                            },
+                        })
+                        .collect(),
+                    plotted_sectors_error,
+                }
+            }
+            SingleDiskPlotSummary::NotFound { directory } => Self::NotFound { directory },
+            SingleDiskPlotSummary::InUse { directory, pid } => Self::InUse { directory, pid },
+            SingleDiskPlotSummary::Error { directory, error } => Self::Error {
+                directory,
+                error: error.to_string(),
+            },
+        }
+    }
+}
+
+pub(crate) fn info(disk_farms: Vec<DiskFarm>, json: bool) {
+    if json {
+        let disk_farms_info = disk_farms
+            .into_iter()
+            .map(|disk_farm| DiskFarmInfo::collect(disk_farm.directory))
+            .collect::<Vec<_>>();
+
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&disk_farms_info)
+                .expect("Info serialization never fails; qed")
+        );
+
+        return;
+    }
 
-pub(crate) fn info(disk_farms: Vec<DiskFarm>) {
     for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
         if disk_farm_index > 0 {
             println!();
@@ -0,0 +1,61 @@
+use crate::DiskFarm;
+use subspace_farmer::single_disk_plot::migration::PlotMigrationStatus;
+use subspace_farmer::single_disk_plot::SingleDiskPlot;
+use tracing::{error, info, info_span};
+
+/// What to do with migration of farms
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum MigrateAction {
+    /// Schedule migration or keep existing one if it has the same target
+    Schedule { pieces_in_sector: Option<u16> },
+    /// Only print progress of migration
+    Status,
+    /// Abort migration, removing everything migrated so far
+    Abort,
+}
+
+pub(crate) fn migrate(disk_farms: Vec<DiskFarm>, action: MigrateAction) -> anyhow::Result<()> {
+    for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
+        let span = info_span!("", %disk_farm_index);
+        let _span_guard = span.enter();
+
+        let DiskFarm { directory, .. } = disk_farm;
+
+        let result = match action {
+            MigrateAction::Schedule { pieces_in_sector } => {
+                SingleDiskPlot::schedule_migration(&directory, pieces_in_sector).map(Some)
+            }
+            MigrateAction::Status => SingleDiskPlot::migration_status(&directory),
+            MigrateAction::Abort => SingleDiskPlot::abort_migration(&directory).map(|()| None),
+        };
+
+        match result {
+            Ok(Some(PlotMigrationStatus {
+                target_pieces_in_sector,
+                migrated_sector_count,
+                target_sector_count,
+            })) => {
+                info!(
+                    directory = %directory.display(),
+                    %target_pieces_in_sector,
+                    "Migrated {migrated_sector_count}/{target_sector_count} sectors"
+                );
+            }
+            Ok(None) => {
+                info!(directory = %directory.display(), "No migration in progress");
+            }
+            Err(error) => {
+                error!(%error, directory = %directory.display(), "Failed to migrate farm");
+            }
+        }
+    }
+
+    if matches!(action, MigrateAction::Schedule { .. }) {
+        info!(
+            "Sectors are migrated one at a time by `farm` command, sectors that were not migrated \
+            yet are farmed in the meantime"
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use crate::commands::shared::derive_libp2p_keypair;
+use crate::utils::shutdown_signal;
+use crate::PlottingServerArgs;
+use anyhow::{anyhow, Context};
+use futures::FutureExt;
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::Record;
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer::single_disk_plot::remote_plotting::run_plotting_server;
+use subspace_farmer::utils::node_piece_getter::NodePieceGetter;
+use subspace_farmer::utils::piece_validator::SegmentCommitmentPieceValidator;
+use subspace_farmer::utils::run_future_in_dedicated_thread;
+use subspace_farmer::utils::shared_secret::SharedSecret;
+use subspace_farmer::{Identity, NodeClient, NodeRpcClient};
+use subspace_farmer_components::piece_caching::PieceMemoryCache;
+use subspace_networking::libp2p::multiaddr::Protocol;
+use subspace_networking::utils::piece_provider::PieceProvider;
+use subspace_networking::{
+    create, peer_id, Config, MemoryProviderStorage, NetworkingParametersManager, PeerInfoProvider,
+};
+use subspace_proof_of_space::Table;
+use tracing::info;
+
+const RECORDS_ROOTS_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(1_000_000).expect("Not zero; qed");
+
+/// Start plotting server that plots sectors on behalf of remote farmers, retrieving pieces from DSN
+pub(crate) async fn plotting_server<PosTable>(
+    base_path: PathBuf,
+    plotting_server_args: PlottingServerArgs,
+) -> Result<(), anyhow::Error>
+where
+    PosTable: Table,
+{
+    let signal = shutdown_signal();
+
+    let PlottingServerArgs {
+        node_rpc_url,
+        listen_on,
+        secret_file,
+        sector_plotting_concurrency,
+        mut bootstrap_nodes,
+        dsn_listen_on,
+    } = plotting_server_args;
+
+    info!(url = %node_rpc_url, "Connecting to node RPC");
+    let node_client = NodeRpcClient::new(&node_rpc_url).await?;
+
+    let farmer_app_info = node_client
+        .farmer_app_info()
+        .await
+        .map_err(|error| anyhow!(error))?;
+
+    if bootstrap_nodes.is_empty() {
+        bootstrap_nodes = farmer_app_info.dsn_bootstrap_nodes.clone();
+    }
+
+    // Plotting server has its own directory, such that it can run alongside farmer with the same
+    // base path
+    let directory = base_path.join("plotting-server");
+    fs::create_dir_all(&directory)?;
+
+    // Plotting server identity is only used for networking, hence it is not encrypted
+    let identity = Identity::open_or_create(&directory, None)?;
+
+    let secret_file = secret_file.unwrap_or_else(|| directory.join("secret"));
+    let secret = SharedSecret::read_or_generate(&secret_file).with_context(|| {
+        format!(
+            "Failed to read plotting server secret from {}",
+            secret_file.display()
+        )
+    })?;
+    info!(secret_file = %secret_file.display(), "Using plotting server secret");
+    let keypair = derive_libp2p_keypair(identity.secret_key());
+
+    let (node, mut node_runner) = {
+        let networking_parameters_registry = NetworkingParametersManager::new(
+            &directory.join("known_addresses_db"),
+            bootstrap_nodes,
+        )
+        .map(|manager| manager.boxed())?;
+
+        let default_config = Config::new(
+            hex::encode(farmer_app_info.genesis_hash),
+            keypair.clone(),
+            MemoryProviderStorage::new(peer_id(&keypair)),
+            PeerInfoProvider::new_client(),
+        );
+        let config = Config {
+            listen_on: dsn_listen_on,
+            networking_parameters_registry,
+            ..default_config
+        };
+
+        create(config)?
+    };
+
+    node.on_new_listener(Arc::new({
+        let node = node.clone();
+
+        move |address| {
+            info!(
+                "DSN listening on {}",
+                address.clone().with(Protocol::P2p(node.id().into()))
+            );
+        }
+    }))
+    .detach();
+
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let erasure_coding = ErasureCoding::new(
+        NonZeroUsize::new(Record::NUM_S_BUCKETS.next_power_of_two().ilog2() as usize).unwrap(),
+    )
+    .map_err(|error| anyhow!(error))?;
+    // TODO: Consider introducing and using global in-memory segment header cache (this comment is
+    //  in multiple files)
+    let segment_commitments_cache = Mutex::new(LruCache::new(RECORDS_ROOTS_CACHE_SIZE));
+    let piece_provider = PieceProvider::new(
+        node.clone(),
+        Some(SegmentCommitmentPieceValidator::new(
+            node.clone(),
+            node_client.clone(),
+            kzg.clone(),
+            segment_commitments_cache,
+        )),
+    );
+
+    let plotting_server = run_plotting_server::<_, _, PosTable>(
+        listen_on,
+        secret,
+        node_client,
+        NodePieceGetter::new(piece_provider),
+        kzg,
+        erasure_coding,
+        PieceMemoryCache::default(),
+        sector_plotting_concurrency,
+    );
+    let mut plotting_server = Box::pin(plotting_server).fuse();
+
+    let networking_fut = run_future_in_dedicated_thread(
+        Box::pin(async move { node_runner.run().await }),
+        "plotting-server-networking".to_string(),
+    )?;
+    let mut networking_fut = Box::pin(networking_fut).fuse();
+
+    futures::select!(
+        // Signal future
+        _ = signal.fuse() => {},
+
+        // Plotting server future
+        result = plotting_server => {
+            result?;
+        },
+
+        // Node runner future
+        _ = networking_fut => {
+            info!("Node runner exited.")
+        },
+    );
+
+    anyhow::Ok(())
+}
@@ -0,0 +1,111 @@
+use crate::DiskFarm;
+use anyhow::anyhow;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::{Record, SegmentIndex};
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer::single_disk_plot::SingleDiskPlot;
+use subspace_farmer::{NodeClient, NodeRpcClient};
+use subspace_proof_of_space::Table;
+use subspace_rpc_primitives::MAX_SEGMENT_INDEXES_PER_REQUEST;
+use tracing::{error, info, info_span, warn};
+
+pub(crate) async fn scrub<PosTable>(
+    disk_farms: Vec<DiskFarm>,
+    node_rpc_url: String,
+    mark_for_replotting: bool,
+) -> anyhow::Result<()>
+where
+    PosTable: Table,
+{
+    info!(url = %node_rpc_url, "Connecting to node RPC");
+    let node_client = NodeRpcClient::new(&node_rpc_url).await?;
+
+    let farmer_protocol_info = node_client
+        .farmer_app_info()
+        .await
+        .map_err(|error| anyhow!(error))?
+        .protocol_info;
+
+    // Witnesses of records are checked against segment commitments, so all of them are needed
+    let segment_indexes = (SegmentIndex::ZERO..=farmer_protocol_info.history_size.segment_index())
+        .collect::<Vec<_>>();
+    let mut segment_commitments = HashMap::with_capacity(segment_indexes.len());
+    // Node rejects requests for more segments than this at once
+    for segment_indexes in segment_indexes.chunks(MAX_SEGMENT_INDEXES_PER_REQUEST) {
+        let batch = node_client
+            .segment_commitments(segment_indexes.to_vec())
+            .await
+            .map_err(|error| anyhow!(error))?;
+
+        for (&segment_index, maybe_segment_commitment) in segment_indexes.iter().zip(batch) {
+            let segment_commitment = maybe_segment_commitment.ok_or_else(|| {
+                anyhow!("Node doesn't have segment commitment for segment {segment_index}")
+            })?;
+            segment_commitments.insert(segment_index, segment_commitment);
+        }
+    }
+
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let erasure_coding = ErasureCoding::new(
+        NonZeroUsize::new(Record::NUM_S_BUCKETS.next_power_of_two().ilog2() as usize).unwrap(),
+    )
+    .map_err(|error| anyhow!(error))?;
+
+    let mut total_corrupted_sectors = 0;
+
+    for (disk_farm_index, disk_farm) in disk_farms.into_iter().enumerate() {
+        let span = info_span!("", %disk_farm_index);
+        let _span_guard = span.enter();
+
+        let DiskFarm { directory, .. } = disk_farm;
+
+        info!(directory = %directory.display(), "Scrubbing farm");
+
+        let corrupted_sectors = match SingleDiskPlot::scrub::<PosTable>(
+            &directory,
+            &farmer_protocol_info,
+            &segment_commitments,
+            &kzg,
+            &erasure_coding,
+            mark_for_replotting,
+        ) {
+            Ok(corrupted_sectors) => corrupted_sectors,
+            Err(error) => {
+                error!(%error, directory = %directory.display(), "Failed to scrub farm");
+                continue;
+            }
+        };
+
+        for corrupted_sector in &corrupted_sectors {
+            warn!(
+                sector_offset = %corrupted_sector.sector_offset,
+                sector_index = %corrupted_sector.sector_index,
+                marked_for_replotting = %corrupted_sector.marked_for_replotting,
+                "Corrupted sector found: {}",
+                corrupted_sector.corruption
+            );
+        }
+
+        if corrupted_sectors.is_empty() {
+            info!("No corrupted sectors found");
+        } else {
+            info!(
+                corrupted_sectors = %corrupted_sectors.len(),
+                "Scrubbing finished with corrupted sectors"
+            );
+        }
+
+        total_corrupted_sectors += corrupted_sectors.len();
+    }
+
+    if total_corrupted_sectors > 0 && !mark_for_replotting {
+        info!(
+            "Corrupted sectors can be replotted by running scrub with `--replot` and starting \
+            farmer afterwards"
+        );
+    }
+
+    Ok(())
+}
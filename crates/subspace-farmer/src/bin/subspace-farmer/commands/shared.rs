@@ -1,5 +1,12 @@
+pub(crate) mod objects;
+
+use crate::IdentityArgs;
+use anyhow::{anyhow, Context};
+use std::fs;
 use std::path::PathBuf;
 use subspace_farmer::single_disk_plot::{SingleDiskPlot, SingleDiskPlotSummary};
+use subspace_networking::libp2p::identity::{ed25519, Keypair};
+use zeroize::Zeroizing;
 
 pub(crate) fn print_disk_farm_info(directory: PathBuf, disk_farm_index: usize) {
     println!("Single disk farm {disk_farm_index}:");
@@ -20,9 +27,80 @@ pub(crate) fn print_disk_farm_info(directory: PathBuf, disk_farm_index: usize) {
             println!("  Plot directory: {}", directory.display());
             println!("  No farm found here yet");
         }
+        SingleDiskPlotSummary::InUse { directory, pid } => {
+            println!("  Directory: {}", directory.display());
+            match pid {
+                Some(pid) => {
+                    println!("  Farm is in use by process with PID {pid}");
+                }
+                None => {
+                    println!("  Farm is in use by another process");
+                }
+            }
+        }
         SingleDiskPlotSummary::Error { directory, error } => {
             println!("  Directory: {}", directory.display());
             println!("  Failed to open farm info: {error}");
         }
     }
 }
+
+pub(crate) fn derive_libp2p_keypair(schnorrkel_sk: &schnorrkel::SecretKey) -> Keypair {
+    let mut secret_bytes = Zeroizing::new(schnorrkel_sk.to_ed25519_bytes());
+
+    let keypair = ed25519::Keypair::from(
+        ed25519::SecretKey::try_from_bytes(&mut secret_bytes.as_mut()[..32])
+            .expect("Secret key is exactly 32 bytes in size; qed"),
+    );
+
+    Keypair::from(keypair)
+}
+
+/// Read identity passphrase from file or prompt for it, returns `None` if identity is not supposed
+/// to be encrypted.
+///
+/// `confirm` makes user enter passphrase twice when prompting, which should be used when new
+/// identity is about to be encrypted with it.
+pub(crate) fn read_identity_passphrase(
+    identity_args: &IdentityArgs,
+    confirm: bool,
+) -> anyhow::Result<Option<Zeroizing<String>>> {
+    if !identity_args.encrypted_identity {
+        return Ok(None);
+    }
+
+    let passphrase = match &identity_args.identity_passphrase_file {
+        Some(identity_passphrase_file) => {
+            let contents = Zeroizing::new(
+                fs::read_to_string(identity_passphrase_file).with_context(|| {
+                    format!(
+                        "Failed to read identity passphrase from {}",
+                        identity_passphrase_file.display()
+                    )
+                })?,
+            );
+
+            Zeroizing::new(contents.lines().next().unwrap_or_default().to_string())
+        }
+        None => {
+            let passphrase = Zeroizing::new(rpassword::prompt_password("Identity passphrase: ")?);
+
+            if confirm {
+                let confirmation =
+                    Zeroizing::new(rpassword::prompt_password("Repeat identity passphrase: ")?);
+
+                if *passphrase != *confirmation {
+                    return Err(anyhow!("Identity passphrases do not match"));
+                }
+            }
+
+            passphrase
+        }
+    };
+
+    if passphrase.is_empty() {
+        return Err(anyhow!("Identity passphrase must not be empty"));
+    }
+
+    Ok(Some(passphrase))
+}
@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod tests;
+
+use anyhow::anyhow;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use subspace_core_primitives::{
+    Piece, PieceIndex, PieceIndexHash, RawRecord, RecordedHistorySegment,
+};
+use subspace_farmer::jsonrpsee::server::{ServerBuilder, ServerHandle};
+use subspace_farmer::utils::readers_and_pieces::ReadersAndPieces;
+use subspace_farmer::ws_rpc_server::{self, RpcServer, RpcServerImpl};
+use subspace_farmer::{NodeClient, ObjectMappings};
+use subspace_farmer_components::plotting::{PieceGetter, PieceGetterRetryPolicy};
+use tokio::runtime::Handle;
+use tracing::{debug, error, info, trace};
+
+/// Number of retries for pieces that are not stored in local plots and need to be retrieved from
+/// DSN
+const DSN_GET_PIECE_RETRIES: u16 = 3;
+
+/// Piece getter for object retrieval that reads pieces from local plots first and falls back to
+/// DSN
+struct ObjectPieceGetter<PG> {
+    handle: Handle,
+    readers_and_pieces: Arc<Mutex<Option<ReadersAndPieces>>>,
+    piece_getter: PG,
+}
+
+impl<PG> ws_rpc_server::PieceGetter for ObjectPieceGetter<PG>
+where
+    PG: PieceGetter + Send + Sync,
+{
+    fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+        piece_index_hash: PieceIndexHash,
+    ) -> Option<Piece> {
+        let maybe_read_piece_fut = self
+            .readers_and_pieces
+            .lock()
+            .as_ref()
+            .and_then(|readers_and_pieces| readers_and_pieces.read_piece(&piece_index_hash));
+
+        // RPC methods are blocking, so they are running on a thread where blocking is allowed
+        self.handle.block_on(async move {
+            if let Some(read_piece_fut) = maybe_read_piece_fut {
+                if let Some(piece) = read_piece_fut.await {
+                    trace!(%piece_index, "Got piece from local plot");
+                    return Some(piece);
+                }
+            }
+
+            match self
+                .piece_getter
+                .get_piece(
+                    piece_index,
+                    PieceGetterRetryPolicy::Limited(DSN_GET_PIECE_RETRIES),
+                )
+                .await
+            {
+                Ok(maybe_piece) => maybe_piece,
+                Err(error) => {
+                    debug!(%error, %piece_index, "Failed to get piece from DSN");
+                    None
+                }
+            }
+        })
+    }
+}
+
+/// Create object retrieval server backed by pieces in local plots (if any) and DSN.
+///
+/// Must be called from within Tokio runtime.
+pub(crate) fn create_object_server<PG>(
+    readers_and_pieces: Arc<Mutex<Option<ReadersAndPieces>>>,
+    piece_getter: PG,
+    object_mappings: ObjectMappings,
+) -> RpcServerImpl
+where
+    PG: PieceGetter + Send + Sync + 'static,
+{
+    RpcServerImpl::new(
+        RawRecord::SIZE as u32,
+        RecordedHistorySegment::SIZE as u32,
+        Arc::new(ObjectPieceGetter {
+            handle: Handle::current(),
+            readers_and_pieces,
+            piece_getter,
+        }),
+        Arc::new(vec![object_mappings]),
+    )
+}
+
+/// Start WebSocket RPC server that serves pieces and objects on specified address
+pub(crate) async fn start_object_rpc_server<PG>(
+    listen_on: SocketAddr,
+    readers_and_pieces: Arc<Mutex<Option<ReadersAndPieces>>>,
+    piece_getter: PG,
+    object_mappings: ObjectMappings,
+) -> anyhow::Result<ServerHandle>
+where
+    PG: PieceGetter + Send + Sync + 'static,
+{
+    let rpc_server = create_object_server(readers_and_pieces, piece_getter, object_mappings);
+
+    let server = ServerBuilder::default().build(listen_on).await?;
+    let address = server.local_addr()?;
+    let server_handle = server
+        .start(rpc_server.into_rpc())
+        .map_err(|error| anyhow!(error))?;
+
+    info!(%address, "Started object RPC server");
+
+    Ok(server_handle)
+}
+
+/// Subscribes to object mappings of archived segments and stores them in the database
+pub(crate) async fn fill_object_mappings<NC>(node_client: NC, object_mappings: ObjectMappings)
+where
+    NC: NodeClient,
+{
+    let mut archived_object_mappings_notifications =
+        match node_client.subscribe_archived_object_mappings().await {
+            Ok(archived_object_mappings_notifications) => archived_object_mappings_notifications,
+            Err(error) => {
+                error!(%error, "Failed to subscribe to archived object mappings");
+                return;
+            }
+        };
+
+    while let Some(archived_object_mappings) = archived_object_mappings_notifications.next().await {
+        let segment_index = archived_object_mappings.segment_index;
+
+        if let Err(error) = object_mappings.store(&archived_object_mappings.objects) {
+            error!(%error, %segment_index, "Failed to store object mappings");
+        } else {
+            debug!(
+                %segment_index,
+                objects = archived_object_mappings.objects.len(),
+                "Stored object mappings"
+            );
+        }
+    }
+}
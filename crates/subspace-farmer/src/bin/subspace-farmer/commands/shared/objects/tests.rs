@@ -0,0 +1,100 @@
+use crate::commands::shared::objects::{fill_object_mappings, ObjectPieceGetter};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::error::Error;
+use std::sync::Arc;
+use subspace_core_primitives::objects::GlobalObject;
+use subspace_core_primitives::{Piece, PieceIndex, PublicKey, SegmentIndex};
+use subspace_farmer::node_client::test_node_client::TestNode;
+use subspace_farmer::ws_rpc_server::PieceGetter as _;
+use subspace_farmer::ObjectMappings;
+use subspace_farmer_components::plotting::{PieceGetter, PieceGetterRetryPolicy};
+use subspace_rpc_primitives::ArchivedObjectMappings;
+use tempfile::TempDir;
+use tokio::runtime::Handle;
+
+/// Piece getter that only has the very first piece
+struct TestPieceGetter;
+
+#[async_trait]
+impl PieceGetter for TestPieceGetter {
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+        _retry_policy: PieceGetterRetryPolicy,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
+        Ok((piece_index == PieceIndex::ZERO).then(Piece::default))
+    }
+}
+
+#[tokio::test]
+async fn object_mappings_are_stored_from_notifications() {
+    let directory = TempDir::new().unwrap();
+    let object_mappings =
+        ObjectMappings::open_or_create(directory.path(), PublicKey::default(), 1024 * 1024)
+            .unwrap();
+
+    let first_object = GlobalObject::V0 {
+        piece_index: PieceIndex::ZERO,
+        offset: 1,
+    };
+    let second_object = GlobalObject::V0 {
+        piece_index: PieceIndex::ONE,
+        offset: 2,
+    };
+    let TestNode {
+        client: node_client,
+        archived_object_mappings_sender,
+        ..
+    } = TestNode::new(None);
+    for archived_object_mappings in [
+        ArchivedObjectMappings {
+            segment_index: SegmentIndex::ZERO,
+            objects: vec![([1; 32], first_object)],
+        },
+        ArchivedObjectMappings {
+            segment_index: SegmentIndex::ONE,
+            objects: vec![([2; 32], second_object)],
+        },
+    ] {
+        archived_object_mappings_sender
+            .unbounded_send(archived_object_mappings)
+            .unwrap();
+    }
+    drop(archived_object_mappings_sender);
+
+    // Returns once notifications stream ends
+    fill_object_mappings(node_client, object_mappings.clone()).await;
+
+    assert_eq!(
+        object_mappings.retrieve(&[1; 32]).unwrap(),
+        Some(first_object)
+    );
+    assert_eq!(
+        object_mappings.retrieve(&[2; 32]).unwrap(),
+        Some(second_object)
+    );
+    assert_eq!(object_mappings.retrieve(&[3; 32]).unwrap(), None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn object_piece_getter_falls_back_to_dsn() {
+    let object_piece_getter = ObjectPieceGetter {
+        handle: Handle::current(),
+        readers_and_pieces: Arc::new(Mutex::new(None)),
+        piece_getter: TestPieceGetter,
+    };
+
+    // RPC methods are blocking, so are called from a thread where blocking is allowed
+    let (first_piece, second_piece) = tokio::task::spawn_blocking(move || {
+        (
+            object_piece_getter.get_piece(PieceIndex::ZERO, PieceIndex::ZERO.hash()),
+            object_piece_getter.get_piece(PieceIndex::ONE, PieceIndex::ONE.hash()),
+        )
+    })
+    .await
+    .unwrap();
+
+    assert!(first_piece.is_some());
+    assert!(second_piece.is_none());
+}
@@ -0,0 +1,66 @@
+use crate::commands::shared::read_identity_passphrase;
+use crate::utils::shutdown_signal;
+use crate::SignerArgs;
+use anyhow::Context;
+use futures::FutureExt;
+use std::fs;
+use std::path::PathBuf;
+use subspace_farmer::reward_signer::remote::run_reward_signer_server;
+use subspace_farmer::Identity;
+use tracing::info;
+
+/// Start standalone reward signer that signs rewards on behalf of farmers connected to it
+pub(crate) async fn signer(base_path: PathBuf, signer_args: SignerArgs) -> anyhow::Result<()> {
+    let signal = shutdown_signal();
+
+    let SignerArgs {
+        listen_on,
+        secret_file,
+        identity,
+    } = signer_args;
+
+    let identity_file = match &identity.identity_file {
+        Some(identity_file) => identity_file.clone(),
+        None => {
+            fs::create_dir_all(&base_path)?;
+            Identity::file_path(&base_path)
+        }
+    };
+    let passphrase =
+        read_identity_passphrase(&identity, !Identity::is_file_encrypted(&identity_file)?)?;
+    let identity =
+        Identity::open_or_create_file(&identity_file, passphrase.as_deref().map(String::as_str))
+            .with_context(|| format!("Failed to open identity at {}", identity_file.display()))?;
+
+    let secret_file = match secret_file {
+        Some(secret_file) => secret_file,
+        None => {
+            fs::create_dir_all(&base_path)?;
+            base_path.join("signer-secret")
+        }
+    };
+    let secret = SharedSecret::read_or_generate(&secret_file).with_context(|| {
+        format!(
+            "Failed to read signer secret from {}",
+            secret_file.display()
+        )
+    })?;
+
+    info!(
+        public_key = %hex::encode(identity.public_key().to_bytes()),
+        secret_file = %secret_file.display(),
+        "Starting reward signer"
+    );
+
+    futures::select!(
+        // Signal future
+        _ = signal.fuse() => {},
+
+        // Signer server future
+        result = Box::pin(run_reward_signer_server(&listen_on, identity, secret)).fuse() => {
+            result?;
+        },
+    );
+
+    anyhow::Ok(())
+}
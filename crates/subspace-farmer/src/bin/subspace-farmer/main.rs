@@ -5,16 +5,18 @@ mod ss58;
 mod utils;
 
 use crate::utils::get_usable_plot_space;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use bytesize::ByteSize;
 use clap::{Parser, ValueEnum, ValueHint};
 use ss58::parse_ss58_reward_address;
 use std::fs;
+use std::net::SocketAddr;
 use std::num::{NonZeroU16, NonZeroUsize};
 use std::path::PathBuf;
 use std::str::FromStr;
 use subspace_core_primitives::PublicKey;
-use subspace_farmer::single_disk_plot::SingleDiskPlot;
+use subspace_farmer::reward_signer::remote::RewardSignerAddress;
+use subspace_farmer::single_disk_plot::{SingleDiskPlot, SolutionSelectionPolicy};
 use subspace_networking::libp2p::Multiaddr;
 use subspace_proof_of_space::chia::ChiaTable;
 use tempfile::TempDir;
@@ -37,12 +39,30 @@ static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 /// Arguments for farmer
 #[derive(Debug, Parser)]
 struct FarmingArgs {
-    /// WebSocket RPC URL of the Subspace node to connect to
+    /// WebSocket RPC URL of the Subspace node to connect to, can be specified multiple times to
+    /// connect to multiple nodes, such that farming continues when one of them is unavailable.
     #[arg(long, value_hint = ValueHint::Url, default_value = "ws://127.0.0.1:9944")]
-    node_rpc_url: String,
+    node_rpc_url: Vec<String>,
     /// Address for farming rewards
     #[arg(long, value_parser = parse_ss58_reward_address)]
     reward_address: PublicKey,
+    /// Identity parameters
+    #[clap(flatten)]
+    identity: IdentityArgs,
+    /// Address of standalone reward signer (see `signer` subcommand) to use instead of local
+    /// identity, either Unix socket path like `unix:/run/subspace-signer.sock` or TCP socket
+    /// address on loopback interface like `127.0.0.1:9977`. Connection is not encrypted, forward
+    /// Unix socket over SSH to reach signer on another machine.
+    #[arg(
+        long,
+        conflicts_with_all = ["identity_file", "encrypted_identity"],
+        requires = "reward_signer_secret_file"
+    )]
+    reward_signer: Option<RewardSignerAddress>,
+    /// Path to the file with secret shared with standalone reward signer, copy of the file
+    /// created by `signer` subcommand.
+    #[arg(long, requires = "reward_signer")]
+    reward_signer_secret_file: Option<PathBuf>,
     /// Maximum plot size in human readable format (e.g. 10GB, 2TiB) or just bytes (e.g. 4096).
     #[arg(long, default_value_t)]
     plot_size: ByteSize,
@@ -61,9 +81,173 @@ struct FarmingArgs {
     /// Number of plots that can be plotted concurrently, impacts RAM usage.
     #[arg(long, default_value = "10")]
     max_concurrent_plots: NonZeroUsize,
+    /// Number of sectors that can be plotted concurrently within each plot, impacts RAM usage.
+    #[arg(long, default_value = "1")]
+    sector_plotting_concurrency: NonZeroUsize,
+    /// Number of threads used for auditing and proving in each plot, defaults to number of CPU
+    /// cores.
+    #[arg(long)]
+    farming_thread_pool_size: Option<NonZeroUsize>,
+    /// Maximum number of solutions submitted per challenge by each plot, solutions beyond the
+    /// first one are used as votes.
+    #[arg(long, default_value = "1")]
+    solutions_limit: NonZeroUsize,
+    /// Policy for selecting solutions when there are more of them than solutions limit.
+    #[arg(long, value_enum, default_value_t = SolutionSelection::First)]
+    solution_selection_policy: SolutionSelection,
     /// Do not print info about configured farms on startup.
     #[arg(long)]
     no_info: bool,
+    /// Address to serve Prometheus metrics of plotting and farming on (`/metrics` path), for
+    /// instance `127.0.0.1:9616`, metrics are not served by default.
+    #[arg(long)]
+    metrics_endpoint: Option<SocketAddr>,
+    /// Address to serve pieces and objects over WebSocket RPC on (`getPiece` and `findObject`
+    /// methods), for instance `127.0.0.1:9955`, objects are not served by default.
+    ///
+    /// Object mappings are collected from the node as new segments are archived.
+    #[arg(long)]
+    object_rpc_listen_on: Option<SocketAddr>,
+    /// Maximum size of object mappings database in human readable format (e.g. 10GB, 2TiB) or just
+    /// bytes (e.g. 4096), only used when objects are served.
+    #[arg(long, default_value_t = ByteSize::gib(1))]
+    object_mappings_size: ByteSize,
+    /// Address of plotting server (see `plotting-server` subcommand) to send sectors to for
+    /// plotting instead of plotting them locally, for instance `10.0.0.3:9988`, can be specified
+    /// multiple times to spread plotting across multiple servers.
+    #[arg(long, requires = "plotting_server_secret_file")]
+    plotting_server: Vec<SocketAddr>,
+    /// Path to the file with secret shared with plotting servers, copy of the file created by
+    /// `plotting-server` subcommand.
+    #[arg(long, requires = "plotting_server")]
+    plotting_server_secret_file: Option<PathBuf>,
+    /// Replot sectors that produced invalid solutions, which usually indicates disk corruption.
+    ///
+    /// Solutions are always verified before submission and invalid ones are logged and dropped.
+    #[arg(long)]
+    replot_invalid_sectors: bool,
+    /// Assign new sector index range to farms whose range overlaps with another farm that uses the
+    /// same identity instead of refusing to start, such farms are replotted from scratch.
+    #[arg(long)]
+    reassign_overlapping_sector_ranges: bool,
+    /// Maximum number of providers the same piece is requested from concurrently when piece is
+    /// downloaded from DSN.
+    #[arg(long, default_value = "2")]
+    piece_request_concurrency: NonZeroUsize,
+    /// Time in milliseconds to wait for piece provider to respond before requesting the same piece
+    /// from another provider too (up to `--piece-request-concurrency` providers).
+    #[arg(long, default_value_t = 2000)]
+    piece_request_hedge_threshold_ms: u64,
+}
+
+/// Arguments for farmer identity
+#[derive(Debug, Parser)]
+struct IdentityArgs {
+    /// Path to identity file shared by all farms, by default each farm uses identity stored in its
+    /// own directory.
+    ///
+    /// Sharing identity file avoids copying the secret onto every disk.
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    identity_file: Option<PathBuf>,
+    /// Encrypt identity with passphrase, passphrase is prompted for interactively unless
+    /// `--identity-passphrase-file` is specified. Existing plaintext identity is encrypted in
+    /// place.
+    #[arg(long)]
+    encrypted_identity: bool,
+    /// Read identity passphrase from the first line of specified file instead of prompting for it.
+    #[arg(long, requires = "encrypted_identity", value_hint = ValueHint::FilePath)]
+    identity_passphrase_file: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum IdentityCommand {
+    /// Print BIP39 mnemonic of identity, which can be used to restore it with `identity import`
+    Export {
+        /// Identity parameters
+        #[clap(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Restore identity from BIP39 mnemonic, mnemonic is prompted for interactively
+    Import {
+        /// Identity parameters
+        #[clap(flatten)]
+        identity: IdentityArgs,
+        /// Replace identity that already exists, plots created with previous identity will not be
+        /// usable anymore
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Arguments for standalone reward signer
+#[derive(Debug, Parser)]
+struct SignerArgs {
+    /// Address to listen on for farmer connections, either Unix socket path like
+    /// `unix:/run/subspace-signer.sock` or TCP socket address on loopback interface like
+    /// `127.0.0.1:9977`.
+    #[arg(long)]
+    listen_on: RewardSignerAddress,
+    /// Path to the file with secret shared with farmers, farmers that don't have the same secret
+    /// are rejected. Secret is generated if file doesn't exist yet, defaults to `signer-secret`
+    /// in base path.
+    #[arg(long)]
+    secret_file: Option<PathBuf>,
+    /// Identity parameters, identity is stored in base path by default
+    #[clap(flatten)]
+    identity: IdentityArgs,
+}
+
+/// Arguments for HTTP gateway
+#[derive(Debug, Parser)]
+struct GatewayArgs {
+    /// WebSocket RPC URL of the Subspace node to connect to
+    #[arg(long, value_hint = ValueHint::Url, default_value = "ws://127.0.0.1:9944")]
+    node_rpc_url: String,
+    /// Address to serve objects over HTTP on, objects are available at `/{object_hash}` path
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen_on: SocketAddr,
+    /// Multiaddrs of bootstrap nodes to connect to on startup, multiple are supported, defaults to
+    /// bootstrap nodes of the node
+    #[arg(long)]
+    bootstrap_nodes: Vec<Multiaddr>,
+    /// Multiaddr to listen on for subspace networking, for instance `/ip4/0.0.0.0/tcp/0`,
+    /// multiple are supported.
+    #[arg(long, default_value = "/ip4/0.0.0.0/tcp/30534")]
+    dsn_listen_on: Vec<Multiaddr>,
+    /// Maximum size of object mappings database in human readable format (e.g. 10GB, 2TiB) or just
+    /// bytes (e.g. 4096).
+    #[arg(long, default_value_t = ByteSize::gib(1))]
+    object_mappings_size: ByteSize,
+}
+
+/// Arguments for plotting server
+#[derive(Debug, Parser)]
+struct PlottingServerArgs {
+    /// WebSocket RPC URL of the Subspace node to connect to
+    #[arg(long, value_hint = ValueHint::Url, default_value = "ws://127.0.0.1:9944")]
+    node_rpc_url: String,
+    /// Address to listen on for plotting jobs from farmers.
+    ///
+    /// Connection is not encrypted, so plotting server should only be reachable through trusted
+    /// network.
+    #[arg(long, default_value = "127.0.0.1:9988")]
+    listen_on: SocketAddr,
+    /// Path to the file with secret shared with farmers, farmers that don't have the same secret
+    /// are rejected. Secret is generated if file doesn't exist yet, defaults to `secret` in
+    /// `plotting-server` directory in base path.
+    #[arg(long)]
+    secret_file: Option<PathBuf>,
+    /// Number of sectors that can be plotted concurrently, impacts RAM usage.
+    #[arg(long, default_value = "1")]
+    sector_plotting_concurrency: NonZeroUsize,
+    /// Multiaddrs of bootstrap nodes to connect to on startup, multiple are supported, defaults to
+    /// bootstrap nodes of the node
+    #[arg(long)]
+    bootstrap_nodes: Vec<Multiaddr>,
+    /// Multiaddr to listen on for subspace networking, for instance `/ip4/0.0.0.0/tcp/0`,
+    /// multiple are supported.
+    #[arg(long, default_value = "/ip4/0.0.0.0/tcp/30535")]
+    dsn_listen_on: Vec<Multiaddr>,
 }
 
 /// Arguments for DSN
@@ -79,12 +263,22 @@ struct DsnArgs {
     /// Piece cache size in pieces.
     #[arg(long, default_value = "1000")]
     piece_cache_size: NonZeroUsize,
+    /// Size of dedicated piece cache file in each farm in human readable format (e.g. 10GB, 2TiB)
+    /// or just bytes (e.g. 4096), `0` disables it.
+    ///
+    /// When enabled, replaces piece cache in base path (`--piece-cache-size` is ignored), cache
+    /// file is taken out of plot size. Pieces in cache survive restarts.
+    #[arg(long, default_value_t = ByteSize::b(0))]
+    disk_piece_cache_size: ByteSize,
     /// Number of provided keys (by other peers) that will be stored.
     #[arg(long, default_value = "655360")]
     provided_keys_limit: NonZeroUsize,
     /// Determines whether we allow keeping non-global (private, shared, loopback..) addresses in Kademlia DHT.
     #[arg(long, default_value_t = false)]
     disable_private_ips: bool,
+    /// Discover other farmers and nodes in local network using mDNS.
+    #[arg(long, default_value_t = false)]
+    enable_mdns: bool,
     /// Multiaddrs of reserved nodes to maintain a connection to, multiple are supported
     #[arg(long)]
     reserved_peers: Vec<Multiaddr>,
@@ -118,6 +312,24 @@ impl Default for WriteToDisk {
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SolutionSelection {
+    /// Solutions from sectors in the order they are stored in the plot
+    First,
+    /// Solutions closest to the challenge first
+    ClosestToChallenge,
+}
+
+impl From<SolutionSelection> for SolutionSelectionPolicy {
+    #[inline]
+    fn from(solution_selection: SolutionSelection) -> Self {
+        match solution_selection {
+            SolutionSelection::First => Self::First,
+            SolutionSelection::ClosestToChallenge => Self::ClosestToChallenge,
+        }
+    }
+}
+
 #[derive(Debug, clap::Subcommand)]
 enum Subcommand {
     /// Wipes plot and identity
@@ -125,7 +337,61 @@ enum Subcommand {
     /// Start a farmer using previously created plot
     Farm(FarmingArgs),
     /// Print information about farm and its content
-    Info,
+    Info {
+        /// Print information in JSON format, including details about every plotted sector
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check integrity of plotted sectors and report corrupted ones
+    Scrub {
+        /// WebSocket RPC URL of the Subspace node to retrieve segment commitments from
+        #[arg(long, value_hint = ValueHint::Url, default_value = "ws://127.0.0.1:9944")]
+        node_rpc_url: String,
+        /// Mark corrupted sectors as expired, such that they are replotted next time farmer starts
+        #[arg(long)]
+        replot: bool,
+    },
+    /// Migrate farms to the current plot format and/or different number of pieces in sector.
+    ///
+    /// Migration is only scheduled by this command, sectors are migrated one at a time in the
+    /// background by `farm` command, which keeps farming sectors that were not migrated yet.
+    /// Migration continues where it left off if interrupted.
+    Migrate {
+        /// Number of pieces in sector to migrate to, defaults to the current value of each farm
+        #[arg(long, conflicts_with_all = ["status", "abort"])]
+        pieces_in_sector: Option<u16>,
+        /// Only print progress of migration
+        #[arg(long, conflicts_with = "abort")]
+        status: bool,
+        /// Abort migration, removing everything migrated so far
+        #[arg(long)]
+        abort: bool,
+    },
+    /// Benchmark auditing, proving and plotting using existing farms
+    Benchmark {
+        /// Number of sectors in each farm to benchmark proving on
+        #[arg(long, default_value = "10")]
+        proving_sectors: usize,
+        /// Do not benchmark plotting
+        #[arg(long)]
+        no_plotting: bool,
+    },
+    /// Serve archived objects over HTTP by their hash, retrieving pieces from DSN.
+    ///
+    /// Only objects archived since gateway was started for the first time are available, node
+    /// doesn't provide object mappings of earlier segments.
+    Gateway(GatewayArgs),
+    /// Plot sectors on behalf of farmers that use this server with `--plotting-server`, retrieving
+    /// pieces from DSN
+    PlottingServer(PlottingServerArgs),
+    /// Run standalone reward signer, such that secret key doesn't need to be present on machines
+    /// that do plotting and farming
+    Signer(SignerArgs),
+    /// Export and import farmer identity
+    Identity {
+        #[clap(subcommand)]
+        command: IdentityCommand,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -225,6 +491,29 @@ struct Command {
     tmp: bool,
 }
 
+/// Farms specified with `--farm` or a single farm in base path if none were specified.
+///
+/// Returns an error if directory of any of specified farms doesn't exist.
+fn disk_farms_or_default(farm: Vec<DiskFarm>, base_path: PathBuf) -> Result<Vec<DiskFarm>> {
+    if farm.is_empty() {
+        return Ok(vec![DiskFarm {
+            directory: base_path,
+            allocated_plotting_space: get_usable_plot_space(0),
+        }]);
+    }
+
+    for farm in &farm {
+        if !farm.directory.exists() {
+            return Err(anyhow!(
+                "Directory {} doesn't exist",
+                farm.directory.display()
+            ));
+        }
+    }
+
+    Ok(farm)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::registry()
@@ -304,7 +593,77 @@ async fn main() -> Result<()> {
 
             commands::farm_multi_disk::<PosTable>(base_path, disk_farms, farming_args).await?;
         }
-        Subcommand::Info => {
+        Subcommand::Info { json } => {
+            let disk_farms = disk_farms_or_default(command.farm, base_path)?;
+
+            commands::info(disk_farms, json);
+        }
+        Subcommand::Scrub {
+            node_rpc_url,
+            replot,
+        } => {
+            let disk_farms = if command.farm.is_empty() {
+                vec![DiskFarm {
+                    directory: base_path,
+                    allocated_plotting_space: get_usable_plot_space(0),
+                }]
+            } else {
+                command.farm
+            };
+
+            commands::scrub::<PosTable>(disk_farms, node_rpc_url, replot).await?;
+        }
+        Subcommand::Migrate {
+            pieces_in_sector,
+            status,
+            abort,
+        } => {
+            let disk_farms = if command.farm.is_empty() {
+                vec![DiskFarm {
+                    directory: base_path,
+                    allocated_plotting_space: get_usable_plot_space(0),
+                }]
+            } else {
+                command.farm
+            };
+
+            let action = if abort {
+                commands::MigrateAction::Abort
+            } else if status {
+                commands::MigrateAction::Status
+            } else {
+                commands::MigrateAction::Schedule { pieces_in_sector }
+            };
+
+            commands::migrate(disk_farms, action)?;
+        }
+        Subcommand::Benchmark {
+            proving_sectors,
+            no_plotting,
+        } => {
+            let disk_farms = if command.farm.is_empty() {
+                vec![DiskFarm {
+                    directory: base_path,
+                    allocated_plotting_space: get_usable_plot_space(0),
+                }]
+            } else {
+                command.farm
+            };
+
+            commands::benchmark::<PosTable>(disk_farms, proving_sectors, !no_plotting).await?;
+        }
+        Subcommand::Gateway(gateway_args) => {
+            commands::gateway(base_path, gateway_args).await?;
+        }
+        Subcommand::PlottingServer(plotting_server_args) => {
+            commands::plotting_server::<PosTable>(base_path, plotting_server_args).await?;
+        }
+        Subcommand::Signer(signer_args) => {
+            commands::signer(base_path, signer_args).await?;
+        }
+        Subcommand::Identity {
+            command: identity_command,
+        } => {
             let disk_farms = if command.farm.is_empty() {
                 vec![DiskFarm {
                     directory: base_path,
@@ -314,7 +673,7 @@ async fn main() -> Result<()> {
                 command.farm
             };
 
-            commands::info(disk_farms);
+            commands::identity(disk_farms, identity_command)?;
         }
     }
     Ok(())
@@ -1,23 +1,197 @@
-use anyhow::Error;
-use parity_scale_codec::{Decode, Encode};
+#[cfg(test)]
+mod tests;
+
+use anyhow::{anyhow, Error};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
 use schnorrkel::context::SigningContext;
 use schnorrkel::{ExpansionMode, Keypair, PublicKey, SecretKey, Signature};
 use std::fs;
 use std::ops::Deref;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use subspace_solving::REWARD_SIGNING_CONTEXT;
 use substrate_bip39::mini_secret_from_entropy;
-use tracing::debug;
+use tracing::{debug, info};
 use zeroize::Zeroizing;
 
 /// Entropy used for identity generation.
 const ENTROPY_LENGTH: usize = 32;
+/// Size of the salt used for deriving encryption key from passphrase.
+const SALT_LENGTH: usize = 16;
+/// Size of XChaCha20-Poly1305 nonce.
+const NONCE_LENGTH: usize = 24;
+/// Size of XChaCha20-Poly1305 key.
+const KEY_LENGTH: usize = 32;
+/// Prefix of encrypted identity file.
+///
+/// Plaintext identity file starts with SCALE compact length of entropy, which only starts with
+/// `0x03` for entropy of 2^30 bytes or more, so encrypted identity can't be confused with it.
+const ENCRYPTED_IDENTITY_PREFIX: [u8; 4] = [0x03, b'e', b'n', b'c'];
 
+/// Identity file contents as it was written before encryption support was added, plaintext
+/// identities are still written this way, such that older versions of the farmer can read them.
 #[derive(Debug, Encode, Decode)]
-struct IdentityFileContents {
+struct LegacyIdentityFileContents {
     entropy: Vec<u8>,
 }
 
+/// Parameters of Argon2 used to derive encryption key from passphrase, stored next to the salt
+/// such that changes of defaults don't affect existing identities.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Encode, Decode)]
+struct KeyDerivationParams {
+    /// Argon2 algorithm: `0` for Argon2d, `1` for Argon2i and `2` for Argon2id
+    algorithm: u8,
+    /// Argon2 version
+    version: u32,
+    /// Memory size in KiB
+    m_cost: u32,
+    /// Number of iterations
+    t_cost: u32,
+    /// Degree of parallelism
+    p_cost: u32,
+}
+
+impl Default for KeyDerivationParams {
+    fn default() -> Self {
+        Self {
+            algorithm: 2,
+            version: Version::V0x13.into(),
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KeyDerivationParams {
+    fn derive_key(
+        &self,
+        passphrase: &str,
+        salt: &[u8],
+    ) -> Result<Zeroizing<[u8; KEY_LENGTH]>, Error> {
+        let algorithm = match self.algorithm {
+            0 => Algorithm::Argon2d,
+            1 => Algorithm::Argon2i,
+            2 => Algorithm::Argon2id,
+            algorithm => {
+                return Err(anyhow!("Unsupported Argon2 algorithm {algorithm}"));
+            }
+        };
+        let version = Version::try_from(self.version)
+            .map_err(|error| anyhow!("Unsupported Argon2 version {}: {error}", self.version))?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(KEY_LENGTH))
+            .map_err(|error| anyhow!("Invalid Argon2 parameters: {error}"))?;
+
+        let mut key = Zeroizing::new([0; KEY_LENGTH]);
+        Argon2::new(algorithm, version, params)
+            .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+            .map_err(|error| anyhow!("Failed to derive key from passphrase: {error}"))?;
+
+        Ok(key)
+    }
+}
+
+/// Entropy encrypted with XChaCha20-Poly1305 using key derived from passphrase with Argon2
+#[derive(Debug, Encode, Decode)]
+struct EncryptedIdentityFileContents {
+    key_derivation_params: KeyDerivationParams,
+    salt: [u8; SALT_LENGTH],
+    nonce: [u8; NONCE_LENGTH],
+    ciphertext: Vec<u8>,
+}
+
+#[derive(Debug)]
+enum IdentityFileContents {
+    /// Entropy stored as is
+    Plain(LegacyIdentityFileContents),
+    /// Encrypted entropy, stored with [`ENCRYPTED_IDENTITY_PREFIX`]
+    Encrypted(EncryptedIdentityFileContents),
+}
+
+impl IdentityFileContents {
+    fn decode_from(bytes: &[u8]) -> Result<Self, Error> {
+        if let Some(mut encrypted_bytes) = bytes.strip_prefix(&ENCRYPTED_IDENTITY_PREFIX) {
+            return Ok(Self::Encrypted(EncryptedIdentityFileContents::decode_all(
+                &mut encrypted_bytes,
+            )?));
+        }
+
+        Ok(Self::Plain(LegacyIdentityFileContents::decode_all(
+            &mut &*bytes,
+        )?))
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Plain(legacy_identity_file_contents) => legacy_identity_file_contents.encode(),
+            Self::Encrypted(encrypted_identity_file_contents) => {
+                let mut bytes = ENCRYPTED_IDENTITY_PREFIX.to_vec();
+                encrypted_identity_file_contents.encode_to(&mut bytes);
+                bytes
+            }
+        }
+    }
+
+    fn new(entropy: &[u8], passphrase: Option<&str>) -> Result<Self, Error> {
+        match passphrase {
+            Some(passphrase) => {
+                Self::new_encrypted(entropy, passphrase, KeyDerivationParams::default())
+            }
+            None => Ok(Self::Plain(LegacyIdentityFileContents {
+                entropy: entropy.to_vec(),
+            })),
+        }
+    }
+
+    fn new_encrypted(
+        entropy: &[u8],
+        passphrase: &str,
+        key_derivation_params: KeyDerivationParams,
+    ) -> Result<Self, Error> {
+        let salt = rand::random::<[u8; SALT_LENGTH]>();
+        let nonce = rand::random::<[u8; NONCE_LENGTH]>();
+        let key = key_derivation_params.derive_key(passphrase, &salt)?;
+
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+            .encrypt(XNonce::from_slice(&nonce), entropy)
+            .map_err(|error| anyhow!("Failed to encrypt identity: {error}"))?;
+
+        Ok(Self::Encrypted(EncryptedIdentityFileContents {
+            key_derivation_params,
+            salt,
+            nonce,
+            ciphertext,
+        }))
+    }
+
+    fn entropy(&self, passphrase: Option<&str>) -> Result<Zeroizing<Vec<u8>>, Error> {
+        match self {
+            Self::Plain(LegacyIdentityFileContents { entropy }) => {
+                Ok(Zeroizing::new(entropy.clone()))
+            }
+            Self::Encrypted(EncryptedIdentityFileContents {
+                key_derivation_params,
+                salt,
+                nonce,
+                ciphertext,
+            }) => {
+                let passphrase = passphrase
+                    .ok_or_else(|| anyhow!("Identity is encrypted, passphrase is required"))?;
+                let key = key_derivation_params.derive_key(passphrase, salt)?;
+
+                XChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+                    .decrypt(XNonce::from_slice(nonce), ciphertext.as_slice())
+                    .map(Zeroizing::new)
+                    .map_err(|_error| {
+                        anyhow!("Failed to decrypt identity, wrong passphrase or corrupted file")
+                    })
+            }
+        }
+    }
+}
+
 fn keypair_from_entropy(entropy: &[u8]) -> Keypair {
     mini_secret_from_entropy(entropy, "")
         .expect("32 bytes can always build a key; qed")
@@ -28,6 +202,9 @@ fn keypair_from_entropy(entropy: &[u8]) -> Keypair {
 ///
 /// It is basically a wrapper of the keypair (which holds public & secret keys)
 /// and a context that will be used for signing.
+///
+/// Identity file can optionally be encrypted with a passphrase, in which case the same passphrase
+/// must be provided to open it.
 #[derive(Clone)]
 pub struct Identity {
     keypair: Zeroizing<Keypair>,
@@ -45,74 +222,164 @@ impl Deref for Identity {
 }
 
 impl Identity {
+    const FILE_NAME: &'static str = "identity.bin";
+
+    /// Path to identity file in provided base directory.
+    pub fn file_path<B: AsRef<Path>>(base_directory: B) -> PathBuf {
+        base_directory.as_ref().join(Self::FILE_NAME)
+    }
+
     /// Opens the existing identity, or creates a new one.
-    pub fn open_or_create<B: AsRef<Path>>(base_directory: B) -> Result<Self, Error> {
-        if let Some(identity) = Self::open(base_directory.as_ref())? {
+    pub fn open_or_create<B: AsRef<Path>>(
+        base_directory: B,
+        passphrase: Option<&str>,
+    ) -> Result<Self, Error> {
+        Self::open_or_create_file(Self::file_path(base_directory), passphrase)
+    }
+
+    /// Opens the existing identity, returns `Ok(None)` if it doesn't exist.
+    pub fn open<B: AsRef<Path>>(
+        base_directory: B,
+        passphrase: Option<&str>,
+    ) -> Result<Option<Self>, Error> {
+        Self::open_file(Self::file_path(base_directory), passphrase)
+    }
+
+    /// Creates new identity, overrides identity that might already exist.
+    pub fn create<B: AsRef<Path>>(
+        base_directory: B,
+        passphrase: Option<&str>,
+    ) -> Result<Self, Error> {
+        Self::create_file(Self::file_path(base_directory), passphrase)
+    }
+
+    /// Create identity from given entropy, overrides identity that might already exist.
+    pub fn from_entropy<B: AsRef<Path>>(
+        base_directory: B,
+        entropy: Vec<u8>,
+        passphrase: Option<&str>,
+    ) -> Result<Self, Error> {
+        Self::from_entropy_file(Self::file_path(base_directory), entropy, passphrase)
+    }
+
+    /// Same as [`Self::open_or_create`], but with explicit path to identity file, which allows
+    /// using one identity file for multiple plots.
+    pub fn open_or_create_file<P: AsRef<Path>>(
+        identity_file: P,
+        passphrase: Option<&str>,
+    ) -> Result<Self, Error> {
+        if let Some(identity) = Self::open_file(identity_file.as_ref(), passphrase)? {
             Ok(identity)
         } else {
-            Self::create(base_directory)
+            Self::create_file(identity_file, passphrase)
         }
     }
 
-    /// Opens the existing identity, returns `Ok(None)` if it doesn't exist.
-    pub fn open<B: AsRef<Path>>(base_directory: B) -> Result<Option<Self>, Error> {
-        let identity_file = base_directory.as_ref().join("identity.bin");
+    /// Same as [`Self::open`], but with explicit path to identity file.
+    pub fn open_file<P: AsRef<Path>>(
+        identity_file: P,
+        passphrase: Option<&str>,
+    ) -> Result<Option<Self>, Error> {
+        let identity_file = identity_file.as_ref();
         if identity_file.exists() {
             debug!("Opening existing keypair");
             let bytes = Zeroizing::new(fs::read(identity_file)?);
-            let IdentityFileContents { entropy } =
-                IdentityFileContents::decode(&mut bytes.as_ref())?;
-
-            Ok(Some(Self {
-                keypair: Zeroizing::new(keypair_from_entropy(&entropy)),
-                entropy: Zeroizing::new(entropy),
-                substrate_ctx: schnorrkel::context::signing_context(REWARD_SIGNING_CONTEXT),
-            }))
+            let identity_file_contents = IdentityFileContents::decode_from(&bytes)?;
+            let entropy = identity_file_contents.entropy(passphrase)?;
+
+            // Passphrase means identity is supposed to be encrypted, so plaintext identity is
+            // encrypted in place rather than silently left as is
+            if matches!(identity_file_contents, IdentityFileContents::Plain(_))
+                && passphrase.is_some()
+            {
+                info!(
+                    path = %identity_file.display(),
+                    "Encrypting existing identity with provided passphrase"
+                );
+                Self::store(identity_file, &entropy, passphrase)?;
+            }
+
+            Ok(Some(Self::new(entropy)))
         } else {
             debug!("Existing keypair not found");
             Ok(None)
         }
     }
 
-    /// Creates new identity, overrides identity that might already exist.
-    pub fn create<B: AsRef<Path>>(base_directory: B) -> Result<Self, Error> {
-        let identity_file = base_directory.as_ref().join("identity.bin");
+    /// Same as [`Self::create`], but with explicit path to identity file.
+    pub fn create_file<P: AsRef<Path>>(
+        identity_file: P,
+        passphrase: Option<&str>,
+    ) -> Result<Self, Error> {
         debug!("Generating new keypair");
         let entropy = rand::random::<[u8; ENTROPY_LENGTH]>().to_vec();
 
-        let identity_file_contents = IdentityFileContents { entropy };
-        fs::write(identity_file, identity_file_contents.encode())?;
-
-        let IdentityFileContents { entropy } = identity_file_contents;
-
-        Ok(Self {
-            keypair: Zeroizing::new(keypair_from_entropy(&entropy)),
-            entropy: Zeroizing::new(entropy),
-            substrate_ctx: schnorrkel::context::signing_context(REWARD_SIGNING_CONTEXT),
-        })
+        Self::write(identity_file.as_ref(), Zeroizing::new(entropy), passphrase)
     }
 
-    /// Create identity from given entropy, overrides identity that might already exist.
-    ///
-    /// Primarily used for testing.
-    #[doc(hidden)]
-    pub fn from_entropy<B: AsRef<Path>>(
-        base_directory: B,
+    /// Same as [`Self::from_entropy`], but with explicit path to identity file.
+    pub fn from_entropy_file<P: AsRef<Path>>(
+        identity_file: P,
         entropy: Vec<u8>,
+        passphrase: Option<&str>,
     ) -> Result<Self, Error> {
-        let identity_file = base_directory.as_ref().join("identity.bin");
         debug!("Creating identity from provided entropy");
 
-        let identity_file_contents = IdentityFileContents { entropy };
-        fs::write(identity_file, identity_file_contents.encode())?;
+        Self::write(identity_file.as_ref(), Zeroizing::new(entropy), passphrase)
+    }
+
+    /// Whether identity file exists and is encrypted with a passphrase.
+    pub fn is_file_encrypted<P: AsRef<Path>>(identity_file: P) -> Result<bool, Error> {
+        let identity_file = identity_file.as_ref();
+        if !identity_file.exists() {
+            return Ok(false);
+        }
+
+        let bytes = Zeroizing::new(fs::read(identity_file)?);
 
-        let IdentityFileContents { entropy } = identity_file_contents;
+        Ok(matches!(
+            IdentityFileContents::decode_from(&bytes)?,
+            IdentityFileContents::Encrypted(_)
+        ))
+    }
+
+    fn write(
+        identity_file: &Path,
+        entropy: Zeroizing<Vec<u8>>,
+        passphrase: Option<&str>,
+    ) -> Result<Self, Error> {
+        Self::store(identity_file, &entropy, passphrase)?;
+
+        Ok(Self::new(entropy))
+    }
+
+    /// Store identity file, existing file is replaced atomically, such that identity is never lost
+    /// if interrupted.
+    fn store(identity_file: &Path, entropy: &[u8], passphrase: Option<&str>) -> Result<(), Error> {
+        let identity_file_contents = IdentityFileContents::new(entropy, passphrase)?;
 
-        Ok(Self {
+        let mut tmp_file_name = identity_file
+            .file_name()
+            .ok_or_else(|| anyhow!("Identity file path {} is invalid", identity_file.display()))?
+            .to_os_string();
+        tmp_file_name.push(".tmp");
+        let tmp_identity_file = identity_file.with_file_name(tmp_file_name);
+
+        fs::write(
+            &tmp_identity_file,
+            Zeroizing::new(identity_file_contents.to_bytes()),
+        )?;
+        fs::rename(tmp_identity_file, identity_file)?;
+
+        Ok(())
+    }
+
+    fn new(entropy: Zeroizing<Vec<u8>>) -> Self {
+        Self {
             keypair: Zeroizing::new(keypair_from_entropy(&entropy)),
-            entropy: Zeroizing::new(entropy),
+            entropy,
             substrate_ctx: schnorrkel::context::signing_context(REWARD_SIGNING_CONTEXT),
-        })
+        }
     }
 
     /// Returns the public key of the identity.
@@ -0,0 +1,135 @@
+use crate::identity::{
+    EncryptedIdentityFileContents, Identity, IdentityFileContents, KeyDerivationParams,
+    LegacyIdentityFileContents,
+};
+use parity_scale_codec::{DecodeAll, Encode};
+use std::fs;
+use tempfile::TempDir;
+
+const PASSPHRASE: &str = "correct horse battery staple";
+
+#[test]
+fn encrypted_identity_roundtrip() {
+    let directory = TempDir::new().unwrap();
+
+    let identity = Identity::create(directory.path(), Some(PASSPHRASE)).unwrap();
+    assert!(Identity::is_file_encrypted(Identity::file_path(directory.path())).unwrap());
+
+    let opened_identity = Identity::open(directory.path(), Some(PASSPHRASE))
+        .unwrap()
+        .unwrap();
+    assert_eq!(opened_identity.public_key(), identity.public_key());
+    assert_eq!(opened_identity.entropy(), identity.entropy());
+}
+
+#[test]
+fn encrypted_identity_wrong_passphrase() {
+    let directory = TempDir::new().unwrap();
+
+    Identity::create(directory.path(), Some(PASSPHRASE)).unwrap();
+
+    assert!(Identity::open(directory.path(), Some("wrong passphrase")).is_err());
+    assert!(Identity::open(directory.path(), None).is_err());
+}
+
+#[test]
+fn legacy_identity_decoding() {
+    let directory = TempDir::new().unwrap();
+    let entropy = vec![7; 32];
+
+    fs::write(
+        Identity::file_path(directory.path()),
+        LegacyIdentityFileContents {
+            entropy: entropy.clone(),
+        }
+        .encode(),
+    )
+    .unwrap();
+
+    assert!(!Identity::is_file_encrypted(Identity::file_path(directory.path())).unwrap());
+
+    let identity = Identity::open(directory.path(), None).unwrap().unwrap();
+    assert_eq!(identity.entropy(), entropy.as_slice());
+}
+
+#[test]
+fn plaintext_identity_is_encrypted_with_passphrase() {
+    let directory = TempDir::new().unwrap();
+    let identity_file = Identity::file_path(directory.path());
+
+    let identity = Identity::create(directory.path(), None).unwrap();
+    assert!(!Identity::is_file_encrypted(&identity_file).unwrap());
+
+    let opened_identity = Identity::open(directory.path(), Some(PASSPHRASE))
+        .unwrap()
+        .unwrap();
+    assert_eq!(opened_identity.public_key(), identity.public_key());
+    assert!(Identity::is_file_encrypted(&identity_file).unwrap());
+
+    // Passphrase is now required
+    assert!(Identity::open(directory.path(), None).is_err());
+    let opened_identity = Identity::open(directory.path(), Some(PASSPHRASE))
+        .unwrap()
+        .unwrap();
+    assert_eq!(opened_identity.entropy(), identity.entropy());
+}
+
+#[test]
+fn plaintext_identity_is_written_in_legacy_format() {
+    let directory = TempDir::new().unwrap();
+
+    let identity = Identity::create(directory.path(), None).unwrap();
+
+    // Older versions of the farmer can still read plaintext identity
+    let LegacyIdentityFileContents { entropy } = LegacyIdentityFileContents::decode_all(
+        &mut fs::read(Identity::file_path(directory.path()))
+            .unwrap()
+            .as_slice(),
+    )
+    .unwrap();
+    assert_eq!(entropy, identity.entropy());
+}
+
+#[test]
+fn encrypted_identity_uses_stored_key_derivation_params() {
+    let directory = TempDir::new().unwrap();
+    let identity_file = Identity::file_path(directory.path());
+
+    Identity::create(directory.path(), Some(PASSPHRASE)).unwrap();
+    match IdentityFileContents::decode_from(&fs::read(&identity_file).unwrap()).unwrap() {
+        IdentityFileContents::Encrypted(EncryptedIdentityFileContents {
+            key_derivation_params,
+            ..
+        }) => {
+            assert_eq!(key_derivation_params, KeyDerivationParams::default());
+        }
+        IdentityFileContents::Plain(_) => {
+            panic!("Identity must be encrypted");
+        }
+    }
+
+    // Identity encrypted with non-default parameters is decrypted with the same parameters
+    let entropy = vec![7; 32];
+    fs::write(
+        &identity_file,
+        IdentityFileContents::new_encrypted(
+            &entropy,
+            PASSPHRASE,
+            KeyDerivationParams {
+                algorithm: 1,
+                version: 0x10,
+                m_cost: 8,
+                t_cost: 1,
+                p_cost: 1,
+            },
+        )
+        .unwrap()
+        .to_bytes(),
+    )
+    .unwrap();
+
+    let identity = Identity::open(directory.path(), Some(PASSPHRASE))
+        .unwrap()
+        .unwrap();
+    assert_eq!(identity.entropy(), entropy.as_slice());
+}
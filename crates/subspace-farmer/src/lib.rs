@@ -36,6 +36,7 @@
 pub(crate) mod identity;
 pub mod node_client;
 pub(crate) mod object_mappings;
+pub mod reward_signer;
 pub mod reward_signing;
 pub mod single_disk_plot;
 pub mod utils;
@@ -43,6 +44,7 @@ pub mod ws_rpc_server;
 
 pub use identity::Identity;
 pub use jsonrpsee;
+pub use node_client::multi_node_client::{ConnectableNodeClient, MultiNodeClient};
 pub use node_client::node_rpc_client::NodeRpcClient;
 pub use node_client::{Error as RpcClientError, NodeClient};
 pub use object_mappings::{ObjectMappingError, ObjectMappings};
@@ -1,11 +1,15 @@
+pub(crate) mod multi_node_client;
 pub(crate) mod node_rpc_client;
+#[doc(hidden)]
+pub mod test_node_client;
 
 use async_trait::async_trait;
 use futures::Stream;
 use std::pin::Pin;
 use subspace_core_primitives::{Piece, PieceIndex, SegmentCommitment, SegmentHeader, SegmentIndex};
 use subspace_rpc_primitives::{
-    FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo, SolutionResponse,
+    ArchivedObjectMappings, FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo,
+    SolutionResponse,
 };
 
 /// To become error type agnostic
@@ -44,6 +48,11 @@ pub trait NodeClient: Clone + Send + Sync + 'static {
         &self,
     ) -> Result<Pin<Box<dyn Stream<Item = SegmentHeader> + Send + 'static>>, Error>;
 
+    /// Subscribe to object mappings of archived segments
+    async fn subscribe_archived_object_mappings(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = ArchivedObjectMappings> + Send + 'static>>, Error>;
+
     /// Get segment commitments for the segments
     async fn segment_commitments(
         &self,
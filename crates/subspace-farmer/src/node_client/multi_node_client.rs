@@ -0,0 +1,623 @@
+#[cfg(test)]
+mod tests;
+
+use crate::node_client::node_rpc_client::NodeRpcClient;
+use crate::node_client::{Error, NodeClient};
+use async_trait::async_trait;
+use backoff::backoff::Backoff;
+use backoff::ExponentialBackoff;
+use futures::future::{join_all, select, Either};
+use futures::{stream, Stream, StreamExt};
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::future::Future;
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use subspace_core_primitives::{
+    Piece, PieceIndex, SegmentCommitment, SegmentHeader, SegmentIndex, SlotNumber,
+};
+use subspace_rpc_primitives::{
+    ArchivedObjectMappings, FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo,
+    SolutionResponse,
+};
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+
+/// How often connection to each node is checked
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Number of recent notifications remembered in order to filter out duplicates received from
+/// different nodes
+const RECENT_NOTIFICATIONS_CACHE_SIZE: NonZeroUsize =
+    NonZeroUsize::new(1_000).expect("Not zero; qed");
+/// Size of the buffer for notifications from all nodes
+const NOTIFICATIONS_BUFFER_SIZE: usize = 10;
+
+type NotificationStream<T> = Pin<Box<dyn Stream<Item = T> + Send + 'static>>;
+
+/// Node client that [`MultiNodeClient`] can (re-)connect to by URL
+#[async_trait]
+pub trait ConnectableNodeClient: NodeClient {
+    /// Connect to the node at provided URL
+    async fn connect(url: &str) -> Result<Self, Error>;
+
+    /// Whether connection to the node is still alive
+    fn is_connected(&self) -> bool;
+}
+
+#[async_trait]
+impl ConnectableNodeClient for NodeRpcClient {
+    async fn connect(url: &str) -> Result<Self, Error> {
+        Ok(NodeRpcClient::new(url).await?)
+    }
+
+    fn is_connected(&self) -> bool {
+        NodeRpcClient::is_connected(self)
+    }
+}
+
+struct Node<NC> {
+    url: String,
+    client: Mutex<Option<NC>>,
+}
+
+impl<NC> Node<NC>
+where
+    NC: ConnectableNodeClient,
+{
+    fn healthy_client(&self) -> Option<NC> {
+        self.client
+            .lock()
+            .as_ref()
+            .filter(|client| client.is_connected())
+            .cloned()
+    }
+}
+
+/// Nodes that delivered particular notification
+#[derive(Debug, Default)]
+struct Delivery {
+    /// Indexes of nodes in the order they delivered notification
+    nodes: Vec<usize>,
+    /// Whether notification was acknowledged by farmer already
+    acknowledged: bool,
+}
+
+type Deliveries<K> = Mutex<LruCache<K, Delivery>>;
+
+fn delivery_mut<K>(deliveries: &mut LruCache<K, Delivery>, key: K) -> &mut Delivery
+where
+    K: Hash + Eq + Copy,
+{
+    if !deliveries.contains(&key) {
+        deliveries.put(key, Delivery::default());
+    }
+
+    deliveries
+        .get_mut(&key)
+        .expect("Inserted above if didn't exist; qed")
+}
+
+/// Record that node delivered notification with provided key.
+///
+/// Returns whether notification was already acknowledged or `None` if delivery by this node was
+/// already recorded before.
+fn record_delivery<K>(deliveries: &Deliveries<K>, key: K, node_index: usize) -> Option<bool>
+where
+    K: Hash + Eq + Copy,
+{
+    let mut deliveries = deliveries.lock();
+    let delivery = delivery_mut(&mut deliveries, key);
+
+    if delivery.nodes.contains(&node_index) {
+        return None;
+    }
+
+    delivery.nodes.push(node_index);
+    Some(delivery.acknowledged)
+}
+
+/// Node client that is connected to multiple nodes at once, such that farming continues when one
+/// of the nodes is restarted or upgraded.
+///
+/// Notifications are received from all healthy nodes with duplicates filtered out, requests are
+/// sent to healthy nodes until one of them succeeds. Connections to nodes are re-established in
+/// the background with exponential backoff.
+///
+/// Nodes that delivered each slot and archived segment header are tracked, such that solutions are
+/// submitted to the node that issued the challenge and every node receives acknowledgement for its
+/// own archived segment header notification.
+pub struct MultiNodeClient<NC = NodeRpcClient> {
+    nodes: Arc<[Arc<Node<NC>>]>,
+    slot_deliveries: Arc<Deliveries<SlotNumber>>,
+    archived_segment_header_deliveries: Arc<Deliveries<SegmentIndex>>,
+}
+
+impl<NC> Clone for MultiNodeClient<NC> {
+    fn clone(&self) -> Self {
+        Self {
+            nodes: Arc::clone(&self.nodes),
+            slot_deliveries: Arc::clone(&self.slot_deliveries),
+            archived_segment_header_deliveries: Arc::clone(
+                &self.archived_segment_header_deliveries,
+            ),
+        }
+    }
+}
+
+impl MultiNodeClient {
+    /// Create a new instance connected to nodes with provided URLs, at least one node must be
+    /// reachable, connection to the rest will be established in the background.
+    pub async fn new(urls: Vec<String>) -> Result<Self, Error> {
+        Self::connect(urls).await
+    }
+}
+
+impl<NC> MultiNodeClient<NC>
+where
+    NC: ConnectableNodeClient,
+{
+    async fn connect(urls: Vec<String>) -> Result<Self, Error> {
+        let mut last_error = None;
+        let mut nodes = Vec::with_capacity(urls.len());
+
+        for url in urls {
+            let client = match NC::connect(&url).await {
+                Ok(client) => Some(client),
+                Err(error) => {
+                    warn!(%url, %error, "Failed to connect to node RPC, will retry later");
+                    last_error.replace(error);
+                    None
+                }
+            };
+
+            nodes.push((url, client));
+        }
+
+        if nodes.iter().all(|(_url, client)| client.is_none()) {
+            return Err(
+                last_error.unwrap_or_else(|| "At least one node RPC URL is required".into())
+            );
+        }
+
+        Ok(Self::from_clients(nodes))
+    }
+
+    /// Create a new instance from already established (or failed) connections to nodes
+    fn from_clients(clients: Vec<(String, Option<NC>)>) -> Self {
+        let nodes = clients
+            .into_iter()
+            .map(|(url, client)| {
+                Arc::new(Node {
+                    url,
+                    client: Mutex::new(client),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for node in &nodes {
+            tokio::spawn(maintain_connection(Arc::downgrade(node)));
+        }
+
+        Self {
+            nodes: nodes.into(),
+            slot_deliveries: Arc::new(Mutex::new(LruCache::new(RECENT_NOTIFICATIONS_CACHE_SIZE))),
+            archived_segment_header_deliveries: Arc::new(Mutex::new(LruCache::new(
+                RECENT_NOTIFICATIONS_CACHE_SIZE,
+            ))),
+        }
+    }
+
+    /// Healthy clients of nodes with provided indexes first (in the same order), followed by the
+    /// rest of healthy clients
+    fn healthy_clients(&self, preferred_node_indexes: &[usize]) -> Vec<NC> {
+        preferred_node_indexes
+            .iter()
+            .copied()
+            .chain(
+                (0..self.nodes.len())
+                    .filter(|node_index| !preferred_node_indexes.contains(node_index)),
+            )
+            .filter_map(|node_index| self.nodes.get(node_index)?.healthy_client())
+            .collect()
+    }
+
+    /// Send request to healthy nodes one by one until one of them succeeds, nodes with provided
+    /// indexes are tried first
+    async fn request_any<T, F, Fut>(
+        &self,
+        preferred_node_indexes: &[usize],
+        request: F,
+    ) -> Result<T, Error>
+    where
+        F: Fn(NC) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut last_error = None;
+
+        for client in self.healthy_clients(preferred_node_indexes) {
+            match request(client).await {
+                Ok(result) => {
+                    return Ok(result);
+                }
+                Err(error) => {
+                    debug!(%error, "Request to node failed, trying next one");
+                    last_error.replace(error);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| "None of the nodes are reachable".into()))
+    }
+
+    /// Send request to healthy nodes with provided indexes concurrently, succeeds if at least one
+    /// of them succeeded
+    async fn request_all<F, Fut>(&self, node_indexes: &[usize], request: F) -> Result<(), Error>
+    where
+        F: Fn(NC) -> Fut,
+        Fut: Future<Output = Result<(), Error>>,
+    {
+        let mut last_error = None;
+        let mut succeeded = false;
+
+        let clients = node_indexes
+            .iter()
+            .filter_map(|&node_index| self.nodes.get(node_index)?.healthy_client());
+
+        for result in join_all(clients.map(request)).await {
+            match result {
+                Ok(()) => {
+                    succeeded = true;
+                }
+                Err(error) => {
+                    debug!(%error, "Request to node failed");
+                    last_error.replace(error);
+                }
+            }
+        }
+
+        if succeeded {
+            Ok(())
+        } else {
+            Err(last_error.unwrap_or_else(|| "None of the nodes are reachable".into()))
+        }
+    }
+
+    fn all_node_indexes(&self) -> Vec<usize> {
+        (0..self.nodes.len()).collect()
+    }
+
+    /// Subscribe to notifications on all nodes (re-subscribing after reconnection), notifications
+    /// with the same key are only yielded once.
+    ///
+    /// `on_delivery` is called with index of the node for every notification received, including
+    /// duplicates.
+    fn subscribe_all<T, K, F, Fut, D>(
+        &self,
+        subscription: &'static str,
+        subscribe: F,
+        key: fn(&T) -> K,
+        on_delivery: D,
+    ) -> NotificationStream<T>
+    where
+        T: Send + 'static,
+        K: Hash + Eq + Send + 'static,
+        F: Fn(NC) -> Fut + Clone + Send + 'static,
+        Fut: Future<Output = Result<NotificationStream<T>, Error>> + Send,
+        D: Fn(usize, &T) + Send + 'static,
+    {
+        let (sender, mut receiver) = mpsc::channel(NOTIFICATIONS_BUFFER_SIZE);
+
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            tokio::spawn(forward_notifications(
+                Arc::downgrade(node),
+                node_index,
+                subscription,
+                subscribe.clone(),
+                sender.clone(),
+            ));
+        }
+
+        let mut recent_notifications = LruCache::new(RECENT_NOTIFICATIONS_CACHE_SIZE);
+
+        Box::pin(
+            stream::poll_fn(move |cx| receiver.poll_recv(cx)).filter_map(
+                move |(node_index, notification)| {
+                    on_delivery(node_index, &notification);
+                    let new = recent_notifications.put(key(&notification), ()).is_none();
+
+                    async move { new.then_some(notification) }
+                },
+            ),
+        )
+    }
+}
+
+/// Check connection to the node periodically and reconnect with exponential backoff when it is
+/// lost, exits when node client is dropped
+async fn maintain_connection<NC>(node: Weak<Node<NC>>)
+where
+    NC: ConnectableNodeClient,
+{
+    let mut backoff = ExponentialBackoff {
+        max_elapsed_time: None,
+        ..ExponentialBackoff::default()
+    };
+
+    loop {
+        let Some(node) = node.upgrade() else {
+            return;
+        };
+
+        let delay = if node.healthy_client().is_some() {
+            backoff.reset();
+            HEALTH_CHECK_INTERVAL
+        } else {
+            if node.client.lock().take().is_some() {
+                warn!(url = %node.url, "Lost connection to node RPC, reconnecting");
+            }
+
+            match NC::connect(&node.url).await {
+                Ok(client) => {
+                    info!(url = %node.url, "Connected to node RPC");
+                    node.client.lock().replace(client);
+                    backoff.reset();
+                    HEALTH_CHECK_INTERVAL
+                }
+                Err(error) => {
+                    let delay = backoff.next_backoff().unwrap_or(backoff.max_interval);
+                    debug!(
+                        url = %node.url,
+                        %error,
+                        ?delay,
+                        "Failed to connect to node RPC, retrying later"
+                    );
+                    delay
+                }
+            }
+        };
+
+        drop(node);
+        sleep(delay).await;
+    }
+}
+
+/// Forward notifications of one node to the sender together with node index, re-subscribing after
+/// reconnection, exits when receiver or node client is dropped
+async fn forward_notifications<NC, T, F, Fut>(
+    node: Weak<Node<NC>>,
+    node_index: usize,
+    subscription: &'static str,
+    subscribe: F,
+    sender: mpsc::Sender<(usize, T)>,
+) where
+    NC: ConnectableNodeClient,
+    F: Fn(NC) -> Fut,
+    Fut: Future<Output = Result<NotificationStream<T>, Error>>,
+{
+    loop {
+        if sender.is_closed() {
+            return;
+        }
+
+        let (url, client) = {
+            let Some(node) = node.upgrade() else {
+                return;
+            };
+
+            (node.url.clone(), node.healthy_client())
+        };
+
+        if let Some(client) = client {
+            match subscribe(client).await {
+                Ok(mut notifications) => {
+                    debug!(%url, %subscription, "Subscribed to node notifications");
+
+                    loop {
+                        match select(notifications.next(), Box::pin(sender.closed())).await {
+                            Either::Left((Some(notification), _)) => {
+                                if sender.send((node_index, notification)).await.is_err() {
+                                    return;
+                                }
+                            }
+                            Either::Left((None, _)) => {
+                                debug!(%url, %subscription, "Node subscription ended");
+                                break;
+                            }
+                            Either::Right(_) => {
+                                return;
+                            }
+                        }
+                    }
+                }
+                Err(error) => {
+                    debug!(%url, %subscription, %error, "Failed to subscribe to node notifications");
+                }
+            }
+        }
+
+        sleep(HEALTH_CHECK_INTERVAL).await;
+    }
+}
+
+#[async_trait]
+impl<NC> NodeClient for MultiNodeClient<NC>
+where
+    NC: ConnectableNodeClient,
+{
+    async fn farmer_app_info(&self) -> Result<FarmerAppInfo, Error> {
+        self.request_any(&[], |client| async move { client.farmer_app_info().await })
+            .await
+    }
+
+    async fn subscribe_slot_info(&self) -> Result<NotificationStream<SlotInfo>, Error> {
+        let slot_deliveries = Arc::clone(&self.slot_deliveries);
+
+        Ok(self.subscribe_all(
+            "slot_info",
+            |client| async move { client.subscribe_slot_info().await },
+            |slot_info| slot_info.slot_number,
+            move |node_index, slot_info| {
+                record_delivery(&slot_deliveries, slot_info.slot_number, node_index);
+            },
+        ))
+    }
+
+    /// Solution is only submitted to one node, preferably the one that delivered the slot first.
+    /// Every node that receives the solution may produce a block with it and multiple blocks with
+    /// the same solution in the same slot are considered an equivocation, so other nodes are only
+    /// tried if submission fails.
+    async fn submit_solution_response(
+        &self,
+        solution_response: SolutionResponse,
+    ) -> Result<(), Error> {
+        let node_indexes = self
+            .slot_deliveries
+            .lock()
+            .peek(&solution_response.slot_number)
+            .map(|delivery| delivery.nodes.clone())
+            .unwrap_or_default();
+
+        self.request_any(&node_indexes, |client| {
+            let solution_response = solution_response.clone();
+
+            async move { client.submit_solution_response(solution_response).await }
+        })
+        .await
+    }
+
+    async fn subscribe_reward_signing(
+        &self,
+    ) -> Result<NotificationStream<RewardSigningInfo>, Error> {
+        Ok(self.subscribe_all(
+            "reward_signing",
+            |client| async move { client.subscribe_reward_signing().await },
+            |reward_signing_info| reward_signing_info.hash,
+            |_node_index, _reward_signing_info| {},
+        ))
+    }
+
+    /// Signature is submitted to all nodes since it is not known which of them requested it
+    async fn submit_reward_signature(
+        &self,
+        reward_signature: RewardSignatureResponse,
+    ) -> Result<(), Error> {
+        self.request_all(&self.all_node_indexes(), |client| async move {
+            client.submit_reward_signature(reward_signature).await
+        })
+        .await
+    }
+
+    async fn subscribe_archived_segment_headers(
+        &self,
+    ) -> Result<NotificationStream<SegmentHeader>, Error> {
+        let nodes = Arc::clone(&self.nodes);
+        let archived_segment_header_deliveries =
+            Arc::clone(&self.archived_segment_header_deliveries);
+
+        Ok(self.subscribe_all(
+            "archived_segment_headers",
+            |client| async move { client.subscribe_archived_segment_headers().await },
+            |segment_header| segment_header.segment_index(),
+            move |node_index, segment_header| {
+                let segment_index = segment_header.segment_index();
+
+                let acknowledged = record_delivery(
+                    &archived_segment_header_deliveries,
+                    segment_index,
+                    node_index,
+                );
+
+                // Farmer has already processed this segment header when it was delivered by
+                // another node, so this node is acknowledged right away
+                if acknowledged == Some(true) {
+                    if let Some(client) =
+                        nodes.get(node_index).and_then(|node| node.healthy_client())
+                    {
+                        tokio::spawn(async move {
+                            if let Err(error) = client
+                                .acknowledge_archived_segment_header(segment_index)
+                                .await
+                            {
+                                debug!(
+                                    %error,
+                                    %segment_index,
+                                    "Failed to acknowledge archived segment header"
+                                );
+                            }
+                        });
+                    }
+                }
+            },
+        ))
+    }
+
+    async fn subscribe_archived_object_mappings(
+        &self,
+    ) -> Result<NotificationStream<ArchivedObjectMappings>, Error> {
+        Ok(self.subscribe_all(
+            "archived_object_mappings",
+            |client| async move { client.subscribe_archived_object_mappings().await },
+            |archived_object_mappings| archived_object_mappings.segment_index,
+            |_node_index, _archived_object_mappings| {},
+        ))
+    }
+
+    async fn segment_commitments(
+        &self,
+        segment_indexes: Vec<SegmentIndex>,
+    ) -> Result<Vec<Option<SegmentCommitment>>, Error> {
+        self.request_any(&[], |client| {
+            let segment_indexes = segment_indexes.clone();
+
+            async move { client.segment_commitments(segment_indexes).await }
+        })
+        .await
+    }
+
+    async fn segment_headers(
+        &self,
+        segment_indexes: Vec<SegmentIndex>,
+    ) -> Result<Vec<Option<SegmentHeader>>, Error> {
+        self.request_any(&[], |client| {
+            let segment_indexes = segment_indexes.clone();
+
+            async move { client.segment_headers(segment_indexes).await }
+        })
+        .await
+    }
+
+    async fn piece(&self, piece_index: PieceIndex) -> Result<Option<Piece>, Error> {
+        self.request_any(&[], |client| async move { client.piece(piece_index).await })
+            .await
+    }
+
+    /// Every node waits for acknowledgement of its own notification, so acknowledgement is sent
+    /// to all nodes that delivered archived segment header so far, the rest are acknowledged as
+    /// soon as their notification arrives
+    async fn acknowledge_archived_segment_header(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> Result<(), Error> {
+        let node_indexes = {
+            let mut deliveries = self.archived_segment_header_deliveries.lock();
+            let delivery = delivery_mut(&mut deliveries, segment_index);
+            delivery.acknowledged = true;
+            delivery.nodes.clone()
+        };
+
+        if node_indexes.is_empty() {
+            return Ok(());
+        }
+
+        self.request_all(&node_indexes, |client| async move {
+            client
+                .acknowledge_archived_segment_header(segment_index)
+                .await
+        })
+        .await
+    }
+}
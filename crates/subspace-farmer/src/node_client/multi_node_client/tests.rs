@@ -0,0 +1,169 @@
+use crate::node_client::multi_node_client::MultiNodeClient;
+use crate::node_client::test_node_client::{TestNode, TestNodeClient};
+use crate::node_client::NodeClient;
+use futures::StreamExt;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use subspace_core_primitives::{
+    ArchivedBlockProgress, LastArchivedBlock, SegmentCommitment, SegmentHeader, SegmentIndex,
+    SlotNumber,
+};
+use subspace_rpc_primitives::{SlotInfo, SolutionResponse};
+use tokio::time::{sleep, timeout};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn multi_node_client(nodes: &[&TestNode]) -> MultiNodeClient<TestNodeClient> {
+    MultiNodeClient::from_clients(
+        nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (format!("test-node-{index}"), Some(node.client.clone())))
+            .collect(),
+    )
+}
+
+fn slot_info(slot_number: SlotNumber) -> SlotInfo {
+    SlotInfo {
+        slot_number,
+        global_challenge: [0; 32],
+        solution_range: 0,
+        voting_solution_range: 0,
+    }
+}
+
+fn segment_header(segment_index: SegmentIndex) -> SegmentHeader {
+    SegmentHeader::V0 {
+        segment_index,
+        segment_commitment: SegmentCommitment::default(),
+        prev_segment_header_hash: [0; 32],
+        last_archived_block: LastArchivedBlock {
+            number: 0,
+            archived_progress: ArchivedBlockProgress::Complete,
+        },
+    }
+}
+
+async fn wait_for<F>(condition: F)
+where
+    F: Fn() -> bool,
+{
+    timeout(TIMEOUT, async {
+        while !condition() {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn archived_segment_headers_are_acknowledged_per_node() {
+    let node_a = TestNode::new(None);
+    let node_b = TestNode::new(None);
+    let node_client = multi_node_client(&[&node_a, &node_b]);
+
+    let mut archived_segment_headers = node_client
+        .subscribe_archived_segment_headers()
+        .await
+        .unwrap();
+
+    node_a
+        .archived_segment_headers_sender
+        .unbounded_send(segment_header(SegmentIndex::ZERO))
+        .unwrap();
+    let received_segment_header = timeout(TIMEOUT, archived_segment_headers.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received_segment_header.segment_index(), SegmentIndex::ZERO);
+
+    // Only the node that delivered segment header is acknowledged
+    node_client
+        .acknowledge_archived_segment_header(SegmentIndex::ZERO)
+        .await
+        .unwrap();
+    assert_eq!(
+        *node_a.client.acknowledged_segments.lock(),
+        vec![SegmentIndex::ZERO]
+    );
+    assert!(node_b.client.acknowledged_segments.lock().is_empty());
+
+    // Duplicate from another node is not yielded, but that node is acknowledged right away
+    node_b
+        .archived_segment_headers_sender
+        .unbounded_send(segment_header(SegmentIndex::ZERO))
+        .unwrap();
+    assert!(
+        timeout(Duration::from_millis(500), archived_segment_headers.next())
+            .await
+            .is_err()
+    );
+    wait_for(|| *node_b.client.acknowledged_segments.lock() == vec![SegmentIndex::ZERO]).await;
+    assert_eq!(
+        *node_a.client.acknowledged_segments.lock(),
+        vec![SegmentIndex::ZERO]
+    );
+
+    // Next segment header is yielded regardless of the node that delivered it first
+    node_b
+        .archived_segment_headers_sender
+        .unbounded_send(segment_header(SegmentIndex::ONE))
+        .unwrap();
+    let received_segment_header = timeout(TIMEOUT, archived_segment_headers.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received_segment_header.segment_index(), SegmentIndex::ONE);
+}
+
+#[tokio::test]
+async fn solutions_are_submitted_to_node_that_delivered_slot() {
+    let node_a = TestNode::new(None);
+    let node_b = TestNode::new(None);
+    let node_client = multi_node_client(&[&node_a, &node_b]);
+
+    let mut slot_info_notifications = node_client.subscribe_slot_info().await.unwrap();
+
+    node_b
+        .slot_info_sender
+        .unbounded_send(slot_info(1))
+        .unwrap();
+    let received_slot_info = timeout(TIMEOUT, slot_info_notifications.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received_slot_info.slot_number, 1);
+
+    node_client
+        .submit_solution_response(SolutionResponse {
+            slot_number: 1,
+            solutions: Vec::new(),
+        })
+        .await
+        .unwrap();
+    assert!(node_a.client.submitted_solutions.lock().is_empty());
+    assert_eq!(*node_b.client.submitted_solutions.lock(), vec![1]);
+
+    // Another healthy node is used when the one that delivered the slot is not reachable
+    node_b.client.connected.store(false, Ordering::Release);
+    node_client
+        .submit_solution_response(SolutionResponse {
+            slot_number: 1,
+            solutions: Vec::new(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(*node_a.client.submitted_solutions.lock(), vec![1]);
+    assert_eq!(*node_b.client.submitted_solutions.lock(), vec![1]);
+
+    // Nothing can be submitted when none of the nodes are reachable
+    node_a.client.connected.store(false, Ordering::Release);
+    assert!(node_client
+        .submit_solution_response(SolutionResponse {
+            slot_number: 1,
+            solutions: Vec::new(),
+        })
+        .await
+        .is_err());
+}
@@ -9,7 +9,8 @@ use std::pin::Pin;
 use std::sync::Arc;
 use subspace_core_primitives::{Piece, PieceIndex, SegmentCommitment, SegmentHeader, SegmentIndex};
 use subspace_rpc_primitives::{
-    FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo, SolutionResponse,
+    ArchivedObjectMappings, FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo,
+    SolutionResponse,
 };
 
 // Defines max_concurrent_requests constant in the node rpc client.
@@ -34,6 +35,11 @@ impl NodeRpcClient {
         );
         Ok(Self { client })
     }
+
+    /// Whether connection to the node is still alive.
+    pub fn is_connected(&self) -> bool {
+        self.client.is_connected()
+    }
 }
 
 #[async_trait]
@@ -123,6 +129,24 @@ impl NodeClient for NodeRpcClient {
         )))
     }
 
+    async fn subscribe_archived_object_mappings(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = ArchivedObjectMappings> + Send + 'static>>, RpcError>
+    {
+        let subscription = self
+            .client
+            .subscribe(
+                "subspace_subscribeArchivedObjectMappings",
+                rpc_params![],
+                "subspace_unsubscribeArchivedObjectMappings",
+            )
+            .await?;
+
+        Ok(Box::pin(subscription.filter_map(
+            |archived_object_mappings_result| async move { archived_object_mappings_result.ok() },
+        )))
+    }
+
     async fn segment_commitments(
         &self,
         segment_indexes: Vec<SegmentIndex>,
@@ -0,0 +1,194 @@
+//! In-memory node client used by tests of the farmer library and binary.
+
+use crate::node_client::multi_node_client::ConnectableNodeClient;
+use crate::node_client::{Error, NodeClient};
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use futures::{stream, Stream};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use subspace_core_primitives::{
+    Piece, PieceIndex, SegmentCommitment, SegmentHeader, SegmentIndex, SlotNumber,
+};
+use subspace_rpc_primitives::{
+    ArchivedObjectMappings, FarmerAppInfo, RewardSignatureResponse, RewardSigningInfo, SlotInfo,
+    SolutionResponse,
+};
+
+type NotificationsReceiver<T> = Arc<Mutex<Option<mpsc::UnboundedReceiver<T>>>>;
+
+fn notifications<T>(
+    receiver: &NotificationsReceiver<T>,
+) -> Pin<Box<dyn Stream<Item = T> + Send + 'static>>
+where
+    T: Send + 'static,
+{
+    match receiver.lock().take() {
+        Some(receiver) => Box::pin(receiver),
+        None => Box::pin(stream::pending()),
+    }
+}
+
+/// Node client that records requests it receives, notifications are sent manually through
+/// [`TestNode`], subscriptions are pending forever otherwise.
+#[derive(Clone, Default)]
+pub struct TestNodeClient {
+    farmer_app_info: Option<FarmerAppInfo>,
+    /// Whether client is connected to the node
+    pub connected: Arc<AtomicBool>,
+    slot_info_receiver: NotificationsReceiver<SlotInfo>,
+    archived_segment_headers_receiver: NotificationsReceiver<SegmentHeader>,
+    archived_object_mappings_receiver: NotificationsReceiver<ArchivedObjectMappings>,
+    /// Slot numbers of submitted solution responses
+    pub submitted_solutions: Arc<Mutex<Vec<SlotNumber>>>,
+    /// Acknowledged archived segments
+    pub acknowledged_segments: Arc<Mutex<Vec<SegmentIndex>>>,
+    /// Segment commitments known to the node, others are not found
+    pub segment_commitments: Arc<Mutex<HashMap<SegmentIndex, SegmentCommitment>>>,
+}
+
+impl TestNodeClient {
+    /// Create connected client that returns provided farmer app info (if any)
+    pub fn new(farmer_app_info: Option<FarmerAppInfo>) -> Self {
+        Self {
+            farmer_app_info,
+            connected: Arc::new(AtomicBool::new(true)),
+            ..Self::default()
+        }
+    }
+}
+
+#[async_trait]
+impl NodeClient for TestNodeClient {
+    async fn farmer_app_info(&self) -> Result<FarmerAppInfo, Error> {
+        self.farmer_app_info
+            .clone()
+            .ok_or_else(|| "Not supported".into())
+    }
+
+    async fn subscribe_slot_info(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = SlotInfo> + Send + 'static>>, Error> {
+        Ok(notifications(&self.slot_info_receiver))
+    }
+
+    async fn submit_solution_response(
+        &self,
+        solution_response: SolutionResponse,
+    ) -> Result<(), Error> {
+        self.submitted_solutions
+            .lock()
+            .push(solution_response.slot_number);
+        Ok(())
+    }
+
+    async fn subscribe_reward_signing(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = RewardSigningInfo> + Send + 'static>>, Error> {
+        Ok(Box::pin(stream::pending()))
+    }
+
+    async fn submit_reward_signature(
+        &self,
+        _reward_signature: RewardSignatureResponse,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn subscribe_archived_segment_headers(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = SegmentHeader> + Send + 'static>>, Error> {
+        Ok(notifications(&self.archived_segment_headers_receiver))
+    }
+
+    async fn subscribe_archived_object_mappings(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = ArchivedObjectMappings> + Send + 'static>>, Error> {
+        Ok(notifications(&self.archived_object_mappings_receiver))
+    }
+
+    async fn segment_commitments(
+        &self,
+        segment_indexes: Vec<SegmentIndex>,
+    ) -> Result<Vec<Option<SegmentCommitment>>, Error> {
+        let segment_commitments = self.segment_commitments.lock();
+
+        Ok(segment_indexes
+            .iter()
+            .map(|segment_index| segment_commitments.get(segment_index).copied())
+            .collect())
+    }
+
+    async fn segment_headers(
+        &self,
+        segment_indexes: Vec<SegmentIndex>,
+    ) -> Result<Vec<Option<SegmentHeader>>, Error> {
+        Ok(vec![None; segment_indexes.len()])
+    }
+
+    async fn piece(&self, _piece_index: PieceIndex) -> Result<Option<Piece>, Error> {
+        Ok(None)
+    }
+
+    async fn acknowledge_archived_segment_header(
+        &self,
+        segment_index: SegmentIndex,
+    ) -> Result<(), Error> {
+        self.acknowledged_segments.lock().push(segment_index);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ConnectableNodeClient for TestNodeClient {
+    async fn connect(_url: &str) -> Result<Self, Error> {
+        Err("Not supported".into())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Acquire)
+    }
+}
+
+/// Test node with client connected to it, notifications sent through senders are delivered to
+/// the first subscriber of the client, notification streams end once senders are dropped.
+pub struct TestNode {
+    /// Client connected to this node
+    pub client: TestNodeClient,
+    /// Sender of slot info notifications
+    pub slot_info_sender: mpsc::UnboundedSender<SlotInfo>,
+    /// Sender of archived segment headers notifications
+    pub archived_segment_headers_sender: mpsc::UnboundedSender<SegmentHeader>,
+    /// Sender of archived object mappings notifications
+    pub archived_object_mappings_sender: mpsc::UnboundedSender<ArchivedObjectMappings>,
+}
+
+impl TestNode {
+    /// Create new test node, client returns provided farmer app info (if any)
+    pub fn new(farmer_app_info: Option<FarmerAppInfo>) -> Self {
+        let (slot_info_sender, slot_info_receiver) = mpsc::unbounded();
+        let (archived_segment_headers_sender, archived_segment_headers_receiver) =
+            mpsc::unbounded();
+        let (archived_object_mappings_sender, archived_object_mappings_receiver) =
+            mpsc::unbounded();
+
+        Self {
+            client: TestNodeClient {
+                slot_info_receiver: Arc::new(Mutex::new(Some(slot_info_receiver))),
+                archived_segment_headers_receiver: Arc::new(Mutex::new(Some(
+                    archived_segment_headers_receiver,
+                ))),
+                archived_object_mappings_receiver: Arc::new(Mutex::new(Some(
+                    archived_object_mappings_receiver,
+                ))),
+                ..TestNodeClient::new(farmer_app_info)
+            },
+            slot_info_sender,
+            archived_segment_headers_sender,
+            archived_object_mappings_sender,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Signing of block rewards.
+//!
+//! Farmer only needs public key for plotting and farming, secret key is only used for signing
+//! rewards of solutions that were included in blocks. [`RewardSigner`] abstracts signing such that
+//! secret key doesn't need to be present on the machine that does plotting and farming, see
+//! [`remote`] module for signer that runs as a separate process.
+
+pub mod remote;
+
+use crate::identity::Identity;
+use async_trait::async_trait;
+use std::error::Error;
+use std::sync::Arc;
+use subspace_core_primitives::{Blake2b256Hash, PublicKey, RewardSignature};
+
+/// Signer of block rewards
+#[async_trait]
+pub trait RewardSigner: Send + Sync + 'static {
+    /// Public key that is used for plotting and included in solutions
+    fn public_key(&self) -> PublicKey;
+
+    /// Sign reward hash of the block (or vote) that includes solution of this farmer
+    async fn sign_reward_hash(
+        &self,
+        hash: Blake2b256Hash,
+    ) -> Result<RewardSignature, Box<dyn Error + Send + Sync + 'static>>;
+}
+
+#[async_trait]
+impl<T> RewardSigner for Arc<T>
+where
+    T: RewardSigner + ?Sized,
+{
+    fn public_key(&self) -> PublicKey {
+        self.as_ref().public_key()
+    }
+
+    async fn sign_reward_hash(
+        &self,
+        hash: Blake2b256Hash,
+    ) -> Result<RewardSignature, Box<dyn Error + Send + Sync + 'static>> {
+        self.as_ref().sign_reward_hash(hash).await
+    }
+}
+
+/// Local signer that uses identity loaded into farmer process
+#[async_trait]
+impl RewardSigner for Identity {
+    fn public_key(&self) -> PublicKey {
+        Identity::public_key(self).to_bytes().into()
+    }
+
+    async fn sign_reward_hash(
+        &self,
+        hash: Blake2b256Hash,
+    ) -> Result<RewardSignature, Box<dyn Error + Send + Sync + 'static>> {
+        Ok(Identity::sign_reward_hash(self, &hash).to_bytes().into())
+    }
+}
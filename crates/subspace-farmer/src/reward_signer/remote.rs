@@ -0,0 +1,556 @@
+//! Reward signer running as a standalone process, reached over Unix socket or TCP.
+//!
+//! Connection is not encrypted, so TCP is only allowed on loopback interface (for platforms without
+//! Unix sockets). Signer on another machine should be reached through Unix socket forwarded with
+//! SSH or similar tool that provides encryption.
+//!
+//! Protocol is newline-delimited JSON: client sends [`SignerRequest`] and signer replies with
+//! [`SignerResponse`] to every request in the same order they were received. Reward signing
+//! requests carry [`RewardSigningInfo`] received from the node and responses carry
+//! [`RewardSignatureResponse`] that is sent back to the node as is.
+//!
+//! Before any requests are processed farmer must authenticate itself: signer sends
+//! [`SignerChallenge`] with random bytes as the first message on every connection and farmer
+//! replies with [`SignerRequest::Authenticate`] containing keyed hash of the challenge, where key
+//! is [`SharedSecret`] shared between signer and farmers. Connections that fail to authenticate
+//! within [`AUTHENTICATION_TIMEOUT`] are closed.
+
+#[cfg(test)]
+mod tests;
+
+use crate::reward_signer::RewardSigner;
+use crate::utils::shared_secret::{SharedSecret, CHALLENGE_LENGTH};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::fs;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fmt, io};
+use subspace_core_primitives::{Blake2b256Hash, PublicKey, RewardSignature};
+use subspace_rpc_primitives::{RewardSignatureResponse, RewardSigningInfo};
+use thiserror::Error;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufStream};
+use tokio::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+/// Maximum size of a single message including trailing newline, all messages are much smaller
+const MAX_MESSAGE_SIZE: u64 = 4096;
+/// Time farmer has to authenticate itself after connecting to signer
+pub const AUTHENTICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Challenge sent by signer as the first message on every connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignerChallenge {
+    /// Random bytes that farmer needs to authenticate with [`SharedSecret`]
+    #[serde(with = "hex::serde")]
+    pub challenge: [u8; CHALLENGE_LENGTH],
+}
+
+/// Request sent to remote signer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignerRequest {
+    /// Authenticate with keyed hash of the challenge, must be the first request on every
+    /// connection
+    Authenticate(#[serde(with = "hex::serde")] Blake2b256Hash),
+    /// Request public key of the signer
+    PublicKey,
+    /// Request signature of the reward hash
+    SignReward(RewardSigningInfo),
+}
+
+/// Response of remote signer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignerResponse {
+    /// Farmer was authenticated successfully
+    Authenticated,
+    /// Public key of the signer
+    PublicKey(#[serde(with = "hex::serde")] [u8; 32]),
+    /// Signature of the reward hash, signature is missing if public key in request didn't match
+    /// signer's public key or signing failed
+    RewardSignature(RewardSignatureResponse),
+}
+
+/// Address of remote signer
+#[derive(Debug, Clone)]
+pub enum RewardSignerAddress {
+    /// TCP socket address on loopback interface, for instance `127.0.0.1:9977`
+    Tcp(SocketAddr),
+    /// Path to Unix socket, for instance `unix:/run/subspace-signer.sock`
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl fmt::Display for RewardSignerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(address) => write!(f, "{address}"),
+            #[cfg(unix)]
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for RewardSignerAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("unix:") {
+            #[cfg(unix)]
+            return Ok(Self::Unix(PathBuf::from(path)));
+            #[cfg(not(unix))]
+            return Err(format!(
+                "Unix sockets are not supported on this platform, can't use {path}"
+            ));
+        }
+
+        let address = s
+            .parse::<SocketAddr>()
+            .map_err(|error| format!("Failed to parse signer address \"{s}\": {error}"))?;
+        let address = Self::Tcp(address);
+        address.check().map_err(|error| error.to_string())?;
+
+        Ok(address)
+    }
+}
+
+impl RewardSignerAddress {
+    /// Connection to signer is not encrypted, so only local addresses are allowed
+    fn check(&self) -> io::Result<()> {
+        match self {
+            Self::Tcp(address) => {
+                if address.ip().is_loopback() {
+                    Ok(())
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "Signer connection is not encrypted, TCP address {address} must be on \
+                            loopback interface, use Unix socket forwarded over SSH for remote \
+                            signer instead"
+                        ),
+                    ))
+                }
+            }
+            #[cfg(unix)]
+            Self::Unix(_path) => Ok(()),
+        }
+    }
+}
+
+/// Errors happening when communicating with remote signer
+#[derive(Debug, Error)]
+pub enum RemoteRewardSignerError {
+    /// I/O error occurred
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Failed to encode or decode message
+    #[error("Failed to encode or decode message: {0}")]
+    Json(#[from] serde_json::Error),
+    /// Connection was closed by the other side
+    #[error("Connection was closed by the other side")]
+    ConnectionClosed,
+    /// Message is larger than allowed
+    #[error("Message is larger than {MAX_MESSAGE_SIZE} bytes")]
+    MessageTooLarge,
+    /// Response doesn't correspond to the request
+    #[error("Response doesn't correspond to the request")]
+    UnexpectedResponse,
+    /// Signer refused to sign reward hash
+    #[error("Signer refused to sign reward hash")]
+    SignatureMissing,
+    /// Authentication failed, signer secret is likely different
+    #[error("Authentication failed, signer secret is likely different")]
+    AuthenticationFailed,
+    /// Farmer didn't authenticate in time
+    #[error("Farmer didn't authenticate in time")]
+    AuthenticationTimeout,
+}
+
+trait SignerStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> SignerStream for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+type Connection = BufStream<Box<dyn SignerStream>>;
+
+async fn connect(
+    address: &RewardSignerAddress,
+    secret: &SharedSecret,
+) -> Result<Connection, RemoteRewardSignerError> {
+    address.check()?;
+
+    let stream: Box<dyn SignerStream> = match address {
+        RewardSignerAddress::Tcp(address) => Box::new(TcpStream::connect(address).await?),
+        #[cfg(unix)]
+        RewardSignerAddress::Unix(path) => Box::new(UnixStream::connect(path).await?),
+    };
+
+    let mut connection = BufStream::new(stream);
+    authenticate(&mut connection, secret).await?;
+
+    Ok(connection)
+}
+
+async fn authenticate<S>(
+    stream: &mut BufStream<S>,
+    secret: &SharedSecret,
+) -> Result<(), RemoteRewardSignerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let SignerChallenge { challenge } = read_message(stream)
+        .await?
+        .ok_or(RemoteRewardSignerError::ConnectionClosed)?;
+
+    // Signer closes connection if authentication fails
+    match request(
+        stream,
+        &SignerRequest::Authenticate(secret.authenticate(&challenge)),
+    )
+    .await
+    {
+        Ok(SignerResponse::Authenticated) => Ok(()),
+        Ok(_) => Err(RemoteRewardSignerError::UnexpectedResponse),
+        Err(RemoteRewardSignerError::ConnectionClosed) => {
+            Err(RemoteRewardSignerError::AuthenticationFailed)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+async fn write_message<S, T>(
+    stream: &mut BufStream<S>,
+    message: &T,
+) -> Result<(), RemoteRewardSignerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line).await?;
+    stream.flush().await?;
+
+    Ok(())
+}
+
+/// Returns `Ok(None)` if connection was closed
+async fn read_message<S, T>(stream: &mut BufStream<S>) -> Result<Option<T>, RemoteRewardSignerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: for<'de> Deserialize<'de>,
+{
+    let mut line = String::new();
+    let bytes_read = stream.take(MAX_MESSAGE_SIZE).read_line(&mut line).await?;
+    if bytes_read == 0 {
+        return Ok(None);
+    }
+    // Newline wasn't found within the limit
+    if bytes_read as u64 == MAX_MESSAGE_SIZE && !line.ends_with('\n') {
+        return Err(RemoteRewardSignerError::MessageTooLarge);
+    }
+
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+async fn request<S>(
+    stream: &mut BufStream<S>,
+    request: &SignerRequest,
+) -> Result<SignerResponse, RemoteRewardSignerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    write_message(stream, request).await?;
+
+    read_message(stream)
+        .await?
+        .ok_or(RemoteRewardSignerError::ConnectionClosed)
+}
+
+/// Reward signer that forwards signing requests to a standalone signer process
+pub struct RemoteRewardSigner {
+    address: RewardSignerAddress,
+    secret: SharedSecret,
+    public_key: PublicKey,
+    connection: Mutex<Option<Connection>>,
+}
+
+impl RemoteRewardSigner {
+    /// Connect to remote signer, authenticate with provided secret and retrieve its public key
+    pub async fn connect(
+        address: RewardSignerAddress,
+        secret: SharedSecret,
+    ) -> Result<Self, RemoteRewardSignerError> {
+        let mut connection = connect(&address, &secret).await?;
+
+        let public_key = match request(&mut connection, &SignerRequest::PublicKey).await? {
+            SignerResponse::PublicKey(public_key) => PublicKey::from(public_key),
+            SignerResponse::Authenticated | SignerResponse::RewardSignature(_) => {
+                return Err(RemoteRewardSignerError::UnexpectedResponse);
+            }
+        };
+
+        Ok(Self {
+            address,
+            secret,
+            public_key,
+            connection: Mutex::new(Some(connection)),
+        })
+    }
+
+    async fn request(
+        &self,
+        signer_request: &SignerRequest,
+    ) -> Result<SignerResponse, RemoteRewardSignerError> {
+        let mut connection = self.connection.lock().await;
+
+        let mut stream = match connection.take() {
+            Some(stream) => stream,
+            None => {
+                debug!(address = %self.address, "Reconnecting to remote signer");
+                connect(&self.address, &self.secret).await?
+            }
+        };
+
+        let result = request(&mut stream, signer_request).await;
+
+        // Broken connection is dropped, new one will be established on next request
+        if result.is_ok() {
+            connection.replace(stream);
+        }
+
+        result
+    }
+}
+
+#[async_trait]
+impl RewardSigner for RemoteRewardSigner {
+    fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    async fn sign_reward_hash(
+        &self,
+        hash: Blake2b256Hash,
+    ) -> Result<RewardSignature, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let response = self
+            .request(&SignerRequest::SignReward(RewardSigningInfo {
+                hash,
+                public_key: self.public_key.into(),
+            }))
+            .await?;
+
+        match response {
+            SignerResponse::RewardSignature(RewardSignatureResponse {
+                hash: signed_hash,
+                signature,
+            }) if signed_hash == hash => {
+                Ok(signature.ok_or(RemoteRewardSignerError::SignatureMissing)?)
+            }
+            _ => Err(RemoteRewardSignerError::UnexpectedResponse.into()),
+        }
+    }
+}
+
+/// Serve signing requests of remote farmers authenticated with provided secret using provided
+/// signer, runs until I/O error occurs while accepting connections
+pub async fn run_reward_signer_server<RS>(
+    address: &RewardSignerAddress,
+    reward_signer: RS,
+    secret: SharedSecret,
+) -> io::Result<()>
+where
+    RS: RewardSigner,
+{
+    address.check()?;
+
+    let reward_signer = Arc::new(reward_signer);
+    let secret = Arc::new(secret);
+
+    match address {
+        RewardSignerAddress::Tcp(address) => {
+            let listener = TcpListener::bind(address).await?;
+            info!(%address, "Reward signer listening on TCP socket");
+
+            loop {
+                let (stream, peer_address) = listener.accept().await?;
+                debug!(%peer_address, "Accepted reward signer connection");
+
+                tokio::spawn(handle_connection(
+                    stream,
+                    Arc::clone(&reward_signer),
+                    Arc::clone(&secret),
+                ));
+            }
+        }
+        #[cfg(unix)]
+        RewardSignerAddress::Unix(path) => {
+            remove_stale_socket(path).await?;
+            let listener = UnixListener::bind(path)?;
+            info!(path = %path.display(), "Reward signer listening on Unix socket");
+
+            loop {
+                let (stream, _peer_address) = listener.accept().await?;
+                debug!("Accepted reward signer connection");
+
+                tokio::spawn(handle_connection(
+                    stream,
+                    Arc::clone(&reward_signer),
+                    Arc::clone(&secret),
+                ));
+            }
+        }
+    }
+}
+
+/// Socket file is left behind when signer is not shut down cleanly and prevents binding to the
+/// same path again, so it is removed unless another signer is still listening on it
+#[cfg(unix)]
+async fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return Ok(());
+        }
+        Err(error) => {
+            return Err(error);
+        }
+    };
+
+    if !metadata.file_type().is_socket() {
+        // Not a socket, let bind fail with a meaningful error
+        return Ok(());
+    }
+
+    if UnixStream::connect(path).await.is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("Another process is listening on {}", path.display()),
+        ));
+    }
+
+    debug!(path = %path.display(), "Removing stale Unix socket");
+    fs::remove_file(path)
+}
+
+async fn handle_connection<S, RS>(stream: S, reward_signer: Arc<RS>, secret: Arc<SharedSecret>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    RS: RewardSigner,
+{
+    let mut stream = BufStream::new(stream);
+
+    if let Err(error) = serve_connection(
+        &mut stream,
+        reward_signer.as_ref(),
+        &secret,
+        AUTHENTICATION_TIMEOUT,
+    )
+    .await
+    {
+        warn!(%error, "Reward signer connection failed");
+    }
+}
+
+/// Authenticate farmer and process its requests until connection is closed
+async fn serve_connection<S, RS>(
+    stream: &mut BufStream<S>,
+    reward_signer: &RS,
+    secret: &SharedSecret,
+    authentication_timeout: Duration,
+) -> Result<(), RemoteRewardSignerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    RS: RewardSigner,
+{
+    let challenge = rand::random::<[u8; CHALLENGE_LENGTH]>();
+    let authentication = async {
+        write_message(stream, &SignerChallenge { challenge }).await?;
+        read_message::<_, SignerRequest>(stream).await
+    };
+    // Unauthenticated connections are not allowed to occupy signer indefinitely
+    let authentication_request = timeout(authentication_timeout, authentication)
+        .await
+        .map_err(|_error| RemoteRewardSignerError::AuthenticationTimeout)??;
+
+    // Challenge is unique for every connection, so there is nothing to learn from timing of the
+    // comparison below
+    match authentication_request {
+        Some(SignerRequest::Authenticate(proof)) if proof == secret.authenticate(&challenge) => {
+            write_message(stream, &SignerResponse::Authenticated).await?;
+        }
+        Some(_) => {
+            return Err(RemoteRewardSignerError::AuthenticationFailed);
+        }
+        None => {
+            return Ok(());
+        }
+    }
+
+    process_requests(stream, reward_signer).await
+}
+
+async fn process_requests<S, RS>(
+    stream: &mut BufStream<S>,
+    reward_signer: &RS,
+) -> Result<(), RemoteRewardSignerError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    RS: RewardSigner,
+{
+    while let Some(signer_request) = read_message::<_, SignerRequest>(stream).await? {
+        let response = match signer_request {
+            SignerRequest::Authenticate(_) => {
+                // Already authenticated
+                SignerResponse::Authenticated
+            }
+            SignerRequest::PublicKey => {
+                SignerResponse::PublicKey(reward_signer.public_key().into())
+            }
+            SignerRequest::SignReward(RewardSigningInfo { hash, public_key }) => {
+                let signature = if PublicKey::from(public_key) == reward_signer.public_key() {
+                    match reward_signer.sign_reward_hash(hash).await {
+                        Ok(signature) => {
+                            info!("Signed reward hash 0x{}", hex::encode(hash));
+                            Some(signature)
+                        }
+                        Err(error) => {
+                            warn!(
+                                %error,
+                                "Failed to sign reward hash 0x{}",
+                                hex::encode(hash),
+                            );
+                            None
+                        }
+                    }
+                } else {
+                    warn!(
+                        "Refusing to sign reward hash 0x{} for unknown public key 0x{}",
+                        hex::encode(hash),
+                        hex::encode(public_key),
+                    );
+                    None
+                };
+
+                SignerResponse::RewardSignature(RewardSignatureResponse { hash, signature })
+            }
+        };
+
+        write_message(stream, &response).await?;
+    }
+
+    Ok(())
+}
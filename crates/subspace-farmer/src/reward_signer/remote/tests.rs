@@ -0,0 +1,238 @@
+use crate::identity::Identity;
+use crate::reward_signer::remote::{
+    authenticate, read_message, request, serve_connection, RemoteRewardSignerError,
+    RewardSignerAddress, SignerChallenge, SignerRequest, SignerResponse, AUTHENTICATION_TIMEOUT,
+    MAX_MESSAGE_SIZE,
+};
+use crate::reward_signer::RewardSigner;
+use crate::utils::shared_secret::SharedSecret;
+use schnorrkel::Signature;
+use std::time::Duration;
+use subspace_core_primitives::PublicKey;
+use subspace_rpc_primitives::{RewardSignatureResponse, RewardSigningInfo};
+use subspace_solving::REWARD_SIGNING_CONTEXT;
+use tempfile::TempDir;
+use tokio::io::{duplex, AsyncWriteExt, BufStream};
+
+#[tokio::test]
+async fn signer_roundtrip() {
+    let directory = TempDir::new().unwrap();
+    let identity = Identity::create(directory.path(), None).unwrap();
+    let secret = SharedSecret::generate();
+
+    let (client, server) = duplex(1024);
+    let server = tokio::spawn({
+        let identity = identity.clone();
+        let secret = secret.clone();
+
+        async move {
+            serve_connection(
+                &mut BufStream::new(server),
+                &identity,
+                &secret,
+                AUTHENTICATION_TIMEOUT,
+            )
+            .await
+        }
+    });
+
+    let mut client = BufStream::new(client);
+    authenticate(&mut client, &secret).await.unwrap();
+
+    let public_key = match request(&mut client, &SignerRequest::PublicKey)
+        .await
+        .unwrap()
+    {
+        SignerResponse::PublicKey(public_key) => PublicKey::from(public_key),
+        response => panic!("Unexpected response {response:?}"),
+    };
+    assert_eq!(public_key, RewardSigner::public_key(&identity));
+
+    let hash = [1; 32];
+    let signature = match request(
+        &mut client,
+        &SignerRequest::SignReward(RewardSigningInfo {
+            hash,
+            public_key: public_key.into(),
+        }),
+    )
+    .await
+    .unwrap()
+    {
+        SignerResponse::RewardSignature(RewardSignatureResponse {
+            hash: signed_hash,
+            signature,
+        }) => {
+            assert_eq!(signed_hash, hash);
+            signature.unwrap()
+        }
+        response => panic!("Unexpected response {response:?}"),
+    };
+    identity
+        .public_key()
+        .verify(
+            schnorrkel::signing_context(REWARD_SIGNING_CONTEXT).bytes(&hash),
+            &Signature::from_bytes(signature.as_ref()).unwrap(),
+        )
+        .unwrap();
+
+    // Signer refuses to sign for public key it doesn't have
+    match request(
+        &mut client,
+        &SignerRequest::SignReward(RewardSigningInfo {
+            hash,
+            public_key: [2; 32],
+        }),
+    )
+    .await
+    .unwrap()
+    {
+        SignerResponse::RewardSignature(RewardSignatureResponse { signature, .. }) => {
+            assert!(signature.is_none());
+        }
+        response => panic!("Unexpected response {response:?}"),
+    }
+
+    // Server finishes once client disconnects
+    drop(client);
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn signer_rejects_wrong_secret() {
+    let directory = TempDir::new().unwrap();
+    let identity = Identity::create(directory.path(), None).unwrap();
+
+    let (client, server) = duplex(1024);
+    let server = tokio::spawn({
+        let secret = SharedSecret::generate();
+
+        async move {
+            serve_connection(
+                &mut BufStream::new(server),
+                &identity,
+                &secret,
+                AUTHENTICATION_TIMEOUT,
+            )
+            .await
+        }
+    });
+
+    let mut client = BufStream::new(client);
+    assert!(matches!(
+        authenticate(&mut client, &SharedSecret::generate()).await,
+        Err(RemoteRewardSignerError::AuthenticationFailed)
+    ));
+    assert!(matches!(
+        server.await.unwrap(),
+        Err(RemoteRewardSignerError::AuthenticationFailed)
+    ));
+}
+
+#[tokio::test]
+async fn signer_rejects_unauthenticated_requests() {
+    let directory = TempDir::new().unwrap();
+    let identity = Identity::create(directory.path(), None).unwrap();
+
+    let (client, server) = duplex(1024);
+    let server = tokio::spawn({
+        let secret = SharedSecret::generate();
+
+        async move {
+            serve_connection(
+                &mut BufStream::new(server),
+                &identity,
+                &secret,
+                AUTHENTICATION_TIMEOUT,
+            )
+            .await
+        }
+    });
+
+    let mut client = BufStream::new(client);
+    // Challenge is ignored and request is sent right away
+    assert!(matches!(
+        request(&mut client, &SignerRequest::PublicKey).await,
+        Err(RemoteRewardSignerError::Json(_))
+    ));
+    assert!(matches!(
+        server.await.unwrap(),
+        Err(RemoteRewardSignerError::AuthenticationFailed)
+    ));
+}
+
+#[tokio::test]
+async fn signer_closes_connection_without_authentication() {
+    let directory = TempDir::new().unwrap();
+    let identity = Identity::create(directory.path(), None).unwrap();
+
+    let (client, server) = duplex(1024);
+    let server = tokio::spawn(async move {
+        serve_connection(
+            &mut BufStream::new(server),
+            &identity,
+            &SharedSecret::generate(),
+            Duration::from_millis(100),
+        )
+        .await
+    });
+
+    // Client connects, but never authenticates
+    let mut client = BufStream::new(client);
+    assert!(read_message::<_, SignerChallenge>(&mut client)
+        .await
+        .unwrap()
+        .is_some());
+    assert!(matches!(
+        server.await.unwrap(),
+        Err(RemoteRewardSignerError::AuthenticationTimeout)
+    ));
+}
+
+#[tokio::test]
+async fn signer_rejects_large_messages() {
+    let directory = TempDir::new().unwrap();
+    let identity = Identity::create(directory.path(), None).unwrap();
+
+    let (client, server) = duplex(1024);
+    let server = tokio::spawn(async move {
+        serve_connection(
+            &mut BufStream::new(server),
+            &identity,
+            &SharedSecret::generate(),
+            AUTHENTICATION_TIMEOUT,
+        )
+        .await
+    });
+
+    // Message without newline is not buffered indefinitely, write might fail since server closes
+    // connection before reading everything
+    let mut client = BufStream::new(client);
+    let _ = client
+        .write_all(&vec![b' '; MAX_MESSAGE_SIZE as usize + 1])
+        .await;
+    let _ = client.flush().await;
+    assert!(matches!(
+        server.await.unwrap(),
+        Err(RemoteRewardSignerError::MessageTooLarge)
+    ));
+}
+
+#[test]
+fn signer_tcp_address_must_be_loopback() {
+    assert!(matches!(
+        "127.0.0.1:9977".parse::<RewardSignerAddress>(),
+        Ok(RewardSignerAddress::Tcp(_))
+    ));
+    assert!(matches!(
+        "[::1]:9977".parse::<RewardSignerAddress>(),
+        Ok(RewardSignerAddress::Tcp(_))
+    ));
+    assert!("0.0.0.0:9977".parse::<RewardSignerAddress>().is_err());
+    assert!("192.168.1.2:9977".parse::<RewardSignerAddress>().is_err());
+    #[cfg(unix)]
+    assert!(matches!(
+        "unix:/run/subspace-signer.sock".parse::<RewardSignerAddress>(),
+        Ok(RewardSignerAddress::Unix(_))
+    ));
+}
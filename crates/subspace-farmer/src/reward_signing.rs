@@ -1,16 +1,20 @@
-use crate::identity::Identity;
 use crate::node_client::NodeClient;
+use crate::reward_signer::RewardSigner;
 use futures::StreamExt;
 use std::future::Future;
+use subspace_core_primitives::{Blake2b256Hash, PublicKey};
 use subspace_rpc_primitives::{RewardSignatureResponse, RewardSigningInfo};
 use tracing::{info, warn};
 
-pub async fn reward_signing<NC>(
+pub async fn reward_signing<NC, RS, F>(
     node_client: NC,
-    identity: Identity,
+    reward_signer: RS,
+    on_reward_signed: F,
 ) -> Result<impl Future<Output = ()>, Box<dyn std::error::Error + Send + Sync>>
 where
     NC: NodeClient,
+    RS: RewardSigner,
+    F: Fn(&Blake2b256Hash) + Send + 'static,
 {
     info!("Subscribing to reward signing notifications");
 
@@ -21,21 +25,28 @@ where
             reward_signing_info_notifications.next().await
         {
             // Multiple plots might have solved, only sign with correct one
-            if identity.public_key().to_bytes() != public_key {
+            if reward_signer.public_key() != PublicKey::from(public_key) {
                 continue;
             }
 
-            let signature = identity.sign_reward_hash(&hash);
+            let signature = match reward_signer.sign_reward_hash(hash).await {
+                Ok(signature) => signature,
+                Err(error) => {
+                    warn!(%error, "Failed to sign reward hash 0x{}", hex::encode(hash));
+                    continue;
+                }
+            };
 
             match node_client
                 .submit_reward_signature(RewardSignatureResponse {
                     hash,
-                    signature: Some(signature.to_bytes().into()),
+                    signature: Some(signature),
                 })
                 .await
             {
                 Ok(_) => {
                     info!("Successfully signed reward hash 0x{}", hex::encode(hash));
+                    on_reward_signed(&hash);
                 }
                 Err(error) => {
                     warn!(
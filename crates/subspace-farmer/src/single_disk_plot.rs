@@ -1,37 +1,61 @@
+pub mod benchmarking;
+pub mod farming;
+pub mod migration;
 pub mod piece_reader;
+pub mod remote_plotting;
+pub mod scrubbing;
+pub mod solution_verification;
+#[cfg(test)]
+mod tests;
 
 use crate::identity::Identity;
 use crate::node_client;
 use crate::node_client::NodeClient;
-use crate::reward_signing::reward_signing;
 use crate::single_disk_plot::auditing::audit_sector;
+use crate::single_disk_plot::benchmarking::{benchmark_plotting, BenchmarkResults};
+use crate::single_disk_plot::farming::{audit_and_prove, farmed_sectors, SlotSolutions};
+use crate::single_disk_plot::migration::{
+    plot_version_migration, MigratedSectors, MigrationFiles, PlotMigration, PlotMigrationStatus,
+};
 use crate::single_disk_plot::piece_reader::{read_piece, PieceReader, ReadPieceRequest};
 use crate::single_disk_plot::plotting::{plot_sector, PlottedSector};
+use crate::single_disk_plot::remote_plotting::{RemotePlotter, RemotePlottingError};
+use crate::single_disk_plot::scrubbing::{scrub_sector, CorruptedSector, SectorCorruption};
+use crate::single_disk_plot::solution_verification::{retain_valid_solutions, SegmentCommitments};
+use crate::utils::disk_piece_cache::DiskPieceCache;
+use crate::utils::sector_index_allocator::SectorIndexAllocator;
 use crate::utils::JoinOnDrop;
 use bytesize::ByteSize;
 use derive_more::{Display, From};
 use event_listener_primitives::{Bag, HandlerId};
 use futures::channel::{mpsc, oneshot};
 use futures::future::{select, Either};
-use futures::stream::FuturesUnordered;
+use futures::stream::{self, FuturesUnordered};
 use futures::StreamExt;
 use memmap2::{Mmap, MmapOptions};
 use parity_scale_codec::{Decode, Encode};
 use parking_lot::{Mutex, RwLock};
+use rand::{thread_rng, RngCore};
+use rayon::prelude::*;
+use rayon::{ThreadPoolBuildError, ThreadPoolBuilder};
 use serde::{Deserialize, Serialize};
 use static_assertions::const_assert;
-use std::fs::OpenOptions;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
 use std::future::Future;
 use std::io::{Seek, SeekFrom};
-use std::num::NonZeroU16;
+use std::num::{NonZeroU16, NonZeroUsize};
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
-use std::time::SystemTime;
-use std::{fmt, fs, io, thread};
+use std::time::{Duration, Instant};
+use std::{fmt, fs, hint, io, mem, process, thread};
 use std_semaphore::{Semaphore, SemaphoreGuard};
 use subspace_core_primitives::crypto::kzg::Kzg;
-use subspace_core_primitives::{PieceOffset, PublicKey, SectorId, SectorIndex, Solution};
+use subspace_core_primitives::{
+    Blake2b256Hash, PieceOffset, PublicKey, SectorId, SectorIndex, SegmentCommitment, SegmentIndex,
+    SlotNumber, SolutionRange,
+};
 use subspace_erasure_coding::ErasureCoding;
 use subspace_farmer_components::file_ext::FileExt;
 use subspace_farmer_components::piece_caching::PieceMemoryCache;
@@ -56,12 +80,6 @@ const_assert!(std::mem::size_of::<usize>() >= std::mem::size_of::<u64>());
 /// Reserve 1M of space for plot metadata (for potential future expansion)
 const RESERVED_PLOT_METADATA: u64 = 1024 * 1024;
 
-/// Self-imposed limit for number of solutions that farmer will not go over per challenge.
-///
-/// Only useful for initial network bootstrapping where due to initial plot size there might be too
-/// many solutions.
-const SOLUTIONS_LIMIT: usize = 1;
-
 /// Semaphore that limits disk access concurrency in strategic places to the number specified during
 /// initialization
 #[derive(Clone)]
@@ -214,6 +232,14 @@ impl SingleDiskPlotInfo {
         *first_sector_index
     }
 
+    /// Update first sector index in this plot, only valid if all plotted sectors are discarded
+    pub fn set_first_sector_index(&mut self, new_first_sector_index: SectorIndex) {
+        let Self::V0 {
+            first_sector_index, ..
+        } = self;
+        *first_sector_index = new_first_sector_index;
+    }
+
     /// How many pieces does one sector contain.
     pub fn pieces_in_sector(&self) -> u16 {
         let Self::V0 {
@@ -229,6 +255,22 @@ impl SingleDiskPlotInfo {
         } = self;
         *allocated_space
     }
+
+    /// Update how much space in bytes is allocated for this plot
+    pub fn set_allocated_space(&mut self, new_allocated_space: u64) {
+        let Self::V0 {
+            allocated_space, ..
+        } = self;
+        *allocated_space = new_allocated_space;
+    }
+
+    /// Update how many pieces does one sector contain, only valid after plot migration
+    pub fn set_pieces_in_sector(&mut self, new_pieces_in_sector: u16) {
+        let Self::V0 {
+            pieces_in_sector, ..
+        } = self;
+        *pieces_in_sector = new_pieces_in_sector;
+    }
 }
 
 /// Summary of single disk plot for presentational purposes
@@ -245,6 +287,13 @@ pub enum SingleDiskPlotSummary {
         /// Path to directory where plot is stored.
         directory: PathBuf,
     },
+    /// Plot is in use by another process
+    InUse {
+        /// Path to directory where plot is stored.
+        directory: PathBuf,
+        /// PID of the process that uses plot, if known
+        pid: Option<u32>,
+    },
     /// Failed to open plot
     Error {
         /// Path to directory where plot is stored.
@@ -272,6 +321,87 @@ impl PlotMetadataHeader {
     }
 }
 
+/// Advisory exclusive lock of single disk plot directory, released on drop
+#[derive(Debug)]
+pub(crate) struct SingleDiskPlotLock {
+    _file: File,
+}
+
+impl SingleDiskPlotLock {
+    pub(crate) const FILE_NAME: &'static str = "single_disk_plot.lock";
+
+    /// Take exclusive lock of the directory, PID of current process is stored in lock file such
+    /// that it can be reported if another process tries to take the same lock
+    pub(crate) fn acquire(directory: &Path) -> Result<Self, SingleDiskPlotError> {
+        let lock_file_path = directory.join(Self::FILE_NAME);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&lock_file_path)?;
+
+        if let Err(error) = fs2::FileExt::try_lock_exclusive(&file) {
+            if error.raw_os_error() == fs2::lock_contended_error().raw_os_error() {
+                // Lock file might not be readable on some platforms while locked
+                let pid = fs::read_to_string(&lock_file_path)
+                    .ok()
+                    .and_then(|pid| pid.trim().parse().ok());
+
+                return Err(SingleDiskPlotError::AlreadyInUse {
+                    directory: directory.to_path_buf(),
+                    pid,
+                });
+            }
+
+            return Err(error.into());
+        }
+
+        file.set_len(0)?;
+        file.write_all_at(process::id().to_string().as_bytes(), 0)?;
+
+        Ok(Self { _file: file })
+    }
+
+    /// Check whether directory is locked by another process without taking the lock for longer
+    /// than the check itself and without modifying anything in the directory
+    pub(crate) fn check(directory: &Path) -> Result<(), SingleDiskPlotError> {
+        let lock_file_path = directory.join(Self::FILE_NAME);
+        let file = match File::open(&lock_file_path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                // Directory was never locked
+                return Ok(());
+            }
+            Err(error) => {
+                return Err(error.into());
+            }
+        };
+
+        if let Err(error) = fs2::FileExt::try_lock_exclusive(&file) {
+            if error.raw_os_error() == fs2::lock_contended_error().raw_os_error() {
+                let pid = fs::read_to_string(&lock_file_path)
+                    .ok()
+                    .and_then(|pid| pid.trim().parse().ok());
+
+                // Lock held by current process doesn't make directory in use by another process
+                if pid == Some(process::id()) {
+                    return Ok(());
+                }
+
+                return Err(SingleDiskPlotError::AlreadyInUse {
+                    directory: directory.to_path_buf(),
+                    pid,
+                });
+            }
+
+            return Err(error.into());
+        }
+
+        // Lock is released when file is closed
+        Ok(())
+    }
+}
+
 /// Options used to open single dis plot
 pub struct SingleDiskPlotOptions<NC, PG> {
     /// Path to directory where plot are stored.
@@ -286,6 +416,13 @@ pub struct SingleDiskPlotOptions<NC, PG> {
     pub node_client: NC,
     /// Address where farming rewards should go
     pub reward_address: PublicKey,
+    /// Public key of the reward signer (see [`RewardSigner`](crate::reward_signer::RewardSigner))
+    /// used for plotting.
+    ///
+    /// Plot doesn't sign rewards itself, since the same signer can be shared by multiple plots,
+    /// rewards should be signed once per signer with
+    /// [`reward_signing()`](crate::reward_signing::reward_signing) instead.
+    pub public_key: PublicKey,
     /// Piece receiver implementation for plotting purposes.
     pub piece_getter: PG,
     /// Kzg instance to use.
@@ -294,8 +431,42 @@ pub struct SingleDiskPlotOptions<NC, PG> {
     pub erasure_coding: ErasureCoding,
     /// Semaphore to limit concurrency of plotting process.
     pub concurrent_plotting_semaphore: Arc<tokio::sync::Semaphore>,
+    /// Number of sectors that can be plotted concurrently within this plot.
+    pub sector_plotting_concurrency: NonZeroUsize,
+    /// Number of threads used for auditing and proving in this plot, defaults to number of CPU
+    /// cores if not specified.
+    pub farming_thread_pool_size: Option<NonZeroUsize>,
+    /// Self-imposed limit for number of solutions that farmer will not go over per challenge.
+    ///
+    /// Values above one allow submitting extra solutions as votes.
+    pub solutions_limit: NonZeroUsize,
+    /// Policy for selecting solutions when there are more candidates than solutions limit
+    pub solution_selection_policy: SolutionSelectionPolicy,
+    /// Allocator of sector indexes for newly created plots
+    pub sector_index_allocator: SectorIndexAllocator,
     /// Additional memory cache for pieces from archival storage
     pub piece_memory_cache: PieceMemoryCache,
+    /// Plotting servers to send sectors to for plotting instead of plotting them locally
+    pub remote_plotter: Option<RemotePlotter>,
+    /// Replot sectors that produced invalid solutions (solutions are verified before submission
+    /// and invalid ones are never submitted regardless of this option)
+    pub replot_invalid_sectors: bool,
+    /// Indexes of archived segments, used to find expired sectors.
+    ///
+    /// Plot doesn't acknowledge archived segments to the node, this is the responsibility of the
+    /// sender, which should only send segment index after it is done with the segment itself.
+    pub archived_segments: broadcast::Receiver<SegmentIndex>,
+}
+
+/// Policy for selecting solutions when there are more of them than can be submitted
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum SolutionSelectionPolicy {
+    /// Sectors are proven in the order they are stored in the plot
+    #[default]
+    First,
+    /// Solution candidates closest to the challenge are proven first, regardless of which sectors
+    /// they belong to
+    ClosestToChallenge,
 }
 
 /// Errors happening when trying to create/open single disk plot
@@ -305,19 +476,6 @@ pub enum SingleDiskPlotError {
     /// I/O error occurred
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
-    /// Can't resize plot after creation
-    #[error(
-        "Usable plotting space of plot {id} {new_space} is different from {old_space} when plot \
-        was created, resizing isn't supported yet"
-    )]
-    CantResize {
-        /// Plot ID
-        id: SingleDiskPlotId,
-        /// Space allocated during plot creation
-        old_space: ByteSize,
-        /// New desired plot size
-        new_space: ByteSize,
-    },
     /// Wrong chain (genesis hash)
     #[error(
         "Genesis hash of plot {id} {wrong_chain} is different from {correct_chain} when plot was \
@@ -358,6 +516,31 @@ pub enum SingleDiskPlotError {
         /// Number of pieces in sector plot is initialized with
         initialized_with: u16,
     },
+    /// Invalid number of pieces in sector to migrate plot to
+    #[error(
+        "Invalid number of pieces in sector to migrate plot to: max supported {max_supported}, \
+        migration target {target}"
+    )]
+    InvalidMigrationPiecesInSector {
+        /// Plot ID
+        id: SingleDiskPlotId,
+        /// Max supported pieces in sector
+        max_supported: u16,
+        /// Number of pieces in sector plot is migrated to
+        target: u16,
+    },
+    /// Single disk plot is already in use by another process
+    #[error(
+        "Single disk plot at {} is already in use by {}",
+        .directory.display(),
+        .pid.map_or_else(|| "another process".to_string(), |pid| format!("process with PID {pid}"))
+    )]
+    AlreadyInUse {
+        /// Path to directory where plot is stored
+        directory: PathBuf,
+        /// PID of the process that holds the lock, if known
+        pid: Option<u32>,
+    },
     /// Failed to decode metadata header
     #[error("Failed to decode metadata header: {0}")]
     FailedToDecodeMetadataHeader(parity_scale_codec::Error),
@@ -367,6 +550,12 @@ pub enum SingleDiskPlotError {
     /// Unexpected metadata version
     #[error("Unexpected metadata version {0}")]
     UnexpectedMetadataVersion(u8),
+    /// Failed to create thread pool
+    #[error("Failed to create thread pool: {0}")]
+    FailedToCreateThreadPool(#[from] ThreadPoolBuildError),
+    /// Failed to plot sector during benchmarking
+    #[error("Failed to plot sector during benchmarking: {0}")]
+    FailedToBenchmarkPlotting(#[from] PlottingError),
     /// Allocated space is not enough for one sector
     #[error(
         "Allocated space is not enough for one sector. \
@@ -396,6 +585,9 @@ pub enum PlottingError {
     /// Low-level plotting error
     #[error("Low-level plotting error: {0}")]
     LowLevel(#[from] plotting::PlottingError),
+    /// Remote plotting error
+    #[error("Remote plotting error: {0}")]
+    RemotePlotting(#[from] RemotePlottingError),
 }
 
 /// Errors that happen during farming
@@ -451,8 +643,46 @@ type Handler<A> = Bag<HandlerFn<A>, A>;
 
 #[derive(Default, Debug)]
 struct Handlers {
-    sector_plotted: Handler<(usize, PlottedSector, Arc<OwnedSemaphorePermit>)>,
+    sector_plotted: Handler<(
+        usize,
+        PlottedSector,
+        Option<PlottedSector>,
+        Arc<OwnedSemaphorePermit>,
+    )>,
+    sector_plotting_finished: Handler<SectorPlottingDetails>,
     solution: Handler<SolutionResponse>,
+    slot_farmed: Handler<SlotFarmingDetails>,
+    slot_skipped: Handler<SlotNumber>,
+}
+
+/// Details about plotting of a single sector
+#[derive(Debug, Copy, Clone)]
+pub struct SectorPlottingDetails {
+    /// Sector offset in the plot
+    pub sector_offset: usize,
+    /// Sector index
+    pub sector_index: SectorIndex,
+    /// Whether expired sector was replotted
+    pub replotting: bool,
+    /// Time it took to plot the sector
+    pub plotting_duration: Duration,
+}
+
+/// Details about farming of a single slot
+#[derive(Debug, Copy, Clone)]
+pub struct SlotFarmingDetails {
+    /// Slot number
+    pub slot: SlotNumber,
+    /// Number of sectors audited
+    pub sector_count: usize,
+    /// Number of sectors that had solution candidates after auditing
+    pub candidate_sector_count: usize,
+    /// Time it took to audit all sectors
+    pub audit_duration: Duration,
+    /// Time it took to create solutions out of candidates
+    pub proving_duration: Duration,
+    /// Number of solutions submitted to the node
+    pub solution_count: usize,
 }
 
 /// Single disk plot abstraction is a container for everything necessary to plot/farm with a single
@@ -465,7 +695,11 @@ pub struct SingleDiskPlot {
     single_disk_plot_info: SingleDiskPlotInfo,
     /// Metadata of all sectors plotted so far
     sectors_metadata: Arc<RwLock<Vec<SectorMetadata>>>,
-    pieces_in_sector: u16,
+    /// Sectors migrated so far if migration is in progress, they replace sectors with the same
+    /// offsets in `sectors_metadata`
+    migrated_sectors: Option<Arc<RwLock<MigratedSectors>>>,
+    /// Number of sectors plot will have once fully plotted
+    total_sectors_count: usize,
     span: Span,
     tasks: FuturesUnordered<BackgroundTask>,
     handlers: Arc<Handlers>,
@@ -477,6 +711,8 @@ pub struct SingleDiskPlot {
     start_sender: Option<broadcast::Sender<()>>,
     /// Sender that will be used to signal to background threads that they must stop
     stop_sender: Option<broadcast::Sender<()>>,
+    /// Lock of plot directory, must be dropped after background threads have exited
+    _single_disk_plot_lock: SingleDiskPlotLock,
 }
 
 impl Drop for SingleDiskPlot {
@@ -515,33 +751,34 @@ impl SingleDiskPlot {
             max_pieces_in_sector,
             node_client,
             reward_address,
+            public_key,
             piece_getter,
             kzg,
             erasure_coding,
             concurrent_plotting_semaphore,
+            sector_plotting_concurrency,
+            farming_thread_pool_size,
+            solutions_limit,
+            solution_selection_policy,
+            sector_index_allocator,
             piece_memory_cache,
+            remote_plotter,
+            replot_invalid_sectors,
+            mut archived_segments,
         } = options;
         fs::create_dir_all(&directory)?;
 
+        let single_disk_plot_lock = SingleDiskPlotLock::acquire(&directory)?;
+
         // TODO: Parametrize concurrency, much higher default due to SSD focus
         // TODO: Use this or remove
         let _single_disk_semaphore =
             SingleDiskSemaphore::new(NonZeroU16::new(10).expect("Not a zero; qed"));
 
-        // TODO: Update `Identity` to use more specific error type and remove this `.unwrap()`
-        let identity = Identity::open_or_create(&directory).unwrap();
-        let public_key = identity.public_key().to_bytes().into();
-
-        let single_disk_plot_info = match SingleDiskPlotInfo::load_from(&directory)? {
-            Some(single_disk_plot_info) => {
-                if allocated_space != single_disk_plot_info.allocated_space() {
-                    return Err(SingleDiskPlotError::CantResize {
-                        id: *single_disk_plot_info.id(),
-                        old_space: ByteSize::b(single_disk_plot_info.allocated_space()),
-                        new_space: ByteSize::b(allocated_space),
-                    });
-                }
+        let mut plot_migration = PlotMigration::load_from(&directory)?;
 
+        let mut single_disk_plot_info = match SingleDiskPlotInfo::load_from(&directory)? {
+            Some(mut single_disk_plot_info) => {
                 if &farmer_app_info.genesis_hash != single_disk_plot_info.genesis_hash() {
                     return Err(SingleDiskPlotError::WrongChain {
                         id: *single_disk_plot_info.id(),
@@ -560,21 +797,90 @@ impl SingleDiskPlot {
 
                 let pieces_in_sector = single_disk_plot_info.pieces_in_sector();
 
-                if max_pieces_in_sector < pieces_in_sector {
-                    return Err(SingleDiskPlotError::InvalidPiecesInSector {
-                        id: *single_disk_plot_info.id(),
-                        max_supported: max_pieces_in_sector,
-                        initialized_with: pieces_in_sector,
-                    });
+                match &plot_migration {
+                    Some(plot_migration) => {
+                        let target = plot_migration.target_pieces_in_sector();
+
+                        if max_pieces_in_sector < target {
+                            return Err(SingleDiskPlotError::InvalidMigrationPiecesInSector {
+                                id: *single_disk_plot_info.id(),
+                                max_supported: max_pieces_in_sector,
+                                target,
+                            });
+                        }
+                    }
+                    None => {
+                        if max_pieces_in_sector < pieces_in_sector {
+                            return Err(SingleDiskPlotError::InvalidPiecesInSector {
+                                id: *single_disk_plot_info.id(),
+                                max_supported: max_pieces_in_sector,
+                                initialized_with: pieces_in_sector,
+                            });
+                        }
+
+                        if max_pieces_in_sector > pieces_in_sector {
+                            info!(
+                                pieces_in_sector,
+                                max_pieces_in_sector,
+                                "Plot initialized with smaller number of pieces in sector, use \
+                                `migrate` command to increase"
+                            );
+                        }
+                    }
                 }
 
-                if max_pieces_in_sector > pieces_in_sector {
+                if let Some(new_first_sector_index) =
+                    sector_index_allocator.reassigned_first_sector_index(&directory)
+                {
+                    warn!(
+                        id = %single_disk_plot_info.id(),
+                        old_first_sector_index = %single_disk_plot_info.first_sector_index(),
+                        %new_first_sector_index,
+                        "Sector index range was reassigned, plot will be replotted from scratch"
+                    );
+
+                    // Sectors plotted so far are forgotten before the new range is stored, such
+                    // that sectors with old indexes are never farmed with the new range
+                    if plot_migration.take().is_some() {
+                        PlotMigration::abort(&directory)?;
+                    }
+                    let metadata_file_path = directory.join(Self::METADATA_FILE);
+                    if metadata_file_path.exists() {
+                        let metadata_header = PlotMetadataHeader {
+                            version: Self::SUPPORTED_PLOT_VERSION,
+                            sector_count: 0,
+                        };
+                        OpenOptions::new()
+                            .write(true)
+                            .open(metadata_file_path)?
+                            .write_all_at(metadata_header.encode().as_slice(), 0)?;
+                    }
+
+                    single_disk_plot_info.set_first_sector_index(new_first_sector_index);
+                    single_disk_plot_info.store_to(&directory)?;
+                }
+
+                if allocated_space != single_disk_plot_info.allocated_space() {
+                    let sector_size = sector_size(pieces_in_sector);
+
+                    if allocated_space / (sector_size as u64) == 0 {
+                        return Err(SingleDiskPlotError::InsufficientAllocatedSpace {
+                            min_size: sector_size,
+                            allocated_space,
+                        });
+                    }
+
                     info!(
-                        pieces_in_sector,
-                        max_pieces_in_sector,
-                        "Plot initialized with smaller number of pieces in sector, plot needs to \
-                        be re-created for increase"
+                        id = %single_disk_plot_info.id(),
+                        old_space = %ByteSize::b(single_disk_plot_info.allocated_space()),
+                        new_space = %ByteSize::b(allocated_space),
+                        "Allocated space changed, resizing plot"
                     );
+
+                    // Files are resized below according to allocated space in plot info, which
+                    // makes resizing resumable in case of interruption
+                    single_disk_plot_info.set_allocated_space(allocated_space);
+                    single_disk_plot_info.store_to(&directory)?;
                 }
 
                 single_disk_plot_info
@@ -591,13 +897,8 @@ impl SingleDiskPlot {
                     });
                 }
 
-                // TODO: Global generator that makes sure to avoid returning the same sector index
-                //  for multiple disks
-                let first_sector_index = SystemTime::UNIX_EPOCH
-                    .elapsed()
-                    .expect("Unix epoch is always in the past; qed")
-                    .as_secs()
-                    .wrapping_mul(u64::from(u32::MAX));
+                let first_sector_index =
+                    sector_index_allocator.allocate(&public_key, &directory)?;
 
                 let single_disk_plot_info = SingleDiskPlotInfo::new(
                     SingleDiskPlotId::new(),
@@ -614,32 +915,49 @@ impl SingleDiskPlot {
             }
         };
 
+        if let Some(migration) = plot_migration.take() {
+            let target_sector_count =
+                migration.target_sector_count(single_disk_plot_info.allocated_space());
+
+            if migration.is_completed()
+                || PlotMigration::migrated_sector_count(&directory)? >= target_sector_count as u64
+            {
+                info!(
+                    id = %single_disk_plot_info.id(),
+                    pieces_in_sector = migration.target_pieces_in_sector(),
+                    "All sectors migrated, replacing old plot"
+                );
+
+                migration.complete(&directory, &mut single_disk_plot_info)?;
+            } else {
+                plot_migration.replace(migration);
+            }
+        }
+
         let pieces_in_sector = single_disk_plot_info.pieces_in_sector();
-        let sector_size = sector_size(max_pieces_in_sector);
+        let sector_size = sector_size(pieces_in_sector);
         let sector_metadata_size = SectorMetadata::encoded_size();
         let target_sector_count =
             (single_disk_plot_info.allocated_space() / sector_size as u64) as usize;
         let first_sector_index = single_disk_plot_info.first_sector_index();
 
-        // TODO: Consider file locking to prevent other apps from modifying it
         let mut metadata_file = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
             .open(directory.join(Self::METADATA_FILE))?;
 
-        let (mut metadata_header, mut metadata_header_mmap) = if metadata_file
-            .seek(SeekFrom::End(0))?
-            == 0
-        {
+        let metadata_file_size = metadata_file.seek(SeekFrom::End(0))?;
+        let expected_metadata_file_size =
+            RESERVED_PLOT_METADATA + sector_metadata_size as u64 * target_sector_count as u64;
+
+        let (mut metadata_header, mut metadata_header_mmap) = if metadata_file_size == 0 {
             let metadata_header = PlotMetadataHeader {
                 version: 0,
                 sector_count: 0,
             };
 
-            metadata_file.preallocate(
-                RESERVED_PLOT_METADATA + sector_metadata_size as u64 * target_sector_count as u64,
-            )?;
+            metadata_file.preallocate(expected_metadata_file_size)?;
             metadata_file.write_all_at(metadata_header.encode().as_slice(), 0)?;
 
             let metadata_header_mmap = unsafe {
@@ -656,13 +974,51 @@ impl SingleDiskPlot {
                     .map_mut(&metadata_file)?
             };
 
-            let metadata_header = PlotMetadataHeader::decode(&mut metadata_header_mmap.as_ref())
-                .map_err(SingleDiskPlotError::FailedToDecodeMetadataHeader)?;
+            let mut metadata_header =
+                PlotMetadataHeader::decode(&mut metadata_header_mmap.as_ref())
+                    .map_err(SingleDiskPlotError::FailedToDecodeMetadataHeader)?;
 
             if metadata_header.version != Self::SUPPORTED_PLOT_VERSION {
-                return Err(SingleDiskPlotError::UnexpectedMetadataVersion(
-                    metadata_header.version,
-                ));
+                let plot_version_migration = match (
+                    &plot_migration,
+                    plot_version_migration(metadata_header.version),
+                ) {
+                    (Some(_), Some(plot_version_migration)) => plot_version_migration,
+                    _ => {
+                        return Err(SingleDiskPlotError::UnexpectedMetadataVersion(
+                            metadata_header.version,
+                        ));
+                    }
+                };
+
+                if !plot_version_migration.can_farm_old_sectors() {
+                    warn!(
+                        version = %metadata_header.version,
+                        "Plot version is not supported, only migrated sectors will be farmed \
+                        until migration is complete"
+                    );
+
+                    // Only used in memory, header on disk is left untouched
+                    metadata_header.sector_count = 0;
+                }
+            } else if metadata_header.sector_count > target_sector_count as u64 {
+                info!(
+                    old_sector_count = %metadata_header.sector_count,
+                    new_sector_count = %target_sector_count,
+                    "Plot was shrunk, removing trailing sectors"
+                );
+
+                // Sectors are plotted in order, so remaining sectors are still valid, they just
+                // need to be forgotten
+                metadata_header.sector_count = target_sector_count as u64;
+                metadata_header_mmap.copy_from_slice(metadata_header.encode().as_slice());
+                metadata_header_mmap.flush()?;
+            }
+
+            if metadata_file_size > expected_metadata_file_size {
+                metadata_file.set_len(expected_metadata_file_size)?;
+            } else {
+                metadata_file.preallocate(expected_metadata_file_size)?;
             }
 
             (metadata_header, metadata_header_mmap)
@@ -691,6 +1047,7 @@ impl SingleDiskPlot {
             Arc::new(RwLock::new(sectors_metadata))
         };
 
+        let metadata_file = Arc::new(metadata_file);
         let plot_file = Arc::new(
             OpenOptions::new()
                 .read(true)
@@ -699,7 +1056,28 @@ impl SingleDiskPlot {
                 .open(directory.join(Self::PLOT_FILE))?,
         );
 
-        plot_file.preallocate(sector_size as u64 * target_sector_count as u64)?;
+        let expected_plot_file_size = sector_size as u64 * target_sector_count as u64;
+        if plot_file.metadata()?.len() > expected_plot_file_size {
+            plot_file.set_len(expected_plot_file_size)?;
+        } else {
+            plot_file.preallocate(expected_plot_file_size)?;
+        }
+
+        let migration_files = plot_migration
+            .as_ref()
+            .map(|plot_migration| {
+                MigrationFiles::open(
+                    &directory,
+                    plot_migration,
+                    single_disk_plot_info.allocated_space(),
+                )
+            })
+            .transpose()?;
+        let migrated_sectors = migration_files
+            .as_ref()
+            .map(MigrationFiles::migrated_sectors)
+            .transpose()?
+            .map(|migrated_sectors| Arc::new(RwLock::new(migrated_sectors)));
 
         let (error_sender, error_receiver) = oneshot::channel();
         let error_sender = Arc::new(Mutex::new(Some(error_sender)));
@@ -717,19 +1095,55 @@ impl SingleDiskPlot {
         let handlers = Arc::<Handlers>::default();
         let (start_sender, mut start_receiver) = broadcast::channel::<()>(1);
         let (stop_sender, mut stop_receiver) = broadcast::channel::<()>(1);
+        // Offsets of sectors that are being overwritten with replotted sectors, such sectors are
+        // neither farmed nor read from, only modified while holding write lock of sectors metadata
+        let sectors_being_replaced = Arc::<Mutex<HashSet<usize>>>::default();
 
         let span = info_span!("single_disk_plot", %disk_farm_index);
 
+        let (archived_segments_sender, mut archived_segments_receiver) =
+            mpsc::unbounded::<SegmentIndex>();
+        // Sectors that produced invalid solutions are marked as expired at segment zero, so
+        // notification about it makes plotting thread replot them without waiting for the next
+        // archived segment
+        let invalid_sectors_sender = archived_segments_sender.clone();
+
+        tasks.push(Box::pin(async move {
+            loop {
+                let segment_index = match archived_segments.recv().await {
+                    Ok(segment_index) => segment_index,
+                    Err(broadcast::error::RecvError::Lagged(skipped_notifications)) => {
+                        // Only the latest segment matters for expiration
+                        debug!(skipped_notifications, "Archived segments receiver lagged");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        break;
+                    }
+                };
+                debug!(%segment_index, "New archived segment");
+
+                // Plotting thread doesn't replot during migration and might have exited
+                let _ = archived_segments_sender.unbounded_send(segment_index);
+            }
+
+            Ok(())
+        }));
+
         let plotting_join_handle = thread::Builder::new()
             .name(format!("plotting-{disk_farm_index}"))
             .spawn({
                 let handle = handle.clone();
                 let sectors_metadata = Arc::clone(&sectors_metadata);
+                let sectors_being_replaced = Arc::clone(&sectors_being_replaced);
+                let migrated_sectors = migrated_sectors.clone();
+                let farmer_protocol_info = farmer_app_info.protocol_info;
                 let kzg = kzg.clone();
                 let erasure_coding = erasure_coding.clone();
                 let handlers = Arc::clone(&handlers);
                 let node_client = node_client.clone();
                 let plot_file = Arc::clone(&plot_file);
+                let metadata_file = Arc::clone(&metadata_file);
                 let error_sender = Arc::clone(&error_sender);
                 let span = span.clone();
 
@@ -737,101 +1151,431 @@ impl SingleDiskPlot {
                     let _tokio_handle_guard = handle.enter();
                     let _span_guard = span.enter();
 
-                    // Initial plotting
-                    let initial_plotting_fut = async move {
+                    let plotting_fut = async move {
                         if start_receiver.recv().await.is_err() {
                             // Dropped before starting
                             return Ok(());
                         }
 
-                        // Some sectors may already be plotted, skip them
-                        let sectors_offsets_left_to_plot =
-                            metadata_header.sector_count as usize..target_sector_count;
+                        // Migration replaces the whole plot, so there is no point in plotting or
+                        // replotting sectors of the old plot while it is in progress, old sectors
+                        // are farmed until sectors with the same offsets are migrated
+                        if let Some((mut migration_files, migrated_sectors)) =
+                            migration_files.zip(migrated_sectors)
+                        {
+                            let target_pieces_in_sector = migration_files.pieces_in_sector();
+                            let sector_offsets_left_to_migrate =
+                                migration_files.sector_offsets_left_to_migrate();
+
+                            info!(
+                                target_pieces_in_sector,
+                                migrated_sectors = %sector_offsets_left_to_migrate.start,
+                                target_sector_count = %sector_offsets_left_to_migrate.end,
+                                "Migrating plot"
+                            );
 
-                        // TODO: Concurrency
-                        for sector_offset in sectors_offsets_left_to_plot {
-                            let sector_index = sector_offset as u64 + first_sector_index;
-                            trace!(%sector_offset, %sector_index, "Preparing to plot sector");
+                            for sector_offset in sector_offsets_left_to_migrate {
+                                let sector_index = sector_offset as u64 + first_sector_index;
 
-                            let mut sector = unsafe {
-                                MmapOptions::new()
-                                    .offset((sector_offset * sector_size) as u64)
-                                    .len(sector_size)
-                                    .map_mut(&*plot_file)?
-                            };
-                            let mut sector_metadata = unsafe {
-                                MmapOptions::new()
-                                    .offset(
-                                        RESERVED_PLOT_METADATA
-                                            + (sector_offset * sector_metadata_size) as u64,
-                                    )
-                                    .len(sector_metadata_size)
-                                    .map_mut(&metadata_file)?
-                            };
-                            let plotting_permit =
-                                match concurrent_plotting_semaphore.clone().acquire_owned().await {
+                                let plotting_permit = match concurrent_plotting_semaphore
+                                    .clone()
+                                    .acquire_owned()
+                                    .await
+                                {
                                     Ok(plotting_permit) => plotting_permit,
                                     Err(error) => {
                                         warn!(
                                             %sector_offset,
                                             %sector_index,
                                             %error,
-                                            "Semaphore was closed, interrupting plotting"
+                                            "Semaphore was closed, interrupting migration"
                                         );
                                         return Ok(());
                                     }
                                 };
 
-                            debug!(%sector_offset, %sector_index, "Plotting sector");
+                                debug!(%sector_offset, %sector_index, "Migrating sector");
+
+                                let plotting_start = Instant::now();
+
+                                let farmer_app_info =
+                                    node_client.farmer_app_info().await.map_err(|error| {
+                                        PlottingError::FailedToGetFarmerInfo { error }
+                                    })?;
+
+                                let mut sector = vec![0; sector_size(target_pieces_in_sector)];
+                                let mut sector_metadata = vec![0; sector_metadata_size];
+
+                                let plotted_sector =
+                                    plot_sector_locally_or_remotely::<_, _, PosTable>(
+                                        remote_plotter.as_ref(),
+                                        &node_client,
+                                        &public_key,
+                                        sector_offset,
+                                        sector_index,
+                                        &piece_getter,
+                                        &farmer_app_info.protocol_info,
+                                        &kzg,
+                                        &erasure_coding,
+                                        target_pieces_in_sector,
+                                        &mut sector,
+                                        &mut sector_metadata,
+                                        piece_memory_cache.clone(),
+                                    )
+                                    .await?;
+
+                                migration_files.write_sector(
+                                    sector_offset,
+                                    &sector,
+                                    &sector_metadata,
+                                )?;
+
+                                let plotting_duration = plotting_start.elapsed();
+
+                                // Lock order matches farming and reading, where sectors metadata
+                                // is locked before migrated sectors
+                                let old_plotted_sector = {
+                                    let sectors_metadata = sectors_metadata.read();
+                                    migrated_sectors
+                                        .write()
+                                        .sectors_metadata
+                                        .push(plotted_sector.sector_metadata.clone());
+
+                                    sectors_metadata.get(sector_offset).cloned().map(
+                                        |old_sector_metadata| {
+                                            plotted_sector_from_metadata(
+                                                &public_key,
+                                                old_sector_metadata,
+                                                &farmer_protocol_info,
+                                            )
+                                        },
+                                    )
+                                };
+
+                                info!(%sector_offset, %sector_index, "Sector migrated successfully");
+
+                                handlers.sector_plotting_finished.call_simple(
+                                    &SectorPlottingDetails {
+                                        sector_offset,
+                                        sector_index,
+                                        replotting: old_plotted_sector.is_some(),
+                                        plotting_duration,
+                                    },
+                                );
+
+                                handlers.sector_plotted.call_simple(&(
+                                    sector_offset,
+                                    plotted_sector,
+                                    old_plotted_sector,
+                                    Arc::new(plotting_permit),
+                                ));
+                            }
+
+                            info!(
+                                "Plot migration complete, migrated sectors are already farmed and \
+                                old plot will be replaced on restart"
+                            );
+
+                            return Ok(());
+                        }
+
+                        let sector_plotting_options = SectorPlottingOptions {
+                            node_client: &node_client,
+                            concurrent_plotting_semaphore: &concurrent_plotting_semaphore,
+                            remote_plotter: remote_plotter.as_ref(),
+                            public_key: &public_key,
+                            piece_getter: &piece_getter,
+                            kzg: &kzg,
+                            erasure_coding: &erasure_coding,
+                            pieces_in_sector,
+                            piece_memory_cache: &piece_memory_cache,
+                        };
+
+                        // Initial plotting
+
+                        // Some sectors may already be plotted, skip them
+                        let sectors_offsets_left_to_plot =
+                            metadata_header.sector_count as usize..target_sector_count;
+
+                        {
+                            let plot_file = &*plot_file;
+                            let metadata_file = &*metadata_file;
+                            let sector_plotting_options = &sector_plotting_options;
+
+                            // Multiple sectors are plotted concurrently, but results are processed
+                            // in order, such that sector count in metadata header only ever covers
+                            // contiguous range of fully plotted sectors
+                            let mut sectors_being_plotted =
+                                stream::iter(sectors_offsets_left_to_plot)
+                                    .map(move |sector_offset| async move {
+                                        let sector_index =
+                                            sector_offset as u64 + first_sector_index;
+                                        trace!(
+                                            %sector_offset,
+                                            %sector_index,
+                                            "Preparing to plot sector"
+                                        );
+
+                                        let mut sector = unsafe {
+                                            MmapOptions::new()
+                                                .offset((sector_offset * sector_size) as u64)
+                                                .len(sector_size)
+                                                .map_mut(plot_file)?
+                                        };
+                                        let mut sector_metadata = unsafe {
+                                            MmapOptions::new()
+                                                .offset(
+                                                    RESERVED_PLOT_METADATA
+                                                        + (sector_offset * sector_metadata_size)
+                                                            as u64,
+                                                )
+                                                .len(sector_metadata_size)
+                                                .map_mut(metadata_file)?
+                                        };
+
+                                        let Some((
+                                            plotted_sector,
+                                            plotting_duration,
+                                            plotting_permit,
+                                        )) = plot_single_sector::<_, _, PosTable>(
+                                            sector_offset,
+                                            sector_index,
+                                            &mut sector,
+                                            &mut sector_metadata,
+                                            sector_plotting_options,
+                                        )
+                                        .await?
+                                        else {
+                                            return Ok(None);
+                                        };
+                                        sector.flush()?;
+                                        sector_metadata.flush()?;
+
+                                        Ok::<_, PlottingError>(Some((
+                                            sector_offset,
+                                            plotted_sector,
+                                            plotting_duration,
+                                            plotting_permit,
+                                        )))
+                                    })
+                                    .buffered(sector_plotting_concurrency.get());
+
+                            while let Some(result) = sectors_being_plotted.next().await {
+                                let Some((
+                                    sector_offset,
+                                    plotted_sector,
+                                    plotting_duration,
+                                    plotting_permit,
+                                )) = result?
+                                else {
+                                    return Ok(());
+                                };
+                                let sector_index = plotted_sector.sector_index;
+
+                                metadata_header.sector_count += 1;
+                                metadata_header_mmap
+                                    .copy_from_slice(metadata_header.encode().as_slice());
+                                sectors_metadata
+                                    .write()
+                                    .push(plotted_sector.sector_metadata.clone());
+
+                                info!(%sector_offset, %sector_index, "Sector plotted successfully");
+
+                                handlers.sector_plotting_finished.call_simple(
+                                    &SectorPlottingDetails {
+                                        sector_offset,
+                                        sector_index,
+                                        replotting: false,
+                                        plotting_duration,
+                                    },
+                                );
+
+                                handlers.sector_plotted.call_simple(&(
+                                    sector_offset,
+                                    plotted_sector,
+                                    None,
+                                    Arc::new(plotting_permit),
+                                ));
+                            }
+                        }
+
+                        info!("Initial plotting complete");
+
+                        // Replotting of expired sectors
 
-                            let farmer_app_info = node_client
+                        // Sectors might have expired while farmer was offline or during initial
+                        // plotting, check them against current history size first
+                        let mut maybe_segment_index = Some(
+                            node_client
                                 .farmer_app_info()
                                 .await
-                                .map_err(|error| PlottingError::FailedToGetFarmerInfo { error })?;
+                                .map_err(|error| PlottingError::FailedToGetFarmerInfo { error })?
+                                .protocol_info
+                                .history_size
+                                .segment_index(),
+                        );
+
+                        loop {
+                            let mut segment_index = match maybe_segment_index.take() {
+                                Some(segment_index) => segment_index,
+                                None => {
+                                    let Some(segment_index) =
+                                        archived_segments_receiver.next().await
+                                    else {
+                                        break;
+                                    };
 
-                            let plot_sector_fut = plot_sector::<_, PosTable>(
-                                &public_key,
-                                sector_offset,
-                                sector_index,
-                                &piece_getter,
-                                PieceGetterRetryPolicy::Limited(PIECE_GETTER_RETRY_NUMBER.get()),
-                                &farmer_app_info.protocol_info,
-                                &kzg,
-                                &erasure_coding,
-                                pieces_in_sector,
-                                &mut sector,
-                                &mut sector_metadata,
-                                piece_memory_cache.clone(),
+                                    segment_index
+                                }
+                            };
+                            // Only the latest of segments archived while previous expired sectors
+                            // were replotted matters for expiration
+                            while let Ok(Some(newer_segment_index)) =
+                                archived_segments_receiver.try_next()
+                            {
+                                segment_index = segment_index.max(newer_segment_index);
+                            }
+
+                            let expired_sector_offsets = sectors_metadata
+                                .read()
+                                .iter()
+                                .enumerate()
+                                .filter_map(|(sector_offset, sector_metadata)| {
+                                    (sector_metadata.expires_at <= segment_index)
+                                        .then_some(sector_offset)
+                                })
+                                .collect::<Vec<_>>();
+
+                            if expired_sector_offsets.is_empty() {
+                                continue;
+                            }
+
+                            debug!(
+                                %segment_index,
+                                expired_sectors = %expired_sector_offsets.len(),
+                                "Found expired sectors"
                             );
-                            let plotted_sector = plot_sector_fut.await?;
-                            sector.flush()?;
-                            sector_metadata.flush()?;
-
-                            metadata_header.sector_count += 1;
-                            metadata_header_mmap
-                                .copy_from_slice(metadata_header.encode().as_slice());
-                            sectors_metadata
-                                .write()
-                                .push(plotted_sector.sector_metadata.clone());
-
-                            info!(%sector_offset, %sector_index, "Sector plotted successfully");
-
-                            handlers.sector_plotted.call_simple(&(
-                                sector_offset,
-                                plotted_sector,
-                                Arc::new(plotting_permit),
-                            ));
+
+                            let sector_plotting_options = &sector_plotting_options;
+
+                            let mut sectors_being_replotted = stream::iter(expired_sector_offsets)
+                                .map(move |sector_offset| async move {
+                                    let sector_index = sector_offset as u64 + first_sector_index;
+                                    trace!(
+                                        %sector_offset,
+                                        %sector_index,
+                                        "Preparing to replot sector"
+                                    );
+
+                                    // Sector is plotted into memory first and only written to disk
+                                    // once ready, such that expired sector can still be farmed and
+                                    // read from until then
+                                    let mut sector = vec![0; sector_size];
+                                    let mut sector_metadata = vec![0; sector_metadata_size];
+
+                                    let Some((plotted_sector, plotting_duration, plotting_permit)) =
+                                        plot_single_sector::<_, _, PosTable>(
+                                            sector_offset,
+                                            sector_index,
+                                            &mut sector,
+                                            &mut sector_metadata,
+                                            sector_plotting_options,
+                                        )
+                                        .await?
+                                    else {
+                                        return Ok(None);
+                                    };
+
+                                    Ok::<_, PlottingError>(Some((
+                                        sector_offset,
+                                        sector,
+                                        sector_metadata,
+                                        plotted_sector,
+                                        plotting_duration,
+                                        plotting_permit,
+                                    )))
+                                })
+                                .buffered(sector_plotting_concurrency.get());
+
+                            while let Some(result) = sectors_being_replotted.next().await {
+                                let Some((
+                                    sector_offset,
+                                    sector,
+                                    sector_metadata,
+                                    plotted_sector,
+                                    plotting_duration,
+                                    plotting_permit,
+                                )) = result?
+                                else {
+                                    return Ok(());
+                                };
+                                let sector_index = plotted_sector.sector_index;
+
+                                // Write lock is only held to exclude sector from farming and
+                                // reading, such that farming is not blocked while sector is
+                                // written to disk
+                                {
+                                    let _sectors_metadata = sectors_metadata.write();
+                                    sectors_being_replaced.lock().insert(sector_offset);
+                                }
+
+                                plot_file
+                                    .write_all_at(&sector, (sector_offset * sector_size) as u64)?;
+                                metadata_file.write_all_at(
+                                    &sector_metadata,
+                                    RESERVED_PLOT_METADATA
+                                        + (sector_offset * sector_metadata_size) as u64,
+                                )?;
+
+                                let old_plotted_sector = {
+                                    let mut sectors_metadata = sectors_metadata.write();
+                                    sectors_being_replaced.lock().remove(&sector_offset);
+
+                                    let old_sector_metadata = mem::replace(
+                                        &mut sectors_metadata[sector_offset],
+                                        plotted_sector.sector_metadata.clone(),
+                                    );
+
+                                    plotted_sector_from_metadata(
+                                        &public_key,
+                                        old_sector_metadata,
+                                        &farmer_protocol_info,
+                                    )
+                                };
+
+                                info!(
+                                    %sector_offset,
+                                    %sector_index,
+                                    "Sector replotted successfully"
+                                );
+
+                                handlers.sector_plotting_finished.call_simple(
+                                    &SectorPlottingDetails {
+                                        sector_offset,
+                                        sector_index,
+                                        replotting: true,
+                                        plotting_duration,
+                                    },
+                                );
+
+                                handlers.sector_plotted.call_simple(&(
+                                    sector_offset,
+                                    plotted_sector,
+                                    Some(old_plotted_sector),
+                                    Arc::new(plotting_permit),
+                                ));
+                            }
                         }
 
                         Ok::<_, PlottingError>(())
                     };
 
-                    let initial_plotting_result = handle.block_on(select(
-                        Box::pin(initial_plotting_fut),
+                    let plotting_result = handle.block_on(select(
+                        Box::pin(plotting_fut),
                         Box::pin(stop_receiver.recv()),
                     ));
 
-                    if let Either::Left((Err(error), _)) = initial_plotting_result {
+                    if let Either::Left((Err(error), _)) = plotting_result {
                         if let Some(error_sender) = error_sender.lock().take() {
                             if let Err(error) = error_sender.send(error.into()) {
                                 error!(%error, "Plotting failed to send error to background task");
@@ -846,6 +1590,7 @@ impl SingleDiskPlot {
 
         tasks.push(Box::pin({
             let node_client = node_client.clone();
+            let handlers = Arc::clone(&handlers);
 
             async move {
                 info!("Subscribing to slot info notifications");
@@ -864,6 +1609,7 @@ impl SingleDiskPlot {
                     // we need to skip this slot
                     if slot_info_forwarder_sender.try_send(slot_info).is_err() {
                         debug!(%slot, "Slow farming, skipping slot");
+                        handlers.slot_skipped.call_simple(&slot);
                     }
                 }
 
@@ -880,13 +1626,24 @@ impl SingleDiskPlot {
                     plot_mmap.advise(memmap2::Advice::Random)?;
                 }
 
+                let farming_thread_pool = ThreadPoolBuilder::new()
+                    .thread_name(move |thread_index| {
+                        format!("farming-{disk_farm_index}.{thread_index}")
+                    })
+                    .num_threads(farming_thread_pool_size.map_or(0, NonZeroUsize::get))
+                    .build()?;
+
                 let handle = handle.clone();
                 let erasure_coding = erasure_coding.clone();
                 let handlers = Arc::clone(&handlers);
                 let sectors_metadata = Arc::clone(&sectors_metadata);
+                let sectors_being_replaced = Arc::clone(&sectors_being_replaced);
+                let migrated_sectors = migrated_sectors.clone();
+                let metadata_file = Arc::clone(&metadata_file);
                 let mut start_receiver = start_sender.subscribe();
                 let mut stop_receiver = stop_sender.subscribe();
                 let node_client = node_client.clone();
+                let farmer_protocol_info = farmer_app_info.protocol_info;
                 let span = span.clone();
 
                 move || {
@@ -899,74 +1656,82 @@ impl SingleDiskPlot {
                             return Ok(());
                         }
 
+                        let mut segment_commitments = SegmentCommitments::default();
+
                         while let Some(slot_info) = slot_info_forwarder_receiver.next().await {
                             let slot = slot_info.slot_number;
-                            let sectors_metadata = sectors_metadata.read();
-                            let sector_count = sectors_metadata.len();
+                            // Sectors can't be replaced while they are being audited and proven
+                            let sectors_metadata_guard = sectors_metadata.read();
+                            let migrated_sectors_guard = migrated_sectors
+                                .as_ref()
+                                .map(|migrated_sectors| migrated_sectors.read());
+                            let sectors = farmed_sectors(
+                                first_sector_index,
+                                &sectors_metadata_guard,
+                                &plot_mmap,
+                                sector_size,
+                                &sectors_being_replaced.lock(),
+                                migrated_sectors_guard.as_deref(),
+                            );
+                            let sector_count = sectors.len();
 
                             debug!(%slot, %sector_count, "Reading sectors");
 
-                            let mut solutions = Vec::<Solution<PublicKey, PublicKey>>::new();
-
-                            for (sector_index, sector_metadata, sector) in sectors_metadata
-                                .iter()
-                                .zip(plot_mmap.chunks_exact(sector_size))
-                                .enumerate()
-                                .map(|(sector_index, (sector, metadata))| {
-                                    (sector_index as u64 + first_sector_index, sector, metadata)
-                                })
-                            {
-                                trace!(%slot, %sector_index, "Auditing sector");
-
-                                let maybe_solution_candidates = audit_sector(
-                                    &public_key,
-                                    sector_index,
-                                    &slot_info.global_challenge,
-                                    slot_info.voting_solution_range,
-                                    sector,
-                                    sector_metadata,
-                                );
-                                let Some(solution_candidates) = maybe_solution_candidates else {
-                                    continue;
-                                };
-
-                                for maybe_solution in solution_candidates.into_iter::<_, PosTable>(
-                                    &reward_address,
-                                    &kzg,
-                                    &erasure_coding,
+                            let SlotSolutions {
+                                mut solutions,
+                                candidate_sector_count,
+                                audit_duration,
+                                proving_duration,
+                            } = audit_and_prove::<PosTable>(
+                                &farming_thread_pool,
+                                &public_key,
+                                &reward_address,
+                                &sectors,
+                                &slot_info,
+                                solutions_limit,
+                                solution_selection_policy,
+                                &kzg,
+                                &erasure_coding,
+                            )?;
+                            drop(sectors);
+                            drop(migrated_sectors_guard);
+                            drop(sectors_metadata_guard);
+
+                            let invalid_sector_indexes = retain_valid_solutions::<PosTable, _>(
+                                &mut solutions,
+                                &slot_info,
+                                &farmer_protocol_info,
+                                &node_client,
+                                &mut segment_commitments,
+                                &kzg,
+                            )
+                            .await;
+
+                            if replot_invalid_sectors && !invalid_sector_indexes.is_empty() {
+                                if migrated_sectors.is_some() {
+                                    // Plot is replaced once migration is complete and sectors are
+                                    // not replotted until then
+                                    warn!(
+                                        invalid_sectors = %invalid_sector_indexes.len(),
+                                        "Sectors that produced invalid solutions can't be \
+                                        replotted while plot migration is in progress"
+                                    );
+                                } else if mark_sectors_for_replotting(
+                                    invalid_sector_indexes.iter().map(|&sector_index| {
+                                        (sector_index - first_sector_index) as usize
+                                    }),
+                                    &mut sectors_metadata.write(),
+                                    &sectors_being_replaced.lock(),
+                                    &metadata_file,
                                 )? {
-                                    let solution = match maybe_solution {
-                                        Ok(solution) => solution,
-                                        Err(error) => {
-                                            error!(%slot, %sector_index, %error, "Failed to prove");
-                                            // Do not error completely on disk corruption or other
-                                            // reasons why proving might fail
-                                            continue;
-                                        }
-                                    };
-
-                                    debug!(%slot, %sector_index, "Solution found");
-                                    trace!(?solution, "Solution found");
-
-                                    solutions.push(solution);
-
-                                    if solutions.len() >= SOLUTIONS_LIMIT {
-                                        break;
-                                    }
-                                }
-
-                                if solutions.len() >= SOLUTIONS_LIMIT {
-                                    break;
-                                }
-                                // TODO: It is known that decoding is slow now and we'll only be
-                                //  able to decode a single sector within time slot reliably, in the
-                                //  future we may want allow more than one sector to be valid within
-                                //  the same disk plot.
-                                if !solutions.is_empty() {
-                                    break;
+                                    // Plotting thread might not be running anymore
+                                    let _ =
+                                        invalid_sectors_sender.unbounded_send(SegmentIndex::ZERO);
                                 }
                             }
 
+                            let solution_count = solutions.len();
+
                             let response = SolutionResponse {
                                 slot_number: slot_info.slot_number,
                                 solutions,
@@ -978,6 +1743,15 @@ impl SingleDiskPlot {
                                 .map_err(|error| FarmingError::FailedToSubmitSolutionsResponse {
                                     error,
                                 })?;
+
+                            handlers.slot_farmed.call_simple(&SlotFarmingDetails {
+                                slot,
+                                sector_count,
+                                candidate_sector_count,
+                                audit_duration,
+                                proving_duration,
+                                solution_count,
+                            });
                         }
 
                         Ok::<_, FarmingError>(())
@@ -1010,6 +1784,8 @@ impl SingleDiskPlot {
                 }
 
                 let sectors_metadata = Arc::clone(&sectors_metadata);
+                let sectors_being_replaced = Arc::clone(&sectors_being_replaced);
+                let migrated_sectors = migrated_sectors.clone();
                 let mut stop_receiver = stop_sender.subscribe();
                 let span = span.clone();
 
@@ -1029,27 +1805,57 @@ impl SingleDiskPlot {
                                 continue;
                             }
 
-                            let (sector_metadata, sector_count) = {
-                                let sectors_metadata = sectors_metadata.read();
+                            // Lock is held during reading such that sector can't be replotted
+                            // in the middle of it
+                            let sectors_metadata = sectors_metadata.read();
+
+                            let sector_offset = (sector_index - first_sector_index) as usize;
+
+                            // Migrated sectors are read from instead of old sectors with the same
+                            // offsets
+                            if let Some(migrated_sectors) = &migrated_sectors {
+                                let migrated_sectors = migrated_sectors.read();
+
+                                if let Some(sector_metadata) =
+                                    migrated_sectors.sectors_metadata.get(sector_offset)
+                                {
+                                    let maybe_piece = read_piece::<PosTable>(
+                                        &public_key,
+                                        piece_offset,
+                                        migrated_sectors.pieces_in_sector,
+                                        migrated_sectors.sectors_metadata.len(),
+                                        first_sector_index,
+                                        sector_metadata,
+                                        &migrated_sectors.plot_mmap,
+                                        &erasure_coding,
+                                    );
+                                    drop(migrated_sectors);
+                                    drop(sectors_metadata);
+
+                                    // Doesn't matter if receiver still cares about it
+                                    let _ = response_sender.send(maybe_piece);
+                                    continue;
+                                }
+                            }
 
-                                let sector_offset = (sector_index - first_sector_index) as usize;
-                                let sector_count = sectors_metadata.len();
+                            let sector_count = sectors_metadata.len();
 
-                                let sector_metadata = match sectors_metadata.get(sector_offset) {
-                                    Some(sector_metadata) => sector_metadata.clone(),
-                                    None => {
-                                        error!(
-                                            %sector_index,
-                                            %first_sector_index,
-                                            %sector_count,
-                                            "Tried to read piece from sector that is not yet \
-                                            plotted"
-                                        );
-                                        continue;
-                                    }
-                                };
+                            if sectors_being_replaced.lock().contains(&sector_offset) {
+                                debug!(%sector_index, "Sector is being replaced, can't read piece");
+                                continue;
+                            }
 
-                                (sector_metadata, sector_count)
+                            let sector_metadata = match sectors_metadata.get(sector_offset) {
+                                Some(sector_metadata) => sector_metadata,
+                                None => {
+                                    error!(
+                                        %sector_index,
+                                        %first_sector_index,
+                                        %sector_count,
+                                        "Tried to read piece from sector that is not yet plotted"
+                                    );
+                                    continue;
+                                }
                             };
 
                             let maybe_piece = read_piece::<PosTable>(
@@ -1058,10 +1864,11 @@ impl SingleDiskPlot {
                                 pieces_in_sector,
                                 sector_count,
                                 first_sector_index,
-                                &sector_metadata,
+                                sector_metadata,
                                 &global_plot_mmap,
                                 &erasure_coding,
                             );
+                            drop(sectors_metadata);
 
                             // Doesn't matter if receiver still cares about it
                             let _ = response_sender.send(maybe_piece);
@@ -1075,18 +1882,12 @@ impl SingleDiskPlot {
                 }
             })?;
 
-        tasks.push(Box::pin(async move {
-            // TODO: Error handling here
-            reward_signing(node_client, identity).await.unwrap().await;
-
-            Ok(())
-        }));
-
         let farm = Self {
             farmer_protocol_info: farmer_app_info.protocol_info,
             single_disk_plot_info,
             sectors_metadata,
-            pieces_in_sector,
+            migrated_sectors,
+            total_sectors_count: target_sector_count,
             span,
             tasks,
             handlers,
@@ -1096,13 +1897,33 @@ impl SingleDiskPlot {
             _reading_join_handle: JoinOnDrop::new(reading_join_handle),
             start_sender: Some(start_sender),
             stop_sender: Some(stop_sender),
+            _single_disk_plot_lock: single_disk_plot_lock,
         };
 
         Ok(farm)
     }
 
-    /// Collect summary of single disk plot for presentational purposes
+    /// Collect summary of single disk plot for presentational purposes.
+    ///
+    /// Files of plot that is in use by another process are not read, plot used by current process
+    /// is not considered to be in use.
     pub fn collect_summary(directory: PathBuf) -> SingleDiskPlotSummary {
+        match SingleDiskPlotLock::check(&directory) {
+            Ok(()) => {}
+            Err(SingleDiskPlotError::AlreadyInUse { directory, pid }) => {
+                return SingleDiskPlotSummary::InUse { directory, pid };
+            }
+            Err(SingleDiskPlotError::Io(error)) => {
+                return SingleDiskPlotSummary::Error { directory, error };
+            }
+            Err(error) => {
+                return SingleDiskPlotSummary::Error {
+                    directory,
+                    error: io::Error::other(error),
+                };
+            }
+        }
+
         let single_disk_plot_info = match SingleDiskPlotInfo::load_from(&directory) {
             Ok(Some(single_disk_plot_info)) => single_disk_plot_info,
             Ok(None) => {
@@ -1126,7 +1947,19 @@ impl SingleDiskPlot {
 
     /// Number of sectors successfully plotted so far
     pub fn plotted_sectors_count(&self) -> usize {
-        self.sectors_metadata.read().len()
+        let sectors_metadata = self.sectors_metadata.read();
+
+        match &self.migrated_sectors {
+            Some(migrated_sectors) => sectors_metadata
+                .len()
+                .max(migrated_sectors.read().sectors_metadata.len()),
+            None => sectors_metadata.len(),
+        }
+    }
+
+    /// Number of sectors plot will have once fully plotted
+    pub fn total_sectors_count(&self) -> usize {
+        self.total_sectors_count
     }
 
     /// Read information about sectors plotted so far
@@ -1134,34 +1967,30 @@ impl SingleDiskPlot {
         &self,
     ) -> impl Iterator<Item = Result<PlottedSector, parity_scale_codec::Error>> + '_ {
         let public_key = self.single_disk_plot_info.public_key();
-        let first_sector_index = self.single_disk_plot_info.first_sector_index();
-
-        (first_sector_index..)
-            .zip(self.sectors_metadata.read().clone())
-            .map(move |(sector_index, sector_metadata)| {
-                let sector_id = SectorId::new(public_key.hash(), sector_index);
-
-                let mut piece_indexes = Vec::with_capacity(self.pieces_in_sector.into());
-                (PieceOffset::ZERO..)
-                    .take(self.pieces_in_sector.into())
-                    .map(|piece_offset| {
-                        sector_id.derive_piece_index(
-                            piece_offset,
-                            sector_metadata.history_size,
-                            self.farmer_protocol_info.max_pieces_in_sector,
-                            self.farmer_protocol_info.recent_segments,
-                            self.farmer_protocol_info.recent_history_fraction,
-                        )
-                    })
-                    .collect_into(&mut piece_indexes);
 
-                Ok(PlottedSector {
-                    sector_id,
-                    sector_index,
-                    sector_metadata,
-                    piece_indexes,
-                })
-            })
+        let mut sectors_metadata = self.sectors_metadata.read().clone();
+        // Migrated sectors replace old sectors with the same offsets
+        if let Some(migrated_sectors) = &self.migrated_sectors {
+            let migrated_sectors_metadata = &migrated_sectors.read().sectors_metadata;
+            for (sector_offset, sector_metadata) in migrated_sectors_metadata.iter().enumerate() {
+                match sectors_metadata.get_mut(sector_offset) {
+                    Some(old_sector_metadata) => {
+                        *old_sector_metadata = sector_metadata.clone();
+                    }
+                    None => {
+                        sectors_metadata.push(sector_metadata.clone());
+                    }
+                }
+            }
+        }
+
+        sectors_metadata.into_iter().map(move |sector_metadata| {
+            Ok(plotted_sector_from_metadata(
+                public_key,
+                sector_metadata,
+                &self.farmer_protocol_info,
+            ))
+        })
     }
 
     /// Get piece reader to read plot pieces later
@@ -1171,20 +2000,46 @@ impl SingleDiskPlot {
 
     /// Subscribe to sector plotting notification
     ///
+    /// Arguments are sector offset, newly plotted sector, previously plotted sector that was
+    /// replaced (in case sector was replotted after expiration) and plotting permit.
+    ///
     /// Plotting permit is given such that it can be dropped later by the implementation is
     /// throttling of the plotting process is desired.
     pub fn on_sector_plotted(
         &self,
-        callback: HandlerFn<(usize, PlottedSector, Arc<OwnedSemaphorePermit>)>,
+        callback: HandlerFn<(
+            usize,
+            PlottedSector,
+            Option<PlottedSector>,
+            Arc<OwnedSemaphorePermit>,
+        )>,
     ) -> HandlerId {
         self.handlers.sector_plotted.add(callback)
     }
 
+    /// Subscribe to notification with details about plotting of each sector
+    pub fn on_sector_plotting_finished(
+        &self,
+        callback: HandlerFn<SectorPlottingDetails>,
+    ) -> HandlerId {
+        self.handlers.sector_plotting_finished.add(callback)
+    }
+
     /// Subscribe to new solution notification
     pub fn on_solution(&self, callback: HandlerFn<SolutionResponse>) -> HandlerId {
         self.handlers.solution.add(callback)
     }
 
+    /// Subscribe to notification with details about farming of each slot
+    pub fn on_slot_farmed(&self, callback: HandlerFn<SlotFarmingDetails>) -> HandlerId {
+        self.handlers.slot_farmed.add(callback)
+    }
+
+    /// Subscribe to notification about slots skipped because farming of previous slot was too slow
+    pub fn on_slot_skipped(&self, callback: HandlerFn<SlotNumber>) -> HandlerId {
+        self.handlers.slot_skipped.add(callback)
+    }
+
     /// Run and wait for background threads to exit or return an error
     pub async fn run(mut self) -> anyhow::Result<()> {
         if let Some(start_sender) = self.start_sender.take() {
@@ -1199,8 +2054,491 @@ impl SingleDiskPlot {
         Ok(())
     }
 
+    /// Check integrity of sectors plotted so far and return those that turned out to be corrupted.
+    ///
+    /// Every record in every sector is read back, its commitment is recomputed and compared to the
+    /// one stored in the sector and its witness is checked against corresponding segment commitment
+    /// from `segment_commitments`, which must contain all segments known to the node. When
+    /// `mark_for_replotting` is `true`, corrupted sectors are marked as expired, such that they are
+    /// replotted next time farming starts.
+    ///
+    /// Plot directory is locked for the duration of scrubbing, so it can't be done while farming.
+    pub fn scrub<PosTable>(
+        directory: &Path,
+        farmer_protocol_info: &FarmerProtocolInfo,
+        segment_commitments: &HashMap<SegmentIndex, SegmentCommitment>,
+        kzg: &Kzg,
+        erasure_coding: &ErasureCoding,
+        mark_for_replotting: bool,
+    ) -> Result<Vec<CorruptedSector>, SingleDiskPlotError>
+    where
+        PosTable: Table,
+    {
+        let single_disk_plot_info = match SingleDiskPlotInfo::load_from(directory)? {
+            Some(single_disk_plot_info) => single_disk_plot_info,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "Single disk plot info not found at {}",
+                        directory.join(SingleDiskPlotInfo::FILE_NAME).display()
+                    ),
+                )
+                .into());
+            }
+        };
+
+        let _single_disk_plot_lock = SingleDiskPlotLock::acquire(directory)?;
+
+        info!("Scrubbing single disk plot {}", single_disk_plot_info.id());
+
+        let public_key = single_disk_plot_info.public_key();
+        let pieces_in_sector = single_disk_plot_info.pieces_in_sector();
+        let first_sector_index = single_disk_plot_info.first_sector_index();
+        let sector_size = sector_size(pieces_in_sector);
+        let sector_metadata_size = SectorMetadata::encoded_size();
+
+        let metadata_file = OpenOptions::new()
+            .read(true)
+            .write(mark_for_replotting)
+            .open(directory.join(Self::METADATA_FILE))?;
+
+        let metadata_header = {
+            let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
+            metadata_file.read_exact_at(&mut metadata_header_bytes, 0)?;
+
+            PlotMetadataHeader::decode(&mut metadata_header_bytes.as_slice())
+                .map_err(SingleDiskPlotError::FailedToDecodeMetadataHeader)?
+        };
+
+        if metadata_header.version != Self::SUPPORTED_PLOT_VERSION {
+            return Err(SingleDiskPlotError::UnexpectedMetadataVersion(
+                metadata_header.version,
+            ));
+        }
+
+        let sector_count = metadata_header.sector_count as usize;
+
+        let metadata_mmap = unsafe {
+            MmapOptions::new()
+                .offset(RESERVED_PLOT_METADATA)
+                .len(sector_metadata_size * sector_count)
+                .map(&metadata_file)?
+        };
+
+        let plot_file = OpenOptions::new()
+            .read(true)
+            .open(directory.join(Self::PLOT_FILE))?;
+        let plot_mmap = unsafe { Mmap::map(&plot_file)? };
+
+        let corrupted_sectors = metadata_mmap
+            .par_chunks_exact(sector_metadata_size)
+            .enumerate()
+            .filter_map(|(sector_offset, mut sector_metadata_bytes)| {
+                let sector_index = first_sector_index + sector_offset as SectorIndex;
+                // Sector may be missing if plot file was truncated
+                let sector =
+                    plot_mmap.get(sector_offset * sector_size..(sector_offset + 1) * sector_size);
+
+                let sector_metadata = match SectorMetadata::decode(&mut sector_metadata_bytes) {
+                    Ok(sector_metadata) => sector_metadata,
+                    Err(error) => {
+                        return Some((
+                            sector_offset,
+                            sector_index,
+                            None,
+                            SectorCorruption::FailedToDecodeSectorMetadata(error),
+                        ));
+                    }
+                };
+
+                let result = scrub_sector::<PosTable>(
+                    public_key,
+                    sector_index,
+                    pieces_in_sector,
+                    &sector_metadata,
+                    sector,
+                    farmer_protocol_info,
+                    segment_commitments,
+                    kzg,
+                    erasure_coding,
+                );
+
+                debug!(%sector_index, "Sector scrubbed");
+
+                result.err().map(|corruption| {
+                    (
+                        sector_offset,
+                        sector_index,
+                        Some(sector_metadata),
+                        corruption,
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+
+        corrupted_sectors
+            .into_iter()
+            .map(
+                |(sector_offset, sector_index, maybe_sector_metadata, corruption)| {
+                    let mut marked_for_replotting = false;
+
+                    if mark_for_replotting {
+                        // Sector index must be correct for replotting, otherwise there is no way
+                        // to know what to plot in its place
+                        if let Some(mut sector_metadata) = maybe_sector_metadata
+                            && sector_metadata.sector_index == sector_index
+                        {
+                            // Sector that is already expired will be replotted in the background
+                            sector_metadata.expires_at = SegmentIndex::ZERO;
+                            metadata_file.write_all_at(
+                                &sector_metadata.encode(),
+                                RESERVED_PLOT_METADATA
+                                    + (sector_offset * sector_metadata_size) as u64,
+                            )?;
+                            marked_for_replotting = true;
+                        }
+                    }
+
+                    Ok(CorruptedSector {
+                        sector_offset,
+                        sector_index,
+                        corruption,
+                        marked_for_replotting,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    /// Benchmark auditing and proving using sectors of existing single disk plot, optionally
+    /// benchmark plotting too.
+    ///
+    /// Proving is benchmarked on up to `proving_sectors` first sectors. Plotting is done in memory
+    /// using pieces of locally archived random data, plot itself is never modified.
+    pub async fn benchmark<PosTable>(
+        directory: &Path,
+        kzg: &Kzg,
+        erasure_coding: &ErasureCoding,
+        proving_sectors: usize,
+        plotting: bool,
+    ) -> Result<BenchmarkResults, SingleDiskPlotError>
+    where
+        PosTable: Table,
+    {
+        let single_disk_plot_info = match SingleDiskPlotInfo::load_from(directory)? {
+            Some(single_disk_plot_info) => single_disk_plot_info,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "Single disk plot info not found at {}",
+                        directory.join(SingleDiskPlotInfo::FILE_NAME).display()
+                    ),
+                )
+                .into());
+            }
+        };
+
+        // Farming in parallel would affect results
+        let _single_disk_plot_lock = SingleDiskPlotLock::acquire(directory)?;
+
+        info!(
+            "Benchmarking single disk plot {}",
+            single_disk_plot_info.id()
+        );
+
+        let public_key = single_disk_plot_info.public_key();
+        let pieces_in_sector = single_disk_plot_info.pieces_in_sector();
+        let first_sector_index = single_disk_plot_info.first_sector_index();
+        let sector_size = sector_size(pieces_in_sector);
+        let sector_metadata_size = SectorMetadata::encoded_size();
+
+        let metadata_file = OpenOptions::new()
+            .read(true)
+            .open(directory.join(Self::METADATA_FILE))?;
+
+        let metadata_header = {
+            let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
+            metadata_file.read_exact_at(&mut metadata_header_bytes, 0)?;
+
+            PlotMetadataHeader::decode(&mut metadata_header_bytes.as_slice())
+                .map_err(SingleDiskPlotError::FailedToDecodeMetadataHeader)?
+        };
+
+        if metadata_header.version != Self::SUPPORTED_PLOT_VERSION {
+            return Err(SingleDiskPlotError::UnexpectedMetadataVersion(
+                metadata_header.version,
+            ));
+        }
+
+        let sectors_metadata = {
+            let metadata_mmap = unsafe {
+                MmapOptions::new()
+                    .offset(RESERVED_PLOT_METADATA)
+                    .len(sector_metadata_size * metadata_header.sector_count as usize)
+                    .map(&metadata_file)?
+            };
+
+            metadata_mmap
+                .chunks_exact(sector_metadata_size)
+                .map(|mut sector_metadata_bytes| SectorMetadata::decode(&mut sector_metadata_bytes))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(SingleDiskPlotError::FailedToDecodeSectorMetadata)?
+        };
+
+        let plot_file = OpenOptions::new()
+            .read(true)
+            .open(directory.join(Self::PLOT_FILE))?;
+        let plot_mmap = unsafe { Mmap::map(&plot_file)? };
+        #[cfg(unix)]
+        {
+            plot_mmap.advise(memmap2::Advice::Random)?;
+        }
+
+        let mut global_challenge = Blake2b256Hash::default();
+        thread_rng().fill_bytes(&mut global_challenge);
+
+        // Tiny solution range, just like in real network most sectors will not have any solution
+        // candidates, but all of the audit work still needs to be done
+        let audit =
+            |(sector_offset, (sector_metadata, sector)): (usize, (&SectorMetadata, &[u8]))| {
+                audit_sector(
+                    public_key,
+                    first_sector_index + sector_offset as SectorIndex,
+                    &global_challenge,
+                    SolutionRange::MIN,
+                    sector,
+                    sector_metadata,
+                )
+                .is_some()
+            };
+
+        let audit_start = Instant::now();
+        let sector_count = sectors_metadata
+            .iter()
+            .zip(plot_mmap.chunks_exact(sector_size))
+            .enumerate()
+            .map(audit)
+            .map(hint::black_box)
+            .count();
+        let audit_duration = audit_start.elapsed();
+
+        let parallel_audit_start = Instant::now();
+        sectors_metadata
+            .par_iter()
+            .zip(plot_mmap.par_chunks_exact(sector_size))
+            .enumerate()
+            .map(audit)
+            .for_each(|result| {
+                hint::black_box(result);
+            });
+        let parallel_audit_duration = parallel_audit_start.elapsed();
+
+        let mut proving_durations = Vec::with_capacity(proving_sectors);
+
+        for (sector_offset, (sector_metadata, sector)) in sectors_metadata
+            .iter()
+            .zip(plot_mmap.chunks_exact(sector_size))
+            .enumerate()
+            .take(proving_sectors)
+        {
+            let sector_index = first_sector_index + sector_offset as SectorIndex;
+
+            // Max solution range guarantees there are candidates to prove
+            let Some(solution_candidates) = audit_sector(
+                public_key,
+                sector_index,
+                &global_challenge,
+                SolutionRange::MAX,
+                sector,
+                sector_metadata,
+            ) else {
+                continue;
+            };
+
+            let proving_start = Instant::now();
+            let maybe_solution = solution_candidates
+                .into_iter::<_, PosTable>(public_key, kzg, erasure_coding)
+                .map(|mut solutions| solutions.next());
+
+            match maybe_solution {
+                Ok(Some(Ok(_solution))) => {
+                    proving_durations.push(proving_start.elapsed());
+                }
+                Ok(None) => {
+                    debug!(%sector_index, "No solutions found in sector");
+                }
+                Ok(Some(Err(error))) | Err(error) => {
+                    warn!(%sector_index, %error, "Failed to prove, sector is likely corrupted");
+                }
+            }
+        }
+
+        let plotting_duration = if plotting {
+            Some(
+                benchmark_plotting::<PosTable>(public_key, pieces_in_sector, kzg, erasure_coding)
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+        Ok(BenchmarkResults {
+            sector_count,
+            audit_duration,
+            parallel_audit_duration,
+            proving_durations,
+            plotting_duration,
+        })
+    }
+
+    /// Schedule migration of plot to the current plot format and specified number of pieces in
+    /// sector (current number of pieces in sector is kept if `None`).
+    ///
+    /// Migration itself happens in the background next time plot is opened for farming, see
+    /// [`migration`] module for details.
+    pub fn schedule_migration(
+        directory: &Path,
+        target_pieces_in_sector: Option<u16>,
+    ) -> Result<PlotMigrationStatus, SingleDiskPlotError> {
+        let single_disk_plot_info = match SingleDiskPlotInfo::load_from(directory)? {
+            Some(single_disk_plot_info) => single_disk_plot_info,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "Single disk plot info not found at {}",
+                        directory.join(SingleDiskPlotInfo::FILE_NAME).display()
+                    ),
+                )
+                .into());
+            }
+        };
+
+        // Migration can't be scheduled while plot is in use, since it is only picked up on start
+        let _single_disk_plot_lock = SingleDiskPlotLock::acquire(directory)?;
+
+        let target_pieces_in_sector =
+            target_pieces_in_sector.unwrap_or_else(|| single_disk_plot_info.pieces_in_sector());
+
+        let plot_migration = match PlotMigration::load_from(directory)? {
+            Some(plot_migration)
+                if plot_migration.target_pieces_in_sector() == target_pieces_in_sector =>
+            {
+                info!("Migration is already scheduled, it will continue where it left off");
+                plot_migration
+            }
+            maybe_plot_migration => {
+                if maybe_plot_migration.is_some() {
+                    info!(
+                        "Migration to different number of pieces in sector was scheduled, \
+                        aborting it"
+                    );
+                    PlotMigration::abort(directory)?;
+                }
+
+                let plot_migration = PlotMigration::new(target_pieces_in_sector);
+                if plot_migration.target_sector_count(single_disk_plot_info.allocated_space()) == 0
+                {
+                    return Err(SingleDiskPlotError::InsufficientAllocatedSpace {
+                        min_size: sector_size(target_pieces_in_sector),
+                        allocated_space: single_disk_plot_info.allocated_space(),
+                    });
+                }
+
+                plot_migration.store_to(directory)?;
+                plot_migration
+            }
+        };
+
+        Self::migration_status_inner(directory, &single_disk_plot_info, &plot_migration)
+    }
+
+    /// Abort scheduled migration of the plot, removing everything migrated so far
+    pub fn abort_migration(directory: &Path) -> Result<(), SingleDiskPlotError> {
+        let _single_disk_plot_lock = SingleDiskPlotLock::acquire(directory)?;
+
+        Ok(PlotMigration::abort(directory)?)
+    }
+
+    /// Progress of plot migration, `None` if no migration is in progress
+    pub fn migration_status(
+        directory: &Path,
+    ) -> Result<Option<PlotMigrationStatus>, SingleDiskPlotError> {
+        let single_disk_plot_info = match SingleDiskPlotInfo::load_from(directory)? {
+            Some(single_disk_plot_info) => single_disk_plot_info,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "Single disk plot info not found at {}",
+                        directory.join(SingleDiskPlotInfo::FILE_NAME).display()
+                    ),
+                )
+                .into());
+            }
+        };
+
+        PlotMigration::load_from(directory)?
+            .map(|plot_migration| {
+                Self::migration_status_inner(directory, &single_disk_plot_info, &plot_migration)
+            })
+            .transpose()
+    }
+
+    fn migration_status_inner(
+        directory: &Path,
+        single_disk_plot_info: &SingleDiskPlotInfo,
+        plot_migration: &PlotMigration,
+    ) -> Result<PlotMigrationStatus, SingleDiskPlotError> {
+        let target_sector_count =
+            plot_migration.target_sector_count(single_disk_plot_info.allocated_space()) as u64;
+
+        Ok(PlotMigrationStatus {
+            target_pieces_in_sector: plot_migration.target_pieces_in_sector(),
+            migrated_sector_count: PlotMigration::migrated_sector_count(directory)?
+                .min(target_sector_count),
+            target_sector_count,
+        })
+    }
+
+    /// Read metadata of sectors plotted so far.
+    ///
+    /// Plot is not locked, so this can be used while plot is being farmed by another process, in
+    /// which case sectors that are being replotted at the moment may be returned in either state.
+    pub fn read_sectors_metadata(
+        directory: &Path,
+    ) -> Result<Vec<Result<SectorMetadata, parity_scale_codec::Error>>, SingleDiskPlotError> {
+        let metadata_file = File::open(directory.join(Self::METADATA_FILE))?;
+
+        let metadata_header = {
+            let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
+            metadata_file.read_exact_at(&mut metadata_header_bytes, 0)?;
+
+            PlotMetadataHeader::decode(&mut metadata_header_bytes.as_slice())
+                .map_err(SingleDiskPlotError::FailedToDecodeMetadataHeader)?
+        };
+
+        if metadata_header.version != Self::SUPPORTED_PLOT_VERSION {
+            return Err(SingleDiskPlotError::UnexpectedMetadataVersion(
+                metadata_header.version,
+            ));
+        }
+
+        let sector_metadata_size = SectorMetadata::encoded_size();
+        let mut sectors_metadata_bytes =
+            vec![0; sector_metadata_size * metadata_header.sector_count as usize];
+        metadata_file.read_exact_at(&mut sectors_metadata_bytes, RESERVED_PLOT_METADATA)?;
+
+        Ok(sectors_metadata_bytes
+            .chunks_exact(sector_metadata_size)
+            .map(|mut sector_metadata_bytes| SectorMetadata::decode(&mut sector_metadata_bytes))
+            .collect())
+    }
+
     /// Wipe everything that belongs to this single disk plot
-    pub fn wipe(directory: &Path) -> io::Result<()> {
+    pub fn wipe(directory: &Path) -> Result<(), SingleDiskPlotError> {
         let single_disk_plot_info_path = directory.join(SingleDiskPlotInfo::FILE_NAME);
         match SingleDiskPlotInfo::load_from(directory) {
             Ok(Some(single_disk_plot_info)) => {
@@ -1213,13 +2551,16 @@ impl SingleDiskPlot {
                         "Single disk plot info not found at {}",
                         single_disk_plot_info_path.display()
                     ),
-                ));
+                )
+                .into());
             }
             Err(error) => {
                 warn!("Found unknown single disk plot: {}", error);
             }
         }
 
+        let single_disk_plot_lock = SingleDiskPlotLock::acquire(directory)?;
+
         {
             let plot = directory.join(Self::PLOT_FILE);
             info!("Deleting plot file at {}", plot.display());
@@ -1230,18 +2571,260 @@ impl SingleDiskPlot {
             info!("Deleting metadata file at {}", metadata.display());
             fs::remove_file(metadata)?;
         }
-        // TODO: Identity should be able to wipe itself instead of assuming a specific file name
-        //  here
+        PlotMigration::abort(directory)?;
+        {
+            let piece_cache = directory.join(DiskPieceCache::FILE_NAME);
+            // Piece cache is optional
+            if piece_cache.exists() {
+                info!("Deleting piece cache file at {}", piece_cache.display());
+                fs::remove_file(piece_cache)?;
+            }
+        }
         {
-            let identity = directory.join("identity.bin");
-            info!("Deleting identity file at {}", identity.display());
-            fs::remove_file(identity)?;
+            let identity = Identity::file_path(directory);
+            // Identity might be stored outside of plot directory and shared with other plots
+            if identity.exists() {
+                info!("Deleting identity file at {}", identity.display());
+                fs::remove_file(identity)?;
+            }
         }
 
         info!(
             "Deleting info file at {}",
             single_disk_plot_info_path.display()
         );
-        fs::remove_file(single_disk_plot_info_path)
+        fs::remove_file(single_disk_plot_info_path)?;
+
+        // Lock file can only be removed once lock is released
+        drop(single_disk_plot_lock);
+        fs::remove_file(directory.join(SingleDiskPlotLock::FILE_NAME))?;
+
+        Ok(())
+    }
+}
+
+/// Everything necessary for plotting sectors that doesn't change from one sector to another
+struct SectorPlottingOptions<'a, NC, PG> {
+    node_client: &'a NC,
+    concurrent_plotting_semaphore: &'a Arc<tokio::sync::Semaphore>,
+    remote_plotter: Option<&'a RemotePlotter>,
+    public_key: &'a PublicKey,
+    piece_getter: &'a PG,
+    kzg: &'a Kzg,
+    erasure_coding: &'a ErasureCoding,
+    pieces_in_sector: u16,
+    piece_memory_cache: &'a PieceMemoryCache,
+}
+
+/// Wait for plotting permit and plot sector into provided outputs.
+///
+/// Returns plotted sector, time it took to plot it and plotting permit or `None` if plotting
+/// semaphore was closed.
+async fn plot_single_sector<NC, PG, PosTable>(
+    sector_offset: usize,
+    sector_index: SectorIndex,
+    sector_output: &mut [u8],
+    sector_metadata_output: &mut [u8],
+    sector_plotting_options: &SectorPlottingOptions<'_, NC, PG>,
+) -> Result<Option<(PlottedSector, Duration, OwnedSemaphorePermit)>, PlottingError>
+where
+    NC: NodeClient,
+    PG: PieceGetter,
+    PosTable: Table,
+{
+    let SectorPlottingOptions {
+        node_client,
+        concurrent_plotting_semaphore,
+        remote_plotter,
+        public_key,
+        piece_getter,
+        kzg,
+        erasure_coding,
+        pieces_in_sector,
+        piece_memory_cache,
+    } = sector_plotting_options;
+
+    let plotting_permit = match Arc::clone(concurrent_plotting_semaphore)
+        .acquire_owned()
+        .await
+    {
+        Ok(plotting_permit) => plotting_permit,
+        Err(error) => {
+            warn!(
+                %sector_offset,
+                %sector_index,
+                %error,
+                "Semaphore was closed, interrupting plotting"
+            );
+            return Ok(None);
+        }
+    };
+
+    debug!(%sector_offset, %sector_index, "Plotting sector");
+
+    let farmer_app_info = node_client
+        .farmer_app_info()
+        .await
+        .map_err(|error| PlottingError::FailedToGetFarmerInfo { error })?;
+
+    let plotting_start = Instant::now();
+
+    let plotted_sector = plot_sector_locally_or_remotely::<_, _, PosTable>(
+        *remote_plotter,
+        *node_client,
+        public_key,
+        sector_offset,
+        sector_index,
+        *piece_getter,
+        &farmer_app_info.protocol_info,
+        kzg,
+        erasure_coding,
+        *pieces_in_sector,
+        sector_output,
+        sector_metadata_output,
+        (*piece_memory_cache).clone(),
+    )
+    .await?;
+
+    Ok(Some((
+        plotted_sector,
+        plotting_start.elapsed(),
+        plotting_permit,
+    )))
+}
+
+/// Plot sector on remote plotting server if there is one or locally otherwise
+#[allow(clippy::too_many_arguments)]
+async fn plot_sector_locally_or_remotely<NC, PG, PosTable>(
+    remote_plotter: Option<&RemotePlotter>,
+    node_client: &NC,
+    public_key: &PublicKey,
+    sector_offset: usize,
+    sector_index: SectorIndex,
+    piece_getter: &PG,
+    farmer_protocol_info: &FarmerProtocolInfo,
+    kzg: &Kzg,
+    erasure_coding: &ErasureCoding,
+    pieces_in_sector: u16,
+    sector_output: &mut [u8],
+    sector_metadata_output: &mut [u8],
+    piece_memory_cache: PieceMemoryCache,
+) -> Result<PlottedSector, PlottingError>
+where
+    NC: NodeClient,
+    PG: PieceGetter,
+    PosTable: Table,
+{
+    let plotted_sector = match remote_plotter {
+        Some(remote_plotter) => {
+            remote_plotter
+                .plot_sector::<_, PosTable>(
+                    public_key,
+                    sector_index,
+                    farmer_protocol_info,
+                    pieces_in_sector,
+                    sector_output,
+                    sector_metadata_output,
+                    node_client,
+                    kzg,
+                    erasure_coding,
+                )
+                .await?
+        }
+        None => {
+            plot_sector::<_, PosTable>(
+                public_key,
+                sector_offset,
+                sector_index,
+                piece_getter,
+                PieceGetterRetryPolicy::Limited(PIECE_GETTER_RETRY_NUMBER.get()),
+                farmer_protocol_info,
+                kzg,
+                erasure_coding,
+                pieces_in_sector,
+                sector_output,
+                sector_metadata_output,
+                piece_memory_cache,
+            )
+            .await?
+        }
+    };
+
+    Ok(plotted_sector)
+}
+
+/// Mark sectors as expired, such that they are replotted as soon as plotting thread checks for
+/// expired sectors, returns `true` if any sectors were marked.
+///
+/// Sectors metadata must be locked for writing, such that sectors can't start or finish being
+/// replaced concurrently. Sectors that are being replaced already are skipped.
+fn mark_sectors_for_replotting<SectorOffsets>(
+    sector_offsets: SectorOffsets,
+    sectors_metadata: &mut [SectorMetadata],
+    sectors_being_replaced: &HashSet<usize>,
+    metadata_file: &File,
+) -> io::Result<bool>
+where
+    SectorOffsets: Iterator<Item = usize>,
+{
+    let sector_metadata_size = SectorMetadata::encoded_size();
+    let mut sectors_marked = false;
+
+    for sector_offset in sector_offsets {
+        if sectors_being_replaced.contains(&sector_offset) {
+            continue;
+        }
+
+        // Sector that is already marked is scheduled for replotting
+        if let Some(sector_metadata) = sectors_metadata.get_mut(sector_offset)
+            && sector_metadata.expires_at != SegmentIndex::ZERO
+        {
+            sector_metadata.expires_at = SegmentIndex::ZERO;
+            // Persisted such that sector is replotted even if farmer is restarted before that
+            metadata_file.write_all_at(
+                &sector_metadata.encode(),
+                RESERVED_PLOT_METADATA + (sector_offset * sector_metadata_size) as u64,
+            )?;
+
+            warn!(
+                %sector_offset,
+                sector_index = %sector_metadata.sector_index,
+                "Sector marked for replotting due to invalid solution"
+            );
+            sectors_marked = true;
+        }
+    }
+
+    Ok(sectors_marked)
+}
+
+/// Reconstruct information about plotted sector from its metadata
+fn plotted_sector_from_metadata(
+    public_key: &PublicKey,
+    sector_metadata: SectorMetadata,
+    farmer_protocol_info: &FarmerProtocolInfo,
+) -> PlottedSector {
+    let sector_index = sector_metadata.sector_index;
+    let sector_id = SectorId::new(public_key.hash(), sector_index);
+
+    let mut piece_indexes = Vec::with_capacity(sector_metadata.pieces_in_sector.into());
+    (PieceOffset::ZERO..)
+        .take(sector_metadata.pieces_in_sector.into())
+        .map(|piece_offset| {
+            sector_id.derive_piece_index(
+                piece_offset,
+                sector_metadata.history_size,
+                farmer_protocol_info.max_pieces_in_sector,
+                farmer_protocol_info.recent_segments,
+                farmer_protocol_info.recent_history_fraction,
+            )
+        })
+        .collect_into(&mut piece_indexes);
+
+    PlottedSector {
+        sector_id,
+        sector_index,
+        sector_metadata,
+        piece_indexes,
     }
 }
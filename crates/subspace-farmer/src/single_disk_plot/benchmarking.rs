@@ -0,0 +1,88 @@
+use crate::single_disk_plot::PlottingError;
+use rand::prelude::*;
+use std::num::NonZeroU64;
+use std::time::{Duration, Instant};
+use subspace_archiving::archiver::Archiver;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{HistorySize, PublicKey, RecordedHistorySegment, SegmentIndex};
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer_components::plotting::{plot_sector, PieceGetterRetryPolicy};
+use subspace_farmer_components::sector::{sector_size, SectorMetadata};
+use subspace_farmer_components::FarmerProtocolInfo;
+use subspace_proof_of_space::Table;
+
+/// Results of single disk plot benchmark
+#[derive(Debug, Clone)]
+pub struct BenchmarkResults {
+    /// Number of sectors audited
+    pub sector_count: usize,
+    /// Time it took to audit all sectors one after another
+    pub audit_duration: Duration,
+    /// Time it took to audit all sectors in parallel on all CPU cores
+    pub parallel_audit_duration: Duration,
+    /// Time it took to create the first solution for each sector proving was benchmarked on
+    pub proving_durations: Vec<Duration>,
+    /// Time it took to plot a single sector, if plotting was benchmarked
+    pub plotting_duration: Option<Duration>,
+}
+
+/// Plot a throwaway sector in memory using pieces of a locally archived segment of random data
+pub(super) async fn benchmark_plotting<PosTable>(
+    public_key: &PublicKey,
+    pieces_in_sector: u16,
+    kzg: &Kzg,
+    erasure_coding: &ErasureCoding,
+) -> Result<Duration, PlottingError>
+where
+    PosTable: Table,
+{
+    let mut input = RecordedHistorySegment::new_boxed();
+    thread_rng().fill(AsMut::<[u8]>::as_mut(input.as_mut()));
+
+    let mut archiver = Archiver::new(kzg.clone())
+        .expect("Archiver is always instantiated with valid erasure coding parameters; qed");
+    let archived_history_segment = archiver
+        .add_block(
+            AsRef::<[u8]>::as_ref(input.as_ref()).to_vec(),
+            Default::default(),
+        )
+        .into_iter()
+        .next()
+        .expect("Full recorded history segment always results in archived segment; qed")
+        .pieces;
+
+    // Only one segment of history exists locally, so all pieces are derived from it
+    let farmer_protocol_info = FarmerProtocolInfo {
+        history_size: HistorySize::from(NonZeroU64::new(1).expect("Not zero; qed")),
+        max_pieces_in_sector: pieces_in_sector,
+        sector_expiration: SegmentIndex::ONE,
+        recent_segments: HistorySize::from(NonZeroU64::new(5).expect("Not zero; qed")),
+        recent_history_fraction: (
+            HistorySize::from(NonZeroU64::new(1).expect("Not zero; qed")),
+            HistorySize::from(NonZeroU64::new(10).expect("Not zero; qed")),
+        ),
+    };
+
+    let mut sector = vec![0; sector_size(pieces_in_sector)];
+    let mut sector_metadata = vec![0; SectorMetadata::encoded_size()];
+
+    let plotting_start = Instant::now();
+
+    plot_sector::<_, PosTable>(
+        public_key,
+        0,
+        0,
+        &archived_history_segment,
+        PieceGetterRetryPolicy::default(),
+        &farmer_protocol_info,
+        kzg,
+        erasure_coding,
+        pieces_in_sector,
+        &mut sector,
+        &mut sector_metadata,
+        Default::default(),
+    )
+    .await?;
+
+    Ok(plotting_start.elapsed())
+}
@@ -0,0 +1,245 @@
+//! Auditing and proving of plotted sectors for a slot.
+//!
+//! Both auditing and proving are done in parallel on the farming thread pool of the plot, but
+//! results are always processed in the order defined by solution selection policy, so the same plot
+//! produces the same solutions for the same slot regardless of the number of threads.
+
+use crate::single_disk_plot::migration::MigratedSectors;
+use crate::single_disk_plot::SolutionSelectionPolicy;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{PublicKey, SectorIndex, Solution};
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer_components::auditing::audit_sector;
+use subspace_farmer_components::proving::ProvingError;
+use subspace_farmer_components::sector::SectorMetadata;
+use subspace_proof_of_space::Table;
+use subspace_rpc_primitives::SlotInfo;
+use tracing::{debug, error, trace};
+
+/// Plotted sector that is audited
+#[derive(Debug, Copy, Clone)]
+pub(super) struct FarmedSector<'a> {
+    /// Sector index
+    pub(super) sector_index: SectorIndex,
+    /// Sector metadata
+    pub(super) sector_metadata: &'a SectorMetadata,
+    /// Sector contents
+    pub(super) sector: &'a [u8],
+}
+
+/// Sectors of the plot that are farmed in order of their offsets, sectors that are being replaced
+/// are skipped.
+///
+/// While migration is in progress, migrated sectors are farmed instead of sectors with the same
+/// offsets in the old plot. `plot` must contain at least as many sectors of `sector_size` as there
+/// are sectors metadata entries.
+pub(super) fn farmed_sectors<'a>(
+    first_sector_index: SectorIndex,
+    sectors_metadata: &'a [SectorMetadata],
+    plot: &'a [u8],
+    sector_size: usize,
+    sectors_being_replaced: &HashSet<usize>,
+    migrated_sectors: Option<&'a MigratedSectors>,
+) -> Vec<FarmedSector<'a>> {
+    let migrated_sector_count = migrated_sectors
+        .map(|migrated_sectors| migrated_sectors.sectors_metadata.len())
+        .unwrap_or_default();
+
+    (0..sectors_metadata.len().max(migrated_sector_count))
+        .filter_map(|sector_offset| {
+            let sector_index = sector_offset as u64 + first_sector_index;
+
+            if let Some((sector_metadata, sector)) =
+                migrated_sectors.and_then(|migrated_sectors| migrated_sectors.get(sector_offset))
+            {
+                return Some(FarmedSector {
+                    sector_index,
+                    sector_metadata,
+                    sector,
+                });
+            }
+
+            if sectors_being_replaced.contains(&sector_offset) {
+                trace!(%sector_index, "Sector is being replaced, skipping");
+                return None;
+            }
+
+            Some(FarmedSector {
+                sector_index,
+                sector_metadata: &sectors_metadata[sector_offset],
+                sector: &plot[sector_offset * sector_size..][..sector_size],
+            })
+        })
+        .collect()
+}
+
+/// Solutions found in a plot for a slot
+#[derive(Debug)]
+pub(super) struct SlotSolutions {
+    /// Solutions in the order they were selected in
+    pub(super) solutions: Vec<Solution<PublicKey, PublicKey>>,
+    /// Number of sectors that had solution candidates
+    pub(super) candidate_sector_count: usize,
+    /// Time it took to audit all sectors
+    pub(super) audit_duration: Duration,
+    /// Time it took to prove solution candidates
+    pub(super) proving_duration: Duration,
+}
+
+/// Audit provided sectors (see [`farmed_sectors()`]) and prove up to `solutions_limit` solutions
+/// according to solution selection policy.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn audit_and_prove<PosTable>(
+    farming_thread_pool: &ThreadPool,
+    public_key: &PublicKey,
+    reward_address: &PublicKey,
+    sectors: &[FarmedSector<'_>],
+    slot_info: &SlotInfo,
+    solutions_limit: NonZeroUsize,
+    solution_selection_policy: SolutionSelectionPolicy,
+    kzg: &Kzg,
+    erasure_coding: &ErasureCoding,
+) -> Result<SlotSolutions, ProvingError>
+where
+    PosTable: Table,
+{
+    let slot = slot_info.slot_number;
+    let audit_start = Instant::now();
+
+    // Indexed parallel iterator preserves order of sectors when collecting
+    let sectors_solution_candidates = farming_thread_pool.install(|| {
+        sectors
+            .par_iter()
+            .filter_map(|farmed_sector| {
+                let FarmedSector {
+                    sector_index,
+                    sector_metadata,
+                    sector,
+                } = *farmed_sector;
+
+                trace!(%slot, %sector_index, "Auditing sector");
+
+                audit_sector(
+                    public_key,
+                    sector_index,
+                    &slot_info.global_challenge,
+                    slot_info.voting_solution_range,
+                    sector,
+                    sector_metadata,
+                )
+                .map(|solution_candidates| (sector_index, solution_candidates))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let audit_duration = audit_start.elapsed();
+    let candidate_sector_count = sectors_solution_candidates.len();
+
+    debug!(
+        %slot,
+        sector_count = %sectors.len(),
+        %candidate_sector_count,
+        ?audit_duration,
+        "Sectors audited"
+    );
+
+    let sectors_solution_candidates = match solution_selection_policy {
+        SolutionSelectionPolicy::First => sectors_solution_candidates,
+        SolutionSelectionPolicy::ClosestToChallenge => {
+            // Sector might have several candidates and the second best candidate of one sector
+            // might be better than the best candidate of another, so candidates are ranked
+            // individually. Stable sort keeps order of sectors and candidates within them for
+            // equal distances.
+            let mut solution_candidates = sectors_solution_candidates
+                .into_iter()
+                .flat_map(|(sector_index, solution_candidates)| {
+                    solution_candidates
+                        .split()
+                        .into_iter()
+                        .map(move |solution_candidates| (sector_index, solution_candidates))
+                })
+                .collect::<Vec<_>>();
+            solution_candidates.sort_by_key(|(_sector_index, solution_candidates)| {
+                solution_candidates.best_solution_distance()
+            });
+            solution_candidates
+        }
+    };
+
+    let proving_start = Instant::now();
+    let mut solutions = Vec::<Solution<PublicKey, PublicKey>>::new();
+    let mut sectors_solution_candidates = sectors_solution_candidates.into_iter();
+
+    // Candidates are proven in parallel batches, but results are processed in the original order,
+    // so solutions stay deterministic. Batch entry with solution candidates typically produces a
+    // solution, so batch is not larger than number of solutions still needed to avoid creating
+    // proof of space tables in vain.
+    loop {
+        let remaining = solutions_limit.get() - solutions.len();
+        let batch = sectors_solution_candidates
+            .by_ref()
+            .take(farming_thread_pool.current_num_threads().min(remaining))
+            .collect::<Vec<_>>();
+
+        if batch.is_empty() {
+            break;
+        }
+
+        let sectors_solutions = farming_thread_pool.install(|| {
+            batch
+                .into_par_iter()
+                .map(|(sector_index, solution_candidates)| {
+                    let mut sector_solutions = Vec::new();
+
+                    for maybe_solution in solution_candidates.into_iter::<_, PosTable>(
+                        reward_address,
+                        kzg,
+                        erasure_coding,
+                    )? {
+                        let solution = match maybe_solution {
+                            Ok(solution) => solution,
+                            Err(error) => {
+                                error!(%slot, %sector_index, %error, "Failed to prove");
+                                // Do not error completely on disk corruption or other reasons why
+                                // proving might fail
+                                continue;
+                            }
+                        };
+
+                        debug!(%slot, %sector_index, "Solution found");
+                        trace!(?solution, "Solution found");
+
+                        sector_solutions.push(solution);
+
+                        if sector_solutions.len() >= remaining {
+                            break;
+                        }
+                    }
+
+                    Ok::<_, ProvingError>(sector_solutions)
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+
+        for sector_solutions in sectors_solutions {
+            let remaining = solutions_limit.get() - solutions.len();
+            solutions.extend(sector_solutions.into_iter().take(remaining));
+        }
+
+        if solutions.len() >= solutions_limit.get() {
+            break;
+        }
+    }
+
+    Ok(SlotSolutions {
+        solutions,
+        candidate_sector_count,
+        audit_duration,
+        proving_duration: proving_start.elapsed(),
+    })
+}
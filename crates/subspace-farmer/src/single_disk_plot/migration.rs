@@ -0,0 +1,382 @@
+//! Migration of single disk plot to the current plot format and/or different number of pieces in
+//! sector.
+//!
+//! Both sector size and encoding might change, so sectors are not converted in place, instead they
+//! are plotted again one at a time into separate plot and metadata files next to the old ones. Old
+//! files stay untouched until migration is complete, so sectors that were not migrated yet are
+//! still farmed (as long as [`PlotVersionMigration`] of the old plot version allows that), while
+//! each migrated sector is farmed instead of the old sector with the same offset as soon as it is
+//! written, see [`MigratedSectors`]. Progress is tracked by sector count in the header of the new
+//! metadata file, which is only updated once sector is written to disk, such that migration resumes
+//! where it left off after interruption. When all sectors are migrated, new files replace old ones
+//! next time plot is opened.
+//!
+//! NOTE: Migration needs enough free disk space for the second copy of the plot.
+
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_plot::{
+    PlotMetadataHeader, SingleDiskPlot, SingleDiskPlotError, SingleDiskPlotInfo,
+    RESERVED_PLOT_METADATA,
+};
+use memmap2::Mmap;
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Seek, SeekFrom};
+use std::path::Path;
+use std::{fs, io};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::{sector_size, SectorMetadata};
+use tracing::info;
+
+/// Migration from plots of a particular [`PlotMetadataHeader::version`].
+///
+/// Sectors are always plotted again from pieces, so old sectors are never decoded, migration only
+/// defines how plot is treated until all of its sectors are replaced.
+pub(super) trait PlotVersionMigration: Sync {
+    /// Plot version sectors are migrated from
+    fn version(&self) -> u8;
+
+    /// Whether sectors of this version can be farmed and read from by the current farmer, such
+    /// sectors are farmed until they are migrated, otherwise only migrated sectors are farmed
+    fn can_farm_old_sectors(&self) -> bool;
+}
+
+/// Migration from the current plot version, which only changes number of pieces in sector
+struct CurrentVersionMigration;
+
+impl PlotVersionMigration for CurrentVersionMigration {
+    fn version(&self) -> u8 {
+        SingleDiskPlot::SUPPORTED_PLOT_VERSION
+    }
+
+    fn can_farm_old_sectors(&self) -> bool {
+        true
+    }
+}
+
+/// Migrations from all plot versions that are known, new plot versions must add migration from the
+/// previous version here
+static PLOT_VERSION_MIGRATIONS: &[&dyn PlotVersionMigration] = &[&CurrentVersionMigration];
+
+/// Migration from specified plot version, `None` if version is not known (plot was created by a
+/// newer farmer)
+pub(super) fn plot_version_migration(version: u8) -> Option<&'static dyn PlotVersionMigration> {
+    PLOT_VERSION_MIGRATIONS
+        .iter()
+        .copied()
+        .find(|plot_version_migration| plot_version_migration.version() == version)
+}
+
+/// Migration of single disk plot that was scheduled, but not finished yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlotMigration {
+    /// Number of pieces in sector plot is migrated to
+    target_pieces_in_sector: u16,
+    /// All sectors were migrated and new files are replacing old ones
+    #[serde(default)]
+    completed: bool,
+}
+
+impl PlotMigration {
+    const FILE_NAME: &'static str = "migration.json";
+    const PLOT_FILE: &'static str = "plot.migration.bin";
+    const METADATA_FILE: &'static str = "metadata.migration.bin";
+
+    /// Create new migration to specified number of pieces in sector
+    pub fn new(target_pieces_in_sector: u16) -> Self {
+        Self {
+            target_pieces_in_sector,
+            completed: false,
+        }
+    }
+
+    /// Load migration from plot directory, `None` means no migration is in progress.
+    pub fn load_from(directory: &Path) -> io::Result<Option<Self>> {
+        let bytes = match fs::read(directory.join(Self::FILE_NAME)) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                return if error.kind() == io::ErrorKind::NotFound {
+                    Ok(None)
+                } else {
+                    Err(error)
+                };
+            }
+        };
+
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// Store migration to plot directory so it can be resumed upon restart.
+    pub fn store_to(&self, directory: &Path) -> io::Result<()> {
+        fs::write(
+            directory.join(Self::FILE_NAME),
+            serde_json::to_vec(self).expect("Migration serialization never fails; qed"),
+        )
+    }
+
+    /// Number of pieces in sector plot is migrated to
+    pub fn target_pieces_in_sector(&self) -> u16 {
+        self.target_pieces_in_sector
+    }
+
+    /// Number of sectors plot will have once migrated
+    pub(super) fn target_sector_count(&self, allocated_space: u64) -> usize {
+        (allocated_space / sector_size(self.target_pieces_in_sector) as u64) as usize
+    }
+
+    /// Number of sectors migrated so far
+    pub(super) fn migrated_sector_count(directory: &Path) -> Result<u64, SingleDiskPlotError> {
+        let metadata_file = match File::open(directory.join(Self::METADATA_FILE)) {
+            Ok(metadata_file) => metadata_file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(0);
+            }
+            Err(error) => {
+                return Err(error.into());
+            }
+        };
+
+        Ok(read_metadata_header(&metadata_file)?
+            .filter(|metadata_header| {
+                metadata_header.version == SingleDiskPlot::SUPPORTED_PLOT_VERSION
+            })
+            .map(|metadata_header| metadata_header.sector_count)
+            .unwrap_or_default())
+    }
+
+    /// Replace old plot files with migrated ones and update plot info accordingly.
+    ///
+    /// Every step can be repeated, so interrupted completion is finished next time plot is opened.
+    pub(super) fn complete(
+        mut self,
+        directory: &Path,
+        single_disk_plot_info: &mut SingleDiskPlotInfo,
+    ) -> io::Result<()> {
+        if !self.completed {
+            self.completed = true;
+            self.store_to(directory)?;
+        }
+
+        for (migrated_file, file) in [
+            (Self::PLOT_FILE, SingleDiskPlot::PLOT_FILE),
+            (Self::METADATA_FILE, SingleDiskPlot::METADATA_FILE),
+        ] {
+            let migrated_file = directory.join(migrated_file);
+            if migrated_file.exists() {
+                fs::rename(migrated_file, directory.join(file))?;
+            }
+        }
+
+        single_disk_plot_info.set_pieces_in_sector(self.target_pieces_in_sector);
+        single_disk_plot_info.store_to(directory)?;
+
+        fs::remove_file(directory.join(Self::FILE_NAME))
+    }
+
+    /// Remove migration and everything migrated so far
+    pub(super) fn abort(directory: &Path) -> io::Result<()> {
+        for file in [Self::PLOT_FILE, Self::METADATA_FILE, Self::FILE_NAME] {
+            let file = directory.join(file);
+            if file.exists() {
+                info!("Deleting {}", file.display());
+                fs::remove_file(file)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether all sectors were migrated and new files are replacing old ones
+    pub(super) fn is_completed(&self) -> bool {
+        self.completed
+    }
+}
+
+/// Progress of plot migration
+#[derive(Debug, Copy, Clone)]
+pub struct PlotMigrationStatus {
+    /// Number of pieces in sector plot is migrated to
+    pub target_pieces_in_sector: u16,
+    /// Number of sectors migrated so far
+    pub migrated_sector_count: u64,
+    /// Number of sectors plot will have once migrated
+    pub target_sector_count: u64,
+}
+
+/// Sectors migrated so far, while migration is in progress they are farmed and read from instead of
+/// sectors with the same offsets in the old plot
+pub(super) struct MigratedSectors {
+    /// Number of pieces in migrated sectors
+    pub(super) pieces_in_sector: u16,
+    /// Metadata of migrated sectors, index is sector offset
+    pub(super) sectors_metadata: Vec<SectorMetadata>,
+    /// Plot file sectors are migrated into, sized for all target sectors
+    pub(super) plot_mmap: Mmap,
+}
+
+impl MigratedSectors {
+    /// Migrated sector at specified offset with its contents
+    pub(super) fn get(&self, sector_offset: usize) -> Option<(&SectorMetadata, &[u8])> {
+        let sector_size = sector_size(self.pieces_in_sector);
+
+        self.sectors_metadata
+            .get(sector_offset)
+            .map(|sector_metadata| {
+                (
+                    sector_metadata,
+                    &self.plot_mmap[sector_offset * sector_size..][..sector_size],
+                )
+            })
+    }
+}
+
+/// Files that sectors are migrated into
+pub(super) struct MigrationFiles {
+    plot_file: File,
+    metadata_file: File,
+    metadata_header: PlotMetadataHeader,
+    pieces_in_sector: u16,
+    target_sector_count: usize,
+}
+
+impl MigrationFiles {
+    /// Open (or create) files for migration, making sure they have enough space for all sectors
+    pub(super) fn open(
+        directory: &Path,
+        plot_migration: &PlotMigration,
+        allocated_space: u64,
+    ) -> Result<Self, SingleDiskPlotError> {
+        let pieces_in_sector = plot_migration.target_pieces_in_sector;
+        let target_sector_count = plot_migration.target_sector_count(allocated_space);
+        let sector_metadata_size = SectorMetadata::encoded_size();
+
+        let mut metadata_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(directory.join(PlotMigration::METADATA_FILE))?;
+
+        let mut metadata_header = read_metadata_header(&metadata_file)?
+            .filter(|metadata_header| {
+                metadata_header.version == SingleDiskPlot::SUPPORTED_PLOT_VERSION
+            })
+            .unwrap_or(PlotMetadataHeader {
+                version: SingleDiskPlot::SUPPORTED_PLOT_VERSION,
+                sector_count: 0,
+            });
+        // Allocated space might have been decreased since migration has started
+        metadata_header.sector_count = metadata_header.sector_count.min(target_sector_count as u64);
+        metadata_file.write_all_at(&metadata_header.encode(), 0)?;
+
+        let metadata_file_size = metadata_file.seek(SeekFrom::End(0))?;
+        let expected_metadata_file_size =
+            RESERVED_PLOT_METADATA + sector_metadata_size as u64 * target_sector_count as u64;
+        if metadata_file_size > expected_metadata_file_size {
+            metadata_file.set_len(expected_metadata_file_size)?;
+        } else {
+            metadata_file.preallocate(expected_metadata_file_size)?;
+        }
+
+        let plot_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(directory.join(PlotMigration::PLOT_FILE))?;
+
+        let expected_plot_file_size =
+            sector_size(pieces_in_sector) as u64 * target_sector_count as u64;
+        if plot_file.metadata()?.len() > expected_plot_file_size {
+            plot_file.set_len(expected_plot_file_size)?;
+        } else {
+            plot_file.preallocate(expected_plot_file_size)?;
+        }
+
+        Ok(Self {
+            plot_file,
+            metadata_file,
+            metadata_header,
+            pieces_in_sector,
+            target_sector_count,
+        })
+    }
+
+    /// Number of pieces in sector plot is migrated to
+    pub(super) fn pieces_in_sector(&self) -> u16 {
+        self.pieces_in_sector
+    }
+
+    /// Read sectors migrated so far, sectors written later with [`Self::write_sector()`] need to be
+    /// added by the caller
+    pub(super) fn migrated_sectors(&self) -> Result<MigratedSectors, SingleDiskPlotError> {
+        let sector_metadata_size = SectorMetadata::encoded_size();
+        let sector_count = self.metadata_header.sector_count as usize;
+
+        let mut sectors_metadata_bytes = vec![0; sector_metadata_size * sector_count];
+        self.metadata_file
+            .read_exact_at(&mut sectors_metadata_bytes, RESERVED_PLOT_METADATA)?;
+
+        let sectors_metadata = sectors_metadata_bytes
+            .chunks_exact(sector_metadata_size)
+            .map(|mut sector_metadata_bytes| SectorMetadata::decode(&mut sector_metadata_bytes))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(SingleDiskPlotError::FailedToDecodeSectorMetadata)?;
+
+        Ok(MigratedSectors {
+            pieces_in_sector: self.pieces_in_sector,
+            sectors_metadata,
+            plot_mmap: unsafe { Mmap::map(&self.plot_file)? },
+        })
+    }
+
+    /// Offsets of sectors that are not migrated yet
+    pub(super) fn sector_offsets_left_to_migrate(&self) -> std::ops::Range<usize> {
+        self.metadata_header.sector_count as usize..self.target_sector_count
+    }
+
+    /// Write migrated sector to disk, sectors must be written in order.
+    ///
+    /// Sector is only accounted for in metadata header once it is durably written, such that
+    /// interrupted migration never considers partially written sector as migrated.
+    pub(super) fn write_sector(
+        &mut self,
+        sector_offset: usize,
+        sector: &[u8],
+        sector_metadata: &[u8],
+    ) -> io::Result<()> {
+        debug_assert_eq!(sector_offset as u64, self.metadata_header.sector_count);
+
+        self.plot_file.write_all_at(
+            sector,
+            (sector_offset * sector_size(self.pieces_in_sector)) as u64,
+        )?;
+        self.metadata_file.write_all_at(
+            sector_metadata,
+            RESERVED_PLOT_METADATA + (sector_offset * SectorMetadata::encoded_size()) as u64,
+        )?;
+        self.plot_file.sync_data()?;
+        self.metadata_file.sync_data()?;
+
+        self.metadata_header.sector_count += 1;
+        self.metadata_file
+            .write_all_at(&self.metadata_header.encode(), 0)?;
+        self.metadata_file.sync_data()
+    }
+}
+
+/// Read metadata header, `None` means file is empty or header is corrupted
+fn read_metadata_header(metadata_file: &File) -> io::Result<Option<PlotMetadataHeader>> {
+    if metadata_file.metadata()?.len() < PlotMetadataHeader::encoded_size() as u64 {
+        return Ok(None);
+    }
+
+    let mut metadata_header_bytes = vec![0; PlotMetadataHeader::encoded_size()];
+    metadata_file.read_exact_at(&mut metadata_header_bytes, 0)?;
+
+    Ok(PlotMetadataHeader::decode(&mut metadata_header_bytes.as_slice()).ok())
+}
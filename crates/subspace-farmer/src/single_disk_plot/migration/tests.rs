@@ -0,0 +1,262 @@
+use crate::single_disk_plot::migration::{plot_version_migration, MigrationFiles, PlotMigration};
+use crate::single_disk_plot::{
+    SingleDiskPlot, SingleDiskPlotId, SingleDiskPlotInfo, RESERVED_PLOT_METADATA,
+};
+use parity_scale_codec::Encode;
+use std::fs;
+use std::num::NonZeroU64;
+use std::path::Path;
+use subspace_core_primitives::{HistorySize, PublicKey, Record, SegmentIndex};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_farmer_components::sector::{sector_size, SectorMetadata};
+use tempfile::TempDir;
+
+const TARGET_PIECES_IN_SECTOR: u16 = 2;
+
+fn write_test_sector(migration_files: &mut MigrationFiles, sector_offset: usize) {
+    migration_files
+        .write_sector(
+            sector_offset,
+            &vec![sector_offset as u8 + 1; sector_size(TARGET_PIECES_IN_SECTOR)],
+            &vec![sector_offset as u8 + 1; SectorMetadata::encoded_size()],
+        )
+        .unwrap();
+}
+
+fn assert_test_sector(directory: &Path, sector_offset: usize) {
+    let plot_file = fs::File::open(directory.join(PlotMigration::PLOT_FILE)).unwrap();
+    let mut sector = vec![0; sector_size(TARGET_PIECES_IN_SECTOR)];
+    plot_file
+        .read_exact_at(&mut sector, (sector_offset * sector.len()) as u64)
+        .unwrap();
+    assert!(sector.iter().all(|&byte| byte == sector_offset as u8 + 1));
+
+    let metadata_file = fs::File::open(directory.join(PlotMigration::METADATA_FILE)).unwrap();
+    let mut sector_metadata = vec![0; SectorMetadata::encoded_size()];
+    metadata_file
+        .read_exact_at(
+            &mut sector_metadata,
+            RESERVED_PLOT_METADATA + (sector_offset * sector_metadata.len()) as u64,
+        )
+        .unwrap();
+    assert!(sector_metadata
+        .iter()
+        .all(|&byte| byte == sector_offset as u8 + 1));
+}
+
+#[test]
+fn migration_resumes_where_it_left_off() {
+    let directory = TempDir::new().unwrap();
+    let directory = directory.path();
+    let allocated_space = sector_size(TARGET_PIECES_IN_SECTOR) as u64 * 3;
+
+    assert!(PlotMigration::load_from(directory).unwrap().is_none());
+    let plot_migration = PlotMigration::new(TARGET_PIECES_IN_SECTOR);
+    plot_migration.store_to(directory).unwrap();
+    let plot_migration = PlotMigration::load_from(directory).unwrap().unwrap();
+    assert_eq!(
+        plot_migration.target_pieces_in_sector(),
+        TARGET_PIECES_IN_SECTOR
+    );
+    assert_eq!(plot_migration.target_sector_count(allocated_space), 3);
+    assert_eq!(PlotMigration::migrated_sector_count(directory).unwrap(), 0);
+
+    {
+        let mut migration_files =
+            MigrationFiles::open(directory, &plot_migration, allocated_space).unwrap();
+        assert_eq!(migration_files.pieces_in_sector(), TARGET_PIECES_IN_SECTOR);
+        assert_eq!(migration_files.sector_offsets_left_to_migrate(), 0..3);
+
+        write_test_sector(&mut migration_files, 0);
+        assert_eq!(migration_files.sector_offsets_left_to_migrate(), 1..3);
+    }
+
+    // Interrupted migration resumes from the next sector
+    assert_eq!(PlotMigration::migrated_sector_count(directory).unwrap(), 1);
+    {
+        let mut migration_files =
+            MigrationFiles::open(directory, &plot_migration, allocated_space).unwrap();
+        assert_eq!(migration_files.sector_offsets_left_to_migrate(), 1..3);
+
+        write_test_sector(&mut migration_files, 1);
+    }
+    assert_eq!(PlotMigration::migrated_sector_count(directory).unwrap(), 2);
+    assert_test_sector(directory, 0);
+    assert_test_sector(directory, 1);
+
+    // Files are sized for all target sectors
+    assert_eq!(
+        fs::metadata(directory.join(PlotMigration::PLOT_FILE))
+            .unwrap()
+            .len(),
+        allocated_space
+    );
+    assert_eq!(
+        fs::metadata(directory.join(PlotMigration::METADATA_FILE))
+            .unwrap()
+            .len(),
+        RESERVED_PLOT_METADATA + SectorMetadata::encoded_size() as u64 * 3
+    );
+
+    // Allocated space was decreased since migration has started
+    let allocated_space = sector_size(TARGET_PIECES_IN_SECTOR) as u64;
+    {
+        let migration_files =
+            MigrationFiles::open(directory, &plot_migration, allocated_space).unwrap();
+        assert!(migration_files.sector_offsets_left_to_migrate().is_empty());
+    }
+    assert_eq!(PlotMigration::migrated_sector_count(directory).unwrap(), 1);
+    assert_eq!(
+        fs::metadata(directory.join(PlotMigration::PLOT_FILE))
+            .unwrap()
+            .len(),
+        allocated_space
+    );
+    assert_test_sector(directory, 0);
+}
+
+#[test]
+fn migration_completion_replaces_plot_files() {
+    let directory = TempDir::new().unwrap();
+    let directory = directory.path();
+    let allocated_space = sector_size(TARGET_PIECES_IN_SECTOR) as u64;
+
+    let mut single_disk_plot_info = SingleDiskPlotInfo::new(
+        SingleDiskPlotId::new(),
+        [1; 32],
+        PublicKey::from([2; 32]),
+        0,
+        TARGET_PIECES_IN_SECTOR * 2,
+        allocated_space,
+    );
+    single_disk_plot_info.store_to(directory).unwrap();
+
+    let plot_migration = PlotMigration::new(TARGET_PIECES_IN_SECTOR);
+    plot_migration.store_to(directory).unwrap();
+    {
+        let mut migration_files =
+            MigrationFiles::open(directory, &plot_migration, allocated_space).unwrap();
+        write_test_sector(&mut migration_files, 0);
+    }
+
+    plot_migration
+        .complete(directory, &mut single_disk_plot_info)
+        .unwrap();
+
+    assert!(PlotMigration::load_from(directory).unwrap().is_none());
+    assert!(!directory.join(PlotMigration::PLOT_FILE).exists());
+    assert!(!directory.join(PlotMigration::METADATA_FILE).exists());
+    assert_eq!(
+        fs::metadata(directory.join(SingleDiskPlot::PLOT_FILE))
+            .unwrap()
+            .len(),
+        allocated_space
+    );
+    assert_eq!(
+        single_disk_plot_info.pieces_in_sector(),
+        TARGET_PIECES_IN_SECTOR
+    );
+    assert_eq!(
+        SingleDiskPlotInfo::load_from(directory)
+            .unwrap()
+            .unwrap()
+            .pieces_in_sector(),
+        TARGET_PIECES_IN_SECTOR
+    );
+}
+
+#[test]
+fn migration_abort_removes_migrated_sectors() {
+    let directory = TempDir::new().unwrap();
+    let directory = directory.path();
+    let allocated_space = sector_size(TARGET_PIECES_IN_SECTOR) as u64 * 2;
+
+    let plot_migration = PlotMigration::new(TARGET_PIECES_IN_SECTOR);
+    plot_migration.store_to(directory).unwrap();
+    {
+        let mut migration_files =
+            MigrationFiles::open(directory, &plot_migration, allocated_space).unwrap();
+        write_test_sector(&mut migration_files, 0);
+    }
+
+    PlotMigration::abort(directory).unwrap();
+
+    assert!(PlotMigration::load_from(directory).unwrap().is_none());
+    assert_eq!(PlotMigration::migrated_sector_count(directory).unwrap(), 0);
+    assert!(!directory.join(PlotMigration::PLOT_FILE).exists());
+
+    // Migration started again begins from scratch
+    let migration_files =
+        MigrationFiles::open(directory, &plot_migration, allocated_space).unwrap();
+    assert_eq!(migration_files.sector_offsets_left_to_migrate(), 0..2);
+}
+
+#[test]
+fn migrated_sectors_are_read_back() {
+    let directory = TempDir::new().unwrap();
+    let directory = directory.path();
+    let allocated_space = sector_size(TARGET_PIECES_IN_SECTOR) as u64 * 3;
+
+    let plot_migration = PlotMigration::new(TARGET_PIECES_IN_SECTOR);
+    plot_migration.store_to(directory).unwrap();
+    {
+        let mut migration_files =
+            MigrationFiles::open(directory, &plot_migration, allocated_space).unwrap();
+        assert!(migration_files
+            .migrated_sectors()
+            .unwrap()
+            .sectors_metadata
+            .is_empty());
+
+        for sector_offset in 0..2 {
+            let sector_metadata = SectorMetadata {
+                sector_index: sector_offset as u64 + 10,
+                pieces_in_sector: TARGET_PIECES_IN_SECTOR,
+                s_bucket_sizes: Box::new([1; Record::NUM_S_BUCKETS]),
+                history_size: HistorySize::from(NonZeroU64::new(1).unwrap()),
+                expires_at: SegmentIndex::from(sector_offset as u64 + 5),
+            };
+            migration_files
+                .write_sector(
+                    sector_offset,
+                    &vec![sector_offset as u8 + 1; sector_size(TARGET_PIECES_IN_SECTOR)],
+                    &sector_metadata.encode(),
+                )
+                .unwrap();
+        }
+    }
+
+    // Migration continues after restart with sectors migrated before
+    let migration_files =
+        MigrationFiles::open(directory, &plot_migration, allocated_space).unwrap();
+    let migrated_sectors = migration_files.migrated_sectors().unwrap();
+    assert_eq!(migrated_sectors.pieces_in_sector, TARGET_PIECES_IN_SECTOR);
+    assert_eq!(migrated_sectors.sectors_metadata.len(), 2);
+
+    for sector_offset in 0..2 {
+        let (sector_metadata, sector) = migrated_sectors.get(sector_offset).unwrap();
+        assert_eq!(sector_metadata.sector_index, sector_offset as u64 + 10);
+        assert_eq!(
+            sector_metadata.expires_at,
+            SegmentIndex::from(sector_offset as u64 + 5)
+        );
+        assert_eq!(sector.len(), sector_size(TARGET_PIECES_IN_SECTOR));
+        assert!(sector.iter().all(|&byte| byte == sector_offset as u8 + 1));
+    }
+    // Sector that is not migrated yet is farmed from the old plot
+    assert!(migrated_sectors.get(2).is_none());
+}
+
+#[test]
+fn plot_version_migrations() {
+    let current_version_migration =
+        plot_version_migration(SingleDiskPlot::SUPPORTED_PLOT_VERSION).unwrap();
+    assert_eq!(
+        current_version_migration.version(),
+        SingleDiskPlot::SUPPORTED_PLOT_VERSION
+    );
+    assert!(current_version_migration.can_farm_old_sectors());
+
+    // Plot created by a newer farmer can't be migrated
+    assert!(plot_version_migration(SingleDiskPlot::SUPPORTED_PLOT_VERSION + 1).is_none());
+}
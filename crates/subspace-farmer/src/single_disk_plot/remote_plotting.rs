@@ -0,0 +1,830 @@
+//! Plotting of sectors on remote plotting servers.
+//!
+//! Plotting needs a lot of CPU and RAM, while auditing and proving only need fast disks, so
+//! plotting can be offloaded to dedicated machines running plotting server, see
+//! [`run_plotting_server()`] and [`RemotePlotter`].
+//!
+//! Protocol works over TCP with one job per connection. Server sends random challenge and client
+//! replies with keyed hash of the challenge, where key is [`SharedSecret`] shared between farmers
+//! and plotting server, followed by [`PlottingJob`] encoded as JSON and prefixed with its length as
+//! big-endian `u32`. Server replies with a single status byte, on success followed by sector bytes
+//! and encoded [`SectorMetadata`] (their sizes are derived from job), on failure followed by error
+//! message in the same length-prefixed format as job.
+//!
+//! Server checks job against protocol info of its own node before allocating anything, such that
+//! malformed or malicious job can't make it allocate arbitrary amount of memory. Client doesn't
+//! trust the server either: server that doesn't respond in time is skipped and returned sector is
+//! checked by proving and verifying a solution from it before it is used.
+
+#[cfg(test)]
+mod tests;
+
+use crate::node_client::NodeClient;
+use crate::single_disk_plot::solution_verification::solution_piece_index;
+use crate::single_disk_plot::{plotted_sector_from_metadata, PIECE_GETTER_RETRY_NUMBER};
+use crate::utils::shared_secret::{SharedSecret, CHALLENGE_LENGTH};
+use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{
+    Blake2b256Hash, HistorySize, PublicKey, SectorIndex, SegmentIndex, SolutionRange,
+};
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer_components::auditing::audit_sector;
+use subspace_farmer_components::piece_caching::PieceMemoryCache;
+use subspace_farmer_components::plotting::{
+    plot_sector, PieceGetter, PieceGetterRetryPolicy, PlottedSector, PlottingError,
+};
+use subspace_farmer_components::proving::ProvingError;
+use subspace_farmer_components::sector::{sector_size, SectorMetadata};
+use subspace_farmer_components::FarmerProtocolInfo;
+use subspace_proof_of_space::Table;
+use subspace_verification::{verify_solution_with_global_challenge, PieceCheckParams};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufStream};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+/// Status byte of successful response
+const RESPONSE_SUCCESS: u8 = 0;
+/// Status byte of failed response
+const RESPONSE_FAILURE: u8 = 1;
+/// Jobs and error messages are tiny, this protects server from allocating huge buffers
+const MAX_MESSAGE_SIZE: u32 = 1024 * 1024;
+/// Number of random challenges sector returned by plotting server is audited with before giving up
+/// on finding a solution to verify, sector has solution candidates for most challenges
+const SECTOR_VERIFICATION_ATTEMPTS: usize = 32;
+/// Time farmer has to authenticate itself and send the job after connecting to plotting server
+pub const AUTHENTICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Timeouts after which plotting server is considered unresponsive and job is retried on the next
+/// server
+#[derive(Debug, Copy, Clone)]
+struct Timeouts {
+    /// Time to establish connection and receive authentication challenge
+    connect: Duration,
+    /// Time for server to start sending response after job was sent, includes time job waits for
+    /// other jobs on the server to finish
+    plotting: Duration,
+    /// Max time without receiving any data while reading response, doesn't depend on sector size
+    /// unlike timeout for reading the whole response would
+    read: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(10),
+            plotting: Duration::from_secs(30 * 60),
+            read: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Sector plotting job sent to plotting server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlottingJob {
+    /// Public key of the farmer
+    pub public_key: PublicKey,
+    /// Index of the sector to plot
+    pub sector_index: SectorIndex,
+    /// Protocol info at the time of plotting
+    pub farmer_protocol_info: FarmerProtocolInfo,
+    /// Number of pieces in sector
+    pub pieces_in_sector: u16,
+}
+
+/// Reasons for plotting server to reject plotting job
+#[derive(Debug, Error)]
+pub enum InvalidPlottingJob {
+    /// Number of pieces in sector is zero or exceeds protocol limit
+    #[error(
+        "Number of pieces in sector {pieces_in_sector} is outside of allowed range \
+        1..={max_pieces_in_sector}"
+    )]
+    InvalidPiecesInSector {
+        /// Number of pieces in sector in the job
+        pieces_in_sector: u16,
+        /// Max number of pieces in sector according to protocol
+        max_pieces_in_sector: u16,
+    },
+    /// Protocol parameters in the job don't match those of the node plotting server is connected to
+    #[error("Protocol info in the job {job:?} doesn't match node's protocol info {node:?}")]
+    ProtocolInfoMismatch {
+        /// Protocol info in the job
+        job: FarmerProtocolInfo,
+        /// Protocol info of the node
+        node: FarmerProtocolInfo,
+    },
+    /// History size in the job is ahead of the node plotting server is connected to
+    #[error("History size {job:?} in the job is ahead of node's history size {node:?}")]
+    HistorySizeAhead {
+        /// History size in the job
+        job: HistorySize,
+        /// History size of the node
+        node: HistorySize,
+    },
+}
+
+/// Check plotting job against protocol info of the node, history size in the job can be behind
+/// node's history size since it is fixed at the time plotting of the sector started
+fn validate_job(
+    job: &PlottingJob,
+    farmer_protocol_info: &FarmerProtocolInfo,
+) -> Result<(), InvalidPlottingJob> {
+    if job.pieces_in_sector == 0 || job.pieces_in_sector > farmer_protocol_info.max_pieces_in_sector
+    {
+        return Err(InvalidPlottingJob::InvalidPiecesInSector {
+            pieces_in_sector: job.pieces_in_sector,
+            max_pieces_in_sector: farmer_protocol_info.max_pieces_in_sector,
+        });
+    }
+
+    let job_protocol_info = &job.farmer_protocol_info;
+    if job_protocol_info.max_pieces_in_sector != farmer_protocol_info.max_pieces_in_sector
+        || job_protocol_info.sector_expiration != farmer_protocol_info.sector_expiration
+        || job_protocol_info.recent_segments != farmer_protocol_info.recent_segments
+        || job_protocol_info.recent_history_fraction != farmer_protocol_info.recent_history_fraction
+    {
+        return Err(InvalidPlottingJob::ProtocolInfoMismatch {
+            job: *job_protocol_info,
+            node: *farmer_protocol_info,
+        });
+    }
+
+    if job_protocol_info.history_size > farmer_protocol_info.history_size {
+        return Err(InvalidPlottingJob::HistorySizeAhead {
+            job: job_protocol_info.history_size,
+            node: farmer_protocol_info.history_size,
+        });
+    }
+
+    Ok(())
+}
+
+/// Errors happening during remote plotting
+#[derive(Debug, Error)]
+pub enum RemotePlottingError {
+    /// I/O error occurred
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Failed to encode or decode job
+    #[error("Failed to encode or decode job: {0}")]
+    Json(#[from] serde_json::Error),
+    /// Message is too large
+    #[error("Message of {size} bytes is too large")]
+    MessageTooLarge {
+        /// Size of the message
+        size: u32,
+    },
+    /// Bad sector output size
+    #[error("Bad sector output size: provided {provided}, expected {expected}")]
+    BadSectorOutputSize {
+        /// Actual size
+        provided: usize,
+        /// Expected size
+        expected: usize,
+    },
+    /// Bad sector metadata output size
+    #[error("Bad sector metadata output size: provided {provided}, expected {expected}")]
+    BadSectorMetadataOutputSize {
+        /// Actual size
+        provided: usize,
+        /// Expected size
+        expected: usize,
+    },
+    /// Failed to decode sector metadata returned by plotting server
+    #[error("Failed to decode sector metadata: {0}")]
+    FailedToDecodeSectorMetadata(parity_scale_codec::Error),
+    /// Sector metadata returned by plotting server doesn't correspond to the job
+    #[error("Sector metadata returned by plotting server doesn't correspond to the job")]
+    UnexpectedSectorMetadata,
+    /// Failed to prove solution from sector returned by plotting server
+    #[error("Failed to prove solution from sector returned by plotting server: {0}")]
+    Proving(#[from] ProvingError),
+    /// Sector returned by plotting server didn't produce any solution to verify
+    #[error("Sector returned by plotting server didn't produce any solution to verify")]
+    NoSolutionToVerify,
+    /// Solution from sector returned by plotting server is invalid
+    #[error("Solution from sector returned by plotting server is invalid: {0}")]
+    InvalidSolution(subspace_verification::Error),
+    /// Failed to retrieve segment commitment needed for sector verification from the node
+    #[error("Failed to retrieve segment commitment of segment {segment_index}: {error}")]
+    FailedToGetSegmentCommitment {
+        /// Segment index
+        segment_index: SegmentIndex,
+        /// Lower-level error
+        error: String,
+    },
+    /// Segment commitment needed for sector verification is not known to the node
+    #[error("Segment commitment of segment {segment_index} not found")]
+    SegmentCommitmentNotFound {
+        /// Segment index
+        segment_index: SegmentIndex,
+    },
+    /// Plotting server didn't respond in time
+    #[error("Plotting server {server} didn't respond in time")]
+    Timeout {
+        /// Address of plotting server
+        server: SocketAddr,
+    },
+    /// Farmer failed to authenticate
+    #[error("Farmer failed to authenticate")]
+    AuthenticationFailed,
+    /// Farmer didn't authenticate in time
+    #[error("Farmer didn't authenticate in time")]
+    AuthenticationTimeout,
+    /// Plotting server failed to plot sector
+    #[error("Plotting server {server} failed to plot sector: {error}")]
+    ServerFailed {
+        /// Address of plotting server
+        server: SocketAddr,
+        /// Error message returned by server
+        error: String,
+    },
+    /// Unexpected status byte in response
+    #[error("Unexpected status byte {0} in response")]
+    UnexpectedStatus(u8),
+    /// Plotting task failed
+    #[error("Plotting task failed: {0}")]
+    TaskFailed(#[from] tokio::task::JoinError),
+}
+
+async fn write_message<S>(stream: &mut S, message: &[u8]) -> Result<(), RemotePlottingError>
+where
+    S: AsyncWrite + Unpin,
+{
+    let size = message.len() as u32;
+    if size > MAX_MESSAGE_SIZE {
+        return Err(RemotePlottingError::MessageTooLarge { size });
+    }
+
+    stream.write_u32(size).await?;
+    stream.write_all(message).await?;
+
+    Ok(())
+}
+
+async fn read_message<S>(stream: &mut S) -> Result<Vec<u8>, RemotePlottingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let size = stream.read_u32().await?;
+    if size > MAX_MESSAGE_SIZE {
+        return Err(RemotePlottingError::MessageTooLarge { size });
+    }
+
+    let mut message = vec![0; size as usize];
+    stream.read_exact(&mut message).await?;
+
+    Ok(message)
+}
+
+/// Client that sends sector plotting jobs to plotting servers
+#[derive(Debug, Clone)]
+pub struct RemotePlotter {
+    servers: Arc<[SocketAddr]>,
+    secret: SharedSecret,
+    next_server: Arc<AtomicUsize>,
+}
+
+impl RemotePlotter {
+    /// Create new instance, jobs are distributed across provided servers in round-robin fashion,
+    /// job is retried on the next server if one of them fails.
+    ///
+    /// `secret` must be the same as the one plotting servers were started with.
+    ///
+    /// Returns `None` if no servers were provided.
+    pub fn new(servers: Vec<SocketAddr>, secret: SharedSecret) -> Option<Self> {
+        if servers.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            servers: servers.into(),
+            secret,
+            next_server: Arc::default(),
+        })
+    }
+
+    /// Plot sector on one of the plotting servers, output buffers have the same meaning as in
+    /// [`plot_sector()`].
+    ///
+    /// Sector is verified before it is returned, node client is used to retrieve segment
+    /// commitment for that.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn plot_sector<NC, PosTable>(
+        &self,
+        public_key: &PublicKey,
+        sector_index: SectorIndex,
+        farmer_protocol_info: &FarmerProtocolInfo,
+        pieces_in_sector: u16,
+        sector_output: &mut [u8],
+        sector_metadata_output: &mut [u8],
+        node_client: &NC,
+        kzg: &Kzg,
+        erasure_coding: &ErasureCoding,
+    ) -> Result<PlottedSector, RemotePlottingError>
+    where
+        NC: NodeClient,
+        PosTable: Table,
+    {
+        if sector_output.len() < sector_size(pieces_in_sector) {
+            return Err(RemotePlottingError::BadSectorOutputSize {
+                provided: sector_output.len(),
+                expected: sector_size(pieces_in_sector),
+            });
+        }
+
+        if sector_metadata_output.len() < SectorMetadata::encoded_size() {
+            return Err(RemotePlottingError::BadSectorMetadataOutputSize {
+                provided: sector_metadata_output.len(),
+                expected: SectorMetadata::encoded_size(),
+            });
+        }
+
+        let job = PlottingJob {
+            public_key: *public_key,
+            sector_index,
+            farmer_protocol_info: *farmer_protocol_info,
+            pieces_in_sector,
+        };
+        let sector_output = &mut sector_output[..sector_size(pieces_in_sector)];
+        let sector_metadata_output = &mut sector_metadata_output[..SectorMetadata::encoded_size()];
+
+        let first_server = self.next_server.fetch_add(1, Ordering::Relaxed);
+        let mut last_error = None;
+
+        for server_offset in 0..self.servers.len() {
+            let server = self.servers[(first_server + server_offset) % self.servers.len()];
+
+            debug!(%server, %sector_index, "Plotting sector remotely");
+
+            let result = async {
+                send_job(
+                    server,
+                    &self.secret,
+                    &job,
+                    &Timeouts::default(),
+                    sector_output,
+                    sector_metadata_output,
+                )
+                .await?;
+
+                let sector_metadata = SectorMetadata::decode(&mut &*sector_metadata_output)
+                    .map_err(RemotePlottingError::FailedToDecodeSectorMetadata)?;
+
+                if sector_metadata.sector_index != sector_index
+                    || sector_metadata.pieces_in_sector != pieces_in_sector
+                    || sector_metadata.history_size != farmer_protocol_info.history_size
+                {
+                    return Err(RemotePlottingError::UnexpectedSectorMetadata);
+                }
+
+                verify_sector::<_, PosTable>(
+                    public_key,
+                    sector_output,
+                    &sector_metadata,
+                    farmer_protocol_info,
+                    node_client,
+                    kzg,
+                    erasure_coding,
+                )
+                .await?;
+
+                Ok::<_, RemotePlottingError>(sector_metadata)
+            }
+            .await;
+
+            match result {
+                Ok(sector_metadata) => {
+                    return Ok(plotted_sector_from_metadata(
+                        public_key,
+                        sector_metadata,
+                        farmer_protocol_info,
+                    ));
+                }
+                // Node problems are not specific to plotting server, there is no point in trying
+                // other servers
+                Err(
+                    error @ (RemotePlottingError::FailedToGetSegmentCommitment { .. }
+                    | RemotePlottingError::SegmentCommitmentNotFound { .. }),
+                ) => {
+                    return Err(error);
+                }
+                Err(error) => {
+                    warn!(
+                        %server,
+                        %sector_index,
+                        %error,
+                        "Failed to plot sector remotely, trying next server"
+                    );
+                    last_error.replace(error);
+                }
+            }
+        }
+
+        Err(last_error.expect("There is always at least one server; qed"))
+    }
+}
+
+async fn send_job(
+    server: SocketAddr,
+    secret: &SharedSecret,
+    job: &PlottingJob,
+    timeouts: &Timeouts,
+    sector_output: &mut [u8],
+    sector_metadata_output: &mut [u8],
+) -> Result<(), RemotePlottingError> {
+    let stream = with_timeout(server, timeouts.connect, TcpStream::connect(server)).await?;
+
+    exchange_job(
+        BufStream::new(stream),
+        server,
+        secret,
+        job,
+        timeouts,
+        sector_output,
+        sector_metadata_output,
+    )
+    .await
+}
+
+/// Run future with timeout, plotting server is considered unresponsive if future doesn't resolve in
+/// time
+async fn with_timeout<F, T, E>(
+    server: SocketAddr,
+    duration: Duration,
+    future: F,
+) -> Result<T, RemotePlottingError>
+where
+    F: Future<Output = Result<T, E>>,
+    RemotePlottingError: From<E>,
+{
+    Ok(timeout(duration, future)
+        .await
+        .map_err(|_elapsed| RemotePlottingError::Timeout { server })??)
+}
+
+/// Fill output buffer from the stream, fails if no data was received within `read_timeout`
+async fn read_exact_with_timeout<S>(
+    stream: &mut S,
+    server: SocketAddr,
+    read_timeout: Duration,
+    output: &mut [u8],
+) -> Result<(), RemotePlottingError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut offset = 0;
+    while offset < output.len() {
+        let read = with_timeout(server, read_timeout, stream.read(&mut output[offset..])).await?;
+        if read == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        offset += read;
+    }
+
+    Ok(())
+}
+
+/// Authenticate, send job over established connection and read response into output buffers
+async fn exchange_job<S>(
+    mut stream: S,
+    server: SocketAddr,
+    secret: &SharedSecret,
+    job: &PlottingJob,
+    timeouts: &Timeouts,
+    sector_output: &mut [u8],
+    sector_metadata_output: &mut [u8],
+) -> Result<(), RemotePlottingError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut challenge = [0; CHALLENGE_LENGTH];
+    with_timeout(server, timeouts.connect, stream.read_exact(&mut challenge)).await?;
+
+    stream.write_all(&secret.authenticate(&challenge)).await?;
+    write_message(&mut stream, &serde_json::to_vec(job)?).await?;
+    stream.flush().await?;
+
+    // Server replies with failure if authentication failed
+    match with_timeout(server, timeouts.plotting, stream.read_u8()).await? {
+        RESPONSE_SUCCESS => {
+            read_exact_with_timeout(&mut stream, server, timeouts.read, sector_output).await?;
+            read_exact_with_timeout(&mut stream, server, timeouts.read, sector_metadata_output)
+                .await?;
+
+            Ok(())
+        }
+        RESPONSE_FAILURE => {
+            let error = with_timeout(server, timeouts.read, read_message(&mut stream)).await?;
+
+            Err(RemotePlottingError::ServerFailed {
+                server,
+                error: String::from_utf8_lossy(&error).into_owned(),
+            })
+        }
+        status => Err(RemotePlottingError::UnexpectedStatus(status)),
+    }
+}
+
+/// Check that sector returned by plotting server is plotted for the job by proving a solution for
+/// random challenge and verifying it the same way node does it, which covers proof of space,
+/// encoding of the record chunk and record commitment against segment commitment from the node.
+///
+/// Only a single record chunk is checked, this protects against faulty servers and servers that
+/// don't plot sectors at all, but not against corruption of individual records.
+async fn verify_sector<NC, PosTable>(
+    public_key: &PublicKey,
+    sector: &[u8],
+    sector_metadata: &SectorMetadata,
+    farmer_protocol_info: &FarmerProtocolInfo,
+    node_client: &NC,
+    kzg: &Kzg,
+    erasure_coding: &ErasureCoding,
+) -> Result<(), RemotePlottingError>
+where
+    NC: NodeClient,
+    PosTable: Table,
+{
+    let sector_index = sector_metadata.sector_index;
+
+    for _ in 0..SECTOR_VERIFICATION_ATTEMPTS {
+        let global_challenge = rand::random::<Blake2b256Hash>();
+
+        // Every audited chunk is a candidate with the widest solution range
+        let Some(solution_candidates) = audit_sector(
+            public_key,
+            sector_index,
+            &global_challenge,
+            SolutionRange::MAX,
+            sector,
+            sector_metadata,
+        ) else {
+            continue;
+        };
+
+        let Some(solution) = solution_candidates
+            .into_iter::<_, PosTable>(public_key, kzg, erasure_coding)?
+            .next()
+        else {
+            continue;
+        };
+        let solution = solution?;
+
+        let segment_index = solution_piece_index(&solution, farmer_protocol_info).segment_index();
+        let segment_commitment = node_client
+            .segment_commitments(vec![segment_index])
+            .await
+            .map_err(|error| RemotePlottingError::FailedToGetSegmentCommitment {
+                segment_index,
+                error: error.to_string(),
+            })?
+            .into_iter()
+            .next()
+            .flatten()
+            .ok_or(RemotePlottingError::SegmentCommitmentNotFound { segment_index })?;
+
+        verify_solution_with_global_challenge::<PosTable, _, _>(
+            &solution,
+            &global_challenge,
+            SolutionRange::MAX,
+            Some(&PieceCheckParams {
+                max_pieces_in_sector: farmer_protocol_info.max_pieces_in_sector,
+                segment_commitment,
+                recent_segments: farmer_protocol_info.recent_segments,
+                recent_history_fraction: farmer_protocol_info.recent_history_fraction,
+            }),
+            kzg,
+        )
+        .map_err(RemotePlottingError::InvalidSolution)?;
+
+        return Ok(());
+    }
+
+    Err(RemotePlottingError::NoSolutionToVerify)
+}
+
+/// Run plotting server that plots sectors on behalf of remote farmers using provided piece getter,
+/// runs until I/O error occurs while accepting connections.
+///
+/// Node client is used to check jobs against current protocol info, only farmers that know
+/// `secret` are served.
+///
+/// `concurrency` limits number of sectors plotted at the same time, which impacts RAM usage.
+pub async fn run_plotting_server<NC, PG, PosTable>(
+    listen_on: SocketAddr,
+    secret: SharedSecret,
+    node_client: NC,
+    piece_getter: PG,
+    kzg: Kzg,
+    erasure_coding: ErasureCoding,
+    piece_memory_cache: PieceMemoryCache,
+    concurrency: NonZeroUsize,
+) -> io::Result<()>
+where
+    NC: NodeClient,
+    PG: PieceGetter + Send + Sync + 'static,
+    PosTable: Table,
+{
+    let listener = TcpListener::bind(listen_on).await?;
+    info!(address = %listen_on, "Plotting server listening");
+
+    let plotting_context = Arc::new(PlottingContext {
+        secret,
+        node_client,
+        piece_getter,
+        kzg,
+        erasure_coding,
+        piece_memory_cache,
+        semaphore: Semaphore::new(concurrency.get()),
+    });
+
+    loop {
+        let (stream, peer_address) = listener.accept().await?;
+        debug!(%peer_address, "Accepted plotting connection");
+
+        tokio::spawn({
+            let plotting_context = Arc::clone(&plotting_context);
+
+            async move {
+                if let Err(error) =
+                    handle_connection::<_, _, _, PosTable>(stream, &plotting_context).await
+                {
+                    warn!(%peer_address, %error, "Plotting connection failed");
+                }
+            }
+        });
+    }
+}
+
+struct PlottingContext<NC, PG> {
+    secret: SharedSecret,
+    node_client: NC,
+    piece_getter: PG,
+    kzg: Kzg,
+    erasure_coding: ErasureCoding,
+    piece_memory_cache: PieceMemoryCache,
+    semaphore: Semaphore,
+}
+
+async fn write_failure<S>(stream: &mut S, error: &str) -> Result<(), RemotePlottingError>
+where
+    S: AsyncWrite + Unpin,
+{
+    stream.write_u8(RESPONSE_FAILURE).await?;
+    write_message(stream, error.as_bytes()).await?;
+    stream.flush().await?;
+
+    Ok(())
+}
+
+/// Authenticate farmer and read job from it
+async fn read_job<S>(
+    stream: &mut S,
+    secret: &SharedSecret,
+) -> Result<PlottingJob, RemotePlottingError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let challenge = rand::random::<[u8; CHALLENGE_LENGTH]>();
+    stream.write_all(&challenge).await?;
+    stream.flush().await?;
+
+    let mut proof = Blake2b256Hash::default();
+    stream.read_exact(&mut proof).await?;
+    // Job is read before connection is closed, such that farmer receives error message instead of
+    // connection reset, its size is limited, so nothing is decoded or allocated beyond that
+    let job = read_message(stream).await?;
+    // Challenge is unique for every connection, so there is nothing to learn from timing of the
+    // comparison below
+    if proof != secret.authenticate(&challenge) {
+        write_failure(stream, "Authentication failed").await?;
+
+        return Err(RemotePlottingError::AuthenticationFailed);
+    }
+
+    Ok(serde_json::from_slice(&job)?)
+}
+
+async fn handle_connection<S, NC, PG, PosTable>(
+    stream: S,
+    plotting_context: &Arc<PlottingContext<NC, PG>>,
+) -> Result<(), RemotePlottingError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    NC: NodeClient,
+    PG: PieceGetter + Send + Sync + 'static,
+    PosTable: Table,
+{
+    let mut stream = BufStream::new(stream);
+
+    // Unauthenticated connections are not allowed to occupy plotting server indefinitely
+    let job = timeout(
+        AUTHENTICATION_TIMEOUT,
+        read_job(&mut stream, &plotting_context.secret),
+    )
+    .await
+    .map_err(|_elapsed| RemotePlottingError::AuthenticationTimeout)??;
+    let sector_index = job.sector_index;
+
+    let validation_result = match plotting_context.node_client.farmer_app_info().await {
+        Ok(farmer_app_info) => {
+            validate_job(&job, &farmer_app_info.protocol_info).map_err(|error| error.to_string())
+        }
+        Err(error) => Err(format!("Failed to retrieve farmer app info: {error}")),
+    };
+    if let Err(error) = validation_result {
+        warn!(%sector_index, %error, "Rejected plotting job");
+
+        return write_failure(&mut stream, &error).await;
+    }
+
+    let result = {
+        let _permit = plotting_context
+            .semaphore
+            .acquire()
+            .await
+            .expect("Semaphore is never closed; qed");
+
+        info!(%sector_index, "Plotting sector");
+
+        // Plotting has blocking code inside, hence dedicated thread
+        tokio::task::spawn_blocking({
+            let plotting_context = Arc::clone(plotting_context);
+            let handle = Handle::current();
+
+            move || handle.block_on(plot_job::<_, _, PosTable>(job, plotting_context.as_ref()))
+        })
+        .await?
+    };
+
+    match result {
+        Ok((sector, sector_metadata)) => {
+            stream.write_u8(RESPONSE_SUCCESS).await?;
+            stream.write_all(&sector).await?;
+            stream.write_all(&sector_metadata).await?;
+            stream.flush().await?;
+
+            info!(%sector_index, "Sector plotted successfully");
+
+            Ok(())
+        }
+        Err(error) => {
+            warn!(%sector_index, %error, "Failed to plot sector");
+
+            write_failure(&mut stream, &error.to_string()).await
+        }
+    }
+}
+
+/// Plot sector described by the job, job must have been validated with [`validate_job()`]
+async fn plot_job<NC, PG, PosTable>(
+    job: PlottingJob,
+    plotting_context: &PlottingContext<NC, PG>,
+) -> Result<(Vec<u8>, Vec<u8>), PlottingError>
+where
+    PG: PieceGetter,
+    PosTable: Table,
+{
+    let PlottingJob {
+        public_key,
+        sector_index,
+        farmer_protocol_info,
+        pieces_in_sector,
+    } = job;
+
+    let mut sector = vec![0; sector_size(pieces_in_sector)];
+    let mut sector_metadata = vec![0; SectorMetadata::encoded_size()];
+
+    plot_sector::<_, PosTable>(
+        &public_key,
+        // Sector offset is only used for logging and is not known to plotting server
+        0,
+        sector_index,
+        &plotting_context.piece_getter,
+        PieceGetterRetryPolicy::Limited(PIECE_GETTER_RETRY_NUMBER.get()),
+        &farmer_protocol_info,
+        &plotting_context.kzg,
+        &plotting_context.erasure_coding,
+        pieces_in_sector,
+        &mut sector,
+        &mut sector_metadata,
+        plotting_context.piece_memory_cache.clone(),
+    )
+    .await?;
+
+    Ok((sector, sector_metadata))
+}
@@ -0,0 +1,448 @@
+use crate::node_client::test_node_client::TestNodeClient;
+use crate::single_disk_plot::remote_plotting::{
+    exchange_job, read_job, read_message, validate_job, verify_sector, write_failure,
+    write_message, InvalidPlottingJob, PlottingJob, RemotePlottingError, Timeouts,
+    MAX_MESSAGE_SIZE, RESPONSE_SUCCESS,
+};
+use crate::utils::shared_secret::SharedSecret;
+use std::net::SocketAddr;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::time::Duration;
+use subspace_archiving::archiver::Archiver;
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::{
+    HistorySize, PublicKey, Record, RecordedHistorySegment, SegmentIndex,
+};
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer_components::piece_caching::PieceMemoryCache;
+use subspace_farmer_components::plotting::{plot_sector, PieceGetterRetryPolicy};
+use subspace_farmer_components::sector::{sector_size, SectorMetadata};
+use subspace_farmer_components::FarmerProtocolInfo;
+use subspace_proof_of_space::chia::ChiaTable;
+use tokio::io::{duplex, AsyncWriteExt};
+
+type PosTable = ChiaTable;
+
+fn farmer_protocol_info() -> FarmerProtocolInfo {
+    FarmerProtocolInfo {
+        history_size: HistorySize::from(NonZeroU64::new(10).unwrap()),
+        max_pieces_in_sector: 4,
+        sector_expiration: SegmentIndex::ONE,
+        recent_segments: HistorySize::from(NonZeroU64::new(5).unwrap()),
+        recent_history_fraction: (
+            HistorySize::from(NonZeroU64::new(1).unwrap()),
+            HistorySize::from(NonZeroU64::new(10).unwrap()),
+        ),
+    }
+}
+
+fn plotting_job() -> PlottingJob {
+    PlottingJob {
+        public_key: PublicKey::from([1; 32]),
+        sector_index: 5,
+        farmer_protocol_info: farmer_protocol_info(),
+        pieces_in_sector: 2,
+    }
+}
+
+fn server_address() -> SocketAddr {
+    "127.0.0.1:9988".parse().unwrap()
+}
+
+fn timeouts() -> Timeouts {
+    Timeouts {
+        connect: Duration::from_secs(1),
+        plotting: Duration::from_millis(200),
+        read: Duration::from_millis(200),
+    }
+}
+
+#[tokio::test]
+async fn message_framing() {
+    let (mut client, mut server) = duplex(1024);
+
+    write_message(&mut client, b"hello").await.unwrap();
+    write_message(&mut client, &[]).await.unwrap();
+    assert_eq!(read_message(&mut server).await.unwrap(), b"hello");
+    assert!(read_message(&mut server).await.unwrap().is_empty());
+
+    // Too large message is neither sent nor accepted
+    assert!(matches!(
+        write_message(&mut client, &vec![0; MAX_MESSAGE_SIZE as usize + 1]).await,
+        Err(RemotePlottingError::MessageTooLarge { .. })
+    ));
+    client.write_u32(MAX_MESSAGE_SIZE + 1).await.unwrap();
+    assert!(matches!(
+        read_message(&mut server).await,
+        Err(RemotePlottingError::MessageTooLarge { size }) if size == MAX_MESSAGE_SIZE + 1
+    ));
+
+    // Truncated message is an error
+    client.write_u32(10).await.unwrap();
+    client.write_all(b"short").await.unwrap();
+    drop(client);
+    assert!(matches!(
+        read_message(&mut server).await,
+        Err(RemotePlottingError::Io(_))
+    ));
+}
+
+#[test]
+fn job_validation() {
+    let farmer_protocol_info = farmer_protocol_info();
+
+    validate_job(&plotting_job(), &farmer_protocol_info).unwrap();
+
+    // Job may have been created when history was shorter
+    let mut job = plotting_job();
+    job.farmer_protocol_info.history_size = HistorySize::from(NonZeroU64::new(1).unwrap());
+    validate_job(&job, &farmer_protocol_info).unwrap();
+
+    for pieces_in_sector in [0, farmer_protocol_info.max_pieces_in_sector + 1, u16::MAX] {
+        let job = PlottingJob {
+            pieces_in_sector,
+            ..plotting_job()
+        };
+        assert!(matches!(
+            validate_job(&job, &farmer_protocol_info),
+            Err(InvalidPlottingJob::InvalidPiecesInSector { .. })
+        ));
+    }
+
+    let mut job = plotting_job();
+    job.farmer_protocol_info.max_pieces_in_sector = u16::MAX;
+    assert!(matches!(
+        validate_job(&job, &farmer_protocol_info),
+        Err(InvalidPlottingJob::ProtocolInfoMismatch { .. })
+    ));
+
+    let mut job = plotting_job();
+    job.farmer_protocol_info.recent_segments = HistorySize::from(NonZeroU64::new(1).unwrap());
+    assert!(matches!(
+        validate_job(&job, &farmer_protocol_info),
+        Err(InvalidPlottingJob::ProtocolInfoMismatch { .. })
+    ));
+
+    let mut job = plotting_job();
+    job.farmer_protocol_info.history_size = HistorySize::from(NonZeroU64::new(11).unwrap());
+    assert!(matches!(
+        validate_job(&job, &farmer_protocol_info),
+        Err(InvalidPlottingJob::HistorySizeAhead { .. })
+    ));
+}
+
+#[tokio::test]
+async fn job_exchange_success() {
+    let (client, mut server) = duplex(1024);
+    let secret = SharedSecret::generate();
+    let job = plotting_job();
+
+    let server = tokio::spawn({
+        let secret = secret.clone();
+
+        async move {
+            let received_job = read_job(&mut server, &secret).await.unwrap();
+
+            server.write_u8(RESPONSE_SUCCESS).await.unwrap();
+            server.write_all(&[1; 8]).await.unwrap();
+            server.write_all(&[2; 4]).await.unwrap();
+            server.flush().await.unwrap();
+
+            received_job
+        }
+    });
+
+    let mut sector_output = [0; 8];
+    let mut sector_metadata_output = [0; 4];
+    exchange_job(
+        client,
+        server_address(),
+        &secret,
+        &job,
+        &timeouts(),
+        &mut sector_output,
+        &mut sector_metadata_output,
+    )
+    .await
+    .unwrap();
+
+    let received_job = server.await.unwrap();
+    assert_eq!(received_job.public_key, job.public_key);
+    assert_eq!(received_job.sector_index, job.sector_index);
+    assert_eq!(received_job.pieces_in_sector, job.pieces_in_sector);
+    assert_eq!(sector_output, [1; 8]);
+    assert_eq!(sector_metadata_output, [2; 4]);
+}
+
+#[tokio::test]
+async fn job_exchange_failure() {
+    let (client, mut server) = duplex(1024);
+    let secret = SharedSecret::generate();
+
+    tokio::spawn({
+        let secret = secret.clone();
+
+        async move {
+            read_job(&mut server, &secret).await.unwrap();
+            write_failure(&mut server, "Not enough pieces")
+                .await
+                .unwrap();
+        }
+    });
+
+    let result = exchange_job(
+        client,
+        server_address(),
+        &secret,
+        &plotting_job(),
+        &timeouts(),
+        &mut [0; 8],
+        &mut [0; 4],
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(RemotePlottingError::ServerFailed { error, .. }) if error == "Not enough pieces"
+    ));
+
+    let (client, mut server) = duplex(1024);
+
+    tokio::spawn({
+        let secret = secret.clone();
+
+        async move {
+            read_job(&mut server, &secret).await.unwrap();
+            server.write_u8(42).await.unwrap();
+        }
+    });
+
+    let result = exchange_job(
+        client,
+        server_address(),
+        &secret,
+        &plotting_job(),
+        &timeouts(),
+        &mut [0; 8],
+        &mut [0; 4],
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(RemotePlottingError::UnexpectedStatus(42))
+    ));
+}
+
+#[tokio::test]
+async fn job_exchange_authentication_failure() {
+    let (client, mut server) = duplex(1024);
+
+    let server = tokio::spawn(async move {
+        let result = read_job(&mut server, &SharedSecret::generate()).await;
+        // Keep connection open until client reads the response
+        (result, server)
+    });
+
+    let result = exchange_job(
+        client,
+        server_address(),
+        &SharedSecret::generate(),
+        &plotting_job(),
+        &timeouts(),
+        &mut [0; 8],
+        &mut [0; 4],
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(RemotePlottingError::ServerFailed { error, .. }) if error == "Authentication failed"
+    ));
+    assert!(matches!(
+        server.await.unwrap().0,
+        Err(RemotePlottingError::AuthenticationFailed)
+    ));
+}
+
+#[tokio::test]
+async fn job_exchange_timeout() {
+    // Server doesn't send challenge
+    let (client, _server) = duplex(1024);
+
+    let result = exchange_job(
+        client,
+        server_address(),
+        &SharedSecret::generate(),
+        &plotting_job(),
+        &Timeouts {
+            connect: Duration::from_millis(200),
+            ..timeouts()
+        },
+        &mut [0; 8],
+        &mut [0; 4],
+    )
+    .await;
+    assert!(matches!(result, Err(RemotePlottingError::Timeout { .. })));
+
+    // Server accepts job, but never finishes plotting
+    let (client, mut server) = duplex(1024);
+    let secret = SharedSecret::generate();
+
+    let server = tokio::spawn({
+        let secret = secret.clone();
+
+        async move {
+            read_job(&mut server, &secret).await.unwrap();
+            // Keep connection open
+            server
+        }
+    });
+
+    let result = exchange_job(
+        client,
+        server_address(),
+        &secret,
+        &plotting_job(),
+        &timeouts(),
+        &mut [0; 8],
+        &mut [0; 4],
+    )
+    .await;
+    assert!(matches!(result, Err(RemotePlottingError::Timeout { .. })));
+    drop(server);
+
+    // Server stalls in the middle of sending sector
+    let (client, mut server) = duplex(1024);
+
+    let server = tokio::spawn({
+        let secret = secret.clone();
+
+        async move {
+            read_job(&mut server, &secret).await.unwrap();
+            server.write_u8(RESPONSE_SUCCESS).await.unwrap();
+            server.write_all(&[1; 4]).await.unwrap();
+            server.flush().await.unwrap();
+            // Keep connection open
+            server
+        }
+    });
+
+    let result = exchange_job(
+        client,
+        server_address(),
+        &secret,
+        &plotting_job(),
+        &timeouts(),
+        &mut [0; 8],
+        &mut [0; 4],
+    )
+    .await;
+    assert!(matches!(result, Err(RemotePlottingError::Timeout { .. })));
+    drop(server);
+}
+
+#[tokio::test]
+async fn remotely_plotted_sector_verification() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let erasure_coding = ErasureCoding::new(
+        NonZeroUsize::new(Record::NUM_S_BUCKETS.next_power_of_two().ilog2() as usize).unwrap(),
+    )
+    .unwrap();
+    let archived_segment = Archiver::new(kzg.clone())
+        .unwrap()
+        .add_block(
+            AsRef::<[u8]>::as_ref(RecordedHistorySegment::new_boxed().as_ref()).to_vec(),
+            Default::default(),
+        )
+        .into_iter()
+        .next()
+        .unwrap();
+
+    let public_key = PublicKey::from([1; 32]);
+    let pieces_in_sector = 2;
+    let farmer_protocol_info = FarmerProtocolInfo {
+        history_size: HistorySize::from(NonZeroU64::new(1).unwrap()),
+        ..farmer_protocol_info()
+    };
+
+    let mut sector = vec![0; sector_size(pieces_in_sector)];
+    let mut sector_metadata = vec![0; SectorMetadata::encoded_size()];
+    let sector_metadata = plot_sector::<_, PosTable>(
+        &public_key,
+        0,
+        5,
+        &archived_segment.pieces,
+        PieceGetterRetryPolicy::Limited(0),
+        &farmer_protocol_info,
+        &kzg,
+        &erasure_coding,
+        pieces_in_sector,
+        &mut sector,
+        &mut sector_metadata,
+        PieceMemoryCache::default(),
+    )
+    .await
+    .unwrap()
+    .sector_metadata;
+
+    let node_client = TestNodeClient::default();
+
+    // Segment commitment from the node is needed for verification
+    assert!(matches!(
+        verify_sector::<_, PosTable>(
+            &public_key,
+            &sector,
+            &sector_metadata,
+            &farmer_protocol_info,
+            &node_client,
+            &kzg,
+            &erasure_coding,
+        )
+        .await,
+        Err(RemotePlottingError::SegmentCommitmentNotFound { .. })
+    ));
+
+    node_client.segment_commitments.lock().insert(
+        archived_segment.segment_header.segment_index(),
+        archived_segment.segment_header.segment_commitment(),
+    );
+
+    verify_sector::<_, PosTable>(
+        &public_key,
+        &sector,
+        &sector_metadata,
+        &farmer_protocol_info,
+        &node_client,
+        &kzg,
+        &erasure_coding,
+    )
+    .await
+    .unwrap();
+
+    // Sector plotted for another farmer
+    assert!(verify_sector::<_, PosTable>(
+        &PublicKey::from([2; 32]),
+        &sector,
+        &sector_metadata,
+        &farmer_protocol_info,
+        &node_client,
+        &kzg,
+        &erasure_coding,
+    )
+    .await
+    .is_err());
+
+    // Sector that wasn't plotted at all
+    let garbage_sector = (0..sector.len())
+        .map(|_| rand::random::<u8>())
+        .collect::<Vec<_>>();
+    assert!(verify_sector::<_, PosTable>(
+        &public_key,
+        &garbage_sector,
+        &sector_metadata,
+        &farmer_protocol_info,
+        &node_client,
+        &kzg,
+        &erasure_coding,
+    )
+    .await
+    .is_err());
+}
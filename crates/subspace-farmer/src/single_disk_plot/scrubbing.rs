@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use subspace_archiving::archiver;
+use subspace_core_primitives::crypto::kzg::{Kzg, Witness};
+use subspace_core_primitives::crypto::{blake2b_256_254_hash_to_scalar, Scalar};
+use subspace_core_primitives::{
+    PieceOffset, PublicKey, SectorId, SectorIndex, SegmentCommitment, SegmentIndex,
+};
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer_components::reading::ReadingError;
+use subspace_farmer_components::sector::{
+    sector_size, SectorContentsMap, SectorContentsMapFromBytesError, SectorMetadata,
+};
+use subspace_farmer_components::{reading, FarmerProtocolInfo};
+use subspace_proof_of_space::Table;
+use thiserror::Error;
+use tracing::trace;
+
+/// Corruption found in a sector during scrubbing
+#[derive(Debug, Error)]
+pub enum SectorCorruption {
+    /// Failed to decode sector metadata
+    #[error("Failed to decode sector metadata: {0}")]
+    FailedToDecodeSectorMetadata(parity_scale_codec::Error),
+    /// Sector index in metadata doesn't match sector position in the plot
+    #[error("Sector index in metadata {actual} doesn't match expected sector index {expected}")]
+    WrongSectorIndex {
+        /// Expected sector index
+        expected: SectorIndex,
+        /// Sector index found in metadata
+        actual: SectorIndex,
+    },
+    /// Number of pieces in sector metadata doesn't match plot
+    #[error("Number of pieces in sector metadata {actual} doesn't match plot {expected}")]
+    WrongPiecesInSector {
+        /// Number of pieces in sector plot was created with
+        expected: u16,
+        /// Number of pieces in sector found in metadata
+        actual: u16,
+    },
+    /// Sector is missing from the plot file, likely because it was truncated
+    #[error("Sector is missing from the plot file")]
+    SectorMissing,
+    /// Failed to decode sector contents map
+    #[error("Failed to decode sector contents map: {0}")]
+    FailedToDecodeSectorContentsMap(#[from] SectorContentsMapFromBytesError),
+    /// Failed to read piece
+    #[error("Failed to read piece at offset {piece_offset}: {error}")]
+    FailedToReadPiece {
+        /// Piece offset
+        piece_offset: PieceOffset,
+        /// Lower-level error
+        error: ReadingError,
+    },
+    /// Record commitment doesn't match record contents
+    #[error("Record commitment doesn't match record contents at offset {piece_offset}")]
+    InvalidRecordCommitment {
+        /// Piece offset
+        piece_offset: PieceOffset,
+    },
+    /// Record witness doesn't prove that record commitment is a part of the segment commitment
+    #[error("Record witness is invalid at offset {piece_offset}")]
+    InvalidRecordWitness {
+        /// Piece offset
+        piece_offset: PieceOffset,
+    },
+}
+
+/// Sector that failed integrity check during scrubbing
+#[derive(Debug)]
+pub struct CorruptedSector {
+    /// Offset of the sector in the plot
+    pub sector_offset: usize,
+    /// Sector index
+    pub sector_index: SectorIndex,
+    /// What is wrong with the sector
+    pub corruption: SectorCorruption,
+    /// Whether sector was marked for replotting
+    pub marked_for_replotting: bool,
+}
+
+/// Check that every record in the sector can be read back, that its commitment is consistent with
+/// record contents and that its witness proves inclusion of the commitment in the segment
+/// commitment.
+///
+/// `segment_commitments` must contain commitments of all segments up to history size of the sector.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn scrub_sector<PosTable>(
+    public_key: &PublicKey,
+    sector_index: SectorIndex,
+    pieces_in_sector: u16,
+    sector_metadata: &SectorMetadata,
+    sector: Option<&[u8]>,
+    farmer_protocol_info: &FarmerProtocolInfo,
+    segment_commitments: &HashMap<SegmentIndex, SegmentCommitment>,
+    kzg: &Kzg,
+    erasure_coding: &ErasureCoding,
+) -> Result<(), SectorCorruption>
+where
+    PosTable: Table,
+{
+    if sector_metadata.sector_index != sector_index {
+        return Err(SectorCorruption::WrongSectorIndex {
+            expected: sector_index,
+            actual: sector_metadata.sector_index,
+        });
+    }
+
+    if sector_metadata.pieces_in_sector != pieces_in_sector {
+        return Err(SectorCorruption::WrongPiecesInSector {
+            expected: pieces_in_sector,
+            actual: sector_metadata.pieces_in_sector,
+        });
+    }
+
+    let sector = match sector {
+        Some(sector) if sector.len() == sector_size(pieces_in_sector) => sector,
+        _ => {
+            return Err(SectorCorruption::SectorMissing);
+        }
+    };
+
+    SectorContentsMap::from_bytes(
+        &sector[..SectorContentsMap::encoded_size(pieces_in_sector)],
+        pieces_in_sector,
+    )?;
+
+    let sector_id = SectorId::new(public_key.hash(), sector_index);
+
+    for piece_offset in (PieceOffset::ZERO..).take(pieces_in_sector.into()) {
+        trace!(%sector_index, %piece_offset, "Scrubbing record");
+
+        let piece = reading::read_piece::<PosTable>(
+            piece_offset,
+            &sector_id,
+            sector_metadata,
+            sector,
+            erasure_coding,
+        )
+        .map_err(|error| SectorCorruption::FailedToReadPiece {
+            piece_offset,
+            error,
+        })?;
+
+        let (record, commitment, witness) = piece.split();
+
+        let piece_index = sector_id.derive_piece_index(
+            piece_offset,
+            sector_metadata.history_size,
+            farmer_protocol_info.max_pieces_in_sector,
+            farmer_protocol_info.recent_segments,
+            farmer_protocol_info.recent_history_fraction,
+        );
+
+        // Segment commitment is missing if sector claims history size larger than node has
+        let record_witness_valid = segment_commitments
+            .get(&piece_index.segment_index())
+            .zip(Witness::try_from_bytes(witness).ok())
+            .map(|(segment_commitment, witness)| {
+                archiver::is_record_commitment_hash_valid(
+                    kzg,
+                    &blake2b_256_254_hash_to_scalar(commitment.as_ref()),
+                    segment_commitment,
+                    &witness,
+                    piece_index.position(),
+                )
+            })
+            .unwrap_or_default();
+
+        if !record_witness_valid {
+            return Err(SectorCorruption::InvalidRecordWitness { piece_offset });
+        }
+
+        let mut scalars = record
+            .iter()
+            .map(Scalar::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_error| SectorCorruption::InvalidRecordCommitment { piece_offset })?;
+
+        // Number of scalars for KZG must be a power of two elements
+        scalars.resize(scalars.len().next_power_of_two(), Scalar::default());
+
+        let record_commitment_matches = kzg
+            .poly(&scalars)
+            .and_then(|polynomial| kzg.commit(&polynomial))
+            .map(|record_commitment| record_commitment.to_bytes() == **commitment)
+            .unwrap_or_default();
+
+        if !record_commitment_matches {
+            return Err(SectorCorruption::InvalidRecordCommitment { piece_offset });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Verification of solutions produced by the farmer before they are submitted to the node.
+//!
+//! Node rejects invalid solutions anyway, but then there is no indication of what went wrong on the
+//! farmer side. Checking solutions locally with the same rules allows to catch bugs in proof of
+//! space table generation and corrupted sectors early and to identify affected sectors.
+
+#[cfg(test)]
+mod tests;
+
+use crate::{node_client, NodeClient};
+use std::collections::HashMap;
+use subspace_core_primitives::crypto::kzg::Kzg;
+use subspace_core_primitives::{
+    PieceIndex, PublicKey, SectorId, SectorIndex, SegmentCommitment, SegmentIndex, Solution,
+};
+use subspace_farmer_components::FarmerProtocolInfo;
+use subspace_proof_of_space::Table;
+use subspace_rpc_primitives::SlotInfo;
+use subspace_verification::{verify_solution_with_global_challenge, Error, PieceCheckParams};
+use tracing::{error, warn};
+
+/// Index of the piece solution was created from, its segment commitment is needed for
+/// verification.
+pub fn solution_piece_index<RewardAddress>(
+    solution: &Solution<PublicKey, RewardAddress>,
+    farmer_protocol_info: &FarmerProtocolInfo,
+) -> PieceIndex {
+    SectorId::new(solution.public_key.hash(), solution.sector_index).derive_piece_index(
+        solution.piece_offset,
+        solution.history_size,
+        farmer_protocol_info.max_pieces_in_sector,
+        farmer_protocol_info.recent_segments,
+        farmer_protocol_info.recent_history_fraction,
+    )
+}
+
+/// Verify solution created for provided slot the same way node does it.
+///
+/// `segment_commitment` must correspond to the segment of the piece solution was created from (see
+/// [`solution_piece_index()`]).
+pub fn verify_solution<PosTable, RewardAddress>(
+    solution: &Solution<PublicKey, RewardAddress>,
+    slot_info: &SlotInfo,
+    farmer_protocol_info: &FarmerProtocolInfo,
+    segment_commitment: &SegmentCommitment,
+    kzg: &Kzg,
+) -> Result<(), Error>
+where
+    PosTable: Table,
+{
+    // Farmer audits sectors with voting solution range, which is the wider one
+    verify_solution_with_global_challenge::<PosTable, _, _>(
+        solution,
+        &slot_info.global_challenge,
+        slot_info.voting_solution_range,
+        Some(&PieceCheckParams {
+            max_pieces_in_sector: farmer_protocol_info.max_pieces_in_sector,
+            segment_commitment: *segment_commitment,
+            recent_segments: farmer_protocol_info.recent_segments,
+            recent_history_fraction: farmer_protocol_info.recent_history_fraction,
+        }),
+        kzg,
+    )
+    .map(|_solution_distance| ())
+}
+
+/// Segment commitments used for verification of solutions.
+///
+/// Segment commitment never changes once segment is archived, so commitments are requested from the
+/// node once and cached for the lifetime of the plot. Commitment is small, so even commitments of
+/// all segments of the history take little memory.
+#[derive(Debug, Default)]
+pub(super) struct SegmentCommitments {
+    cache: HashMap<SegmentIndex, SegmentCommitment>,
+}
+
+impl SegmentCommitments {
+    /// Segment commitments for provided segment indexes in the same order, only commitments that
+    /// are not cached yet are requested from the node, `None` is returned for segments node doesn't
+    /// know about.
+    pub(super) async fn get<NC>(
+        &mut self,
+        node_client: &NC,
+        segment_indexes: &[SegmentIndex],
+    ) -> Result<Vec<Option<SegmentCommitment>>, node_client::Error>
+    where
+        NC: NodeClient,
+    {
+        let mut missing_segment_indexes = segment_indexes
+            .iter()
+            .filter(|segment_index| !self.cache.contains_key(segment_index))
+            .copied()
+            .collect::<Vec<_>>();
+        missing_segment_indexes.sort_unstable();
+        missing_segment_indexes.dedup();
+
+        if !missing_segment_indexes.is_empty() {
+            let segment_commitments = node_client
+                .segment_commitments(missing_segment_indexes.clone())
+                .await?;
+
+            self.cache.extend(
+                missing_segment_indexes
+                    .into_iter()
+                    .zip(segment_commitments)
+                    .filter_map(|(segment_index, maybe_segment_commitment)| {
+                        Some((segment_index, maybe_segment_commitment?))
+                    }),
+            );
+        }
+
+        Ok(segment_indexes
+            .iter()
+            .map(|segment_index| self.cache.get(segment_index).copied())
+            .collect())
+    }
+}
+
+/// Verify solutions and remove invalid ones, returns indexes of sectors that produced invalid
+/// solutions.
+///
+/// Solutions that can't be verified because segment commitments are not available are kept, node
+/// will verify them anyway.
+pub(super) async fn retain_valid_solutions<PosTable, NC>(
+    solutions: &mut Vec<Solution<PublicKey, PublicKey>>,
+    slot_info: &SlotInfo,
+    farmer_protocol_info: &FarmerProtocolInfo,
+    node_client: &NC,
+    segment_commitments: &mut SegmentCommitments,
+    kzg: &Kzg,
+) -> Vec<SectorIndex>
+where
+    PosTable: Table,
+    NC: NodeClient,
+{
+    let slot = slot_info.slot_number;
+
+    if solutions.is_empty() {
+        return Vec::new();
+    }
+
+    let segment_indexes = solutions
+        .iter()
+        .map(|solution| solution_piece_index(solution, farmer_protocol_info).segment_index())
+        .collect::<Vec<_>>();
+    let mut segment_commitments = match segment_commitments.get(node_client, &segment_indexes).await
+    {
+        Ok(segment_commitments) => segment_commitments.into_iter(),
+        Err(error) => {
+            warn!(
+                %slot,
+                %error,
+                "Failed to retrieve segment commitments, submitting solutions without verification"
+            );
+            return Vec::new();
+        }
+    };
+
+    let mut invalid_sector_indexes = Vec::new();
+
+    solutions.retain(|solution| {
+        let sector_index = solution.sector_index;
+
+        let Some(segment_commitment) = segment_commitments.next().flatten() else {
+            warn!(
+                %slot,
+                %sector_index,
+                "Segment commitment not found, submitting solution without verification"
+            );
+            return true;
+        };
+
+        match verify_solution::<PosTable, _>(
+            solution,
+            slot_info,
+            farmer_protocol_info,
+            &segment_commitment,
+            kzg,
+        ) {
+            Ok(()) => true,
+            Err(error) => {
+                error!(
+                    %slot,
+                    %sector_index,
+                    piece_offset = %solution.piece_offset,
+                    history_size = %solution.history_size,
+                    %error,
+                    "Farmer produced invalid solution, sector is likely corrupted"
+                );
+                invalid_sector_indexes.push(sector_index);
+                false
+            }
+        }
+    });
+
+    invalid_sector_indexes
+}
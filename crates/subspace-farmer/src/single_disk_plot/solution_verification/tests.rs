@@ -0,0 +1,312 @@
+use crate::node_client::test_node_client::TestNodeClient;
+use crate::single_disk_plot::solution_verification::{
+    retain_valid_solutions, solution_piece_index, verify_solution, SegmentCommitments,
+};
+use std::num::{NonZeroU64, NonZeroUsize};
+use subspace_archiving::archiver::{Archiver, NewArchivedSegment};
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::{
+    HistorySize, PublicKey, Record, RecordedHistorySegment, SegmentIndex, Solution, SolutionRange,
+};
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer_components::auditing::audit_sector;
+use subspace_farmer_components::piece_caching::PieceMemoryCache;
+use subspace_farmer_components::plotting::{plot_sector, PieceGetterRetryPolicy};
+use subspace_farmer_components::sector::{sector_size, SectorMetadata};
+use subspace_farmer_components::FarmerProtocolInfo;
+use subspace_proof_of_space::chia::ChiaTable;
+use subspace_rpc_primitives::SlotInfo;
+
+type PosTable = ChiaTable;
+
+const PIECES_IN_SECTOR: u16 = 2;
+
+fn farmer_protocol_info() -> FarmerProtocolInfo {
+    FarmerProtocolInfo {
+        history_size: HistorySize::from(NonZeroU64::new(1).unwrap()),
+        max_pieces_in_sector: PIECES_IN_SECTOR,
+        sector_expiration: SegmentIndex::ONE,
+        recent_segments: HistorySize::from(NonZeroU64::new(5).unwrap()),
+        recent_history_fraction: (
+            HistorySize::from(NonZeroU64::new(1).unwrap()),
+            HistorySize::from(NonZeroU64::new(10).unwrap()),
+        ),
+    }
+}
+
+/// Solution produced by plotted sector along with slot it was produced for and the segment sector
+/// was plotted from
+struct PlottedSolution {
+    solution: Solution<PublicKey, PublicKey>,
+    slot_info: SlotInfo,
+    archived_segment: NewArchivedSegment,
+    kzg: Kzg,
+}
+
+async fn plotted_solution() -> PlottedSolution {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let erasure_coding = ErasureCoding::new(
+        NonZeroUsize::new(Record::NUM_S_BUCKETS.next_power_of_two().ilog2() as usize).unwrap(),
+    )
+    .unwrap();
+    let archived_segment = Archiver::new(kzg.clone())
+        .unwrap()
+        .add_block(
+            AsRef::<[u8]>::as_ref(RecordedHistorySegment::new_boxed().as_ref()).to_vec(),
+            Default::default(),
+        )
+        .into_iter()
+        .next()
+        .unwrap();
+
+    let public_key = PublicKey::from([1; 32]);
+    let sector_index = 5;
+
+    let mut sector = vec![0; sector_size(PIECES_IN_SECTOR)];
+    let mut sector_metadata = vec![0; SectorMetadata::encoded_size()];
+    let sector_metadata = plot_sector::<_, PosTable>(
+        &public_key,
+        0,
+        sector_index,
+        &archived_segment.pieces,
+        PieceGetterRetryPolicy::Limited(0),
+        &farmer_protocol_info(),
+        &kzg,
+        &erasure_coding,
+        PIECES_IN_SECTOR,
+        &mut sector,
+        &mut sector_metadata,
+        PieceMemoryCache::default(),
+    )
+    .await
+    .unwrap()
+    .sector_metadata;
+
+    // Not every challenge results in a solution, try until one does
+    for global_challenge in (0..=u8::MAX).map(|byte| [byte; 32]) {
+        let Some(solution_candidates) = audit_sector(
+            &public_key,
+            sector_index,
+            &global_challenge,
+            SolutionRange::MAX,
+            &sector,
+            &sector_metadata,
+        ) else {
+            continue;
+        };
+
+        let Some(solution) = solution_candidates
+            .into_iter::<_, PosTable>(&public_key, &kzg, &erasure_coding)
+            .unwrap()
+            .next()
+        else {
+            continue;
+        };
+
+        return PlottedSolution {
+            solution: solution.unwrap(),
+            slot_info: SlotInfo {
+                slot_number: 1,
+                global_challenge,
+                solution_range: SolutionRange::MAX,
+                voting_solution_range: SolutionRange::MAX,
+            },
+            archived_segment,
+            kzg,
+        };
+    }
+
+    panic!("Sector didn't produce any solutions");
+}
+
+#[tokio::test]
+async fn solution_verification() {
+    let PlottedSolution {
+        solution,
+        slot_info,
+        archived_segment,
+        kzg,
+    } = plotted_solution().await;
+    let farmer_protocol_info = farmer_protocol_info();
+    let segment_commitment = archived_segment.segment_header.segment_commitment();
+
+    assert_eq!(
+        solution_piece_index(&solution, &farmer_protocol_info).segment_index(),
+        archived_segment.segment_header.segment_index()
+    );
+
+    verify_solution::<PosTable, _>(
+        &solution,
+        &slot_info,
+        &farmer_protocol_info,
+        &segment_commitment,
+        &kzg,
+    )
+    .unwrap();
+
+    // Commitment of another segment
+    assert!(verify_solution::<PosTable, _>(
+        &solution,
+        &slot_info,
+        &farmer_protocol_info,
+        &solution.record_commitment,
+        &kzg,
+    )
+    .is_err());
+
+    // Solution for another slot
+    assert!(verify_solution::<PosTable, _>(
+        &solution,
+        &SlotInfo {
+            global_challenge: [0xff; 32],
+            ..slot_info.clone()
+        },
+        &farmer_protocol_info,
+        &segment_commitment,
+        &kzg,
+    )
+    .is_err());
+
+    // Solution from another sector
+    assert!(verify_solution::<PosTable, _>(
+        &Solution {
+            sector_index: solution.sector_index + 1,
+            ..solution.clone()
+        },
+        &slot_info,
+        &farmer_protocol_info,
+        &segment_commitment,
+        &kzg,
+    )
+    .is_err());
+}
+
+#[tokio::test]
+async fn invalid_solutions_are_removed() {
+    let PlottedSolution {
+        solution,
+        slot_info,
+        archived_segment,
+        kzg,
+    } = plotted_solution().await;
+    let farmer_protocol_info = farmer_protocol_info();
+    let invalid_solution = Solution {
+        sector_index: solution.sector_index + 1,
+        ..solution.clone()
+    };
+    let node_client = TestNodeClient::default();
+
+    // Solutions are kept if they can't be verified
+    {
+        let mut solutions = vec![solution.clone(), invalid_solution.clone()];
+        let invalid_sector_indexes = retain_valid_solutions::<PosTable, _>(
+            &mut solutions,
+            &slot_info,
+            &farmer_protocol_info,
+            &node_client,
+            &mut SegmentCommitments::default(),
+            &kzg,
+        )
+        .await;
+
+        assert!(invalid_sector_indexes.is_empty());
+        assert_eq!(solutions, vec![solution.clone(), invalid_solution.clone()]);
+    }
+
+    node_client.segment_commitments.lock().insert(
+        archived_segment.segment_header.segment_index(),
+        archived_segment.segment_header.segment_commitment(),
+    );
+    let mut segment_commitments = SegmentCommitments::default();
+
+    let mut solutions = vec![invalid_solution.clone(), solution.clone()];
+    let invalid_sector_indexes = retain_valid_solutions::<PosTable, _>(
+        &mut solutions,
+        &slot_info,
+        &farmer_protocol_info,
+        &node_client,
+        &mut segment_commitments,
+        &kzg,
+    )
+    .await;
+
+    assert_eq!(invalid_sector_indexes, vec![invalid_solution.sector_index]);
+    assert_eq!(solutions, vec![solution.clone()]);
+
+    // Segment commitment is cached and not requested from the node again
+    node_client.segment_commitments.lock().clear();
+
+    let mut solutions = vec![invalid_solution.clone(), solution.clone()];
+    let invalid_sector_indexes = retain_valid_solutions::<PosTable, _>(
+        &mut solutions,
+        &slot_info,
+        &farmer_protocol_info,
+        &node_client,
+        &mut segment_commitments,
+        &kzg,
+    )
+    .await;
+
+    assert_eq!(invalid_sector_indexes, vec![invalid_solution.sector_index]);
+    assert_eq!(solutions, vec![solution]);
+}
+
+#[tokio::test]
+async fn segment_commitments_are_cached() {
+    let kzg = Kzg::new(embedded_kzg_settings());
+    let mut archiver = Archiver::new(kzg).unwrap();
+    let archived_segments = (0..2)
+        .flat_map(|_| {
+            archiver.add_block(
+                AsRef::<[u8]>::as_ref(RecordedHistorySegment::new_boxed().as_ref()).to_vec(),
+                Default::default(),
+            )
+        })
+        .take(2)
+        .collect::<Vec<_>>();
+    let [first_segment_header, second_segment_header] =
+        [0, 1].map(|index| archived_segments[index].segment_header);
+
+    let node_client = TestNodeClient::default();
+    node_client.segment_commitments.lock().insert(
+        first_segment_header.segment_index(),
+        first_segment_header.segment_commitment(),
+    );
+    let mut segment_commitments = SegmentCommitments::default();
+
+    // Commitments are returned in requested order, including duplicates
+    let segment_indexes = [
+        first_segment_header.segment_index(),
+        second_segment_header.segment_index(),
+        first_segment_header.segment_index(),
+    ];
+    assert_eq!(
+        segment_commitments
+            .get(&node_client, &segment_indexes)
+            .await
+            .unwrap(),
+        vec![
+            Some(first_segment_header.segment_commitment()),
+            None,
+            Some(first_segment_header.segment_commitment()),
+        ]
+    );
+
+    // Cached commitments are not requested again, while commitments that were not found are
+    node_client.segment_commitments.lock().clear();
+    node_client.segment_commitments.lock().insert(
+        second_segment_header.segment_index(),
+        second_segment_header.segment_commitment(),
+    );
+    assert_eq!(
+        segment_commitments
+            .get(&node_client, &segment_indexes)
+            .await
+            .unwrap(),
+        vec![
+            Some(first_segment_header.segment_commitment()),
+            Some(second_segment_header.segment_commitment()),
+            Some(first_segment_header.segment_commitment()),
+        ]
+    );
+}
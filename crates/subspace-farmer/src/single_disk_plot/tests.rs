@@ -0,0 +1,846 @@
+use crate::identity::Identity;
+use crate::node_client::test_node_client::{TestNode, TestNodeClient};
+use crate::reward_signer::RewardSigner;
+use crate::single_disk_plot::farming::{audit_and_prove, farmed_sectors, SlotSolutions};
+use crate::single_disk_plot::migration::{MigratedSectors, MigrationFiles, PlotMigration};
+use crate::single_disk_plot::{
+    mark_sectors_for_replotting, SectorPlottingDetails, SingleDiskPlot, SingleDiskPlotInfo,
+    SingleDiskPlotLock, SingleDiskPlotOptions, SingleDiskPlotSummary, SolutionSelectionPolicy,
+};
+use crate::utils::sector_index_allocator::SectorIndexAllocator;
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use futures::future::{pending, select, Either};
+use futures::StreamExt;
+use parity_scale_codec::Encode;
+use rayon::ThreadPoolBuilder;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use subspace_archiving::archiver::Archiver;
+use subspace_core_primitives::crypto::kzg::{embedded_kzg_settings, Kzg};
+use subspace_core_primitives::{
+    ArchivedHistorySegment, Blake2b256Hash, HistorySize, Piece, PieceIndex, PublicKey, Record,
+    RecordedHistorySegment, SectorIndex, SegmentIndex, SolutionRange,
+};
+use subspace_erasure_coding::ErasureCoding;
+use subspace_farmer_components::plotting::{PieceGetter, PieceGetterRetryPolicy};
+use subspace_farmer_components::sector::{sector_size, SectorMetadata};
+use subspace_farmer_components::FarmerProtocolInfo;
+use subspace_proof_of_space::chia::ChiaTable;
+use subspace_rpc_primitives::{FarmerAppInfo, SlotInfo};
+use subspace_verification::verify_solution_with_global_challenge;
+use tempfile::TempDir;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, timeout};
+
+type PosTable = ChiaTable;
+
+const PIECES_IN_SECTOR: u16 = 2;
+
+/// Piece getter that reports requested pieces, but never returns them
+struct PendingPieceGetter {
+    requested_pieces: mpsc::UnboundedSender<PieceIndex>,
+}
+
+#[async_trait]
+impl PieceGetter for PendingPieceGetter {
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+        _retry_policy: PieceGetterRetryPolicy,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
+        let _ = self.requested_pieces.unbounded_send(piece_index);
+        pending().await
+    }
+}
+
+/// Piece getter that holds requests until more of them are in flight than a single sector needs
+/// (or timeout is reached) and records maximum number of concurrent requests
+struct ConcurrencyTrackingPieceGetter {
+    archived_history_segment: ArchivedHistorySegment,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+#[async_trait]
+impl PieceGetter for ConcurrencyTrackingPieceGetter {
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+        retry_policy: PieceGetterRetryPolicy,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+
+        let _ = timeout(Duration::from_secs(1), async {
+            while self.max_in_flight.load(Ordering::SeqCst) <= usize::from(PIECES_IN_SECTOR) {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        self.archived_history_segment
+            .get_piece(piece_index, retry_policy)
+            .await
+    }
+}
+
+fn erasure_coding() -> ErasureCoding {
+    ErasureCoding::new(
+        NonZeroUsize::new(Record::NUM_S_BUCKETS.next_power_of_two().ilog2() as usize).unwrap(),
+    )
+    .unwrap()
+}
+
+fn archived_history_segment(kzg: &Kzg) -> ArchivedHistorySegment {
+    let input = RecordedHistorySegment::new_boxed();
+    let mut archiver = Archiver::new(kzg.clone()).unwrap();
+
+    archiver
+        .add_block(
+            AsRef::<[u8]>::as_ref(input.as_ref()).to_vec(),
+            Default::default(),
+        )
+        .into_iter()
+        .next()
+        .unwrap()
+        .pieces
+}
+
+fn farmer_app_info() -> FarmerAppInfo {
+    FarmerAppInfo {
+        genesis_hash: [1; 32],
+        dsn_bootstrap_nodes: Vec::new(),
+        protocol_info: FarmerProtocolInfo {
+            history_size: HistorySize::from(NonZeroU64::new(1).unwrap()),
+            max_pieces_in_sector: PIECES_IN_SECTOR,
+            sector_expiration: SegmentIndex::ONE,
+            recent_segments: HistorySize::from(NonZeroU64::new(5).unwrap()),
+            recent_history_fraction: (
+                HistorySize::from(NonZeroU64::new(1).unwrap()),
+                HistorySize::from(NonZeroU64::new(10).unwrap()),
+            ),
+        },
+    }
+}
+
+/// Options of single disk plot in provided directory with space for `sector_count` sectors
+fn single_disk_plot_options<PG>(
+    directory: &Path,
+    node_client: TestNodeClient,
+    piece_getter: PG,
+    sector_count: usize,
+    sector_plotting_concurrency: NonZeroUsize,
+) -> SingleDiskPlotOptions<TestNodeClient, PG> {
+    let identity = Identity::open_or_create(directory, None).unwrap();
+    let public_key = RewardSigner::public_key(&identity);
+
+    SingleDiskPlotOptions {
+        directory: directory.to_path_buf(),
+        farmer_app_info: farmer_app_info(),
+        allocated_space: (sector_size(PIECES_IN_SECTOR) * sector_count) as u64,
+        max_pieces_in_sector: PIECES_IN_SECTOR,
+        node_client,
+        reward_address: public_key,
+        public_key,
+        piece_getter,
+        kzg: Kzg::new(embedded_kzg_settings()),
+        erasure_coding: erasure_coding(),
+        concurrent_plotting_semaphore: Arc::new(tokio::sync::Semaphore::new(
+            sector_plotting_concurrency.get(),
+        )),
+        sector_plotting_concurrency,
+        farming_thread_pool_size: Some(NonZeroUsize::new(1).unwrap()),
+        solutions_limit: NonZeroUsize::new(1).unwrap(),
+        solution_selection_policy: SolutionSelectionPolicy::First,
+        sector_index_allocator: SectorIndexAllocator::new(directory, [directory], false).unwrap(),
+        piece_memory_cache: Default::default(),
+        remote_plotter: None,
+        replot_invalid_sectors: false,
+        archived_segments: broadcast::channel(1).1,
+    }
+}
+
+/// Create single disk plot in provided directory and wait for initial plotting to finish,
+/// returns details of plotted sectors in the order they were reported.
+async fn plot_farm<PG>(
+    directory: &Path,
+    piece_getter: PG,
+    sector_count: usize,
+    sector_plotting_concurrency: NonZeroUsize,
+) -> Vec<SectorPlottingDetails>
+where
+    PG: PieceGetter + Send + 'static,
+{
+    let single_disk_plot = SingleDiskPlot::new::<_, _, PosTable>(
+        single_disk_plot_options(
+            directory,
+            TestNodeClient::new(Some(farmer_app_info())),
+            piece_getter,
+            sector_count,
+            sector_plotting_concurrency,
+        ),
+        0,
+    )
+    .await
+    .unwrap();
+
+    let (plotting_finished_sender, plotting_finished_receiver) = mpsc::unbounded();
+    single_disk_plot
+        .on_sector_plotting_finished(Arc::new(move |sector_plotting_details| {
+            let _ = plotting_finished_sender.unbounded_send(*sector_plotting_details);
+        }))
+        .detach();
+
+    let plotted_sectors = plotting_finished_receiver
+        .take(sector_count)
+        .collect::<Vec<_>>();
+
+    match select(Box::pin(single_disk_plot.run()), Box::pin(plotted_sectors)).await {
+        Either::Left((result, _)) => {
+            panic!("Single disk plot exited before plotting all sectors: {result:?}");
+        }
+        Either::Right((plotted_sectors, _)) => plotted_sectors,
+    }
+}
+
+/// Contents of plotted farm needed for auditing and proving
+struct PlottedFarm {
+    public_key: PublicKey,
+    first_sector_index: SectorIndex,
+    sectors_metadata: Vec<SectorMetadata>,
+    plot: Vec<u8>,
+    kzg: Kzg,
+    erasure_coding: ErasureCoding,
+}
+
+impl PlottedFarm {
+    async fn new(directory: &Path, sector_count: usize) -> Self {
+        let kzg = Kzg::new(embedded_kzg_settings());
+        plot_farm(
+            directory,
+            archived_history_segment(&kzg),
+            sector_count,
+            NonZeroUsize::new(1).unwrap(),
+        )
+        .await;
+
+        let single_disk_plot_info = SingleDiskPlotInfo::load_from(directory).unwrap().unwrap();
+
+        Self {
+            public_key: *single_disk_plot_info.public_key(),
+            first_sector_index: single_disk_plot_info.first_sector_index(),
+            sectors_metadata: SingleDiskPlot::read_sectors_metadata(directory)
+                .unwrap()
+                .into_iter()
+                .map(Result::unwrap)
+                .collect(),
+            plot: fs::read(directory.join(SingleDiskPlot::PLOT_FILE)).unwrap(),
+            kzg,
+            erasure_coding: erasure_coding(),
+        }
+    }
+
+    /// Audit and prove sectors for a slot where all sectors are eligible for solutions
+    fn farm(
+        &self,
+        global_challenge: Blake2b256Hash,
+        farming_thread_pool_size: usize,
+        sectors_being_replaced: &HashSet<usize>,
+        solutions_limit: usize,
+        solution_selection_policy: SolutionSelectionPolicy,
+    ) -> SlotSolutions {
+        self.farm_with_migrated_sectors(
+            global_challenge,
+            farming_thread_pool_size,
+            sectors_being_replaced,
+            None,
+            solutions_limit,
+            solution_selection_policy,
+        )
+    }
+
+    /// Same as [`Self::farm()`], but migrated sectors are farmed instead of sectors with the same
+    /// offsets
+    fn farm_with_migrated_sectors(
+        &self,
+        global_challenge: Blake2b256Hash,
+        farming_thread_pool_size: usize,
+        sectors_being_replaced: &HashSet<usize>,
+        migrated_sectors: Option<&MigratedSectors>,
+        solutions_limit: usize,
+        solution_selection_policy: SolutionSelectionPolicy,
+    ) -> SlotSolutions {
+        let farming_thread_pool = ThreadPoolBuilder::new()
+            .num_threads(farming_thread_pool_size)
+            .build()
+            .unwrap();
+
+        audit_and_prove::<PosTable>(
+            &farming_thread_pool,
+            &self.public_key,
+            &self.public_key,
+            &farmed_sectors(
+                self.first_sector_index,
+                &self.sectors_metadata,
+                &self.plot,
+                sector_size(PIECES_IN_SECTOR),
+                sectors_being_replaced,
+                migrated_sectors,
+            ),
+            &SlotInfo {
+                slot_number: 1,
+                global_challenge,
+                solution_range: SolutionRange::MAX,
+                voting_solution_range: SolutionRange::MAX,
+            },
+            NonZeroUsize::new(solutions_limit).unwrap(),
+            solution_selection_policy,
+            &self.kzg,
+            &self.erasure_coding,
+        )
+        .unwrap()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_plotting_reports_sectors_in_order() {
+    let directory = TempDir::new().unwrap();
+    let sector_count = 4;
+    let piece_getter = Arc::new(ConcurrencyTrackingPieceGetter {
+        archived_history_segment: archived_history_segment(&Kzg::new(embedded_kzg_settings())),
+        in_flight: AtomicUsize::new(0),
+        max_in_flight: AtomicUsize::new(0),
+    });
+
+    let plotted_sectors = plot_farm(
+        directory.path(),
+        Arc::clone(&piece_getter),
+        sector_count,
+        NonZeroUsize::new(2).unwrap(),
+    )
+    .await;
+
+    // Pieces of more than one sector were downloaded at the same time
+    assert!(piece_getter.max_in_flight.load(Ordering::SeqCst) > usize::from(PIECES_IN_SECTOR));
+
+    let sector_offsets = plotted_sectors
+        .iter()
+        .map(|sector_plotting_details| sector_plotting_details.sector_offset)
+        .collect::<Vec<_>>();
+    assert_eq!(sector_offsets, (0..sector_count).collect::<Vec<_>>());
+    assert!(plotted_sectors
+        .iter()
+        .all(|sector_plotting_details| !sector_plotting_details.replotting));
+
+    // All plotted sectors are picked up after restart
+    let sectors_metadata = SingleDiskPlot::read_sectors_metadata(directory.path()).unwrap();
+    assert_eq!(sectors_metadata.len(), sector_count);
+    for (sector_offset, sector_metadata) in sectors_metadata.into_iter().enumerate() {
+        let sector_metadata = sector_metadata.unwrap();
+        assert_eq!(
+            sector_metadata.sector_index - plotted_sectors[0].sector_index,
+            sector_offset as u64
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn benchmark_plotted_farm() {
+    let directory = TempDir::new().unwrap();
+    let sector_count = 2;
+
+    let kzg = Kzg::new(embedded_kzg_settings());
+    plot_farm(
+        directory.path(),
+        archived_history_segment(&kzg),
+        sector_count,
+        NonZeroUsize::new(1).unwrap(),
+    )
+    .await;
+
+    let benchmark_results = SingleDiskPlot::benchmark::<PosTable>(
+        directory.path(),
+        &kzg,
+        &erasure_coding(),
+        sector_count,
+        true,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(benchmark_results.sector_count, sector_count);
+    // Not every sector is guaranteed to have a solution, but there can't be more than sectors
+    assert!(benchmark_results.proving_durations.len() <= sector_count);
+    assert!(benchmark_results.plotting_duration.is_some());
+
+    let benchmark_results =
+        SingleDiskPlot::benchmark::<PosTable>(directory.path(), &kzg, &erasure_coding(), 0, false)
+            .await
+            .unwrap();
+
+    assert_eq!(benchmark_results.sector_count, sector_count);
+    assert!(benchmark_results.proving_durations.is_empty());
+    assert!(benchmark_results.plotting_duration.is_none());
+}
+
+#[test]
+fn summary_of_farm_in_use_is_not_collected() {
+    let directory = TempDir::new().unwrap();
+
+    // Lock held by current process doesn't prevent collecting summary
+    let single_disk_plot_lock = SingleDiskPlotLock::acquire(directory.path()).unwrap();
+    assert!(matches!(
+        SingleDiskPlot::collect_summary(directory.path().to_path_buf()),
+        SingleDiskPlotSummary::NotFound { .. }
+    ));
+    drop(single_disk_plot_lock);
+
+    // Emulate lock held by another process
+    let lock_file_path = directory.path().join(SingleDiskPlotLock::FILE_NAME);
+    fs::write(&lock_file_path, "1").unwrap();
+    let lock_file = fs::File::open(&lock_file_path).unwrap();
+    fs2::FileExt::try_lock_exclusive(&lock_file).unwrap();
+
+    match SingleDiskPlot::collect_summary(directory.path().to_path_buf()) {
+        SingleDiskPlotSummary::InUse { pid, .. } => {
+            assert_eq!(pid, Some(1));
+        }
+        _ => {
+            panic!("Farm must be in use");
+        }
+    }
+
+    // Checking lock doesn't modify lock file
+    assert_eq!(fs::read_to_string(&lock_file_path).unwrap(), "1");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn farming_solutions_do_not_depend_on_thread_count() {
+    let directory = TempDir::new().unwrap();
+    let sector_count = 4;
+    let plotted_farm = PlottedFarm::new(directory.path(), sector_count).await;
+
+    let mut solution_count = 0;
+    for global_challenge in [[1; 32], [2; 32]] {
+        let slot_solutions = plotted_farm.farm(
+            global_challenge,
+            1,
+            &HashSet::new(),
+            usize::MAX,
+            SolutionSelectionPolicy::First,
+        );
+        let parallel_slot_solutions = plotted_farm.farm(
+            global_challenge,
+            sector_count,
+            &HashSet::new(),
+            usize::MAX,
+            SolutionSelectionPolicy::First,
+        );
+
+        assert_eq!(slot_solutions.solutions, parallel_slot_solutions.solutions);
+        assert_eq!(
+            slot_solutions.candidate_sector_count,
+            parallel_slot_solutions.candidate_sector_count
+        );
+        // Solutions are in order of sectors
+        assert!(slot_solutions
+            .solutions
+            .windows(2)
+            .all(|solutions| solutions[0].sector_index <= solutions[1].sector_index));
+
+        solution_count += slot_solutions.solutions.len();
+    }
+
+    // Any sector might not have solutions for a particular challenge, but not all of them
+    assert!(solution_count > 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn farming_respects_solutions_limit_and_skips_sectors_being_replaced() {
+    let directory = TempDir::new().unwrap();
+    let sector_count = 4;
+    let plotted_farm = PlottedFarm::new(directory.path(), sector_count).await;
+    let global_challenge = [1; 32];
+
+    let all_solutions = plotted_farm
+        .farm(
+            global_challenge,
+            sector_count,
+            &HashSet::new(),
+            usize::MAX,
+            SolutionSelectionPolicy::First,
+        )
+        .solutions;
+
+    // Limited number of solutions are the first ones found regardless of batching
+    for solutions_limit in [1, 2, 5] {
+        let slot_solutions = plotted_farm.farm(
+            global_challenge,
+            sector_count,
+            &HashSet::new(),
+            solutions_limit,
+            SolutionSelectionPolicy::First,
+        );
+
+        assert_eq!(
+            slot_solutions.solutions,
+            all_solutions
+                .iter()
+                .take(solutions_limit)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    // First sector is being replaced
+    let slot_solutions = plotted_farm.farm(
+        global_challenge,
+        sector_count,
+        &HashSet::from([0]),
+        usize::MAX,
+        SolutionSelectionPolicy::First,
+    );
+    assert_eq!(
+        slot_solutions.solutions,
+        all_solutions
+            .iter()
+            .filter(|solution| solution.sector_index != plotted_farm.first_sector_index)
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+
+    // All sectors are being replaced
+    let slot_solutions = plotted_farm.farm(
+        global_challenge,
+        sector_count,
+        &(0..sector_count).collect::<HashSet<_>>(),
+        usize::MAX,
+        SolutionSelectionPolicy::First,
+    );
+    assert_eq!(slot_solutions.candidate_sector_count, 0);
+    assert!(slot_solutions.solutions.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn migrated_sectors_are_farmed_instead_of_old_sectors() {
+    let directory = TempDir::new().unwrap();
+    let sector_count = 4;
+    let plotted_farm = PlottedFarm::new(directory.path(), sector_count).await;
+    let global_challenge = [1; 32];
+    let sector_size = sector_size(PIECES_IN_SECTOR);
+
+    let all_solutions = plotted_farm
+        .farm(
+            global_challenge,
+            1,
+            &HashSet::new(),
+            usize::MAX,
+            SolutionSelectionPolicy::First,
+        )
+        .solutions;
+
+    // First two sectors are migrated as is, such that solutions must not change regardless of
+    // which copy of the sector is farmed
+    let migration_directory = TempDir::new().unwrap();
+    let plot_migration = PlotMigration::new(PIECES_IN_SECTOR);
+    let mut migration_files = MigrationFiles::open(
+        migration_directory.path(),
+        &plot_migration,
+        (sector_size * sector_count) as u64,
+    )
+    .unwrap();
+    let mut migrated_sectors = migration_files.migrated_sectors().unwrap();
+    for sector_offset in 0..2 {
+        let sector_metadata = plotted_farm.sectors_metadata[sector_offset].clone();
+        migration_files
+            .write_sector(
+                sector_offset,
+                &plotted_farm.plot[sector_offset * sector_size..][..sector_size],
+                &sector_metadata.encode(),
+            )
+            .unwrap();
+        migrated_sectors.sectors_metadata.push(sector_metadata);
+    }
+
+    let sectors = farmed_sectors(
+        plotted_farm.first_sector_index,
+        &plotted_farm.sectors_metadata,
+        &plotted_farm.plot,
+        sector_size,
+        &HashSet::new(),
+        Some(&migrated_sectors),
+    );
+    assert_eq!(sectors.len(), sector_count);
+    for (sector_offset, farmed_sector) in sectors.iter().enumerate() {
+        let expected_sector = match migrated_sectors.get(sector_offset) {
+            Some((_sector_metadata, sector)) => sector,
+            None => &plotted_farm.plot[sector_offset * sector_size..][..sector_size],
+        };
+        assert_eq!(
+            farmed_sector.sector_index,
+            plotted_farm.first_sector_index + sector_offset as u64
+        );
+        assert!(std::ptr::eq(farmed_sector.sector, expected_sector));
+    }
+
+    let slot_solutions = plotted_farm.farm_with_migrated_sectors(
+        global_challenge,
+        1,
+        &HashSet::new(),
+        Some(&migrated_sectors),
+        usize::MAX,
+        SolutionSelectionPolicy::First,
+    );
+    assert_eq!(slot_solutions.solutions, all_solutions);
+
+    // Old sectors that are being replaced don't prevent farming of migrated sectors
+    let slot_solutions = plotted_farm.farm_with_migrated_sectors(
+        global_challenge,
+        1,
+        &(0..sector_count).collect::<HashSet<_>>(),
+        Some(&migrated_sectors),
+        usize::MAX,
+        SolutionSelectionPolicy::First,
+    );
+    assert_eq!(
+        slot_solutions.solutions,
+        all_solutions
+            .iter()
+            .filter(|solution| { solution.sector_index < plotted_farm.first_sector_index + 2 })
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn closest_to_challenge_policy_selects_closest_solutions_across_sectors() {
+    let directory = TempDir::new().unwrap();
+    let sector_count = 4;
+    let plotted_farm = PlottedFarm::new(directory.path(), sector_count).await;
+
+    let solution_distances = |global_challenge: Blake2b256Hash, slot_solutions: SlotSolutions| {
+        slot_solutions
+            .solutions
+            .iter()
+            .map(|solution| {
+                verify_solution_with_global_challenge::<PosTable, _, _>(
+                    solution,
+                    &global_challenge,
+                    SolutionRange::MAX,
+                    None,
+                    &plotted_farm.kzg,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Find challenge with multiple solutions to compare
+    let (global_challenge, mut all_solution_distances) = (1..=u8::MAX)
+        .map(|byte| [byte; 32])
+        .find_map(|global_challenge| {
+            let all_solution_distances = solution_distances(
+                global_challenge,
+                plotted_farm.farm(
+                    global_challenge,
+                    sector_count,
+                    &HashSet::new(),
+                    usize::MAX,
+                    SolutionSelectionPolicy::First,
+                ),
+            );
+
+            (all_solution_distances.len() > 1).then_some((global_challenge, all_solution_distances))
+        })
+        .unwrap();
+    all_solution_distances.sort_unstable();
+
+    for solutions_limit in [1, all_solution_distances.len() / 2, usize::MAX] {
+        let closest_solution_distances = solution_distances(
+            global_challenge,
+            plotted_farm.farm(
+                global_challenge,
+                sector_count,
+                &HashSet::new(),
+                solutions_limit,
+                SolutionSelectionPolicy::ClosestToChallenge,
+            ),
+        );
+
+        // Solutions are the closest ones across all sectors and sorted by distance
+        assert_eq!(
+            closest_solution_distances,
+            all_solution_distances
+                .iter()
+                .take(solutions_limit)
+                .copied()
+                .collect::<Vec<_>>()
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sectors_marked_for_replotting_are_persisted() {
+    let directory = TempDir::new().unwrap();
+    let sector_count = 3;
+    let plotted_farm = PlottedFarm::new(directory.path(), sector_count).await;
+    let metadata_file = fs::OpenOptions::new()
+        .write(true)
+        .open(directory.path().join(SingleDiskPlot::METADATA_FILE))
+        .unwrap();
+    let mut sectors_metadata = plotted_farm.sectors_metadata.clone();
+
+    // Second sector is being replaced already and the last one doesn't exist
+    assert!(mark_sectors_for_replotting(
+        [0, 1, sector_count].into_iter(),
+        &mut sectors_metadata,
+        &HashSet::from([1]),
+        &metadata_file,
+    )
+    .unwrap());
+
+    let expired = |sectors_metadata: &[SectorMetadata]| {
+        sectors_metadata
+            .iter()
+            .map(|sector_metadata| sector_metadata.expires_at == SegmentIndex::ZERO)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(expired(&sectors_metadata), vec![true, false, false]);
+    assert_eq!(
+        expired(
+            &SingleDiskPlot::read_sectors_metadata(directory.path())
+                .unwrap()
+                .into_iter()
+                .map(Result::unwrap)
+                .collect::<Vec<_>>()
+        ),
+        vec![true, false, false]
+    );
+
+    // Sector that is marked already is not marked again
+    assert!(!mark_sectors_for_replotting(
+        [0].into_iter(),
+        &mut sectors_metadata,
+        &HashSet::new(),
+        &metadata_file,
+    )
+    .unwrap());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sectors_with_invalid_solutions_are_replotted_only_if_enabled() {
+    let directory = TempDir::new().unwrap();
+    let sector_count = 2;
+    let plotted_farm = PlottedFarm::new(directory.path(), sector_count).await;
+
+    let (global_challenge, solution) = (0..=u8::MAX)
+        .map(|byte| [byte; 32])
+        .find_map(|global_challenge| {
+            let solution = plotted_farm
+                .farm(
+                    global_challenge,
+                    1,
+                    &HashSet::new(),
+                    1,
+                    SolutionSelectionPolicy::First,
+                )
+                .solutions
+                .into_iter()
+                .next()?;
+
+            Some((global_challenge, solution))
+        })
+        .unwrap();
+    let sector_offset = (solution.sector_index - plotted_farm.first_sector_index) as usize;
+
+    for (slot_number, replot_invalid_sectors) in [(1, false), (2, true)] {
+        let test_node = TestNode::new(Some(farmer_app_info()));
+        // Segment commitment doesn't match, so solution is invalid
+        test_node
+            .client
+            .segment_commitments
+            .lock()
+            .insert(SegmentIndex::ZERO, solution.record_commitment);
+        let (requested_pieces_sender, mut requested_pieces_receiver) = mpsc::unbounded();
+
+        let single_disk_plot = SingleDiskPlot::new::<_, _, PosTable>(
+            SingleDiskPlotOptions {
+                replot_invalid_sectors,
+                ..single_disk_plot_options(
+                    directory.path(),
+                    test_node.client.clone(),
+                    PendingPieceGetter {
+                        requested_pieces: requested_pieces_sender,
+                    },
+                    sector_count,
+                    NonZeroUsize::new(1).unwrap(),
+                )
+            },
+            0,
+        )
+        .await
+        .unwrap();
+
+        let (solution_sender, mut solution_receiver) = mpsc::unbounded();
+        single_disk_plot
+            .on_solution(Arc::new(move |solution_response| {
+                let _ = solution_sender.unbounded_send(solution_response.clone());
+            }))
+            .detach();
+
+        let test_fut = async {
+            test_node
+                .slot_info_sender
+                .unbounded_send(SlotInfo {
+                    slot_number,
+                    global_challenge,
+                    solution_range: SolutionRange::MAX,
+                    voting_solution_range: SolutionRange::MAX,
+                })
+                .unwrap();
+
+            let solution_response = solution_receiver.next().await.unwrap();
+            assert_eq!(solution_response.slot_number, slot_number);
+            // Invalid solution is not submitted regardless of replotting
+            assert!(solution_response.solutions.is_empty());
+
+            // Sector is marked on disk before solutions response is sent
+            let sector_metadata = SingleDiskPlot::read_sectors_metadata(directory.path())
+                .unwrap()
+                .into_iter()
+                .nth(sector_offset)
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                sector_metadata.expires_at == SegmentIndex::ZERO,
+                replot_invalid_sectors
+            );
+
+            if replot_invalid_sectors {
+                // Replotting starts without waiting for the next archived segment
+                timeout(Duration::from_secs(30), requested_pieces_receiver.next())
+                    .await
+                    .unwrap()
+                    .unwrap();
+            } else {
+                assert!(requested_pieces_receiver.try_next().is_err());
+            }
+        };
+
+        match select(Box::pin(single_disk_plot.run()), Box::pin(test_fut)).await {
+            Either::Left((result, _)) => {
+                panic!("Single disk plot exited before farming: {result:?}");
+            }
+            Either::Right(((), _)) => {}
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod archival_storage_pieces;
+pub mod disk_piece_cache;
 pub mod farmer_piece_cache;
 pub mod farmer_piece_getter;
 pub mod farmer_provider_storage;
@@ -7,6 +8,8 @@ pub mod parity_db_store;
 pub mod piece_cache;
 pub mod piece_validator;
 pub mod readers_and_pieces;
+pub mod sector_index_allocator;
+pub mod shared_secret;
 #[cfg(test)]
 mod tests;
 
@@ -0,0 +1,286 @@
+#[cfg(test)]
+mod tests;
+
+use crate::single_disk_plot::{SingleDiskPlotError, SingleDiskPlotLock};
+use crate::utils::piece_cache::PieceCache;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+use std::sync::Arc;
+use std::{io, mem};
+use subspace_core_primitives::crypto::blake2b_256_hash;
+use subspace_core_primitives::{Piece, BLAKE2B_256_HASH_SIZE};
+use subspace_farmer_components::file_ext::FileExt;
+use subspace_networking::libp2p::kad::record::Key;
+use subspace_networking::libp2p::PeerId;
+use subspace_networking::UniqueRecordBinaryHeap;
+use thiserror::Error;
+use tracing::{debug, info, trace, warn};
+
+/// Maximum supported size of the key, keys are multihashes of piece index hashes, which are much
+/// smaller than this
+const MAX_KEY_SIZE: usize = 128;
+/// Key is stored with its length in front of it
+const KEY_HEADER_SIZE: usize = mem::size_of::<u16>() + MAX_KEY_SIZE;
+/// Every slot contains key header, piece and checksum of both
+const SLOT_SIZE: usize = KEY_HEADER_SIZE + Piece::SIZE + BLAKE2B_256_HASH_SIZE;
+
+/// Errors happening when opening disk piece cache
+#[derive(Debug, Error)]
+pub enum DiskPieceCacheError {
+    /// I/O error occurred
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Failed to lock farm directory
+    #[error("Failed to lock farm directory: {0}")]
+    Lock(#[from] SingleDiskPlotError),
+}
+
+#[derive(Debug, Copy, Clone)]
+struct SlotLocation {
+    file_index: usize,
+    slot_offset: usize,
+}
+
+impl SlotLocation {
+    fn byte_offset(&self) -> u64 {
+        (self.slot_offset * SLOT_SIZE) as u64
+    }
+}
+
+struct State {
+    // Maintains a heap to limit total number of entries to number of slots.
+    heap: UniqueRecordBinaryHeap,
+    slots: HashMap<Key, SlotLocation>,
+    free_slots: Vec<SlotLocation>,
+}
+
+/// Piece cache stored in dedicated files of fixed size (one per farm), where pieces closer to
+/// provided peer ID are retained.
+///
+/// Files are split into slots of fixed size, each slot contains key, piece and checksum of both.
+/// Contents of the cache is restored from files on startup, so pieces are not downloaded again
+/// after restart.
+#[derive(Clone)]
+pub struct DiskPieceCache {
+    files: Arc<[File]>,
+    state: Arc<Mutex<State>>,
+}
+
+impl DiskPieceCache {
+    /// Name of the cache file in farm directory
+    pub const FILE_NAME: &'static str = "piece_cache.bin";
+
+    /// Open (or create) cache files in provided directories with specified sizes in bytes.
+    ///
+    /// Files are resized to specified size (rounded down to the whole number of slots), pieces
+    /// stored in the truncated part of the file are forgotten.
+    ///
+    /// Farm directory is locked while its cache file is resized, such that it is not done while
+    /// farm is used by another process.
+    pub fn open<'a, I>(directories: I, peer_id: PeerId) -> Result<Self, DiskPieceCacheError>
+    where
+        I: IntoIterator<Item = (&'a Path, u64)>,
+    {
+        let mut files = Vec::new();
+        let mut slot_counts = Vec::new();
+
+        for (directory, size) in directories {
+            let slot_count = (size / SLOT_SIZE as u64) as usize;
+            if slot_count == 0 {
+                continue;
+            }
+
+            // Lock is only held while file is resized, afterwards it is taken by single disk plot
+            // in the same directory for as long as farm is running
+            let _single_disk_plot_lock = SingleDiskPlotLock::acquire(directory)?;
+
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .open(directory.join(Self::FILE_NAME))?;
+
+            let expected_file_size = (slot_count * SLOT_SIZE) as u64;
+            if file.metadata()?.len() > expected_file_size {
+                file.set_len(expected_file_size)?;
+            } else {
+                file.preallocate(expected_file_size)?;
+            }
+            file.advise_random_access()?;
+
+            files.push(file);
+            slot_counts.push(slot_count);
+        }
+
+        let total_slot_count = slot_counts.iter().sum::<usize>();
+        let mut state = State {
+            heap: UniqueRecordBinaryHeap::new(peer_id, total_slot_count),
+            slots: HashMap::with_capacity(total_slot_count),
+            free_slots: Vec::new(),
+        };
+
+        let mut key_header = [0; KEY_HEADER_SIZE];
+        for (file_index, (file, slot_count)) in files.iter().zip(slot_counts).enumerate() {
+            for slot_offset in 0..slot_count {
+                let slot_location = SlotLocation {
+                    file_index,
+                    slot_offset,
+                };
+
+                file.read_exact_at(&mut key_header, slot_location.byte_offset())?;
+
+                match decode_key(&key_header) {
+                    Some(key) if state.heap.should_include_key(&key) => {
+                        state.heap.insert(key.clone());
+                        state.slots.insert(key, slot_location);
+                    }
+                    _ => {
+                        state.free_slots.push(slot_location);
+                    }
+                }
+            }
+        }
+
+        if state.slots.is_empty() {
+            debug!(slots = %total_slot_count, "New disk piece cache initialized.");
+        } else {
+            info!(
+                size = %state.slots.len(),
+                slots = %total_slot_count,
+                "Disk piece cache loaded."
+            );
+        }
+
+        Ok(Self {
+            files: files.into(),
+            state: Arc::new(Mutex::new(state)),
+        })
+    }
+
+    /// Number of pieces in cache
+    pub fn size(&self) -> usize {
+        self.state.lock().slots.len()
+    }
+
+    fn write_slot(&self, slot_location: SlotLocation, key: &Key, piece: &Piece) -> io::Result<()> {
+        let file = &self.files[slot_location.file_index];
+        let offset = slot_location.byte_offset();
+
+        let mut slot = vec![0; SLOT_SIZE];
+        let (key_header, rest) = slot.split_at_mut(KEY_HEADER_SIZE);
+        let (slot_piece, checksum) = rest.split_at_mut(Piece::SIZE);
+        key_header[..mem::size_of::<u16>()]
+            .copy_from_slice(&(key.as_ref().len() as u16).to_le_bytes());
+        key_header[mem::size_of::<u16>()..][..key.as_ref().len()].copy_from_slice(key.as_ref());
+        slot_piece.copy_from_slice(piece.as_ref());
+        checksum.copy_from_slice(&blake2b_256_hash(&slot[..KEY_HEADER_SIZE + Piece::SIZE]));
+
+        // Key is cleared first and written last, such that interrupted write never results in
+        // a slot that looks valid
+        file.write_all_at(&[0; KEY_HEADER_SIZE], offset)?;
+        file.write_all_at(&slot[KEY_HEADER_SIZE..], offset + KEY_HEADER_SIZE as u64)?;
+        file.write_all_at(&slot[..KEY_HEADER_SIZE], offset)
+    }
+
+    fn read_slot(&self, slot_location: SlotLocation, key: &Key) -> io::Result<Option<Piece>> {
+        let mut slot = vec![0; SLOT_SIZE];
+        self.files[slot_location.file_index]
+            .read_exact_at(&mut slot, slot_location.byte_offset())?;
+
+        let (key_and_piece, checksum) = slot.split_at(KEY_HEADER_SIZE + Piece::SIZE);
+
+        // Slot might have been overwritten concurrently
+        if decode_key(&key_and_piece[..KEY_HEADER_SIZE]).as_ref() != Some(key)
+            || blake2b_256_hash(key_and_piece).as_slice() != checksum
+        {
+            return Ok(None);
+        }
+
+        Ok(Piece::try_from(&key_and_piece[KEY_HEADER_SIZE..]).ok())
+    }
+}
+
+impl PieceCache for DiskPieceCache {
+    type KeysIterator = Vec<Key>;
+
+    fn should_cache(&self, key: &Key) -> bool {
+        self.state.lock().heap.should_include_key(key)
+    }
+
+    fn add_piece(&mut self, key: Key, piece: Piece) {
+        if key.as_ref().len() > MAX_KEY_SIZE {
+            warn!(?key, "Key is too large for disk piece cache, ignoring");
+            return;
+        }
+
+        let slot_location = {
+            let mut state = self.state.lock();
+
+            if !state.heap.should_include_key(&key) {
+                return;
+            }
+
+            let slot_location = match state.heap.insert(key.clone()) {
+                Some(evicted_key) => {
+                    trace!(key = ?evicted_key, "Record evicted from disk cache.");
+                    state.slots.remove(&evicted_key)
+                }
+                None => state.free_slots.pop(),
+            };
+
+            let Some(slot_location) = slot_location else {
+                state.heap.remove(&key);
+                return;
+            };
+
+            // Slot is assigned before it is written, such that concurrent reads see mismatching
+            // key or checksum instead of wrong piece
+            state.slots.insert(key.clone(), slot_location);
+
+            slot_location
+        };
+
+        if let Err(error) = self.write_slot(slot_location, &key, &piece) {
+            warn!(%error, ?key, "Failed to write piece to disk cache");
+
+            let mut state = self.state.lock();
+            state.heap.remove(&key);
+            state.slots.remove(&key);
+            state.free_slots.push(slot_location);
+        }
+    }
+
+    fn get_piece(&self, key: &Key) -> Option<Piece> {
+        let slot_location = *self.state.lock().slots.get(key)?;
+
+        match self.read_slot(slot_location, key) {
+            Ok(maybe_piece) => maybe_piece,
+            Err(error) => {
+                warn!(%error, ?key, "Failed to read piece from disk cache");
+                None
+            }
+        }
+    }
+
+    fn keys(&self) -> Self::KeysIterator {
+        self.state.lock().slots.keys().cloned().collect()
+    }
+}
+
+/// Decode key from key header, `None` means slot is empty
+fn decode_key(key_header: &[u8]) -> Option<Key> {
+    let (key_length, key) = key_header.split_at(mem::size_of::<u16>());
+    let key_length = u16::from_le_bytes(
+        key_length
+            .try_into()
+            .expect("Slice has exactly two bytes; qed"),
+    ) as usize;
+
+    if key_length == 0 || key_length > MAX_KEY_SIZE {
+        return None;
+    }
+
+    Some(Key::from(key[..key_length].to_vec()))
+}
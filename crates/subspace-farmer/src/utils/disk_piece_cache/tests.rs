@@ -0,0 +1,153 @@
+use crate::single_disk_plot::{SingleDiskPlotError, SingleDiskPlotLock};
+use crate::utils::disk_piece_cache::{
+    decode_key, DiskPieceCache, DiskPieceCacheError, KEY_HEADER_SIZE, MAX_KEY_SIZE, SLOT_SIZE,
+};
+use crate::utils::piece_cache::PieceCache;
+use std::collections::HashMap;
+use std::{fs, mem};
+use subspace_core_primitives::crypto::blake2b_256_hash;
+use subspace_core_primitives::Piece;
+use subspace_networking::libp2p::kad::record::Key;
+use subspace_networking::libp2p::PeerId;
+use tempfile::TempDir;
+
+fn piece(byte: u8) -> Piece {
+    let mut piece = Piece::default();
+    piece.as_mut().fill(byte);
+    piece
+}
+
+fn key(byte: u8) -> Key {
+    Key::from(vec![byte; 34])
+}
+
+#[test]
+fn disk_piece_cache_decode_key() {
+    let mut key_header = [0; KEY_HEADER_SIZE];
+    assert_eq!(decode_key(&key_header), None);
+
+    key_header[..mem::size_of::<u16>()].copy_from_slice(&(MAX_KEY_SIZE as u16 + 1).to_le_bytes());
+    assert_eq!(decode_key(&key_header), None);
+
+    key_header[..mem::size_of::<u16>()].copy_from_slice(&3u16.to_le_bytes());
+    key_header[mem::size_of::<u16>()..][..3].copy_from_slice(&[1, 2, 3]);
+    assert_eq!(decode_key(&key_header), Some(Key::from(vec![1, 2, 3])));
+}
+
+#[test]
+fn disk_piece_cache_slot_encoding() {
+    let directory = TempDir::new().unwrap();
+    let file_path = directory.path().join(DiskPieceCache::FILE_NAME);
+
+    let mut disk_piece_cache =
+        DiskPieceCache::open([(directory.path(), SLOT_SIZE as u64)], PeerId::random()).unwrap();
+    disk_piece_cache.add_piece(key(1), piece(1));
+
+    let slot = fs::read(&file_path).unwrap();
+    assert_eq!(slot.len(), SLOT_SIZE);
+    assert_eq!(decode_key(&slot[..KEY_HEADER_SIZE]), Some(key(1)));
+    assert_eq!(
+        &slot[KEY_HEADER_SIZE..][..Piece::SIZE],
+        AsRef::<[u8]>::as_ref(&piece(1))
+    );
+    assert_eq!(
+        &slot[KEY_HEADER_SIZE + Piece::SIZE..],
+        blake2b_256_hash(&slot[..KEY_HEADER_SIZE + Piece::SIZE]).as_slice()
+    );
+    assert_eq!(disk_piece_cache.get_piece(&key(1)), Some(piece(1)));
+
+    // Keys that do not fit into key header are not stored
+    let large_key = Key::from(vec![2; MAX_KEY_SIZE + 1]);
+    disk_piece_cache.add_piece(large_key.clone(), piece(2));
+    assert_eq!(disk_piece_cache.get_piece(&large_key), None);
+    assert_eq!(disk_piece_cache.size(), 1);
+
+    // Corrupted piece doesn't match checksum and is not returned
+    let mut corrupted_slot = slot;
+    corrupted_slot[KEY_HEADER_SIZE] ^= 1;
+    fs::write(&file_path, &corrupted_slot).unwrap();
+    assert_eq!(disk_piece_cache.get_piece(&key(1)), None);
+}
+
+#[test]
+fn disk_piece_cache_reopen() {
+    let first_directory = TempDir::new().unwrap();
+    let second_directory = TempDir::new().unwrap();
+    let peer_id = PeerId::random();
+    let pieces = (1..=3)
+        .map(|byte| (key(byte), piece(byte)))
+        .collect::<HashMap<_, _>>();
+
+    {
+        let mut disk_piece_cache = DiskPieceCache::open(
+            [
+                (first_directory.path(), 2 * SLOT_SIZE as u64),
+                (second_directory.path(), SLOT_SIZE as u64),
+            ],
+            peer_id,
+        )
+        .unwrap();
+
+        for (key, piece) in &pieces {
+            disk_piece_cache.add_piece(key.clone(), piece.clone());
+        }
+        assert_eq!(disk_piece_cache.size(), pieces.len());
+    }
+
+    let check_contents = |disk_piece_cache: &DiskPieceCache| {
+        for key in disk_piece_cache.keys() {
+            assert_eq!(disk_piece_cache.get_piece(&key).as_ref(), pieces.get(&key));
+        }
+    };
+
+    // Contents is restored from files
+    let disk_piece_cache = DiskPieceCache::open(
+        [
+            (first_directory.path(), 2 * SLOT_SIZE as u64),
+            (second_directory.path(), SLOT_SIZE as u64),
+        ],
+        peer_id,
+    )
+    .unwrap();
+    assert_eq!(disk_piece_cache.size(), pieces.len());
+    check_contents(&disk_piece_cache);
+    drop(disk_piece_cache);
+
+    // Pieces in truncated slots are forgotten
+    let disk_piece_cache = DiskPieceCache::open(
+        [
+            (first_directory.path(), SLOT_SIZE as u64 + 1),
+            (second_directory.path(), SLOT_SIZE as u64),
+        ],
+        peer_id,
+    )
+    .unwrap();
+    assert_eq!(disk_piece_cache.size(), 2);
+    check_contents(&disk_piece_cache);
+    assert_eq!(
+        fs::metadata(first_directory.path().join(DiskPieceCache::FILE_NAME))
+            .unwrap()
+            .len(),
+        SLOT_SIZE as u64
+    );
+}
+
+#[test]
+fn disk_piece_cache_respects_farm_lock() {
+    let directory = TempDir::new().unwrap();
+
+    let single_disk_plot_lock = SingleDiskPlotLock::acquire(directory.path()).unwrap();
+    let result = DiskPieceCache::open([(directory.path(), SLOT_SIZE as u64)], PeerId::random());
+    assert!(matches!(
+        result,
+        Err(DiskPieceCacheError::Lock(
+            SingleDiskPlotError::AlreadyInUse { .. }
+        ))
+    ));
+    assert!(!directory.path().join(DiskPieceCache::FILE_NAME).exists());
+
+    drop(single_disk_plot_lock);
+    DiskPieceCache::open([(directory.path(), SLOT_SIZE as u64)], PeerId::random()).unwrap();
+    // Lock is released once cache is opened
+    SingleDiskPlotLock::acquire(directory.path()).unwrap();
+}
@@ -1,16 +1,24 @@
 use async_trait::async_trait;
 use std::error::Error;
+use std::sync::Arc;
 use subspace_core_primitives::{Piece, PieceIndex};
 use subspace_farmer_components::plotting::{PieceGetter, PieceGetterRetryPolicy};
 use subspace_networking::utils::piece_provider::{PieceProvider, PieceValidator, RetryPolicy};
 
 pub struct NodePieceGetter<RV> {
-    piece_provider: PieceProvider<RV>,
+    piece_provider: Arc<PieceProvider<RV>>,
 }
 
 impl<RV> NodePieceGetter<RV> {
     pub fn new(piece_provider: PieceProvider<RV>) -> Self {
-        Self { piece_provider }
+        Self {
+            piece_provider: Arc::new(piece_provider),
+        }
+    }
+
+    /// Piece provider used by this piece getter, can be used to query provider statistics
+    pub fn piece_provider(&self) -> &Arc<PieceProvider<RV>> {
+        &self.piece_provider
     }
 }
 
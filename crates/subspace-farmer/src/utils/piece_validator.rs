@@ -7,7 +7,7 @@ use subspace_core_primitives::crypto::kzg::Kzg;
 use subspace_core_primitives::{Piece, PieceIndex, SegmentCommitment, SegmentIndex};
 use subspace_networking::libp2p::PeerId;
 use subspace_networking::utils::piece_provider::PieceValidator;
-use subspace_networking::Node;
+use subspace_networking::{Node, ReputationChange};
 use tracing::{error, warn};
 
 pub struct SegmentCommitmentPieceValidator<NC> {
@@ -105,7 +105,10 @@ where
                 );
 
                 // We don't care about result here
-                let _ = self.dsn_node.ban_peer(source_peer_id).await;
+                let _ = self
+                    .dsn_node
+                    .report_peer(source_peer_id, ReputationChange::InvalidData)
+                    .await;
                 return None;
             }
         }
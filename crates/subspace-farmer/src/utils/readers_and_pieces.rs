@@ -73,6 +73,29 @@ impl ReadersAndPieces {
         self.pieces.extend(pieces)
     }
 
+    /// Delete pieces that belong to the specified sector of the specified plot.
+    ///
+    /// Pieces that are known to be stored in a different location (in case the same piece was
+    /// plotted more than once) are left intact.
+    pub fn delete_pieces<I>(
+        &mut self,
+        disk_farm_index: usize,
+        sector_index: SectorIndex,
+        piece_index_hashes: I,
+    ) where
+        I: Iterator<Item = PieceIndexHash>,
+    {
+        for piece_index_hash in piece_index_hashes {
+            if let Some(piece_details) = self.pieces.get(&piece_index_hash) {
+                if piece_details.disk_farm_index == disk_farm_index
+                    && piece_details.sector_index == sector_index
+                {
+                    self.pieces.remove(&piece_index_hash);
+                }
+            }
+        }
+    }
+
     pub fn piece_index_hashes(&self) -> impl Iterator<Item = &PieceIndexHash> {
         self.pieces.keys()
     }
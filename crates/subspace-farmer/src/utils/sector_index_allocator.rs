@@ -0,0 +1,238 @@
+//! Allocation of sector indexes for single disk plots.
+//!
+//! Every single disk plot reserves a range of [`SECTOR_INDEX_RANGE_SIZE`] sector indexes starting
+//! at its first sector index, which is persisted in plot info next to plot's identity. Plots that
+//! share the same identity must have non-overlapping ranges, otherwise they would plot identical
+//! sectors.
+//!
+//! Reserved ranges are also persisted in a registry file next to the identity, such that ranges of
+//! plots that are not configured at the moment (like disks that are temporarily disconnected) are
+//! not allocated again.
+
+use crate::single_disk_plot::SingleDiskPlotInfo;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use std::{fs, io};
+use subspace_core_primitives::{PublicKey, SectorIndex};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+/// Number of sector indexes reserved by each single disk plot
+pub const SECTOR_INDEX_RANGE_SIZE: u64 = u32::MAX as u64;
+
+/// Errors happening when creating sector index allocator
+#[derive(Debug, Error)]
+pub enum SectorIndexAllocatorError {
+    /// I/O error occurred
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// Sector index ranges of two plots with the same identity overlap
+    #[error(
+        "Plots at {} and {} have the same identity and overlapping sector indexes, they would \
+        plot identical sectors, one of them needs to be wiped or reassigned a new range",
+        .first_directory.display(),
+        .second_directory.display()
+    )]
+    OverlappingRanges {
+        /// Directory of the first plot
+        first_directory: PathBuf,
+        /// Directory of the second plot
+        second_directory: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Reservation {
+    public_key: PublicKey,
+    first_sector_index: SectorIndex,
+    directory: PathBuf,
+}
+
+impl Reservation {
+    fn overlaps(&self, public_key: &PublicKey, first_sector_index: SectorIndex) -> bool {
+        &self.public_key == public_key
+            && self.first_sector_index.abs_diff(first_sector_index) < SECTOR_INDEX_RANGE_SIZE
+    }
+}
+
+/// Persisted sector index ranges reserved by plots
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum SectorIndexRegistry {
+    /// V0 of the registry
+    #[serde(rename_all = "camelCase")]
+    V0 { reservations: Vec<Reservation> },
+}
+
+#[derive(Debug)]
+struct Inner {
+    reservations: Vec<Reservation>,
+    /// First sector indexes of plots whose ranges were reassigned due to overlap
+    reassigned: HashMap<PathBuf, SectorIndex>,
+    registry_file: PathBuf,
+}
+
+impl Inner {
+    fn find_free_range(&self, public_key: &PublicKey) -> SectorIndex {
+        // Time-based starting point makes collisions unlikely even with plots this allocator
+        // doesn't know about (like those on other machines)
+        let mut first_sector_index = SystemTime::UNIX_EPOCH
+            .elapsed()
+            .expect("Unix epoch is always in the past; qed")
+            .as_secs()
+            .wrapping_mul(SECTOR_INDEX_RANGE_SIZE);
+
+        while self
+            .reservations
+            .iter()
+            .any(|reservation| reservation.overlaps(public_key, first_sector_index))
+        {
+            first_sector_index = first_sector_index.wrapping_add(SECTOR_INDEX_RANGE_SIZE);
+        }
+
+        first_sector_index
+    }
+
+    fn store(&self) -> io::Result<()> {
+        let registry = SectorIndexRegistry::V0 {
+            reservations: self.reservations.clone(),
+        };
+
+        fs::write(
+            &self.registry_file,
+            serde_json::to_vec(&registry).expect("Registry serialization never fails; qed"),
+        )
+    }
+}
+
+/// Allocator of sector indexes that makes sure plots with the same identity don't overlap
+#[derive(Debug, Clone)]
+pub struct SectorIndexAllocator {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl SectorIndexAllocator {
+    /// Name of the registry file with reserved sector index ranges
+    pub const REGISTRY_FILE_NAME: &'static str = "sector_index_ranges.json";
+
+    /// Create new allocator from registry in `registry_directory` (usually directory where identity
+    /// is stored) and plots in provided directories.
+    ///
+    /// Returns an error if any of existing plots with the same identity have overlapping sector
+    /// index ranges, unless `reassign_overlapping` is `true`, in which case overlapping plot gets a
+    /// new range (see [`Self::reassigned_first_sector_index()`]).
+    pub fn new<'a, I>(
+        registry_directory: &Path,
+        directories: I,
+        reassign_overlapping: bool,
+    ) -> Result<Self, SectorIndexAllocatorError>
+    where
+        I: IntoIterator<Item = &'a Path>,
+    {
+        let registry_file = registry_directory.join(Self::REGISTRY_FILE_NAME);
+        let mut reservations = match fs::read(&registry_file) {
+            Ok(bytes) => {
+                let SectorIndexRegistry::V0 { reservations } = serde_json::from_slice(&bytes)
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                reservations
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => {
+                return Err(error.into());
+            }
+        };
+
+        let directories = directories.into_iter().collect::<Vec<_>>();
+
+        // Plot info is the source of truth for configured plots, registry only remembers the rest
+        reservations.retain(|reservation| !directories.contains(&reservation.directory.as_path()));
+
+        let mut inner = Inner {
+            reservations,
+            reassigned: HashMap::new(),
+            registry_file,
+        };
+
+        for directory in directories {
+            let Some(single_disk_plot_info) = SingleDiskPlotInfo::load_from(directory)? else {
+                continue;
+            };
+
+            let public_key = single_disk_plot_info.public_key();
+            let mut first_sector_index = single_disk_plot_info.first_sector_index();
+
+            if let Some(reservation) = inner
+                .reservations
+                .iter()
+                .find(|reservation| reservation.overlaps(public_key, first_sector_index))
+            {
+                if !reassign_overlapping {
+                    return Err(SectorIndexAllocatorError::OverlappingRanges {
+                        first_directory: reservation.directory.clone(),
+                        second_directory: directory.to_path_buf(),
+                    });
+                }
+
+                let old_first_sector_index = first_sector_index;
+                first_sector_index = inner.find_free_range(public_key);
+
+                warn!(
+                    %old_first_sector_index,
+                    new_first_sector_index = %first_sector_index,
+                    directory = %directory.display(),
+                    overlaps_with = %reservation.directory.display(),
+                    "Sector index range overlaps with another plot, reassigning"
+                );
+
+                inner
+                    .reassigned
+                    .insert(directory.to_path_buf(), first_sector_index);
+            }
+
+            inner.reservations.push(Reservation {
+                public_key: *public_key,
+                first_sector_index,
+                directory: directory.to_path_buf(),
+            });
+        }
+
+        inner.store()?;
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(inner)),
+        })
+    }
+
+    /// Allocate first sector index for a new plot in specified directory
+    pub fn allocate(&self, public_key: &PublicKey, directory: &Path) -> io::Result<SectorIndex> {
+        let mut inner = self.inner.lock();
+
+        let first_sector_index = inner.find_free_range(public_key);
+
+        debug!(
+            %first_sector_index,
+            directory = %directory.display(),
+            "Allocated sector index range"
+        );
+
+        inner.reservations.push(Reservation {
+            public_key: *public_key,
+            first_sector_index,
+            directory: directory.to_path_buf(),
+        });
+        inner.store()?;
+
+        Ok(first_sector_index)
+    }
+
+    /// New first sector index of the plot in specified directory if its range overlapped with
+    /// another plot and was reassigned, sectors plotted with old range must be replotted.
+    pub fn reassigned_first_sector_index(&self, directory: &Path) -> Option<SectorIndex> {
+        self.inner.lock().reassigned.get(directory).copied()
+    }
+}
@@ -0,0 +1,80 @@
+//! Secret shared between farmer and standalone services it talks to over the network (reward
+//! signer and plotting servers), used by farmer to authenticate itself.
+//!
+//! Service sends random challenge on every connection and farmer replies with keyed hash of the
+//! challenge, where key is [`SharedSecret`], so secret itself is never sent over the network.
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::{fmt, fs, io};
+use subspace_core_primitives::crypto::blake2b_256_hash_with_key;
+use subspace_core_primitives::Blake2b256Hash;
+use zeroize::Zeroizing;
+
+/// Length of the shared secret
+const SHARED_SECRET_LENGTH: usize = 32;
+/// Length of the challenge farmer needs to authenticate
+pub(crate) const CHALLENGE_LENGTH: usize = 32;
+
+/// Secret shared between farmer and standalone service
+#[derive(Clone)]
+pub struct SharedSecret(Zeroizing<[u8; SHARED_SECRET_LENGTH]>);
+
+impl fmt::Debug for SharedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedSecret").finish_non_exhaustive()
+    }
+}
+
+impl SharedSecret {
+    /// Generate new random secret
+    pub fn generate() -> Self {
+        Self(Zeroizing::new(rand::random()))
+    }
+
+    /// Read hex-encoded secret from file
+    pub fn read_from(path: &Path) -> io::Result<Self> {
+        let contents = Zeroizing::new(fs::read_to_string(path)?);
+        let mut secret = Zeroizing::new([0; SHARED_SECRET_LENGTH]);
+        hex::decode_to_slice(contents.trim(), secret.as_mut()).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid secret in {}: {error}", path.display()),
+            )
+        })?;
+
+        Ok(Self(secret))
+    }
+
+    /// Read secret from file, new secret is generated and written to the file if it doesn't exist
+    pub fn read_or_generate(path: &Path) -> io::Result<Self> {
+        match Self::read_from(path) {
+            Ok(secret) => Ok(secret),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                let secret = Self::generate();
+
+                let mut open_options = fs::OpenOptions::new();
+                open_options.write(true).create_new(true);
+                // Secret must only be readable by the owner
+                #[cfg(unix)]
+                open_options.mode(0o600);
+                io::Write::write_all(
+                    &mut open_options.open(path)?,
+                    Zeroizing::new(hex::encode(secret.0.as_ref())).as_bytes(),
+                )?;
+
+                Ok(secret)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Keyed hash of the challenge that proves knowledge of the secret
+    pub(crate) fn authenticate(&self, challenge: &[u8; CHALLENGE_LENGTH]) -> Blake2b256Hash {
+        blake2b_256_hash_with_key(self.0.as_ref(), challenge)
+    }
+}
@@ -0,0 +1,28 @@
+use crate::utils::shared_secret::SharedSecret;
+use tempfile::TempDir;
+
+#[test]
+fn shared_secret_is_persisted() {
+    let directory = TempDir::new().unwrap();
+    let secret_file = directory.path().join("secret");
+
+    let secret = SharedSecret::read_or_generate(&secret_file).unwrap();
+    let challenge = [3; 32];
+
+    assert_eq!(
+        SharedSecret::read_or_generate(&secret_file)
+            .unwrap()
+            .authenticate(&challenge),
+        secret.authenticate(&challenge)
+    );
+    assert_eq!(
+        SharedSecret::read_from(&secret_file)
+            .unwrap()
+            .authenticate(&challenge),
+        secret.authenticate(&challenge)
+    );
+    assert_ne!(
+        SharedSecret::generate().authenticate(&challenge),
+        secret.authenticate(&challenge)
+    );
+}
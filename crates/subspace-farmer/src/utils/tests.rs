@@ -1,5 +1,12 @@
+use crate::single_disk_plot::{SingleDiskPlotId, SingleDiskPlotInfo};
 use crate::utils::run_future_in_dedicated_thread;
+use crate::utils::sector_index_allocator::{
+    SectorIndexAllocator, SectorIndexAllocatorError, SECTOR_INDEX_RANGE_SIZE,
+};
 use std::future;
+use std::path::Path;
+use subspace_core_primitives::{PublicKey, SectorIndex, PUBLIC_KEY_LENGTH};
+use tempfile::TempDir;
 
 #[tokio::test]
 async fn run_future_in_dedicated_thread_ready() {
@@ -25,3 +32,127 @@ async fn run_future_in_dedicated_thread_cancellation() {
         .unwrap(),
     );
 }
+
+#[test]
+fn sector_index_allocator_avoids_overlaps() {
+    let public_key = PublicKey::from([1; PUBLIC_KEY_LENGTH]);
+    let registry_directory = TempDir::new().unwrap();
+    let first_directory = TempDir::new().unwrap();
+    let second_directory = TempDir::new().unwrap();
+
+    let sector_index_allocator = SectorIndexAllocator::new(
+        registry_directory.path(),
+        [first_directory.path(), second_directory.path()],
+        false,
+    )
+    .unwrap();
+
+    let first_sector_index = sector_index_allocator
+        .allocate(&public_key, first_directory.path())
+        .unwrap();
+    let second_sector_index = sector_index_allocator
+        .allocate(&public_key, second_directory.path())
+        .unwrap();
+
+    assert!(first_sector_index.abs_diff(second_sector_index) >= SECTOR_INDEX_RANGE_SIZE);
+
+    // Same ranges persisted for both plots must be rejected on startup
+    for directory in [&first_directory, &second_directory] {
+        store_plot_info(directory.path(), public_key, first_sector_index);
+    }
+
+    assert!(matches!(
+        SectorIndexAllocator::new(
+            registry_directory.path(),
+            [first_directory.path(), second_directory.path()],
+            false,
+        ),
+        Err(SectorIndexAllocatorError::OverlappingRanges { .. })
+    ));
+}
+
+#[test]
+fn sector_index_allocator_remembers_unconfigured_plots() {
+    let public_key = PublicKey::from([1; PUBLIC_KEY_LENGTH]);
+    let registry_directory = TempDir::new().unwrap();
+    let first_directory = TempDir::new().unwrap();
+    let second_directory = TempDir::new().unwrap();
+
+    let first_sector_index =
+        SectorIndexAllocator::new(registry_directory.path(), [first_directory.path()], false)
+            .unwrap()
+            .allocate(&public_key, first_directory.path())
+            .unwrap();
+    store_plot_info(first_directory.path(), public_key, first_sector_index);
+
+    // First plot is not configured anymore, but its range is still reserved in the registry
+    let sector_index_allocator =
+        SectorIndexAllocator::new(registry_directory.path(), [second_directory.path()], false)
+            .unwrap();
+    let second_sector_index = sector_index_allocator
+        .allocate(&public_key, second_directory.path())
+        .unwrap();
+
+    assert!(first_sector_index.abs_diff(second_sector_index) >= SECTOR_INDEX_RANGE_SIZE);
+
+    // Plot that was configured with the same range as the one in registry must be rejected
+    store_plot_info(second_directory.path(), public_key, first_sector_index);
+    assert!(matches!(
+        SectorIndexAllocator::new(registry_directory.path(), [second_directory.path()], false,),
+        Err(SectorIndexAllocatorError::OverlappingRanges { .. })
+    ));
+}
+
+#[test]
+fn sector_index_allocator_reassigns_overlapping_ranges() {
+    let public_key = PublicKey::from([1; PUBLIC_KEY_LENGTH]);
+    let registry_directory = TempDir::new().unwrap();
+    let first_directory = TempDir::new().unwrap();
+    let second_directory = TempDir::new().unwrap();
+
+    for directory in [&first_directory, &second_directory] {
+        store_plot_info(directory.path(), public_key, 0);
+    }
+
+    let sector_index_allocator = SectorIndexAllocator::new(
+        registry_directory.path(),
+        [first_directory.path(), second_directory.path()],
+        true,
+    )
+    .unwrap();
+
+    assert_eq!(
+        sector_index_allocator.reassigned_first_sector_index(first_directory.path()),
+        None
+    );
+    let new_first_sector_index = sector_index_allocator
+        .reassigned_first_sector_index(second_directory.path())
+        .unwrap();
+    assert!(new_first_sector_index.abs_diff(0) >= SECTOR_INDEX_RANGE_SIZE);
+
+    // Once reassigned range is stored in plot info, there is nothing to reassign anymore
+    store_plot_info(second_directory.path(), public_key, new_first_sector_index);
+    let sector_index_allocator = SectorIndexAllocator::new(
+        registry_directory.path(),
+        [first_directory.path(), second_directory.path()],
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        sector_index_allocator.reassigned_first_sector_index(second_directory.path()),
+        None
+    );
+}
+
+fn store_plot_info(directory: &Path, public_key: PublicKey, first_sector_index: SectorIndex) {
+    SingleDiskPlotInfo::new(
+        SingleDiskPlotId::new(),
+        [0; 32],
+        public_key,
+        first_sector_index,
+        1,
+        1,
+    )
+    .store_to(directory)
+    .unwrap();
+}
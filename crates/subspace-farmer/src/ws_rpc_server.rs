@@ -1,3 +1,6 @@
+#[cfg(test)]
+mod tests;
+
 use crate::object_mappings::{ObjectMappingError, ObjectMappings};
 use jsonrpsee::core::error::Error;
 use jsonrpsee::proc_macros::rpc;
@@ -5,6 +8,7 @@ use parity_scale_codec::{Compact, CompactLen, Decode, Encode};
 use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use std::{fmt, iter};
 use subspace_archiving::archiver::{Segment, SegmentItem};
 use subspace_core_primitives::objects::GlobalObject;
 use subspace_core_primitives::{
@@ -147,6 +151,66 @@ pub struct Object {
     data: Vec<u8>,
 }
 
+impl Object {
+    /// Piece index where object is contained (at least its beginning, might not fit fully)
+    pub fn piece_index(&self) -> PieceIndex {
+        self.piece_index
+    }
+
+    /// Offset of the object
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// The data object contains
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Convert into the data object contains
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Data of the object that is retrieved piece by piece as it is read, see
+/// [`RpcServerImpl::read_object()`]
+pub struct ObjectData {
+    length: u32,
+    chunks: Box<dyn Iterator<Item = Result<Vec<u8>, Error>> + Send>,
+}
+
+impl fmt::Debug for ObjectData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectData")
+            .field("length", &self.length)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ObjectData {
+    /// Length of the object data in bytes
+    pub fn length(&self) -> u32 {
+        self.length
+    }
+
+    /// Consecutive chunks of object data, every chunk requires retrieval of one or more pieces,
+    /// iteration ends after the first error
+    pub fn into_chunks(self) -> Box<dyn Iterator<Item = Result<Vec<u8>, Error>> + Send> {
+        self.chunks
+    }
+
+    /// Read all object data at once
+    fn into_vec(self) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::with_capacity(self.length as usize);
+        for chunk in self.chunks {
+            data.extend_from_slice(&chunk?);
+        }
+
+        Ok(data)
+    }
+}
+
 #[rpc(server, client)]
 pub trait Rpc {
     /// Get single piece by its index
@@ -183,29 +247,92 @@ impl RpcServerImpl {
         }
     }
 
-    /// Assemble object that starts at `piece_index` at `offset` by reading necessary pieces from
-    /// plot and putting necessary bytes together.
-    fn assemble_object(
+    /// Find object by its ID and start reading it, only pieces necessary to know object length are
+    /// retrieved before returning, the rest are retrieved as returned data is read.
+    ///
+    /// Objects that cross segment boundary require the whole segments to be assembled, such objects
+    /// are read entirely before returning.
+    pub fn read_object(&self, object_id: Blake2b256Hash) -> Result<Option<ObjectData>, Error> {
+        let object_id_string = hex::encode(object_id);
+
+        let Some(global_object) = self.find_global_object(object_id, &object_id_string)? else {
+            return Ok(None);
+        };
+
+        self.object_data(
+            global_object.piece_index(),
+            global_object.offset(),
+            &object_id_string,
+        )
+        .map(Some)
+    }
+
+    /// Find object mapping by object ID
+    fn find_global_object(
+        &self,
+        object_id: Blake2b256Hash,
+        object_id_string: &str,
+    ) -> Result<Option<GlobalObject>, Error> {
+        let global_object_handle = || -> Result<Option<GlobalObject>, ObjectMappingError> {
+            for object_mappings in self.object_mappings.iter() {
+                let maybe_global_object = object_mappings.retrieve(&object_id)?;
+
+                if let Some(global_object) = maybe_global_object {
+                    return Ok(Some(global_object));
+                }
+            }
+
+            Ok(None)
+        };
+
+        let global_object = global_object_handle().map_err(|error| {
+            error!(
+                object_id = %object_id_string,
+                %error,
+                "Object mapping retrieving failed",
+            );
+
+            Error::Custom("Failed to find an object due to internal error".to_string())
+        })?;
+
+        if global_object.is_none() {
+            debug!(object_id = %object_id_string, "Object not found");
+        }
+
+        Ok(global_object)
+    }
+
+    /// Data of object that starts at `piece_index` at `offset`, which is read from necessary
+    /// pieces and put together.
+    fn object_data(
         &self,
         piece_index: PieceIndex,
         offset: u32,
         object_id: &str,
-    ) -> Result<Vec<u8>, Error> {
+    ) -> Result<ObjectData, Error> {
         // Try fast object assembling
-        if let Some(data) = self.assemble_object_fast(piece_index, offset)? {
-            return Ok(data);
+        if let Some(object_data) = self.object_data_fast(piece_index, offset)? {
+            return Ok(object_data);
         }
 
-        self.assemble_object_regular(piece_index, offset, object_id)
+        let data = self.assemble_object_regular(piece_index, offset, object_id)?;
+
+        Ok(ObjectData {
+            length: data.len() as u32,
+            chunks: Box::new(iter::once(Ok(data))),
+        })
     }
 
     /// Fast object assembling in case object doesn't cross piece (super fast) or segment (just
     /// fast) boundary, returns `Ok(None)` if fast retrieval possibility is not guaranteed.
-    fn assemble_object_fast(
+    ///
+    /// Only pieces needed to know object length are read before returning, the rest are read
+    /// lazily as data chunks are consumed.
+    fn object_data_fast(
         &self,
         piece_index: PieceIndex,
         offset: u32,
-    ) -> Result<Option<Vec<u8>>, Error> {
+    ) -> Result<Option<ObjectData>, Error> {
         // We care if the offset is before the last 2 bytes of a piece because if not we might be
         // able to do very fast object retrieval without assembling and processing the whole
         // segment. `-2` is because last 2 bytes might contain padding if a piece is the last piece
@@ -301,18 +428,40 @@ impl RpcServerImpl {
         let mut data =
             read_records_data[offset as usize + data_length_bytes_length as usize..].to_vec();
         drop(read_records_data);
+        // Trim the excess
+        data.truncate(data_length as usize);
+
+        let piece_getter = Arc::clone(&self.piece_getter);
+        let record_size = self.record_size as usize;
+        let mut bytes_left = data_length as usize - data.len();
 
         // Read more pieces until we have enough data
-        while data.len() <= data_length as usize {
-            let piece = self.read_and_decode_piece(next_piece_index)?;
-            next_piece_index += PieceIndex::ONE;
-            data.extend_from_slice(&piece[..self.record_size as usize]);
-        }
+        let remaining_chunks = iter::from_fn(move || {
+            if bytes_left == 0 {
+                return None;
+            }
 
-        // Trim the excess
-        data.truncate(data_length as usize);
+            let chunk_result =
+                read_and_decode_piece(piece_getter.as_ref(), next_piece_index).map(|piece| {
+                    next_piece_index += PieceIndex::ONE;
+                    // Trim the excess
+                    let chunk = piece[..record_size.min(bytes_left)].to_vec();
+                    bytes_left -= chunk.len();
+                    chunk
+                });
+
+            if chunk_result.is_err() {
+                // No point in reading further
+                bytes_left = 0;
+            }
+
+            Some(chunk_result)
+        });
 
-        Ok(Some(data))
+        Ok(Some(ObjectData {
+            length: data_length,
+            chunks: Box::new(iter::once(Ok(data)).chain(remaining_chunks)),
+        }))
     }
 
     /// Assemble object that can cross segment boundary, which requires assembling and iterating
@@ -435,15 +584,20 @@ impl RpcServerImpl {
 
     /// Read and decode the whole piece
     fn read_and_decode_piece(&self, piece_index: PieceIndex) -> Result<Piece, Error> {
-        let piece_getter = self.piece_getter.clone();
-        piece_getter
-            .get_piece(piece_index, piece_index.hash())
-            .ok_or_else(|| {
-                Error::Custom("Object mapping found, but reading piece failed".to_string())
-            })
+        read_and_decode_piece(self.piece_getter.as_ref(), piece_index)
     }
 }
 
+/// Read and decode the whole piece
+fn read_and_decode_piece(
+    piece_getter: &(dyn PieceGetter + Send + Sync),
+    piece_index: PieceIndex,
+) -> Result<Piece, Error> {
+    piece_getter
+        .get_piece(piece_index, piece_index.hash())
+        .ok_or_else(|| Error::Custom("Object mapping found, but reading piece failed".to_string()))
+}
+
 impl RpcServer for RpcServerImpl {
     fn get_piece(&self, piece_index: PieceIndex) -> Result<Option<HexPiece>, Error> {
         let piece_getter = self.piece_getter.clone();
@@ -456,43 +610,19 @@ impl RpcServer for RpcServerImpl {
 
     /// Find object by its ID
     fn find_object(&self, object_id: HexBlake2b256Hash) -> Result<Option<Object>, Error> {
-        let global_object_handle = || -> Result<Option<GlobalObject>, ObjectMappingError> {
-            for object_mappings in self.object_mappings.iter() {
-                let maybe_global_object = object_mappings.retrieve(&object_id.into())?;
-
-                if let Some(global_object) = maybe_global_object {
-                    return Ok(Some(global_object));
-                }
-            }
-
-            Ok(None)
-        };
-
         let object_id_string = hex::encode(object_id);
 
-        let global_object = global_object_handle().map_err(|error| {
-            error!(
-                object_id = %object_id_string,
-                %error,
-                "Object mapping retrieving failed",
-            );
-
-            Error::Custom("Failed to find an object due to internal error".to_string())
-        })?;
-
-        let global_object = match global_object {
-            Some(global_object) => global_object,
-            None => {
-                debug!(object_id = %object_id_string, "Object not found");
-
-                return Ok(None);
-            }
+        let Some(global_object) = self.find_global_object(object_id.into(), &object_id_string)?
+        else {
+            return Ok(None);
         };
 
         let piece_index = global_object.piece_index();
         let offset = global_object.offset();
 
-        let data = self.assemble_object(piece_index, offset, &object_id_string)?;
+        let data = self
+            .object_data(piece_index, offset, &object_id_string)?
+            .into_vec()?;
 
         Ok(Some(Object {
             piece_index,
@@ -0,0 +1,141 @@
+use crate::object_mappings::ObjectMappings;
+use crate::ws_rpc_server::{PieceGetter, RpcServer, RpcServerImpl};
+use parity_scale_codec::{Compact, Encode};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use subspace_core_primitives::objects::GlobalObject;
+use subspace_core_primitives::{
+    Piece, PieceIndex, PieceIndexHash, PublicKey, RawRecord, Record, RecordedHistorySegment,
+};
+use tempfile::TempDir;
+
+const OBJECT_ID: [u8; 32] = [1; 32];
+
+/// Piece getter that records which pieces were requested
+#[derive(Default)]
+struct TestPieceGetter {
+    pieces: Vec<Piece>,
+    requested_pieces: Mutex<Vec<PieceIndex>>,
+}
+
+impl PieceGetter for TestPieceGetter {
+    fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+        _piece_index_hash: PieceIndexHash,
+    ) -> Option<Piece> {
+        self.requested_pieces.lock().push(piece_index);
+        self.pieces.get(u64::from(piece_index) as usize).cloned()
+    }
+}
+
+/// Object that starts at the beginning of the first piece and spans `pieces` pieces
+fn object_pieces(pieces: usize) -> (u32, Vec<Piece>) {
+    let data_length = (Record::SIZE * (pieces - 1)) as u32;
+
+    let pieces = (0..pieces)
+        .map(|piece_position| {
+            let mut piece = Piece::default();
+            piece.record_mut().as_mut().fill(piece_position as u8 + 1);
+            if piece_position == 0 {
+                let encoded_length = Compact(data_length).encode();
+                piece.record_mut().as_mut()[..encoded_length.len()]
+                    .copy_from_slice(&encoded_length);
+            }
+            piece
+        })
+        .collect();
+
+    (data_length, pieces)
+}
+
+fn rpc_server(directory: &TempDir, piece_getter: Arc<TestPieceGetter>) -> RpcServerImpl {
+    let object_mappings =
+        ObjectMappings::open_or_create(directory.path(), PublicKey::from([0; 32]), u64::MAX)
+            .unwrap();
+    object_mappings
+        .store(&[(
+            OBJECT_ID,
+            GlobalObject::V0 {
+                piece_index: PieceIndex::default(),
+                offset: 0,
+            },
+        )])
+        .unwrap();
+
+    RpcServerImpl::new(
+        RawRecord::SIZE as u32,
+        RecordedHistorySegment::SIZE as u32,
+        piece_getter,
+        Arc::new(vec![object_mappings]),
+    )
+}
+
+#[test]
+fn object_pieces_are_read_lazily() {
+    let directory = TempDir::new().unwrap();
+    let (data_length, pieces) = object_pieces(3);
+    let piece_getter = Arc::new(TestPieceGetter {
+        pieces: pieces.clone(),
+        ..TestPieceGetter::default()
+    });
+    let rpc_server = rpc_server(&directory, Arc::clone(&piece_getter));
+
+    assert!(rpc_server.read_object([2; 32]).unwrap().is_none());
+    assert!(piece_getter.requested_pieces.lock().is_empty());
+
+    let object_data = rpc_server.read_object(OBJECT_ID).unwrap().unwrap();
+    assert_eq!(object_data.length(), data_length);
+    // Only the first piece is needed to know object length
+    assert_eq!(
+        *piece_getter.requested_pieces.lock(),
+        vec![PieceIndex::from(0)]
+    );
+
+    let mut chunks = object_data.into_chunks();
+    let first_chunk = chunks.next().unwrap().unwrap();
+    assert_eq!(first_chunk, &pieces[0].record().as_ref()[4..]);
+    assert_eq!(piece_getter.requested_pieces.lock().len(), 1);
+
+    let mut data = first_chunk;
+    for chunk in chunks {
+        data.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(data.len(), data_length as usize);
+    assert_eq!(
+        *piece_getter.requested_pieces.lock(),
+        vec![
+            PieceIndex::from(0),
+            PieceIndex::from(1),
+            PieceIndex::from(2)
+        ]
+    );
+
+    // The whole object is returned over RPC
+    let object = rpc_server.find_object(OBJECT_ID.into()).unwrap().unwrap();
+    assert_eq!(object.data(), data);
+}
+
+#[test]
+fn object_reading_stops_after_error() {
+    let directory = TempDir::new().unwrap();
+    let (data_length, mut pieces) = object_pieces(3);
+    // Second piece can't be retrieved
+    pieces.truncate(1);
+    let piece_getter = Arc::new(TestPieceGetter {
+        pieces,
+        ..TestPieceGetter::default()
+    });
+    let rpc_server = rpc_server(&directory, Arc::clone(&piece_getter));
+
+    let object_data = rpc_server.read_object(OBJECT_ID).unwrap().unwrap();
+    assert_eq!(object_data.length(), data_length);
+
+    let mut chunks = object_data.into_chunks();
+    assert!(chunks.next().unwrap().is_ok());
+    assert!(chunks.next().unwrap().is_err());
+    assert!(chunks.next().is_none());
+    assert_eq!(piece_getter.requested_pieces.lock().len(), 2);
+
+    assert!(rpc_server.find_object(OBJECT_ID.into()).is_err());
+}
@@ -15,23 +15,67 @@ use crate::reserved_peers::{
 use crate::PeerInfoProvider;
 use derive_more::From;
 use libp2p::allow_block_list::{Behaviour as AllowBlockListBehaviour, BlockedPeers};
+use libp2p::autonat::{Behaviour as Autonat, Config as AutonatConfig, Event as AutonatEvent};
 use libp2p::connection_limits::{Behaviour as ConnectionLimitsBehaviour, ConnectionLimits};
+use libp2p::dcutr::{Behaviour as Dcutr, Event as DcutrEvent};
 use libp2p::gossipsub::{
-    Behaviour as Gossipsub, Config as GossipsubConfig, Event as GossipsubEvent, MessageAuthenticity,
+    Behaviour as Gossipsub, Config as GossipsubConfig, Event as GossipsubEvent,
+    MessageAuthenticity, PeerScoreParams, PeerScoreThresholds, TopicScoreParams,
 };
 use libp2p::identify::{Behaviour as Identify, Config as IdentifyConfig, Event as IdentifyEvent};
 use libp2p::kad::{Kademlia, KademliaConfig, KademliaEvent};
+use libp2p::mdns::tokio::Behaviour as Mdns;
+use libp2p::mdns::Event as MdnsEvent;
 use libp2p::ping::{Behaviour as Ping, Event as PingEvent};
+use libp2p::relay::client::{Behaviour as RelayClient, Event as RelayClientEvent};
+use libp2p::relay::{
+    Behaviour as RelayServer, Config as RelayServerConfig, Event as RelayServerEvent,
+};
 use libp2p::swarm::behaviour::toggle::Toggle;
 use libp2p::swarm::NetworkBehaviour;
-use libp2p::PeerId;
+use libp2p::{identity, PeerId};
 use void::Void as VoidEvent;
 
 type BlockListBehaviour = AllowBlockListBehaviour<BlockedPeers>;
 
+/// Gossipsub ignores all messages from peers with score below this threshold, such peers are
+/// banned.
+pub(crate) const GOSSIPSUB_GRAYLIST_THRESHOLD: f64 = -80.0;
+
+/// Gossipsub peer score thresholds, peers are penalized below each of them.
+fn gossipsub_peer_score_thresholds() -> PeerScoreThresholds {
+    PeerScoreThresholds {
+        gossip_threshold: -10.0,
+        publish_threshold: -50.0,
+        graylist_threshold: GOSSIPSUB_GRAYLIST_THRESHOLD,
+        ..PeerScoreThresholds::default()
+    }
+}
+
+/// Score parameters of subscribed gossipsub topics.
+///
+/// Only messages rejected by topic validators lower peer score (quadratically), such that peer is
+/// graylisted after 3 of them in quick succession. Message delivery rate is not scored, since
+/// honest peers on quiet topics would be penalized otherwise.
+pub(crate) fn gossipsub_topic_score_params() -> TopicScoreParams {
+    TopicScoreParams {
+        topic_weight: 1.0,
+        time_in_mesh_weight: 0.0,
+        first_message_deliveries_weight: 0.0,
+        mesh_message_deliveries_weight: 0.0,
+        mesh_failure_penalty_weight: 0.0,
+        invalid_message_deliveries_weight: -10.0,
+        // Rejected messages are forgotten slowly (counter is decayed every second)
+        invalid_message_deliveries_decay: 0.9,
+        ..TopicScoreParams::default()
+    }
+}
+
 pub(crate) struct BehaviorConfig<RecordStore> {
     /// Identity keypair of a node used for authenticated connections.
     pub(crate) peer_id: PeerId,
+    /// Identity keypair of a node used for signing [`Gossipsub`] messages.
+    pub(crate) keypair: identity::Keypair,
     /// The configuration for the [`Identify`] behaviour.
     pub(crate) identify: IdentifyConfig,
     /// The configuration for the [`Kademlia`] behaviour.
@@ -50,6 +94,14 @@ pub(crate) struct BehaviorConfig<RecordStore> {
     pub(crate) peer_info_config: PeerInfoConfig,
     /// Provides peer-info for local peer.
     pub(crate) peer_info_provider: PeerInfoProvider,
+    /// The configuration for the [`Autonat`] behaviour.
+    pub(crate) autonat: AutonatConfig,
+    /// Relay client behaviour, created together with relay transport.
+    pub(crate) relay_client: RelayClient,
+    /// The configuration for the [`RelayServer`] behaviour, relay server is disabled if `None`.
+    pub(crate) relay_server: Option<RelayServerConfig>,
+    /// The [`Mdns`] behaviour for local peer discovery, disabled if `None`.
+    pub(crate) mdns: Option<Mdns>,
 }
 
 #[derive(NetworkBehaviour)]
@@ -65,6 +117,11 @@ pub(crate) struct Behavior<RecordStore> {
     pub(crate) block_list: BlockListBehaviour,
     pub(crate) reserved_peers: ReservedPeersBehaviour,
     pub(crate) peer_info: PeerInfoBehaviour,
+    pub(crate) autonat: Autonat,
+    pub(crate) relay_client: RelayClient,
+    pub(crate) relay_server: Toggle<RelayServer>,
+    pub(crate) dcutr: Dcutr,
+    pub(crate) mdns: Toggle<Mdns>,
 }
 
 impl<RecordStore> Behavior<RecordStore>
@@ -81,12 +138,17 @@ where
         let gossipsub = config
             .gossipsub
             .map(|gossip_config| {
-                Gossipsub::new(
-                    // TODO: Do we want message signing?
-                    MessageAuthenticity::Anonymous,
-                    gossip_config,
-                )
-                .expect("Correct configuration")
+                let mut gossipsub =
+                    Gossipsub::new(MessageAuthenticity::Signed(config.keypair), gossip_config)
+                        .expect("Correct configuration");
+                // Peers sending messages rejected by topic validators are penalized
+                gossipsub
+                    .with_peer_score(
+                        PeerScoreParams::default(),
+                        gossipsub_peer_score_thresholds(),
+                    )
+                    .expect("Peer score configuration is correct; qed");
+                gossipsub
             })
             .into();
 
@@ -104,6 +166,14 @@ where
             block_list: BlockListBehaviour::default(),
             reserved_peers: ReservedPeersBehaviour::new(config.reserved_peers),
             peer_info: PeerInfoBehaviour::new(config.peer_info_config, config.peer_info_provider),
+            autonat: Autonat::new(config.peer_id, config.autonat),
+            relay_client: config.relay_client,
+            relay_server: config
+                .relay_server
+                .map(|relay_server_config| RelayServer::new(config.peer_id, relay_server_config))
+                .into(),
+            dcutr: Dcutr::new(config.peer_id),
+            mdns: config.mdns.into(),
         }
     }
 }
@@ -119,4 +189,9 @@ pub(crate) enum Event {
     VoidEventStub(VoidEvent),
     ReservedPeers(ReservedPeersEvent),
     PeerInfo(PeerInfoEvent),
+    Autonat(AutonatEvent),
+    RelayClient(RelayClientEvent),
+    RelayServer(RelayServerEvent),
+    Dcutr(DcutrEvent),
+    Mdns(MdnsEvent),
 }
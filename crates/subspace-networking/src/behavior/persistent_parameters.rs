@@ -1,3 +1,4 @@
+use crate::peer_reputation::PeerReputation;
 use crate::utils::{convert_multiaddresses, CollectionBatcher, PeerAddress};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -57,6 +58,14 @@ pub trait NetworkingParametersRegistry: Send + Sync {
     /// Reset the batching process to the initial state.
     fn start_over_address_batching(&mut self) {}
 
+    /// Returns peer reputations persisted previously.
+    fn peer_reputations(&self) -> HashMap<PeerId, PeerReputation> {
+        HashMap::new()
+    }
+
+    /// Persists peer reputations, replacing previously persisted ones.
+    async fn set_peer_reputations(&mut self, _peer_reputations: HashMap<PeerId, PeerReputation>) {}
+
     /// Drive async work in the persistence provider
     async fn run(&mut self);
 
@@ -133,6 +142,8 @@ pub struct NetworkingParametersManager {
     cache_need_saving: bool,
     // LRU cache for the known peers and their addresses
     known_peers: LruCache<PeerId, LruCache<Multiaddr, FailureTime>>,
+    // Reputations of peers
+    peer_reputations: HashMap<PeerId, PeerReputation>,
     // Period between networking parameters saves.
     networking_parameters_save_delay: Pin<Box<Fuse<Sleep>>>,
     // Parity DB instance
@@ -162,12 +173,12 @@ impl NetworkingParametersManager {
         let column_id = 0u8;
         let object_id = b"global_networking_parameters_key";
 
-        // load known peers cache.
-        let cache = db
+        // load known peers cache and peer reputations.
+        let (cache, peer_reputations) = db
             .get(column_id, object_id)?
             .map(|data| {
                 let result = serde_json::from_slice::<NetworkingParameters>(&data)
-                    .map(|data| data.to_cache());
+                    .map(|data| (data.to_cache(), data.peer_reputations));
 
                 if result.is_ok() {
                     debug!("Networking parameters loaded from DB");
@@ -175,7 +186,7 @@ impl NetworkingParametersManager {
 
                 result
             })
-            .unwrap_or_else(|| Ok(LruCache::new(PEER_CACHE_SIZE)))?;
+            .unwrap_or_else(|| Ok((LruCache::new(PEER_CACHE_SIZE), HashMap::new())))?;
 
         Ok(Self {
             cache_need_saving: false,
@@ -183,6 +194,7 @@ impl NetworkingParametersManager {
             column_id,
             object_id,
             known_peers: cache,
+            peer_reputations,
             networking_parameters_save_delay: Self::default_delay(),
             bootstrap_addresses,
             collection_batcher: CollectionBatcher::new(
@@ -323,13 +335,31 @@ impl NetworkingParametersRegistry for NetworkingParametersManager {
         self.collection_batcher.reset();
     }
 
+    fn peer_reputations(&self) -> HashMap<PeerId, PeerReputation> {
+        self.peer_reputations.clone()
+    }
+
+    async fn set_peer_reputations(&mut self, peer_reputations: HashMap<PeerId, PeerReputation>) {
+        trace!(
+            peers = peer_reputations.len(),
+            "Update peer reputations in the networking parameters registry"
+        );
+
+        self.peer_reputations = peer_reputations;
+
+        self.cache_need_saving = true;
+    }
+
     async fn run(&mut self) {
         loop {
             (&mut self.networking_parameters_save_delay).await;
 
             if self.cache_need_saving {
                 // save accumulated cache to DB
-                let dto = NetworkingParameters::from_cache(self.clone_known_peers());
+                let dto = NetworkingParameters::from_cache(
+                    self.clone_known_peers(),
+                    self.peer_reputations.clone(),
+                );
                 let save_result = serde_json::to_vec(&dto)
                     .map_err(NetworkParametersPersistenceError::from)
                     .and_then(|data| {
@@ -355,6 +385,7 @@ impl NetworkingParametersRegistry for NetworkingParametersManager {
         Self {
             cache_need_saving: self.cache_need_saving,
             known_peers: self.clone_known_peers(),
+            peer_reputations: self.peer_reputations.clone(),
             networking_parameters_save_delay: Self::default_delay(),
             db: self.db.clone(),
             column_id: self.column_id,
@@ -370,10 +401,15 @@ impl NetworkingParametersRegistry for NetworkingParametersManager {
 #[derive(Default, Debug, Serialize, Deserialize)]
 struct NetworkingParameters {
     pub known_peers: HashMap<PeerId, HashMap<Multiaddr, FailureTime>>,
+    #[serde(default)]
+    pub peer_reputations: HashMap<PeerId, PeerReputation>,
 }
 
 impl NetworkingParameters {
-    fn from_cache(cache: LruCache<PeerId, LruCache<Multiaddr, FailureTime>>) -> Self {
+    fn from_cache(
+        cache: LruCache<PeerId, LruCache<Multiaddr, FailureTime>>,
+        peer_reputations: HashMap<PeerId, PeerReputation>,
+    ) -> Self {
         Self {
             known_peers: cache
                 .into_iter()
@@ -381,6 +417,7 @@ impl NetworkingParameters {
                     (peer_id, addresses.into_iter().collect::<HashMap<_, _>>())
                 })
                 .collect::<HashMap<_, _>>(),
+            peer_reputations,
         }
     }
 
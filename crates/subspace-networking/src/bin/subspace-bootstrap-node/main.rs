@@ -16,7 +16,7 @@ use std::sync::Arc;
 use subspace_networking::libp2p::multiaddr::Protocol;
 use subspace_networking::{
     peer_id, BootstrappedNetworkingParameters, Config, NetworkingParametersManager,
-    ParityDbProviderStorage, PeerInfoProvider, VoidProviderStorage,
+    ParityDbProviderStorage, PeerInfoProvider, RelayMode, VoidProviderStorage,
 };
 use tracing::{debug, info, Level};
 use tracing_subscriber::fmt::Subscriber;
@@ -180,6 +180,8 @@ async fn main() -> anyhow::Result<()> {
                 max_established_outgoing_connections: out_peers,
                 max_pending_incoming_connections: pending_in_peers,
                 max_pending_outgoing_connections: pending_out_peers,
+                // Bootstrap node is publicly reachable and helps peers behind NAT
+                relay_mode: RelayMode::Server,
                 ..Config::new(
                     protocol_version.to_string(),
                     keypair,
@@ -11,6 +11,7 @@ use crate::create::transport::build_transport;
 use crate::node::Node;
 use crate::node_runner::{NodeRunner, NodeRunnerConfig};
 use crate::peer_info::PeerInfoProvider;
+use crate::peer_reputation::PeerReputations;
 use crate::request_responses::RequestHandler;
 use crate::reserved_peers::Config as ReservedPeersConfig;
 use crate::shared::Shared;
@@ -18,6 +19,7 @@ use crate::utils::{convert_multiaddresses, ResizableSemaphore};
 use crate::PeerInfoConfig;
 use backoff::{ExponentialBackoff, SystemClock};
 use futures::channel::mpsc;
+use libp2p::autonat::Config as AutonatConfig;
 use libp2p::connection_limits::ConnectionLimits;
 use libp2p::gossipsub::{
     Config as GossipsubConfig, ConfigBuilder as GossipsubConfigBuilder,
@@ -29,11 +31,13 @@ use libp2p::kad::store::RecordStore;
 use libp2p::kad::{
     store, KademliaBucketInserts, KademliaConfig, KademliaStoreInserts, ProviderRecord, Record,
 };
+use libp2p::mdns::tokio::Behaviour as Mdns;
+use libp2p::mdns::Config as MdnsConfig;
 use libp2p::metrics::Metrics;
 use libp2p::multiaddr::Protocol;
 use libp2p::swarm::SwarmBuilder;
 use libp2p::yamux::Config as YamuxConfig;
-use libp2p::{identity, Multiaddr, PeerId, TransportError};
+use libp2p::{identity, relay, Multiaddr, PeerId, TransportError};
 use parking_lot::Mutex;
 use std::borrow::Cow;
 use std::iter::Empty;
@@ -75,9 +79,6 @@ const KADEMLIA_PROVIDER_REPUBLICATION_INTERVAL_IN_SECS: Option<Duration> =
 const YAMUX_MAX_STREAMS: usize = 256;
 const KADEMLIA_QUERY_TIMEOUT: Duration = Duration::from_secs(40);
 const SWARM_MAX_ESTABLISHED_CONNECTIONS_PER_PEER: Option<u32> = Some(2);
-// TODO: Consider moving this constant to configuration or removing `Toggle` wrapper when we find a
-// use-case for gossipsub protocol.
-const ENABLE_GOSSIP_PROTOCOL: bool = false;
 
 /// Base limit for number of concurrent tasks initiated towards Kademlia.
 ///
@@ -167,12 +168,19 @@ where
 pub enum RelayMode {
     /// No relay configured.
     NoRelay,
-    /// The node enables the relay behaviour.
+    /// The node enables the relay behaviour, such that nodes behind NAT can be reached through it.
+    ///
+    /// Should only be used on publicly reachable nodes.
     Server,
     /// Client relay configuration (enables relay client behavior).
     /// It uses a circuit relay server address as a parameter.
     ///
     /// Example: /memory/\<port>/p2p/\<server_peer_id>/p2p-circuit
+    ///
+    /// Once AutoNAT detects that node is not publicly reachable, the node reserves a slot on
+    /// provided relay server (and on other relay servers it is connected to), such that other
+    /// nodes can reach it through relay servers and upgrade to direct connection with hole
+    /// punching.
     Client(Multiaddr),
 }
 
@@ -181,6 +189,11 @@ impl RelayMode {
     pub fn is_relay_server(&self) -> bool {
         matches!(self, RelayMode::Server)
     }
+
+    /// Defines whether the node uses relay servers when it is not publicly reachable.
+    pub fn is_relay_client(&self) -> bool {
+        matches!(self, RelayMode::Client(_))
+    }
 }
 
 /// [`Node`] configuration.
@@ -198,7 +211,9 @@ pub struct Config<ProviderStorage> {
     pub identify: IdentifyConfig,
     /// The configuration for the Kademlia behaviour.
     pub kademlia: KademliaConfig,
-    /// The configuration for the Gossip behaviour.
+    /// The configuration for the Gossip behaviour, gossipsub is disabled if `None`.
+    ///
+    /// See [`default_gossipsub_config()`] for configuration that works with topic validators.
     pub gossipsub: Option<GossipsubConfig>,
     /// Externally provided implementation of the custom provider storage for Kademlia DHT,
     pub provider_storage: ProviderStorage,
@@ -234,6 +249,14 @@ pub struct Config<ProviderStorage> {
     pub protocol_version: String,
     /// Specifies a source for peer information.
     pub peer_info_provider: PeerInfoProvider,
+    /// The configuration for the AutoNAT behaviour, which detects whether node is publicly
+    /// reachable.
+    pub autonat: AutonatConfig,
+    /// Defines how the node uses circuit relays.
+    pub relay_mode: RelayMode,
+    /// The configuration for the mDNS behaviour, which discovers peers in local network, mDNS is
+    /// disabled if `None`.
+    pub mdns: Option<MdnsConfig>,
 }
 
 impl<ProviderStorage> fmt::Debug for Config<ProviderStorage> {
@@ -288,20 +311,6 @@ where
         let mut yamux_config = YamuxConfig::default();
         yamux_config.set_max_num_streams(YAMUX_MAX_STREAMS);
 
-        let gossipsub = ENABLE_GOSSIP_PROTOCOL.then(|| {
-            GossipsubConfigBuilder::default()
-                .protocol_id_prefix(GOSSIPSUB_PROTOCOL_PREFIX)
-                // TODO: Do we want message signing?
-                .validation_mode(ValidationMode::None)
-                // To content-address message, we can take the hash of message and use it as an ID.
-                .message_id_fn(|message: &GossipsubMessage| {
-                    MessageId::from(crypto::blake2b_256_hash(&message.data))
-                })
-                .max_transmit_size(2 * 1024 * 1024) // 2MB
-                .build()
-                .expect("Default config for gossipsub is always correct; qed")
-        });
-
         let protocol_version = format!("/subspace/{}", protocol_version);
         let identify = IdentifyConfig::new(protocol_version.clone(), keypair.public());
 
@@ -323,7 +332,8 @@ where
             timeout: Duration::from_secs(10),
             identify,
             kademlia,
-            gossipsub,
+            // Gossipsub is disabled by default, see `default_gossipsub_config()` for enabling it
+            gossipsub: None,
             provider_storage,
             allow_non_global_addresses_in_dht: false,
             initial_random_query_interval: Duration::from_secs(1),
@@ -341,6 +351,9 @@ where
             metrics: None,
             protocol_version,
             peer_info_provider,
+            autonat: AutonatConfig::default(),
+            relay_mode: RelayMode::NoRelay,
+            mdns: None,
         }
     }
 }
@@ -362,6 +375,24 @@ pub enum CreationError {
     ParityDbStorageError(#[from] parity_db::Error),
 }
 
+/// Default configuration of the Gossip behaviour, messages are only propagated further once they
+/// are accepted by topic validator (see [`Node::set_topic_validator()`]).
+pub fn default_gossipsub_config() -> GossipsubConfig {
+    GossipsubConfigBuilder::default()
+        .protocol_id_prefix(GOSSIPSUB_PROTOCOL_PREFIX)
+        // Messages are signed by their authors and must carry valid signatures
+        .validation_mode(ValidationMode::Strict)
+        // Messages are only propagated further once accepted by topic validator
+        .validate_messages()
+        // To content-address message, we can take the hash of message and use it as an ID.
+        .message_id_fn(|message: &GossipsubMessage| {
+            MessageId::from(crypto::blake2b_256_hash(&message.data))
+        })
+        .max_transmit_size(2 * 1024 * 1024) // 2MB
+        .build()
+        .expect("Default config for gossipsub is always correct; qed")
+}
+
 /// Converts public key from keypair to PeerId.
 /// It serves as the shared PeerId generating algorithm.
 pub fn peer_id(keypair: &identity::Keypair) -> PeerId {
@@ -400,9 +431,16 @@ where
         metrics,
         protocol_version,
         peer_info_provider,
+        mut autonat,
+        relay_mode,
+        mdns,
     } = config;
     let local_peer_id = peer_id(&keypair);
 
+    // Relay client is always present since it is also needed to dial nodes behind NAT through
+    // relay servers, listening on relay servers is controlled by node runner
+    let (relay_transport, relay_client) = relay::client::new(local_peer_id);
+
     let temporary_bans = Arc::new(Mutex::new(TemporaryBans::new(
         temporary_bans_cache_size,
         temporary_ban_backoff,
@@ -413,15 +451,24 @@ where
         Arc::clone(&temporary_bans),
         timeout,
         yamux_config,
+        relay_transport,
     )?;
 
     info!(
         %allow_non_global_addresses_in_dht,
         peer_id = %local_peer_id,
         %protocol_version,
+        ?relay_mode,
         "DSN instance configured."
     );
 
+    // Probing private addresses is only useful in local networks
+    autonat.only_global_ips = !allow_non_global_addresses_in_dht;
+
+    let mdns = mdns
+        .map(|mdns_config| Mdns::new(mdns_config, local_peer_id))
+        .transpose()?;
+
     let connection_limits = ConnectionLimits::default()
         .with_max_established_per_peer(SWARM_MAX_ESTABLISHED_CONNECTIONS_PER_PEER)
         .with_max_pending_incoming(Some(max_pending_incoming_connections))
@@ -433,6 +480,7 @@ where
 
     let behaviour = Behavior::new(BehaviorConfig {
         peer_id: local_peer_id,
+        keypair,
         identify,
         kademlia,
         gossipsub,
@@ -445,6 +493,10 @@ where
         },
         peer_info_config: PeerInfoConfig::new(PEER_INFO_PROTOCOL_NAME),
         peer_info_provider,
+        autonat,
+        relay_client,
+        relay_server: relay_mode.is_relay_server().then(relay::Config::default),
+        mdns,
     });
 
     let mut swarm = SwarmBuilder::with_tokio_executor(transport, behaviour, local_peer_id)
@@ -474,11 +526,14 @@ where
     let kademlia_tasks_semaphore = ResizableSemaphore::new(KADEMLIA_BASE_CONCURRENT_TASKS);
     let regular_tasks_semaphore = ResizableSemaphore::new(REGULAR_BASE_CONCURRENT_TASKS);
 
+    let peer_reputations = PeerReputations::new(networking_parameters_registry.peer_reputations());
+
     let shared = Arc::new(Shared::new(
         local_peer_id,
         command_sender,
         kademlia_tasks_semaphore,
         regular_tasks_semaphore,
+        peer_reputations,
     ));
     let shared_weak = Arc::downgrade(&shared);
 
@@ -495,6 +550,7 @@ where
         temporary_bans,
         metrics,
         protocol_version,
+        relay_mode,
     });
 
     Ok((node, node_runner))
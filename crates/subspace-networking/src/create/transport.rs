@@ -8,6 +8,7 @@ use libp2p::core::Transport;
 use libp2p::dns::TokioDnsConfig;
 use libp2p::quic::tokio::Transport as QuicTransport;
 use libp2p::quic::Config as QuicConfig;
+use libp2p::relay::client::Transport as RelayClientTransport;
 use libp2p::tcp::tokio::Transport as TokioTcpTransport;
 use libp2p::tcp::Config as GenTcpConfig;
 use libp2p::websocket::WsConfig;
@@ -28,6 +29,7 @@ pub(super) fn build_transport(
     temporary_bans: Arc<Mutex<TemporaryBans>>,
     timeout: Duration,
     yamux_config: YamuxConfig,
+    relay_transport: RelayClientTransport,
 ) -> Result<Boxed<(PeerId, StreamMuxerBox)>, CreationError> {
     let wrapped_tcp_ws = {
         let wrapped_tcp = CustomTransportWrapper::new(
@@ -49,7 +51,10 @@ pub(super) fn build_transport(
         let noise =
             noise::Config::new(keypair).expect("Signing libp2p-noise static DH keypair failed.");
 
+        // Connections through relay servers are authenticated and multiplexed end-to-end just
+        // like direct connections
         wrapped_tcp_ws
+            .or_transport(relay_transport)
             .upgrade(core::upgrade::Version::V1Lazy)
             .authenticate(noise)
             .multiplex(yamux_config)
@@ -23,6 +23,7 @@ mod create;
 mod node;
 mod node_runner;
 mod peer_info;
+mod peer_reputation;
 mod request_handlers;
 mod request_responses;
 mod reserved_peers;
@@ -41,10 +42,12 @@ pub use crate::peer_info::{
     Config as PeerInfoConfig, CuckooFilterDTO, CuckooFilterProvider, Notification,
     NotificationHandler, PeerInfo, PeerInfoProvider,
 };
+pub use crate::peer_reputation::{PeerReputation, ReputationChange};
+pub use crate::shared::TopicValidatorFn;
 pub use behavior::provider_storage::{
     MemoryProviderStorage, ParityDbProviderStorage, ProviderStorage, VoidProviderStorage,
 };
-pub use create::{create, peer_id, Config, CreationError, RelayMode};
+pub use create::{create, default_gossipsub_config, peer_id, Config, CreationError, RelayMode};
 pub use libp2p;
 pub use request_handlers::generic_request_handler::{GenericRequest, GenericRequestHandler};
 pub use request_handlers::object_mappings::{
@@ -1,16 +1,19 @@
+use crate::peer_reputation::ReputationChange;
 use crate::request_handlers::generic_request_handler::GenericRequest;
-use crate::request_responses;
-use crate::shared::{Command, CreatedSubscription, HandlerFn, Shared};
+use crate::request_responses::RequestFailure;
+use crate::shared::{Command, CreatedSubscription, HandlerFn, Shared, TopicValidatorFn};
 use crate::utils::ResizableSemaphorePermit;
 use bytes::Bytes;
 use event_listener_primitives::HandlerId;
 use futures::channel::mpsc::SendError;
 use futures::channel::{mpsc, oneshot};
 use futures::{SinkExt, Stream};
+use libp2p::autonat::NatStatus;
 use libp2p::core::multihash::Multihash;
 use libp2p::gossipsub::{Sha256Topic, SubscriptionError};
 use libp2p::kad::record::Key;
 use libp2p::kad::PeerRecord;
+use libp2p::request_response::OutboundFailure;
 use libp2p::{Multiaddr, PeerId};
 use parity_scale_codec::Decode;
 use std::pin::Pin;
@@ -19,7 +22,7 @@ use std::task::{Context, Poll};
 use std::time::Duration;
 use thiserror::Error;
 use tokio::time::sleep;
-use tracing::{error, trace};
+use tracing::{debug, error, trace};
 
 /// Topic subscription, will unsubscribe when last instance is dropped for a particular topic.
 #[derive(Debug)]
@@ -242,7 +245,7 @@ pub enum SendRequestError {
     NodeRunnerDropped,
     /// Underlying protocol returned an error, impossible to get response.
     #[error("Underlying protocol returned an error: {0}")]
-    ProtocolFailure(#[from] request_responses::RequestFailure),
+    ProtocolFailure(#[from] RequestFailure),
     /// Underlying protocol returned an incorrect format, impossible to get response.
     #[error("Received incorrectly formatted response: {0}")]
     IncorrectResponseFormat(#[from] parity_scale_codec::Error),
@@ -346,6 +349,26 @@ impl Node {
         })
     }
 
+    /// Set validator for messages received on some topic on the DSN, replacing previously set one.
+    ///
+    /// Only messages accepted by validator are delivered to subscribers and propagated further.
+    /// Rejected messages lower the score of the peer that sent them and peer is eventually banned
+    /// if it keeps doing that, ignored messages are dropped without penalty. Messages on topics
+    /// without validator are accepted.
+    ///
+    /// Validator is called from the networking event loop and must not block.
+    pub fn set_topic_validator(&self, topic: &Sha256Topic, validator: TopicValidatorFn) {
+        self.shared
+            .topic_validators
+            .lock()
+            .insert(topic.hash(), validator);
+    }
+
+    /// Remove validator for messages received on some topic on the DSN.
+    pub fn remove_topic_validator(&self, topic: &Sha256Topic) {
+        self.shared.topic_validators.lock().remove(&topic.hash());
+    }
+
     /// Subcribe a messgo to some topic on the DSN.
     pub async fn publish(&self, topic: Sha256Topic, message: Vec<u8>) -> Result<(), PublishError> {
         let _permit = self.shared.regular_tasks_semaphore.acquire().await;
@@ -384,9 +407,45 @@ impl Node {
 
         self.shared.command_sender.clone().send(command).await?;
 
-        let result = result_receiver.await??;
+        let result = match result_receiver.await? {
+            Ok(result) => Request::Response::decode(&mut result.as_slice()).map_err(Into::into),
+            Err(error) => Err(error.into()),
+        };
+
+        let maybe_reputation_change = match &result {
+            Ok(_response) => Some(ReputationChange::RequestSucceeded),
+            // Empty response or timeout
+            Err(SendRequestError::ProtocolFailure(
+                RequestFailure::Refused | RequestFailure::Network(OutboundFailure::Timeout),
+            )) => Some(ReputationChange::RequestFailed),
+            Err(SendRequestError::IncorrectResponseFormat(error)) => {
+                debug!(%peer_id, %error, "Peer sent response that can't be decoded");
+
+                Some(ReputationChange::ProtocolViolation)
+            }
+            // Not peer's fault
+            Err(
+                SendRequestError::SendCommand(_)
+                | SendRequestError::NodeRunnerDropped
+                | SendRequestError::ProtocolFailure(
+                    RequestFailure::NotConnected
+                    | RequestFailure::UnknownProtocol
+                    | RequestFailure::Obsolete
+                    | RequestFailure::Network(
+                        OutboundFailure::DialFailure
+                        | OutboundFailure::ConnectionClosed
+                        | OutboundFailure::UnsupportedProtocols,
+                    ),
+                ),
+            ) => None,
+        };
+        if let Some(reputation_change) = maybe_reputation_change {
+            if let Err(error) = self.report_peer(peer_id, reputation_change).await {
+                debug!(%peer_id, %error, "Failed to report peer");
+            }
+        }
 
-        Request::Response::decode(&mut result.as_slice()).map_err(Into::into)
+        result
     }
 
     /// Get closest peers by multihash key using Kademlia DHT.
@@ -524,6 +583,39 @@ impl Node {
             .await
     }
 
+    /// Current reputation of the peer, peers without known interactions have zero reputation.
+    pub fn peer_reputation(&self, peer_id: &PeerId) -> i32 {
+        self.shared.peer_reputations.lock().reputation(peer_id)
+    }
+
+    /// Report peer behaviour, which affects its reputation.
+    ///
+    /// Peers with reputation that is too low are temporarily banned, ban is extended with
+    /// exponential backoff while peer keeps misbehaving.
+    pub async fn report_peer(
+        &self,
+        peer_id: PeerId,
+        reputation_change: ReputationChange,
+    ) -> Result<(), SendError> {
+        trace!(%peer_id, ?reputation_change, "Reporting peer");
+
+        let should_ban = self
+            .shared
+            .peer_reputations
+            .lock()
+            .apply(peer_id, reputation_change);
+
+        if should_ban {
+            self.shared
+                .command_sender
+                .clone()
+                .send(Command::TemporarilyBanPeer { peer_id })
+                .await?;
+        }
+
+        Ok(())
+    }
+
     /// Dial multiaddress.
     /// It could be used to test libp2p transports bypassing protocol checks for bootstrap
     /// or listen-on addresses.
@@ -546,6 +638,11 @@ impl Node {
         self.shared.external_addresses.lock().clone()
     }
 
+    /// Node's reachability from outside as detected by AutoNAT.
+    pub fn nat_status(&self) -> NatStatus {
+        self.shared.nat_status.lock().clone()
+    }
+
     /// Callback is called when node starts listening on new address.
     pub fn on_new_listener(&self, callback: HandlerFn<Multiaddr>) -> HandlerId {
         self.shared.handlers.new_listener.add(callback)
@@ -561,4 +658,9 @@ impl Node {
             .num_established_peer_connections_change
             .add(callback)
     }
+
+    /// Callback is called when node's reachability detected by AutoNAT changes.
+    pub fn on_nat_status_change(&self, callback: HandlerFn<NatStatus>) -> HandlerId {
+        self.shared.handlers.nat_status_change.add(callback)
+    }
 }
@@ -1,8 +1,13 @@
+#[cfg(test)]
+mod tests;
+
 use crate::behavior::persistent_parameters::NetworkingParametersRegistry;
-use crate::behavior::{provider_storage, Behavior, Event};
+use crate::behavior::{
+    gossipsub_topic_score_params, provider_storage, Behavior, Event, GOSSIPSUB_GRAYLIST_THRESHOLD,
+};
 use crate::create::temporary_bans::TemporaryBans;
 use crate::create::{
-    ProviderOnlyRecordStore, KADEMLIA_CONCURRENT_TASKS_BOOST_PER_PEER,
+    ProviderOnlyRecordStore, RelayMode, KADEMLIA_CONCURRENT_TASKS_BOOST_PER_PEER,
     REGULAR_CONCURRENT_TASKS_BOOST_PER_PEER,
 };
 use crate::request_responses::{Event as RequestResponseEvent, IfDisconnected};
@@ -12,8 +17,10 @@ use bytes::Bytes;
 use futures::channel::mpsc;
 use futures::future::Fuse;
 use futures::{FutureExt, StreamExt};
+use libp2p::autonat::{Event as AutonatEvent, NatStatus};
+use libp2p::core::transport::ListenerId;
 use libp2p::core::ConnectedPoint;
-use libp2p::gossipsub::{Event as GossipsubEvent, TopicHash};
+use libp2p::gossipsub::{Event as GossipsubEvent, MessageAcceptance, MessageId, TopicHash};
 use libp2p::identify::Event as IdentifyEvent;
 use libp2p::kad::store::RecordStore;
 use libp2p::kad::{
@@ -21,9 +28,11 @@ use libp2p::kad::{
     GetRecordOk, InboundRequest, Kademlia, KademliaEvent, PeerRecord, ProgressStep, ProviderRecord,
     PutRecordOk, QueryId, QueryResult, Quorum, Record,
 };
+use libp2p::mdns::Event as MdnsEvent;
 use libp2p::metrics::{Metrics, Recorder};
+use libp2p::multiaddr::Protocol;
 use libp2p::swarm::dial_opts::DialOpts;
-use libp2p::swarm::{DialError, SwarmEvent};
+use libp2p::swarm::{AddressScore, DialError, SwarmEvent};
 use libp2p::{futures, Multiaddr, PeerId, Swarm, TransportError};
 use nohash_hasher::IntMap;
 use parking_lot::Mutex;
@@ -47,6 +56,12 @@ const CONCURRENT_TASKS_BOOST_PEERS_THRESHOLD: NonZeroUsize =
 /// Defines an expiration interval for item providers in Kademlia network.
 pub const KADEMLIA_PROVIDER_TTL_IN_SECS: Option<Duration> = Some(Duration::from_secs(86400)); /* 1 day */
 
+/// How many relay reservations node behind NAT should maintain at the same time.
+const MAX_RELAY_RESERVATIONS: usize = 2;
+
+/// Protocol advertised by peers that can act as relay servers.
+const RELAY_HOP_PROTOCOL: &str = "/libp2p/circuit/relay/0.2.0/hop";
+
 enum QueryResultSender {
     Value {
         sender: mpsc::UnboundedSender<PeerRecord>,
@@ -107,6 +122,14 @@ where
     established_connections: HashMap<(PeerId, ConnectedPoint), usize>,
     /// Defines protocol version for the network peers. Affects network partition.
     protocol_version: String,
+    /// Defines whether node acts as relay server, relay client or neither.
+    relay_mode: RelayMode,
+    /// Reachability of the node as detected by AutoNAT.
+    nat_status: NatStatus,
+    /// Listeners on relayed addresses (reservations) by relay server peer ID.
+    relay_listeners: HashMap<PeerId, ListenerId>,
+    /// Connected peers that support relay protocol and can be used for reservations.
+    relay_server_candidates: HashMap<PeerId, Multiaddr>,
 }
 
 // Helper struct for NodeRunner configuration (clippy requirement).
@@ -125,6 +148,7 @@ where
     pub(crate) temporary_bans: Arc<Mutex<TemporaryBans>>,
     pub(crate) metrics: Option<Metrics>,
     pub(crate) protocol_version: String,
+    pub(crate) relay_mode: RelayMode,
 }
 
 impl<ProviderStorage> NodeRunner<ProviderStorage>
@@ -144,6 +168,7 @@ where
             temporary_bans,
             metrics,
             protocol_version,
+            relay_mode,
         }: NodeRunnerConfig<ProviderStorage>,
     ) -> Self {
         Self {
@@ -166,6 +191,10 @@ where
             metrics,
             established_connections: HashMap::new(),
             protocol_version,
+            relay_mode,
+            nat_status: NatStatus::Unknown,
+            relay_listeners: HashMap::new(),
+            relay_server_candidates: HashMap::new(),
         }
     }
 
@@ -280,12 +309,7 @@ where
         }
 
         // Renew known external addresses.
-        let mut external_addresses = self
-            .swarm
-            .external_addresses()
-            .cloned()
-            .map(|item| item.addr)
-            .collect::<Vec<_>>();
+        let mut external_addresses = self.confirmed_external_addresses();
 
         if let Some(shared) = self.shared_weak.upgrade() {
             debug!(?external_addresses, "Renew external addresses.",);
@@ -293,6 +317,27 @@ where
             addresses.clear();
             addresses.append(&mut external_addresses);
         }
+
+        // Persist peer reputations if they changed.
+        let maybe_peer_reputations = self
+            .shared_weak
+            .upgrade()
+            .and_then(|shared| shared.peer_reputations.lock().take_changed());
+        if let Some(peer_reputations) = maybe_peer_reputations {
+            self.networking_parameters_registry
+                .set_peer_reputations(peer_reputations)
+                .await;
+        }
+    }
+
+    /// External addresses that were confirmed to be reachable (by AutoNAT or because they are
+    /// relayed), addresses merely observed by other peers are not advertised.
+    fn confirmed_external_addresses(&self) -> Vec<Multiaddr> {
+        self.swarm
+            .external_addresses()
+            .filter(|record| record.score == AddressScore::Infinite)
+            .map(|record| record.addr.clone())
+            .collect()
     }
 
     fn dial_peer(&mut self, peer_id: PeerId, addr: Multiaddr) {
@@ -339,7 +384,32 @@ where
             SwarmEvent::Behaviour(Event::RequestResponse(event)) => {
                 self.handle_request_response_event(event).await;
             }
+            SwarmEvent::Behaviour(Event::Autonat(event)) => {
+                self.handle_autonat_event(event);
+            }
+            SwarmEvent::Behaviour(Event::RelayClient(event)) => {
+                debug!(?event, "Relay client event.");
+            }
+            SwarmEvent::Behaviour(Event::RelayServer(event)) => {
+                debug!(?event, "Relay server event.");
+            }
+            SwarmEvent::Behaviour(Event::Dcutr(event)) => {
+                debug!(?event, "DCUtR event.");
+            }
+            SwarmEvent::Behaviour(Event::Mdns(event)) => {
+                self.handle_mdns_event(event).await;
+            }
             SwarmEvent::NewListenAddr { address, .. } => {
+                // Relayed addresses are reachable by definition, advertise them right away
+                if address
+                    .iter()
+                    .any(|protocol| protocol == Protocol::P2pCircuit)
+                {
+                    debug!(%address, "Listening on relayed address.");
+                    self.swarm
+                        .add_external_address(address.clone(), AddressScore::Infinite);
+                }
+
                 let shared = match self.shared_weak.upgrade() {
                     Some(shared) => shared,
                     None => {
@@ -349,6 +419,34 @@ where
                 shared.listeners.lock().push(address.clone());
                 shared.handlers.new_listener.call_simple(&address);
             }
+            SwarmEvent::ExpiredListenAddr { address, .. } => {
+                if address
+                    .iter()
+                    .any(|protocol| protocol == Protocol::P2pCircuit)
+                {
+                    debug!(%address, "Relayed address expired.");
+                    self.swarm.remove_external_address(&address);
+                }
+            }
+            SwarmEvent::ListenerClosed {
+                listener_id,
+                reason,
+                ..
+            } => {
+                let relay_server =
+                    self.relay_listeners
+                        .iter()
+                        .find_map(|(peer_id, relay_listener_id)| {
+                            (*relay_listener_id == listener_id).then_some(*peer_id)
+                        });
+
+                if let Some(relay_server) = relay_server {
+                    debug!(%relay_server, ?reason, "Relay reservation closed.");
+
+                    self.relay_listeners.remove(&relay_server);
+                    self.maybe_listen_on_relays();
+                }
+            }
             SwarmEvent::ConnectionEstablished {
                 peer_id,
                 endpoint,
@@ -365,9 +463,6 @@ where
                     }
                 };
 
-                // Remove temporary ban if there was any
-                self.temporary_bans.lock().remove(&peer_id);
-
                 let shared = match self.shared_weak.upgrade() {
                     Some(shared) => shared,
                     None => {
@@ -375,6 +470,16 @@ where
                     }
                 };
 
+                // Remove temporary ban if there was any, unless peer was banned due to low
+                // reputation
+                if !shared
+                    .peer_reputations
+                    .lock()
+                    .is_below_ban_threshold(&peer_id)
+                {
+                    self.temporary_bans.lock().remove(&peer_id);
+                }
+
                 let is_reserved_peer = self.reserved_peers.contains_key(&peer_id);
                 debug!(
                     %peer_id,
@@ -428,6 +533,10 @@ where
                 };
                 debug!("Connection closed with peer {peer_id} [{num_established} from peer]");
 
+                if num_established == 0 {
+                    self.relay_server_candidates.remove(&peer_id);
+                }
+
                 // TODO: Workaround for https://github.com/libp2p/rust-libp2p/discussions/3418
                 {
                     match self.established_connections.entry((peer_id, endpoint)) {
@@ -563,6 +672,27 @@ where
                 info.listen_addrs.truncate(30);
             }
 
+            if info
+                .protocols
+                .iter()
+                .any(|protocol| protocol == RELAY_HOP_PROTOCOL)
+            {
+                let maybe_relay_address = info.listen_addrs.iter().find(|address| {
+                    is_global_address_or_dns(address)
+                        && !address
+                            .iter()
+                            .any(|protocol| protocol == Protocol::P2pCircuit)
+                });
+
+                if let Some(relay_address) = maybe_relay_address {
+                    trace!(%peer_id, %relay_address, "Found relay server candidate.");
+
+                    self.relay_server_candidates
+                        .insert(peer_id, relay_address.clone());
+                    self.maybe_listen_on_relays();
+                }
+            }
+
             let kademlia = &mut self.swarm.behaviour_mut().kademlia;
             let full_kademlia_support = kademlia.protocol_names().iter().all(|local_protocol| {
                 info.protocols
@@ -615,6 +745,152 @@ where
         }
     }
 
+    async fn handle_mdns_event(&mut self, event: MdnsEvent) {
+        match event {
+            MdnsEvent::Discovered(peers) => {
+                self.handle_mdns_discovered(peers).await;
+            }
+            MdnsEvent::Expired(peers) => {
+                // Addresses might still be valid, they just weren't announced recently, so
+                // connections are left alone and regular mechanisms take care of them
+                trace!(?peers, "mDNS records expired.");
+            }
+        }
+    }
+
+    async fn handle_mdns_discovered<I>(&mut self, peers: I)
+    where
+        I: IntoIterator<Item = (PeerId, Multiaddr)>,
+    {
+        let connected_peers = self.swarm.connected_peers().cloned().collect::<Vec<_>>();
+
+        for (peer_id, address) in peers {
+            if !self.allow_non_global_addresses_in_dht && !is_global_address_or_dns(&address) {
+                trace!(
+                    %peer_id,
+                    %address,
+                    "Ignoring non-global address discovered with mDNS.",
+                );
+                continue;
+            }
+
+            debug!(%peer_id, %address, "Peer discovered with mDNS.");
+
+            self.swarm
+                .behaviour_mut()
+                .kademlia
+                .add_address(&peer_id, address.clone());
+            self.networking_parameters_registry
+                .add_known_peer(peer_id, vec![address.clone()])
+                .await;
+
+            if !connected_peers.contains(&peer_id) {
+                self.dial_peer(peer_id, address);
+            }
+        }
+    }
+
+    fn handle_autonat_event(&mut self, event: AutonatEvent) {
+        trace!(?event, "AutoNAT event.");
+
+        if let AutonatEvent::StatusChanged { old, new } = event {
+            debug!(?old, ?new, "NAT status changed.");
+
+            self.nat_status = new.clone();
+
+            match &self.nat_status {
+                NatStatus::Public(_address) => {
+                    // Node is reachable directly, relays are not needed anymore
+                    for (relay_server, listener_id) in self.relay_listeners.drain() {
+                        debug!(%relay_server, "Removing relay reservation.");
+
+                        self.swarm.remove_listener(listener_id);
+                    }
+                }
+                NatStatus::Private => {
+                    self.maybe_listen_on_relays();
+                }
+                NatStatus::Unknown => {
+                    // Nothing to do until status is known
+                }
+            }
+
+            if let Some(shared) = self.shared_weak.upgrade() {
+                *shared.nat_status.lock() = new.clone();
+                shared.handlers.nat_status_change.call_simple(&new);
+            }
+        }
+    }
+
+    /// Make reservations with known relay servers when node is not reachable from outside, such
+    /// that other peers can connect to it through relay and upgrade connection to direct one with
+    /// hole punching.
+    ///
+    /// Configured relay server is preferred, other connected relay servers are used after it.
+    fn maybe_listen_on_relays(&mut self) {
+        let RelayMode::Client(configured_relay_address) = &self.relay_mode else {
+            return;
+        };
+        if self.nat_status != NatStatus::Private {
+            return;
+        }
+
+        let configured_relay = configured_relay_address.iter().find_map(|protocol| {
+            if let Protocol::P2p(multihash) = protocol {
+                PeerId::from_multihash(multihash)
+                    .ok()
+                    .map(|peer_id| (peer_id, configured_relay_address.clone()))
+            } else {
+                None
+            }
+        });
+
+        let candidates = configured_relay
+            .into_iter()
+            .chain(
+                self.relay_server_candidates
+                    .iter()
+                    .map(|(peer_id, address)| {
+                        (
+                            *peer_id,
+                            address
+                                .clone()
+                                .with(Protocol::P2p((*peer_id).into()))
+                                .with(Protocol::P2pCircuit),
+                        )
+                    }),
+            )
+            .filter(|(peer_id, _address)| !self.relay_listeners.contains_key(peer_id))
+            .collect::<Vec<_>>();
+
+        for (relay_server, relayed_address) in candidates {
+            if self.relay_listeners.len() >= MAX_RELAY_RESERVATIONS {
+                break;
+            }
+            if self.relay_listeners.contains_key(&relay_server) {
+                continue;
+            }
+
+            match self.swarm.listen_on(relayed_address.clone()) {
+                Ok(listener_id) => {
+                    debug!(%relay_server, %relayed_address, "Listening on relay.");
+
+                    self.relay_listeners.insert(relay_server, listener_id);
+                }
+                Err(error) => {
+                    debug!(
+                        %relay_server,
+                        %relayed_address,
+                        %error,
+                        "Failed to listen on relay."
+                    );
+
+                    self.relay_server_candidates.remove(&relay_server);
+                }
+            }
+        }
+    }
+
     async fn handle_kademlia_event(&mut self, event: KademliaEvent) {
         trace!("Kademlia event: {:?}", event);
 
@@ -844,18 +1120,105 @@ where
     }
 
     async fn handle_gossipsub_event(&mut self, event: GossipsubEvent) {
-        if let GossipsubEvent::Message { message, .. } = event {
-            if let Some(senders) = self.topic_subscription_senders.get(&message.topic) {
-                let bytes = Bytes::from(message.data);
+        if let GossipsubEvent::Message {
+            propagation_source,
+            message_id,
+            message,
+        } = event
+        {
+            let maybe_topic_validator = self
+                .shared_weak
+                .upgrade()
+                .and_then(|shared| shared.topic_validators.lock().get(&message.topic).cloned());
+            let acceptance = match maybe_topic_validator {
+                Some(topic_validator) => topic_validator(&propagation_source, &message.data),
+                None => MessageAcceptance::Accept,
+            };
+
+            match acceptance {
+                MessageAcceptance::Accept => {
+                    self.report_gossipsub_validation_result(
+                        &message_id,
+                        &propagation_source,
+                        MessageAcceptance::Accept,
+                    );
+
+                    if let Some(senders) = self.topic_subscription_senders.get(&message.topic) {
+                        let bytes = Bytes::from(message.data);
+
+                        for sender in senders.values() {
+                            // Doesn't matter if receiver is still listening for messages or not.
+                            let _ = sender.unbounded_send(bytes.clone());
+                        }
+                    }
+                }
+                MessageAcceptance::Reject => {
+                    self.report_gossipsub_validation_result(
+                        &message_id,
+                        &propagation_source,
+                        MessageAcceptance::Reject,
+                    );
+
+                    let peer_score = self
+                        .swarm
+                        .behaviour()
+                        .gossipsub
+                        .as_ref()
+                        .and_then(|gossipsub| gossipsub.peer_score(&propagation_source));
+                    debug!(
+                        %propagation_source,
+                        topic = %message.topic,
+                        ?peer_score,
+                        "Gossipsub message rejected."
+                    );
+
+                    // Gossipsub ignores graylisted peers, so their score would not go any lower
+                    if peer_score.unwrap_or_default() < GOSSIPSUB_GRAYLIST_THRESHOLD {
+                        debug!(
+                            %propagation_source,
+                            ?peer_score,
+                            "Peer keeps sending invalid gossipsub messages. Peer was banned."
+                        );
+
+                        self.ban_peer(propagation_source).await;
+                    }
+                }
+                MessageAcceptance::Ignore => {
+                    self.report_gossipsub_validation_result(
+                        &message_id,
+                        &propagation_source,
+                        MessageAcceptance::Ignore,
+                    );
 
-                for sender in senders.values() {
-                    // Doesn't matter if receiver is still listening for messages or not.
-                    let _ = sender.unbounded_send(bytes.clone());
+                    trace!(
+                        %propagation_source,
+                        topic = %message.topic,
+                        "Gossipsub message ignored."
+                    );
                 }
             }
         }
     }
 
+    /// Reports validation result to gossipsub, such that accepted messages are propagated further
+    /// and peers that sent rejected messages are penalized.
+    fn report_gossipsub_validation_result(
+        &mut self,
+        message_id: &MessageId,
+        propagation_source: &PeerId,
+        acceptance: MessageAcceptance,
+    ) {
+        if let Some(gossipsub) = self.swarm.behaviour_mut().gossipsub.as_mut() {
+            if let Err(error) = gossipsub.report_message_validation_result(
+                message_id,
+                propagation_source,
+                acceptance,
+            ) {
+                debug!(%error, %message_id, "Failed to report message validation result.");
+            }
+        }
+    }
+
     async fn handle_request_response_event(&mut self, event: RequestResponseEvent) {
         // No actions on statistics events.
         trace!("Request response event: {:?}", event);
@@ -950,6 +1313,19 @@ where
                         if let Some(gossipsub) = self.swarm.behaviour_mut().gossipsub.as_mut() {
                             match gossipsub.subscribe(&topic) {
                                 Ok(true) => {
+                                    // Invalid messages only affect peer score on topics with
+                                    // score parameters
+                                    if let Err(error) = gossipsub.set_topic_params(
+                                        topic.clone(),
+                                        gossipsub_topic_score_params(),
+                                    ) {
+                                        warn!(
+                                            %topic,
+                                            %error,
+                                            "Failed to set topic score parameters"
+                                        );
+                                    }
+
                                     if result_sender.send(Ok(created_subscription)).is_ok() {
                                         entry
                                             .insert(IntMap::from_iter([(subscription_id, sender)]));
@@ -1056,11 +1432,7 @@ where
             }
             Command::StartLocalAnnouncing { key, result_sender } => {
                 let local_peer_id = *self.swarm.local_peer_id();
-                let addresses = self
-                    .swarm
-                    .external_addresses()
-                    .map(|rec| rec.addr.clone())
-                    .collect::<Vec<_>>();
+                let addresses = self.confirmed_external_addresses();
 
                 let provider_record = ProviderRecord {
                     provider: local_peer_id,
@@ -1115,6 +1487,9 @@ where
             Command::BanPeer { peer_id } => {
                 self.ban_peer(peer_id).await;
             }
+            Command::TemporarilyBanPeer { peer_id } => {
+                self.temporarily_ban_peer(peer_id);
+            }
             Command::Dial { address } => {
                 let _ = self.swarm.dial(address);
             }
@@ -1134,6 +1509,13 @@ where
             .await;
     }
 
+    fn temporarily_ban_peer(&mut self, peer_id: PeerId) {
+        debug!(%peer_id, "Temporarily banning peer due to low reputation");
+
+        self.temporary_bans.lock().create_or_extend(&peer_id);
+        let _ = self.swarm.disconnect_peer_id(peer_id);
+    }
+
     fn register_event_metrics<E: Debug>(&mut self, swarm_event: &SwarmEvent<Event, E>) {
         if let Some(ref mut metrics) = self.metrics {
             match swarm_event {
@@ -1149,6 +1531,12 @@ where
                 SwarmEvent::Behaviour(Event::Gossipsub(gossipsub_event)) => {
                     metrics.record(gossipsub_event);
                 }
+                SwarmEvent::Behaviour(Event::RelayServer(relay_server_event)) => {
+                    metrics.record(relay_server_event);
+                }
+                SwarmEvent::Behaviour(Event::Dcutr(dcutr_event)) => {
+                    metrics.record(dcutr_event);
+                }
                 // TODO: implement in the upstream repository
                 // SwarmEvent::Behaviour(Event::RequestResponse(request_response_event)) => {
                 //     self.metrics.record(request_response_event);
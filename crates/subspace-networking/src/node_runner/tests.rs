@@ -0,0 +1,358 @@
+use crate::behavior::provider_storage;
+use crate::create::RelayMode;
+use crate::node_runner::{NodeRunner, MAX_RELAY_RESERVATIONS};
+use crate::{
+    default_gossipsub_config, BootstrappedNetworkingParameters, Config, Node, TopicSubscription,
+};
+use futures::channel::oneshot;
+use futures::StreamExt;
+use libp2p::autonat::{Event as AutonatEvent, NatStatus};
+use libp2p::gossipsub::{MessageAcceptance, Sha256Topic};
+use libp2p::mdns::Config as MdnsConfig;
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::AddressScore;
+use libp2p::{Multiaddr, PeerId};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+fn relayed_address(relay_server: PeerId) -> Multiaddr {
+    "/ip4/1.2.3.4/tcp/30333"
+        .parse::<Multiaddr>()
+        .unwrap()
+        .with(Protocol::P2p(relay_server.into()))
+        .with(Protocol::P2pCircuit)
+}
+
+#[tokio::test]
+async fn only_confirmed_external_addresses_are_advertised() {
+    let (_node, mut node_runner) = crate::create(Config::default()).unwrap();
+
+    let observed_address = "/ip4/1.2.3.4/tcp/30333".parse::<Multiaddr>().unwrap();
+    let confirmed_address = "/ip4/5.6.7.8/tcp/30333".parse::<Multiaddr>().unwrap();
+
+    node_runner
+        .swarm
+        .add_external_address(observed_address, AddressScore::Finite(1));
+    node_runner
+        .swarm
+        .add_external_address(confirmed_address.clone(), AddressScore::Infinite);
+
+    assert_eq!(
+        node_runner.confirmed_external_addresses(),
+        vec![confirmed_address]
+    );
+}
+
+#[tokio::test]
+async fn relay_reservations_are_opt_in() {
+    let (_node, mut node_runner) = crate::create(Config::default()).unwrap();
+
+    node_runner
+        .relay_server_candidates
+        .insert(PeerId::random(), "/ip4/1.2.3.4/tcp/30333".parse().unwrap());
+    node_runner.handle_autonat_event(AutonatEvent::StatusChanged {
+        old: NatStatus::Unknown,
+        new: NatStatus::Private,
+    });
+
+    assert!(node_runner.relay_listeners.is_empty());
+}
+
+#[tokio::test]
+async fn relay_reservations_follow_nat_status() {
+    let configured_relay_server = PeerId::random();
+    let (_node, mut node_runner) = crate::create(Config {
+        relay_mode: RelayMode::Client(relayed_address(configured_relay_server)),
+        ..Config::default()
+    })
+    .unwrap();
+
+    // No reservations until node is known to be behind NAT
+    node_runner.maybe_listen_on_relays();
+    assert!(node_runner.relay_listeners.is_empty());
+
+    node_runner.handle_autonat_event(AutonatEvent::StatusChanged {
+        old: NatStatus::Unknown,
+        new: NatStatus::Private,
+    });
+    assert_eq!(node_runner.relay_listeners.len(), 1);
+    assert!(node_runner
+        .relay_listeners
+        .contains_key(&configured_relay_server));
+
+    // Discovered relay servers are used in addition to configured one, up to the limit
+    for _ in 0..MAX_RELAY_RESERVATIONS {
+        node_runner
+            .relay_server_candidates
+            .insert(PeerId::random(), "/ip4/1.2.3.4/tcp/30333".parse().unwrap());
+    }
+    node_runner.maybe_listen_on_relays();
+    assert_eq!(node_runner.relay_listeners.len(), MAX_RELAY_RESERVATIONS);
+    assert!(node_runner
+        .relay_listeners
+        .contains_key(&configured_relay_server));
+
+    // Reservations are removed once node is publicly reachable
+    node_runner.handle_autonat_event(AutonatEvent::StatusChanged {
+        old: NatStatus::Private,
+        new: NatStatus::Public("/ip4/1.2.3.4/tcp/30333".parse().unwrap()),
+    });
+    assert!(node_runner.relay_listeners.is_empty());
+}
+
+/// Addresses of the peer known to Kademlia
+fn kademlia_addresses<ProviderStorage>(
+    node_runner: &mut NodeRunner<ProviderStorage>,
+    peer_id: &PeerId,
+) -> Vec<Multiaddr>
+where
+    ProviderStorage: Send + Sync + provider_storage::ProviderStorage + 'static,
+{
+    node_runner
+        .swarm
+        .behaviour_mut()
+        .kademlia
+        .kbuckets()
+        .flat_map(|bucket| {
+            bucket
+                .iter()
+                .filter(|entry| entry.node.key.preimage() == peer_id)
+                .flat_map(|entry| entry.node.value.iter().cloned().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn mdns_is_opt_in() {
+    let (_node, node_runner) = crate::create(Config::default()).unwrap();
+    assert!(!node_runner.swarm.behaviour().mdns.is_enabled());
+
+    let (_node, node_runner) = crate::create(Config {
+        mdns: Some(MdnsConfig::default()),
+        ..Config::default()
+    })
+    .unwrap();
+    assert!(node_runner.swarm.behaviour().mdns.is_enabled());
+}
+
+#[tokio::test]
+async fn mdns_discovered_peers_are_added_and_dialed() {
+    let (_node, mut node_runner) = crate::create(Config::default()).unwrap();
+
+    let global_peer_id = PeerId::random();
+    let global_address = "/ip4/1.2.3.4/tcp/30333".parse::<Multiaddr>().unwrap();
+    let local_peer_id = PeerId::random();
+    let local_address = "/ip4/192.168.1.2/tcp/30333".parse::<Multiaddr>().unwrap();
+
+    node_runner
+        .handle_mdns_discovered([
+            (global_peer_id, global_address.clone()),
+            (local_peer_id, local_address.clone()),
+        ])
+        .await;
+
+    assert_eq!(
+        kademlia_addresses(&mut node_runner, &global_peer_id),
+        vec![global_address]
+    );
+    // Non-global addresses are ignored unless explicitly allowed
+    assert!(kademlia_addresses(&mut node_runner, &local_peer_id).is_empty());
+    assert_eq!(
+        node_runner
+            .swarm
+            .network_info()
+            .connection_counters()
+            .num_pending_outgoing(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn mdns_discovered_non_global_peers_are_added_when_allowed() {
+    let (_node, mut node_runner) = crate::create(Config {
+        allow_non_global_addresses_in_dht: true,
+        ..Config::default()
+    })
+    .unwrap();
+
+    let peer_id = PeerId::random();
+    let address = "/ip4/192.168.1.2/tcp/30333".parse::<Multiaddr>().unwrap();
+
+    node_runner
+        .handle_mdns_discovered([(peer_id, address.clone())])
+        .await;
+
+    assert_eq!(
+        kademlia_addresses(&mut node_runner, &peer_id),
+        vec![address]
+    );
+}
+
+/// Two connected nodes with gossipsub enabled, second node is subscribed to `topic` and rejects
+/// messages that start with `invalid`, returns once first node can publish messages to it.
+async fn gossipsub_nodes(topic: &Sha256Topic) -> (Node, Node, TopicSubscription) {
+    let config_1 = Config {
+        listen_on: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+        allow_non_global_addresses_in_dht: true,
+        gossipsub: Some(default_gossipsub_config()),
+        ..Config::default()
+    };
+    let (node_1, mut node_runner_1) = crate::create(config_1).unwrap();
+
+    let (node_1_address_sender, node_1_address_receiver) = oneshot::channel();
+    let on_new_listener_handler = node_1.on_new_listener(Arc::new({
+        let node_1_address_sender = Mutex::new(Some(node_1_address_sender));
+
+        move |address| {
+            if let Some(node_1_address_sender) = node_1_address_sender.lock().take() {
+                node_1_address_sender.send(address.clone()).unwrap();
+            }
+        }
+    }));
+
+    tokio::spawn(async move {
+        node_runner_1.run().await;
+    });
+
+    // Wait for first node to know its address
+    let node_1_addr = node_1_address_receiver.await.unwrap();
+    drop(on_new_listener_handler);
+
+    let config_2 = Config {
+        networking_parameters_registry: BootstrappedNetworkingParameters::new(vec![
+            node_1_addr.with(Protocol::P2p(node_1.id().into()))
+        ])
+        .boxed(),
+        listen_on: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+        allow_non_global_addresses_in_dht: true,
+        gossipsub: Some(default_gossipsub_config()),
+        ..Config::default()
+    };
+    let (node_2, mut node_runner_2) = crate::create(config_2).unwrap();
+
+    tokio::spawn(async move {
+        node_runner_2.run().await;
+    });
+
+    node_2.set_topic_validator(
+        topic,
+        Arc::new(|_peer_id: &PeerId, message: &[u8]| {
+            if message.starts_with(b"invalid") {
+                MessageAcceptance::Reject
+            } else {
+                MessageAcceptance::Accept
+            }
+        }),
+    );
+    let subscription = node_2.subscribe(topic.clone()).await.unwrap();
+
+    node_2
+        .wait_for_connected_peers(Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    // Publishing fails until first node learns about subscription of the second node
+    let mut published = false;
+    for _ in 0..50 {
+        if node_1
+            .publish(topic.clone(), b"valid".to_vec())
+            .await
+            .is_ok()
+        {
+            published = true;
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert!(
+        published,
+        "Second node must become known as topic subscriber"
+    );
+
+    (node_1, node_2, subscription)
+}
+
+#[tokio::test]
+async fn gossipsub_rejected_messages_are_not_delivered() {
+    let topic = Sha256Topic::new("test-topic");
+    let (node_1, _node_2, mut subscription) = gossipsub_nodes(&topic).await;
+
+    let message = timeout(Duration::from_secs(5), subscription.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(message.as_ref(), b"valid");
+
+    node_1
+        .publish(topic.clone(), b"invalid".to_vec())
+        .await
+        .unwrap();
+    node_1
+        .publish(topic.clone(), b"valid again".to_vec())
+        .await
+        .unwrap();
+
+    let message = timeout(Duration::from_secs(5), subscription.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(message.as_ref(), b"valid again");
+}
+
+#[tokio::test]
+async fn gossipsub_peer_sending_rejected_messages_is_banned() {
+    let topic = Sha256Topic::new("test-topic");
+    let (node_1, node_2, _subscription) = gossipsub_nodes(&topic).await;
+
+    let (disconnected_sender, disconnected_receiver) = oneshot::channel();
+    let _on_connections_change_handler =
+        node_2.on_num_established_peer_connections_change(Arc::new({
+            let disconnected_sender = Mutex::new(Some(disconnected_sender));
+
+            move |&num_established_peer_connections| {
+                if num_established_peer_connections == 0 {
+                    if let Some(disconnected_sender) = disconnected_sender.lock().take() {
+                        let _ = disconnected_sender.send(());
+                    }
+                }
+            }
+        }));
+
+    // Messages must be different, otherwise they are dropped as duplicates before validation
+    for index in 0..10 {
+        // Publishing fails once peer is banned and connection is closed
+        if node_1
+            .publish(topic.clone(), format!("invalid {index}").into_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    timeout(Duration::from_secs(5), disconnected_receiver)
+        .await
+        .expect("Peer sending rejected messages must be banned and disconnected")
+        .unwrap();
+
+    // Banned peer can't connect again
+    node_2
+        .dial(
+            node_1
+                .listeners()
+                .into_iter()
+                .next()
+                .unwrap()
+                .with(Protocol::P2p(node_1.id().into())),
+        )
+        .await
+        .unwrap();
+    assert!(node_2
+        .wait_for_connected_peers(Duration::from_secs(1))
+        .await
+        .is_err());
+}
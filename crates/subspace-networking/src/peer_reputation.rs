@@ -0,0 +1,146 @@
+//! Reputation of peers based on the outcomes of interactions with them.
+//!
+//! Reputation improves when peer responds to requests and worsens when it doesn't respond in time
+//! or closes the stream without response. Protocol violations worsen it much more and
+//! cryptographically invalid data results in temporary ban right away, regardless of prior good
+//! behaviour. Peers with reputation below the threshold are temporarily banned, bans are extended
+//! with exponential backoff while peer keeps misbehaving. Reputation decays towards neutral with
+//! time, so misbehaving peers are eventually forgiven and well-behaved peers need to keep behaving
+//! well to stay on top.
+
+use chrono::{DateTime, Utc};
+use libp2p::PeerId;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+/// Highest reputation peer can have.
+pub(crate) const MAX_REPUTATION: i32 = 1_000;
+/// Lowest reputation peer can have.
+pub(crate) const MIN_REPUTATION: i32 = -10_000;
+/// Peers with reputation below this threshold are temporarily banned.
+pub(crate) const BAN_THRESHOLD: i32 = -1_000;
+/// Reputation halves every this period of time.
+pub(crate) const REPUTATION_HALF_LIFE: Duration = Duration::from_secs(3600);
+/// Number of peers reputation is tracked for.
+const REPUTATIONS_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(10_000).expect("Not zero; qed");
+
+/// Change of peer reputation caused by peer behaviour.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReputationChange {
+    /// Peer responded to the request.
+    RequestSucceeded,
+    /// Peer failed to respond in time or closed the stream without response.
+    RequestFailed,
+    /// Peer violated the protocol, for instance sent response that can't be decoded.
+    ProtocolViolation,
+    /// Peer sent data that failed verification, for instance piece that doesn't match segment
+    /// commitment.
+    InvalidData,
+}
+
+impl ReputationChange {
+    pub(crate) fn value(&self) -> i32 {
+        match self {
+            Self::RequestSucceeded => 10,
+            Self::RequestFailed => -50,
+            Self::ProtocolViolation => -500,
+            // Below ban threshold even for peer with the highest reputation
+            Self::InvalidData => BAN_THRESHOLD - MAX_REPUTATION - 1,
+        }
+    }
+}
+
+/// Reputation of the peer at the time of the last update.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct PeerReputation {
+    /// Reputation value, positive means peer behaved well.
+    pub value: i32,
+    /// Last time reputation was updated.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PeerReputation {
+    /// Reputation value with decay since the last update applied.
+    fn current_value(&self, now: DateTime<Utc>) -> i32 {
+        let elapsed = (now - self.updated_at).to_std().unwrap_or_default();
+        let decay = 0.5_f64.powf(elapsed.as_secs_f64() / REPUTATION_HALF_LIFE.as_secs_f64());
+
+        (f64::from(self.value) * decay) as i32
+    }
+}
+
+/// Reputations of peers, unknown peers have neutral (zero) reputation.
+#[derive(Debug)]
+pub(crate) struct PeerReputations {
+    reputations: LruCache<PeerId, PeerReputation>,
+    /// Reputations changed since they were last taken for persistence.
+    changed: bool,
+}
+
+impl PeerReputations {
+    pub(crate) fn new(reputations: HashMap<PeerId, PeerReputation>) -> Self {
+        let mut cache = LruCache::new(REPUTATIONS_CACHE_SIZE);
+
+        for (peer_id, reputation) in reputations {
+            cache.put(peer_id, reputation);
+        }
+
+        Self {
+            reputations: cache,
+            changed: false,
+        }
+    }
+
+    /// Current reputation of the peer.
+    pub(crate) fn reputation(&self, peer_id: &PeerId) -> i32 {
+        self.reputations
+            .peek(peer_id)
+            .map(|reputation| reputation.current_value(Utc::now()))
+            .unwrap_or_default()
+    }
+
+    /// Whether peer reputation is low enough for it to be banned.
+    pub(crate) fn is_below_ban_threshold(&self, peer_id: &PeerId) -> bool {
+        self.reputation(peer_id) < BAN_THRESHOLD
+    }
+
+    /// Apply reputation change to the peer.
+    ///
+    /// Returns `true` if peer misbehaved while its reputation is below the ban threshold and
+    /// should be (temporarily) banned.
+    pub(crate) fn apply(&mut self, peer_id: PeerId, change: ReputationChange) -> bool {
+        let now = Utc::now();
+        let value =
+            (self.reputation(&peer_id) + change.value()).clamp(MIN_REPUTATION, MAX_REPUTATION);
+
+        self.reputations.put(
+            peer_id,
+            PeerReputation {
+                value,
+                updated_at: now,
+            },
+        );
+        self.changed = true;
+
+        change.value() < 0 && value < BAN_THRESHOLD
+    }
+
+    /// All reputations if any of them changed since the last call.
+    pub(crate) fn take_changed(&mut self) -> Option<HashMap<PeerId, PeerReputation>> {
+        if !self.changed {
+            return None;
+        }
+
+        self.changed = false;
+
+        Some(
+            self.reputations
+                .iter()
+                .map(|(peer_id, reputation)| (*peer_id, *reputation))
+                .collect(),
+        )
+    }
+}
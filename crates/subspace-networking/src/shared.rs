@@ -1,17 +1,22 @@
 //! Data structures shared between node and node runner, facilitating exchange and creation of
 //! queries, subscriptions, various events and shared information.
 
+use crate::peer_reputation::PeerReputations;
 use crate::request_responses::RequestFailure;
 use crate::utils::{ResizableSemaphore, ResizableSemaphorePermit};
 use bytes::Bytes;
 use event_listener_primitives::Bag;
 use futures::channel::{mpsc, oneshot};
+use libp2p::autonat::NatStatus;
 use libp2p::core::multihash::Multihash;
-use libp2p::gossipsub::{PublishError, Sha256Topic, SubscriptionError};
+use libp2p::gossipsub::{
+    MessageAcceptance, PublishError, Sha256Topic, SubscriptionError, TopicHash,
+};
 use libp2p::kad::record::Key;
 use libp2p::kad::PeerRecord;
 use libp2p::{Multiaddr, PeerId};
 use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
@@ -79,6 +84,9 @@ pub(crate) enum Command {
     BanPeer {
         peer_id: PeerId,
     },
+    TemporarilyBanPeer {
+        peer_id: PeerId,
+    },
     Dial {
         address: Multiaddr,
     },
@@ -87,10 +95,16 @@ pub(crate) enum Command {
 pub(crate) type HandlerFn<A> = Arc<dyn Fn(&A) + Send + Sync + 'static>;
 type Handler<A> = Bag<HandlerFn<A>, A>;
 
+/// Validator of gossipsub messages received on a topic, is called with the peer that propagated
+/// the message and message contents.
+pub type TopicValidatorFn =
+    Arc<dyn Fn(&PeerId, &[u8]) -> MessageAcceptance + Send + Sync + 'static>;
+
 #[derive(Default, Debug)]
 pub(crate) struct Handlers {
     pub(crate) new_listener: Handler<Multiaddr>,
     pub(crate) num_established_peer_connections_change: Handler<usize>,
+    pub(crate) nat_status_change: Handler<NatStatus>,
 }
 
 #[derive(Debug)]
@@ -100,6 +114,13 @@ pub(crate) struct Shared {
     /// Addresses on which node is listening for incoming requests.
     pub(crate) listeners: Mutex<Vec<Multiaddr>>,
     pub(crate) external_addresses: Mutex<Vec<Multiaddr>>,
+    /// Reachability of the node as detected by AutoNAT.
+    pub(crate) nat_status: Mutex<NatStatus>,
+    /// Validators of gossipsub messages by topic, messages on topics without validator are
+    /// accepted.
+    pub(crate) topic_validators: Mutex<HashMap<TopicHash, TopicValidatorFn>>,
+    /// Reputations of peers based on the outcomes of interactions with them.
+    pub(crate) peer_reputations: Mutex<PeerReputations>,
     pub(crate) num_established_peer_connections: Arc<AtomicUsize>,
     /// Sender end of the channel for sending commands to the swarm.
     pub(crate) command_sender: mpsc::Sender<Command>,
@@ -113,12 +134,16 @@ impl Shared {
         command_sender: mpsc::Sender<Command>,
         kademlia_tasks_semaphore: ResizableSemaphore,
         regular_tasks_semaphore: ResizableSemaphore,
+        peer_reputations: PeerReputations,
     ) -> Self {
         Self {
             handlers: Handlers::default(),
             id,
             listeners: Mutex::default(),
             external_addresses: Mutex::default(),
+            nat_status: Mutex::new(NatStatus::Unknown),
+            topic_validators: Mutex::default(),
+            peer_reputations: Mutex::new(peer_reputations),
             num_established_peer_connections: Arc::new(AtomicUsize::new(0)),
             command_sender,
             kademlia_tasks_semaphore,
@@ -5,11 +5,16 @@ use crate::{Node, PieceByHashRequest, PieceByHashResponse};
 use async_trait::async_trait;
 use backoff::future::retry;
 use backoff::ExponentialBackoff;
+use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use libp2p::PeerId;
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
 use std::error::Error;
+use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use subspace_core_primitives::{Piece, PieceIndex};
 use tracing::{debug, error, trace, warn};
 
@@ -17,6 +22,10 @@ use tracing::{debug, error, trace, warn};
 const GET_PIECE_INITIAL_INTERVAL: Duration = Duration::from_secs(3);
 /// Defines max duration between get_piece calls.
 const GET_PIECE_MAX_INTERVAL: Duration = Duration::from_secs(40);
+/// Number of providers latency statistics is kept for.
+const PROVIDER_STATS_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(1_000).expect("Not zero; qed");
+/// Weight of the latest request latency in average latency.
+const AVERAGE_LATENCY_WEIGHT: f64 = 0.2;
 
 /// Validates piece against using its commitment.
 #[async_trait]
@@ -56,10 +65,201 @@ impl PieceValidator for NoPieceValidator {
     }
 }
 
+/// Configuration of hedged piece requests.
+///
+/// Piece is requested from one provider at first, if it doesn't respond within hedge threshold,
+/// request to the next provider is started without cancelling the previous one and so on until
+/// concurrency limit is reached. The first valid piece wins and other requests are cancelled.
+#[derive(Debug, Copy, Clone)]
+pub struct PieceProviderConfig {
+    /// Maximum number of concurrent requests to different providers for the same piece.
+    pub max_concurrent_requests: NonZeroUsize,
+    /// Time to wait for response before starting request to another provider.
+    pub hedge_threshold: Duration,
+}
+
+impl Default for PieceProviderConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: NonZeroUsize::new(2).expect("Not zero; qed"),
+            hedge_threshold: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Statistics of piece requests to a provider.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ProviderStats {
+    /// Number of requests that returned valid piece.
+    pub successful_requests: u64,
+    /// Number of requests that failed or returned no piece or invalid piece.
+    pub failed_requests: u64,
+    /// Number of requests cancelled because piece was received from another provider first.
+    pub cancelled_requests: u64,
+    /// Latency of the last successful request.
+    pub last_latency: Option<Duration>,
+    /// Exponential moving average of successful request latency.
+    pub average_latency: Option<Duration>,
+}
+
+impl ProviderStats {
+    fn record_success(&mut self, latency: Duration) {
+        self.successful_requests += 1;
+        self.last_latency = Some(latency);
+        self.average_latency = Some(match self.average_latency {
+            Some(average_latency) => {
+                average_latency.mul_f64(1.0 - AVERAGE_LATENCY_WEIGHT)
+                    + latency.mul_f64(AVERAGE_LATENCY_WEIGHT)
+            }
+            None => latency,
+        });
+    }
+}
+
+/// Records request as cancelled unless outcome was recorded before drop.
+struct RequestStatsGuard<'a> {
+    provider_stats: &'a Mutex<LruCache<PeerId, ProviderStats>>,
+    provider_id: PeerId,
+    finished: bool,
+}
+
+impl Drop for RequestStatsGuard<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            update_provider_stats(self.provider_stats, self.provider_id, |provider_stats| {
+                provider_stats.cancelled_requests += 1;
+            });
+        }
+    }
+}
+
+fn update_provider_stats<F>(
+    provider_stats: &Mutex<LruCache<PeerId, ProviderStats>>,
+    provider_id: PeerId,
+    update: F,
+) where
+    F: FnOnce(&mut ProviderStats),
+{
+    let mut provider_stats = provider_stats.lock();
+
+    if let Some(provider_stats) = provider_stats.get_mut(&provider_id) {
+        update(provider_stats);
+    } else {
+        let mut new_provider_stats = ProviderStats::default();
+        update(&mut new_provider_stats);
+        provider_stats.put(provider_id, new_provider_stats);
+    }
+}
+
+/// State of hedged requests for a single piece, decides when and which provider to request next.
+#[derive(Debug)]
+pub(crate) struct HedgedRequests {
+    config: PieceProviderConfig,
+    /// Providers that were found, but not requested yet, along with their reputation, best
+    /// reputation first
+    providers: VecDeque<(i32, PeerId)>,
+    /// Providers with negative reputation are only tried after all others, best reputation first
+    deferred_providers: VecDeque<(i32, PeerId)>,
+    /// Whether all providers were found
+    providers_finished: bool,
+    requests_in_progress: usize,
+    /// Whether request to the next provider can be started even though there are requests in
+    /// progress
+    start_next_request: bool,
+}
+
+impl HedgedRequests {
+    pub(crate) fn new(config: PieceProviderConfig) -> Self {
+        Self {
+            config,
+            providers: VecDeque::new(),
+            deferred_providers: VecDeque::new(),
+            providers_finished: false,
+            requests_in_progress: 0,
+            start_next_request: true,
+        }
+    }
+
+    /// Add found provider, providers are requested in order of their reputation (providers with
+    /// the same reputation in order they were found), providers with negative reputation are
+    /// deferred.
+    pub(crate) fn add_provider(&mut self, provider_id: PeerId, reputation: i32) {
+        let providers = if reputation < 0 {
+            &mut self.deferred_providers
+        } else {
+            &mut self.providers
+        };
+
+        let position =
+            providers.partition_point(|(other_reputation, _)| *other_reputation >= reputation);
+        providers.insert(position, (reputation, provider_id));
+    }
+
+    /// All providers were found.
+    pub(crate) fn finish_providers(&mut self) {
+        self.providers_finished = true;
+    }
+
+    pub(crate) fn providers_finished(&self) -> bool {
+        self.providers_finished
+    }
+
+    /// Provider to request next if request can be started right now, caller is expected to restart
+    /// hedge threshold timer when provider is returned.
+    pub(crate) fn next_provider(&mut self) -> Option<PeerId> {
+        if self.providers_finished
+            && self.providers.is_empty()
+            && self.requests_in_progress == 0
+            && !self.deferred_providers.is_empty()
+        {
+            self.providers.append(&mut self.deferred_providers);
+            self.start_next_request = true;
+        }
+
+        if !self.start_next_request
+            || self.requests_in_progress >= self.config.max_concurrent_requests.get()
+        {
+            return None;
+        }
+
+        let (_reputation, provider_id) = self.providers.pop_front()?;
+        self.requests_in_progress += 1;
+        self.start_next_request = false;
+
+        Some(provider_id)
+    }
+
+    /// Request finished without valid piece, next provider can be requested without waiting for
+    /// hedge threshold.
+    pub(crate) fn request_failed(&mut self) {
+        self.requests_in_progress -= 1;
+        self.start_next_request = true;
+    }
+
+    pub(crate) fn is_waiting_for_hedge_threshold(&self) -> bool {
+        self.requests_in_progress > 0 && !self.start_next_request
+    }
+
+    pub(crate) fn hedge_threshold_reached(&mut self) {
+        self.start_next_request = true;
+    }
+
+    /// Whether all providers were requested unsuccessfully.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.providers_finished
+            && self.providers.is_empty()
+            && self.deferred_providers.is_empty()
+            && self.requests_in_progress == 0
+    }
+}
+
 /// Piece provider with cancellation and optional piece validator.
 pub struct PieceProvider<PV> {
     node: Node,
     piece_validator: Option<PV>,
+    config: PieceProviderConfig,
+    provider_stats: Mutex<LruCache<PeerId, ProviderStats>>,
 }
 
 impl<PV> PieceProvider<PV>
@@ -68,56 +268,158 @@ where
 {
     /// Creates new piece provider.
     pub fn new(node: Node, piece_validator: Option<PV>) -> Self {
+        Self::with_config(node, piece_validator, PieceProviderConfig::default())
+    }
+
+    /// Creates new piece provider with custom configuration of hedged requests.
+    pub fn with_config(
+        node: Node,
+        piece_validator: Option<PV>,
+        config: PieceProviderConfig,
+    ) -> Self {
         Self {
             node,
             piece_validator,
+            config,
+            provider_stats: Mutex::new(LruCache::new(PROVIDER_STATS_CACHE_SIZE)),
         }
     }
 
+    /// Statistics of piece requests to providers that were requested recently.
+    pub fn provider_stats(&self) -> Vec<(PeerId, ProviderStats)> {
+        self.provider_stats
+            .lock()
+            .iter()
+            .map(|(provider_id, provider_stats)| (*provider_id, *provider_stats))
+            .collect()
+    }
+
     // Get from piece cache (L2) or archival storage (L1)
     async fn get_piece_from_storage(&self, piece_index: PieceIndex) -> Option<Piece> {
         let piece_index_hash = piece_index.hash();
         let key = piece_index_hash.to_multihash();
 
-        let get_providers_result = self.node.get_providers(key).await;
+        let mut get_providers_stream = match self.node.get_providers(key).await {
+            Ok(get_providers_stream) => get_providers_stream,
+            Err(err) => {
+                warn!(%piece_index,?key, ?err, "get_providers returned an error");
+                return None;
+            }
+        };
+        let mut hedged_requests = HedgedRequests::new(self.config);
+        let mut requests = FuturesUnordered::new();
+        let hedge_delay = tokio::time::sleep(self.config.hedge_threshold);
+        tokio::pin!(hedge_delay);
+
+        loop {
+            if let Some(provider_id) = hedged_requests.next_provider() {
+                requests.push(self.get_piece_from_provider(provider_id, piece_index));
+                hedge_delay
+                    .as_mut()
+                    .reset(tokio::time::Instant::now() + self.config.hedge_threshold);
+                continue;
+            }
 
-        match get_providers_result {
-            Ok(mut get_providers_stream) => {
-                while let Some(provider_id) = get_providers_stream.next().await {
-                    trace!(%piece_index, %provider_id, "get_providers returned an item");
+            if hedged_requests.is_finished() {
+                return None;
+            }
 
-                    let request_result = self
-                        .node
-                        .send_generic_request(provider_id, PieceByHashRequest { piece_index_hash })
-                        .await;
+            tokio::select! {
+                maybe_piece = requests.next(), if !requests.is_empty() => {
+                    if let Some(Some(piece)) = maybe_piece {
+                        // Remaining requests are cancelled on drop
+                        return Some(piece);
+                    }
 
-                    match request_result {
-                        Ok(PieceByHashResponse { piece: Some(piece) }) => {
-                            trace!(%provider_id, %piece_index, ?key, "Piece request succeeded.");
+                    hedged_requests.request_failed();
+                }
+                maybe_provider_id = get_providers_stream.next(),
+                    if !hedged_requests.providers_finished() => {
+                    match maybe_provider_id {
+                        Some(provider_id) => {
+                            trace!(%piece_index, %provider_id, "get_providers returned an item");
 
-                            if let Some(validator) = &self.piece_validator {
-                                return validator
-                                    .validate_piece(provider_id, piece_index, piece)
-                                    .await;
-                            } else {
-                                return Some(piece);
+                            let reputation = self.node.peer_reputation(&provider_id);
+                            if reputation < 0 {
+                                trace!(
+                                    %piece_index,
+                                    %provider_id,
+                                    %reputation,
+                                    "Deferring provider"
+                                );
                             }
+
+                            hedged_requests.add_provider(provider_id, reputation);
                         }
-                        Ok(PieceByHashResponse { piece: None }) => {
-                            debug!(%provider_id, %piece_index, ?key, "Piece request returned empty piece.");
-                        }
-                        Err(error) => {
-                            debug!(%provider_id, %piece_index, ?key, ?error, "Piece request failed.");
+                        None => {
+                            hedged_requests.finish_providers();
                         }
                     }
                 }
-            }
-            Err(err) => {
-                warn!(%piece_index,?key, ?err, "get_providers returned an error");
+                _ = &mut hedge_delay, if hedged_requests.is_waiting_for_hedge_threshold() => {
+                    trace!(%piece_index, "Hedge threshold reached, requesting another provider");
+
+                    hedged_requests.hedge_threshold_reached();
+                }
             }
         }
+    }
+
+    async fn get_piece_from_provider(
+        &self,
+        provider_id: PeerId,
+        piece_index: PieceIndex,
+    ) -> Option<Piece> {
+        let piece_index_hash = piece_index.hash();
+        let key = piece_index_hash.to_multihash();
+
+        let mut stats_guard = RequestStatsGuard {
+            provider_stats: &self.provider_stats,
+            provider_id,
+            finished: false,
+        };
+        let started_at = Instant::now();
+
+        let request_result = self
+            .node
+            .send_generic_request(provider_id, PieceByHashRequest { piece_index_hash })
+            .await;
+        let latency = started_at.elapsed();
+
+        let maybe_piece = match request_result {
+            Ok(PieceByHashResponse { piece: Some(piece) }) => {
+                trace!(%provider_id, %piece_index, ?key, ?latency, "Piece request succeeded.");
+
+                if let Some(validator) = &self.piece_validator {
+                    validator
+                        .validate_piece(provider_id, piece_index, piece)
+                        .await
+                } else {
+                    Some(piece)
+                }
+            }
+            Ok(PieceByHashResponse { piece: None }) => {
+                debug!(%provider_id, %piece_index, ?key, "Piece request returned empty piece.");
+
+                None
+            }
+            Err(error) => {
+                debug!(%provider_id, %piece_index, ?key, ?error, "Piece request failed.");
+
+                None
+            }
+        };
+
+        stats_guard.finished = true;
+        update_provider_stats(&self.provider_stats, provider_id, |provider_stats| {
+            if maybe_piece.is_some() {
+                provider_stats.record_success(latency);
+            } else {
+                provider_stats.failed_requests += 1;
+            }
+        });
 
-        None
+        maybe_piece
     }
 
     /// Returns piece by its index. Uses retry policy for error handling.
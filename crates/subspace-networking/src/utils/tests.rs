@@ -1,5 +1,14 @@
+use super::piece_provider::{HedgedRequests, PieceProviderConfig};
 use super::{CollectionBatcher, ResizableSemaphore};
+use crate::peer_reputation::{
+    PeerReputation, PeerReputations, ReputationChange, BAN_THRESHOLD, MAX_REPUTATION,
+    MIN_REPUTATION, REPUTATION_HALF_LIFE,
+};
+use chrono::Utc;
+use libp2p::PeerId;
+use std::collections::HashMap;
 use std::num::NonZeroUsize;
+use std::time::Duration;
 
 #[test]
 fn test_empty_collection() {
@@ -119,3 +128,251 @@ fn test_resizable_semaphore_shrink() {
     drop(permit_1);
     assert!(sem.try_acquire().is_some());
 }
+
+fn peer_reputations_with(peer_id: PeerId, value: i32, age: chrono::Duration) -> PeerReputations {
+    PeerReputations::new(HashMap::from([(
+        peer_id,
+        PeerReputation {
+            value,
+            updated_at: Utc::now() - age,
+        },
+    )]))
+}
+
+#[test]
+fn test_peer_reputation_decay() {
+    let peer_id = PeerId::random();
+    let half_life = chrono::Duration::from_std(REPUTATION_HALF_LIFE).unwrap();
+
+    // Unknown peers have neutral reputation
+    let peer_reputations = PeerReputations::new(HashMap::new());
+    assert_eq!(peer_reputations.reputation(&peer_id), 0);
+
+    let peer_reputations = peer_reputations_with(peer_id, 1_000, chrono::Duration::zero());
+    assert!((999..=1_000).contains(&peer_reputations.reputation(&peer_id)));
+
+    let peer_reputations = peer_reputations_with(peer_id, 1_000, half_life);
+    assert!((499..=500).contains(&peer_reputations.reputation(&peer_id)));
+
+    let peer_reputations = peer_reputations_with(peer_id, -1_000, half_life * 2);
+    assert!((-250..=-249).contains(&peer_reputations.reputation(&peer_id)));
+
+    // Updates from the future (clock skew) are not decayed
+    let peer_reputations = peer_reputations_with(peer_id, 1_000, -half_life);
+    assert_eq!(peer_reputations.reputation(&peer_id), 1_000);
+}
+
+#[test]
+fn test_peer_reputation_clamping() {
+    let peer_id = PeerId::random();
+
+    let mut peer_reputations =
+        peer_reputations_with(peer_id, MAX_REPUTATION, chrono::Duration::zero());
+    peer_reputations.apply(peer_id, ReputationChange::RequestSucceeded);
+    let reputation = peer_reputations.reputation(&peer_id);
+    assert!((MAX_REPUTATION - 1..=MAX_REPUTATION).contains(&reputation));
+
+    let mut peer_reputations =
+        peer_reputations_with(peer_id, MIN_REPUTATION, chrono::Duration::zero());
+    peer_reputations.apply(peer_id, ReputationChange::RequestFailed);
+    let reputation = peer_reputations.reputation(&peer_id);
+    assert!((MIN_REPUTATION..=MIN_REPUTATION + 1).contains(&reputation));
+}
+
+#[test]
+fn test_peer_reputation_ban_threshold() {
+    let peer_id = PeerId::random();
+    let mut peer_reputations = PeerReputations::new(HashMap::new());
+
+    let failures_to_reach_threshold =
+        BAN_THRESHOLD.unsigned_abs() / ReputationChange::RequestFailed.value().unsigned_abs();
+    for _ in 0..failures_to_reach_threshold {
+        assert!(!peer_reputations.apply(peer_id, ReputationChange::RequestFailed));
+        assert!(!peer_reputations.is_below_ban_threshold(&peer_id));
+    }
+
+    assert!(peer_reputations.apply(peer_id, ReputationChange::RequestFailed));
+    assert!(peer_reputations.is_below_ban_threshold(&peer_id));
+
+    // Good behaviour doesn't result in ban even with low reputation
+    assert!(!peer_reputations.apply(peer_id, ReputationChange::RequestSucceeded));
+
+    // Other peers are not affected
+    assert!(!peer_reputations.is_below_ban_threshold(&PeerId::random()));
+}
+
+#[test]
+fn test_peer_reputation_misbehaviour() {
+    let peer_id = PeerId::random();
+
+    // Invalid data results in ban even for peer with the highest reputation
+    let mut peer_reputations =
+        peer_reputations_with(peer_id, MAX_REPUTATION, chrono::Duration::zero());
+    assert!(peer_reputations.apply(peer_id, ReputationChange::InvalidData));
+    assert!(peer_reputations.is_below_ban_threshold(&peer_id));
+
+    // Protocol violations are penalized much more than failed requests, but don't result in ban
+    // right away
+    let mut peer_reputations = PeerReputations::new(HashMap::new());
+    assert!(!peer_reputations.apply(peer_id, ReputationChange::ProtocolViolation));
+    assert!(peer_reputations.reputation(&peer_id) < ReputationChange::RequestFailed.value() * 5);
+    assert!(!peer_reputations.apply(peer_id, ReputationChange::ProtocolViolation));
+    assert!(peer_reputations.apply(peer_id, ReputationChange::ProtocolViolation));
+}
+
+#[test]
+fn test_peer_reputation_take_changed() {
+    let peer_id = PeerId::random();
+    let mut peer_reputations = peer_reputations_with(peer_id, 100, chrono::Duration::zero());
+
+    // Loaded reputations are not considered changed
+    assert!(peer_reputations.take_changed().is_none());
+
+    peer_reputations.apply(peer_id, ReputationChange::RequestSucceeded);
+    let changed = peer_reputations.take_changed().unwrap();
+    assert_eq!(changed.len(), 1);
+    assert!(changed.contains_key(&peer_id));
+
+    assert!(peer_reputations.take_changed().is_none());
+}
+
+fn hedged_requests(max_concurrent_requests: usize) -> HedgedRequests {
+    HedgedRequests::new(PieceProviderConfig {
+        max_concurrent_requests: NonZeroUsize::new(max_concurrent_requests).unwrap(),
+        hedge_threshold: Duration::from_secs(1),
+    })
+}
+
+#[test]
+fn test_hedged_requests_threshold_reset() {
+    let mut hedged_requests = hedged_requests(3);
+    let providers = [PeerId::random(), PeerId::random(), PeerId::random()];
+    for provider_id in providers {
+        hedged_requests.add_provider(provider_id, 0);
+    }
+
+    assert_eq!(hedged_requests.next_provider(), Some(providers[0]));
+    // Next provider is only requested after hedge threshold
+    assert!(hedged_requests.is_waiting_for_hedge_threshold());
+    assert_eq!(hedged_requests.next_provider(), None);
+
+    hedged_requests.hedge_threshold_reached();
+    assert!(!hedged_requests.is_waiting_for_hedge_threshold());
+    assert_eq!(hedged_requests.next_provider(), Some(providers[1]));
+
+    // Threshold is reset after starting a request
+    assert!(hedged_requests.is_waiting_for_hedge_threshold());
+    assert_eq!(hedged_requests.next_provider(), None);
+
+    // Failed request doesn't need to wait for threshold
+    hedged_requests.request_failed();
+    assert!(!hedged_requests.is_waiting_for_hedge_threshold());
+    assert_eq!(hedged_requests.next_provider(), Some(providers[2]));
+}
+
+#[test]
+fn test_hedged_requests_concurrency_cap() {
+    let mut hedged_requests = hedged_requests(2);
+    let providers = [PeerId::random(), PeerId::random(), PeerId::random()];
+    for provider_id in providers {
+        hedged_requests.add_provider(provider_id, 0);
+    }
+
+    assert_eq!(hedged_requests.next_provider(), Some(providers[0]));
+    hedged_requests.hedge_threshold_reached();
+    assert_eq!(hedged_requests.next_provider(), Some(providers[1]));
+
+    // Concurrency limit is reached, threshold doesn't allow more requests
+    hedged_requests.hedge_threshold_reached();
+    assert_eq!(hedged_requests.next_provider(), None);
+
+    hedged_requests.request_failed();
+    assert_eq!(hedged_requests.next_provider(), Some(providers[2]));
+
+    hedged_requests.finish_providers();
+    hedged_requests.request_failed();
+    hedged_requests.request_failed();
+    assert_eq!(hedged_requests.next_provider(), None);
+    assert!(hedged_requests.is_finished());
+}
+
+#[test]
+fn test_hedged_requests_deferred_providers() {
+    let mut hedged_requests = hedged_requests(2);
+    let worst_provider = PeerId::random();
+    let bad_provider = PeerId::random();
+    let neutral_provider = PeerId::random();
+    hedged_requests.add_provider(worst_provider, -500);
+    hedged_requests.add_provider(bad_provider, -10);
+    hedged_requests.add_provider(neutral_provider, 0);
+
+    assert_eq!(hedged_requests.next_provider(), Some(neutral_provider));
+    hedged_requests.request_failed();
+
+    // Deferred providers are not requested until all providers are found
+    assert_eq!(hedged_requests.next_provider(), None);
+    assert!(!hedged_requests.is_finished());
+
+    hedged_requests.finish_providers();
+    assert_eq!(hedged_requests.next_provider(), Some(bad_provider));
+    hedged_requests.request_failed();
+    assert_eq!(hedged_requests.next_provider(), Some(worst_provider));
+    assert!(!hedged_requests.is_finished());
+
+    hedged_requests.request_failed();
+    assert_eq!(hedged_requests.next_provider(), None);
+    assert!(hedged_requests.is_finished());
+}
+
+#[test]
+fn test_hedged_requests_reputation_order() {
+    let mut hedged_requests = hedged_requests(1);
+    let neutral_provider = PeerId::random();
+    let good_provider = PeerId::random();
+    let best_provider = PeerId::random();
+    let another_best_provider = PeerId::random();
+    let bad_provider = PeerId::random();
+    let worst_provider = PeerId::random();
+    hedged_requests.add_provider(neutral_provider, 0);
+    hedged_requests.add_provider(worst_provider, -500);
+    hedged_requests.add_provider(best_provider, 100);
+    hedged_requests.add_provider(good_provider, 50);
+    hedged_requests.add_provider(bad_provider, -10);
+    hedged_requests.add_provider(another_best_provider, 100);
+    hedged_requests.finish_providers();
+
+    // Better reputation first, providers with the same reputation in order they were found
+    for provider_id in [
+        best_provider,
+        another_best_provider,
+        good_provider,
+        neutral_provider,
+        bad_provider,
+        worst_provider,
+    ] {
+        assert_eq!(hedged_requests.next_provider(), Some(provider_id));
+        hedged_requests.request_failed();
+    }
+
+    assert_eq!(hedged_requests.next_provider(), None);
+    assert!(hedged_requests.is_finished());
+}
+
+#[test]
+fn test_hedged_requests_late_providers_by_reputation() {
+    let mut hedged_requests = hedged_requests(1);
+    let first_provider = PeerId::random();
+    let neutral_provider = PeerId::random();
+    let good_provider = PeerId::random();
+    hedged_requests.add_provider(first_provider, 0);
+
+    assert_eq!(hedged_requests.next_provider(), Some(first_provider));
+
+    // Providers found while request is in progress are ordered by reputation
+    hedged_requests.add_provider(neutral_provider, 0);
+    hedged_requests.add_provider(good_provider, 10);
+    hedged_requests.request_failed();
+    assert_eq!(hedged_requests.next_provider(), Some(good_provider));
+    hedged_requests.request_failed();
+    assert_eq!(hedged_requests.next_provider(), Some(neutral_provider));
+}
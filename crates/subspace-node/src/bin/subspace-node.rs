@@ -451,6 +451,7 @@ fn main() -> Result<(), Error> {
                             bootstrap_nodes: dsn_bootstrap_nodes,
                             reserved_peers: cli.dsn_reserved_peers,
                             allow_non_global_addresses_in_dht: !cli.dsn_disable_private_ips,
+                            enable_mdns: cli.dsn_enable_mdns,
                             max_in_connections: cli.dsn_in_connections,
                             max_out_connections: cli.dsn_out_connections,
                             max_pending_in_connections: cli.dsn_pending_in_connections,
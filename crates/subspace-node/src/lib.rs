@@ -223,6 +223,10 @@ pub struct Cli {
     #[arg(long, default_value_t = false)]
     pub dsn_disable_private_ips: bool,
 
+    /// Discover other nodes and farmers in local network using mDNS for the DSN.
+    #[arg(long, default_value_t = false)]
+    pub dsn_enable_mdns: bool,
+
     /// Enables DSN-sync on startup.
     #[arg(long, default_value_t = false)]
     pub sync_from_dsn: bool,
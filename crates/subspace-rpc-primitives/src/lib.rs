@@ -16,8 +16,9 @@
 //! Primitives for Subspace RPC.
 
 use serde::{Deserialize, Serialize};
+use subspace_core_primitives::objects::GlobalObject;
 use subspace_core_primitives::{
-    Blake2b256Hash, PublicKey, RewardSignature, SlotNumber, Solution, SolutionRange,
+    Blake2b256Hash, PublicKey, RewardSignature, SegmentIndex, SlotNumber, Solution, SolutionRange,
 };
 use subspace_farmer_components::FarmerProtocolInfo;
 use subspace_networking::libp2p::Multiaddr;
@@ -87,3 +88,13 @@ pub struct RewardSignatureResponse {
     /// Pre-header or vote hash signature.
     pub signature: Option<RewardSignature>,
 }
+
+/// Object mappings of a newly archived segment.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedObjectMappings {
+    /// Index of the segment objects were archived in.
+    pub segment_index: SegmentIndex,
+    /// Hashes of objects stored in the segment along with their location in archived history.
+    pub objects: Vec<(Blake2b256Hash, GlobalObject)>,
+}
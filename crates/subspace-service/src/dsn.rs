@@ -12,6 +12,7 @@ use std::path::PathBuf;
 use std::time::Instant;
 use subspace_core_primitives::{SegmentHeader, SegmentIndex};
 use subspace_networking::libp2p::kad::ProviderRecord;
+use subspace_networking::libp2p::mdns::Config as MdnsConfig;
 use subspace_networking::libp2p::{identity, Multiaddr};
 use subspace_networking::{
     peer_id, BootstrappedNetworkingParameters, CreationError, MemoryProviderStorage,
@@ -61,6 +62,9 @@ pub struct DsnConfig {
     /// Determines whether we allow keeping non-global (private, shared, loopback..) addresses in Kademlia DHT.
     pub allow_non_global_addresses_in_dht: bool,
 
+    /// Determines whether peers in local network are discovered using mDNS.
+    pub enable_mdns: bool,
+
     /// System base path.
     pub base_path: Option<PathBuf>,
 
@@ -140,6 +144,7 @@ where
         keypair: dsn_config.keypair.clone(),
         listen_on: dsn_config.listen_on,
         allow_non_global_addresses_in_dht: dsn_config.allow_non_global_addresses_in_dht,
+        mdns: dsn_config.enable_mdns.then(MdnsConfig::default),
         networking_parameters_registry,
         request_response_protocols: vec![
             PieceAnnouncementRequestHandler::create({
@@ -92,7 +92,7 @@ pub fn check_reward_signature(
 
 /// Calculates solution distance for given parameters, is used as a primitive to check whether
 /// solution distance is within solution range (see [`is_within_solution_range()`]).
-fn calculate_solution_distance(
+pub fn calculate_solution_distance(
     global_challenge: &Blake2b256Hash,
     audit_chunk: SolutionRange,
     sector_slot_challenge: &SectorSlotChallenge,
@@ -178,13 +178,37 @@ where
         piece_check_params,
     } = params;
 
+    verify_solution_with_global_challenge::<PosTable, _, _>(
+        solution,
+        &global_randomness.derive_global_challenge(slot),
+        *solution_range,
+        piece_check_params.as_ref(),
+        kzg,
+    )
+}
+
+/// Verify whether solution is valid for already derived global challenge, returns solution
+/// distance that is `<= solution_range/2` on success.
+///
+/// This is the core of [`verify_solution()`] that can be used when global challenge is known
+/// instead of global randomness (like on the farmer side).
+pub fn verify_solution_with_global_challenge<'a, PosTable, FarmerPublicKey, RewardAddress>(
+    solution: &'a Solution<FarmerPublicKey, RewardAddress>,
+    global_challenge: &Blake2b256Hash,
+    solution_range: SolutionRange,
+    piece_check_params: Option<&PieceCheckParams>,
+    kzg: &Kzg,
+) -> Result<SolutionRange, Error>
+where
+    PosTable: Table,
+    PublicKey: From<&'a FarmerPublicKey>,
+{
     let sector_id = SectorId::new(
         PublicKey::from(&solution.public_key).hash(),
         solution.sector_index,
     );
 
-    let global_challenge = global_randomness.derive_global_challenge(slot);
-    let sector_slot_challenge = sector_id.derive_sector_slot_challenge(&global_challenge);
+    let sector_slot_challenge = sector_id.derive_sector_slot_challenge(global_challenge);
     let s_bucket_audit_index = sector_slot_challenge.s_bucket_audit_index();
 
     // Check that proof of space is valid
@@ -212,7 +236,7 @@ where
     };
 
     let solution_distance =
-        calculate_solution_distance(&global_challenge, audit_chunk, &sector_slot_challenge);
+        calculate_solution_distance(global_challenge, audit_chunk, &sector_slot_challenge);
 
     // Check that solution is within solution range
     if solution_distance > solution_range / 2 {